@@ -0,0 +1,28 @@
+#!/usr/bin/env python3
+# short corridor (example 13.1) over the JSON-line protocol of src/external.rs
+# the state is observed here, so a tabular agent can learn the deterministic optimum
+import json
+import sys
+
+state = 0
+
+for line in sys.stdin:
+    req = json.loads(line)
+    cmd = req["cmd"]
+    if cmd == "reset":
+        state = 0
+        reply = {"state": state}
+    elif cmd == "step":
+        # 0 right, 1 left, reversed in state 1
+        move = 1 if req["action"] == 0 else -1
+        if state == 1:
+            move = -move
+        state = max(state + move, 0)
+        reply = {"state": state, "reward": -1.0, "done": state == 3}
+    elif cmd == "actions":
+        reply = {"actions": [0, 1]}
+    elif cmd == "close":
+        break
+    else:
+        reply = {"error": "unknown cmd " + cmd}
+    print(json.dumps(reply), flush=True)
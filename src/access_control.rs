@@ -0,0 +1,128 @@
+use std::error::Error;
+use rand::prelude::*;
+
+use crate::differential::DifferentialSarsa;
+use crate::env::Environment;
+use crate::progress;
+use crate::rng::{ self, SeededRng };
+use crate::schedules::Schedule;
+use crate::vfa::{ FeatureVec, Features };
+
+type State = (usize, usize);//free servers, priority index
+type Action = usize;//0 reject, 1 accept
+
+const PRIORITY:[f64; 4] = [1.0, 2.0, 4.0, 8.0];
+const ACTION:[Action; 2] = [0, 1];
+
+//example 10.2, a continuing task without discounting
+pub struct World {
+    pub servers:usize,
+    pub p_free:f64,//per busy server and step
+    pub state:State,
+}
+
+struct ControlInfo<A = f64, X = f64> {
+    pub max_step:usize,
+    pub epsilon:X,//by step
+    pub alpha:A,
+    pub beta:f64,//average reward step size
+}
+
+//one feature per state-action pair
+struct Table {
+    pub servers:usize,
+}
+
+impl World {
+    pub fn new(servers:usize, p_free:f64) -> Self {
+        Self { servers, p_free, state:(servers, 0) }
+    }
+}
+
+//continuing, never terminates
+impl Environment for World {
+    type State = State;
+    type Action = Action;
+
+    fn reset(&mut self, rng:&mut SeededRng) -> State {
+        self.state = (self.servers, rng.gen_range(0..PRIORITY.len()));
+        self.state
+    }
+
+    fn step(&mut self, a:&Action, rng:&mut SeededRng) -> (State, f64, bool) {
+        let (mut free, priority) = self.state;
+        let mut r = 0.0;
+        if *a == 1 && free > 0 {
+            free -= 1;
+            r = PRIORITY[priority];
+        }
+        let busy = self.servers - free;
+        free += (0..busy).filter(|_| rng.gen::<f64>() < self.p_free).count();
+        self.state = (free, rng.gen_range(0..PRIORITY.len()));
+        (self.state, r, false)
+    }
+
+    //customers can only be accepted by a free server
+    fn actions(&self, s:&State) -> Vec<Action> {
+        ACTION.iter().cloned().filter(|a| *a == 0 || s.0 > 0).collect()
+    }
+}
+
+impl Features<(State, Action)> for Table {
+    fn dim(&self) -> usize {
+        (self.servers + 1) * PRIORITY.len() * ACTION.len()
+    }
+
+    fn features(&self, ((free, priority), a):&(State, Action)) -> FeatureVec {
+        vec!(((free * PRIORITY.len() + priority) * ACTION.len() + a, 1.0))
+    }
+}
+
+type Agent<A, X> = DifferentialSarsa<Table, A, X>;
+
+fn print_policy<A:Schedule, X:Schedule>(agent:&Agent<A, X>, w:&World) {
+    println!("policy, 1 accept");
+    print!("{:>9}", "free");
+    for free in 0..=w.servers {
+        print!("{:>3}", free);
+    }
+    println!();
+    for (k, p) in PRIORITY.iter().enumerate().rev() {
+        print!("{:>9}", format!("prio {}", p));
+        for free in 0..=w.servers {
+            print!("{:>3}", agent.greedy(&(free, k), w));
+        }
+        println!();
+    }
+}
+
+fn print_value<A:Schedule, X:Schedule>(agent:&Agent<A, X>, w:&World) {
+    println!("differential value of the best action");
+    for (k, p) in PRIORITY.iter().enumerate().rev() {
+        print!("{:>9}", format!("prio {}", p));
+        for free in 0..=w.servers {
+            let s = (free, k);
+            print!("{:>7.2}", agent.value::<World>(&s, &agent.greedy(&s, w)));
+        }
+        println!();
+    }
+}
+
+pub fn run(seed:u64) -> Result<(), Box<dyn Error>> {
+    let c_info = ControlInfo {
+        max_step:2000000,
+        epsilon:0.1,
+        alpha:0.01, beta:0.01,
+    };
+    let mut rng = rng::from_seed(seed);
+    let mut w = World::new(10, 0.06);
+    let mut agent = DifferentialSarsa::new(Table { servers:w.servers }, 0.0, 0.0, c_info.beta)
+        .with_alpha(c_info.alpha).with_epsilon(c_info.epsilon);
+    let pb = progress::counter(c_info.max_step as u64, "step");
+    agent.run(&mut w, c_info.max_step, &mut rng, |_| pb.inc(1));
+    pb.finish_and_clear();
+    print_policy(&agent, &w);
+    print_value(&agent, &w);
+    println!("average reward {:.4}", agent.r_avg);
+    Ok(())
+}
@@ -0,0 +1,108 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::env::Environment;
+use crate::policy::Behavior;
+use crate::rng::SeededRng;
+use crate::schedules::{ self, Schedule };
+use crate::td::Method;
+
+//environments where an action has a known deterministic effect before any reward or randomness,
+//section 6.8. every (s, a) leading to the same afterstate shares one value
+pub trait Afterstate:Environment {
+    type After:Clone + Eq + Hash;
+    fn afterstate(&self, s:&Self::State, a:&Self::Action) -> Self::After;
+}
+
+//tabular TD control with values indexed by afterstate instead of (state, action). the
+//action values of s are read through v, so behavior, targets and schedules work as in TdControl
+pub struct AfterstateControl<K, A = f64, X = f64> {
+    pub method:Method,
+    pub behavior:Behavior,
+    pub alpha:A,//by the updates of the afterstate so far
+    pub epsilon:Option<X>,//by episode, replaces behavior with ε-greedy when set
+    pub gamma:f64,
+    pub v:HashMap<K, f64>,
+    pub updates:HashMap<K, usize>,
+    pub episodes:usize,
+}
+
+impl<K:Clone + Eq + Hash> AfterstateControl<K> {
+    pub fn new(method:Method, behavior:Behavior, alpha:f64, gamma:f64) -> Self {
+        Self { method, behavior, alpha, epsilon:None, gamma, v:HashMap::new(), updates:HashMap::new(), episodes:0 }
+    }
+}
+
+impl<K:Clone + Eq + Hash, A:Schedule, X:Schedule> AfterstateControl<K, A, X> {
+    pub fn with_alpha<B:Schedule>(self, alpha:B) -> AfterstateControl<K, B, X> {
+        let Self { method, behavior, epsilon, gamma, v, updates, episodes, .. } = self;
+        AfterstateControl { method, behavior, alpha, epsilon, gamma, v, updates, episodes }
+    }
+
+    pub fn with_epsilon<B:Schedule>(self, epsilon:B) -> AfterstateControl<K, A, B> {
+        let Self { method, behavior, alpha, gamma, v, updates, episodes, .. } = self;
+        AfterstateControl { method, behavior, alpha, epsilon:Some(epsilon), gamma, v, updates, episodes }
+    }
+
+    //α for the next update of k
+    pub fn step_size(&mut self, k:&K) -> f64 {
+        let count = self.updates.entry(k.clone()).or_insert(0);
+        *count += 1;
+        self.alpha.at(*count)
+    }
+
+    //afterstate and value of every action in s, in env.actions(s) order, unseen ones at 0
+    pub fn values<E:Afterstate<After = K>>(&self, s:&E::State, env:&E) -> (Vec<K>, Vec<f64>) {
+        env.actions(s).iter()
+            .map(|a| {
+                let k = env.afterstate(s, a);
+                let v = self.v.get(&k).copied().unwrap_or(0.0);
+                (k, v)
+            })
+            .unzip()
+    }
+
+    pub fn select<E:Afterstate<After = K>>(&self, s:&E::State, env:&E, rng:&mut SeededRng) -> (usize, K) {
+        let (mut k, q) = self.values(s, env);
+        let a = self.behavior.sample(&q, &vec![true; q.len()], rng);
+        (a, k.swap_remove(a))
+    }
+
+    //index of the best action in s, the first one on ties
+    pub fn greedy<E:Afterstate<After = K>>(&self, s:&E::State, env:&E) -> usize {
+        let (_, q) = self.values(s, env);
+        q.iter().enumerate().fold(0, |best, (k, v)| if *v > q[best] { k } else { best })
+    }
+
+    //learns along one episode, returns the sum of rewards and the steps taken
+    pub fn episode<E:Afterstate<After = K>>(&mut self, env:&mut E, rng:&mut SeededRng, max_step:usize) -> (f64, usize) {
+        self.episodes += 1;
+        self.behavior = schedules::behavior(self.behavior, self.epsilon.as_ref(), self.episodes);
+        let mut s = env.reset(rng);
+        let (mut a, mut k) = self.select(&s, env, rng);
+        let mut g = 0.0;
+        let mut step = 0;
+        while step < max_step {
+            step += 1;
+            let action = env.actions(&s)[a].clone();
+            let (ss, r, done) = env.step(&action, rng);
+            g += r;
+            let (v_next, aa, kk) = if done { (0.0, 0, None) } else {
+                let (aa, kk) = self.select(&ss, env, rng);
+                let (_, q) = self.values(&ss, env);
+                (self.method.target(self.behavior, &q, aa), aa, Some(kk))
+            };
+            let alpha = self.step_size(&k);
+            let v = self.v.entry(k).or_insert(0.0);
+            *v += alpha * (r + self.gamma * v_next - *v);
+            match kk {
+                Some(kk) => k = kk,
+                None => break,
+            }
+            s = ss;
+            a = aa;
+        }
+        (g, step)
+    }
+
+}
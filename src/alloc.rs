@@ -0,0 +1,33 @@
+use std::alloc::{ GlobalAlloc, Layout, System };
+use std::sync::atomic::{ AtomicUsize, Ordering };
+
+static ALLOCATIONS:AtomicUsize = AtomicUsize::new(0);
+static BYTES:AtomicUsize = AtomicUsize::new(0);
+
+//the system allocator counting every allocation, the rl_exercise binary installs it as
+//#[global_allocator]. elsewhere nothing is counted and every reading stays 0
+pub struct Counting;
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout:Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr:*mut u8, layout:Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    //a growing vec counts once per move to a bigger block
+    unsafe fn realloc(&self, ptr:*mut u8, layout:Layout, new_size:usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        BYTES.fetch_add(new_size, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+//allocations and bytes requested so far, differences of two readings count what ran between
+pub fn count() -> (usize, usize) {
+    (ALLOCATIONS.load(Ordering::Relaxed), BYTES.load(Ordering::Relaxed))
+}
@@ -0,0 +1,35 @@
+use std::io::IsTerminal;
+use std::sync::OnceLock;
+
+//24-bit background colours for grid printouts, plain text when stdout is
+//not a terminal or NO_COLOR is set
+pub fn enabled() -> bool {
+    static ENABLED:OnceLock<bool> = OnceLock::new();
+    *ENABLED.get_or_init(|| std::env::var_os("NO_COLOR").is_none() && std::io::stdout().is_terminal())
+}
+
+//t in [0, 1], dark blue through yellow
+pub fn sequential(t:f64) -> (u8, u8, u8) {
+    let t = t.clamp(0.0, 1.0);
+    ((255.0 * t) as u8, (64.0 + 160.0 * t) as u8, (160.0 * (1.0 - t)) as u8)
+}
+
+//t in [-1, 1], red for negative, white at zero, blue for positive
+pub fn diverging(t:f64) -> (u8, u8, u8) {
+    let t = t.clamp(-1.0, 1.0);
+    let c = (255.0 * (1.0 - t.abs())) as u8;
+    if t < 0.0 { (255, c, c) } else { (c, c, 255) }
+}
+
+//text on the given background, black or white foreground for contrast
+pub fn cell(text:&str, (r, g, b):(u8, u8, u8)) -> String {
+    if !enabled() { return text.to_string() }
+    let luma = 0.299 * r as f64 + 0.587 * g as f64 + 0.114 * b as f64;
+    let fg = if luma > 128.0 { 30 } else { 97 };
+    format!("\x1b[{};48;2;{};{};{}m{}\x1b[0m", fg, r, g, b, text)
+}
+
+//position of v between lo and hi, 0 when the range is empty
+pub fn scale(v:f64, lo:f64, hi:f64) -> f64 {
+    if hi > lo { (v - lo) / (hi - lo) } else { 0.0 }
+}
@@ -0,0 +1,204 @@
+use std::error::Error;
+use plotters::{prelude::*, coord::Shift};
+use rand::prelude::*;
+
+use crate::emphatic::EmphaticTd;
+use crate::gtd::{ self, GradientTd };
+use crate::rng::{ self, SeededRng };
+use crate::vfa::{ FeatureVec, Features, LinearVfa };
+
+type State = usize;//0..=5 upper, 6 lower
+
+const STATE:usize = 7;
+const LOWER:State = 6;
+
+//figure 11.1, every transition goes to the lower state under the target policy
+//the behavior policy takes dashed to a random upper state with 6/7, solid with 1/7
+struct World {
+    pub gamma:f64,
+}
+
+struct ControlInfo {
+    pub max_step:usize,
+    pub alpha:f64,
+    pub beta:f64,//gradient-TD secondary weights
+}
+
+//2w_i + w_8 for the upper states, w_7 + 2w_8 for the lower one
+struct BairdFeatures {}
+
+#[derive(Clone, Copy)]
+enum Method {
+    SemiGradientTd,
+    SemiGradientDp,
+    Gtd2,
+    Tdc,
+    ExpectedTdc,
+    EmphaticTd,
+    ExpectedEmphaticTd,
+}
+
+//μ is uniform and unaffected by the policy, so steps need not follow a trajectory
+struct Transition {
+    pub s:State,
+    pub ss:State,
+    pub rho:f64,//π(a|s)/b(a|s)
+}
+
+impl World {
+    fn step(&self, rng:&mut SeededRng) -> Transition {
+        let s = rng.gen_range(0..STATE);
+        self.step_from(s, rng)
+    }
+
+    //b from s, for methods that need a trajectory
+    fn step_from(&self, s:State, rng:&mut SeededRng) -> Transition {
+        if rng.gen_range(0..STATE) == LOWER {
+            Transition { s, ss:LOWER, rho:STATE as f64 }
+        }
+        else {
+            Transition { s, ss:rng.gen_range(0..LOWER), rho:0.0 }
+        }
+    }
+}
+
+impl World {
+    //expected followon trace of each state under b with interest 1 everywhere. π only leads
+    //to the lower state, so f(upper) = 1 and f(lower) = 1 + γ Σ_s f(s)
+    fn followon(&self) -> Vec<f64> {
+        let lower = (1.0 + self.gamma * LOWER as f64) / (1.0 - self.gamma);
+        (0..STATE).map(|s| if s == LOWER { lower } else { 1.0 }).collect()
+    }
+}
+
+impl Features<State> for BairdFeatures {
+    fn dim(&self) -> usize {
+        STATE + 1
+    }
+
+    fn features(&self, s:&State) -> FeatureVec {
+        if *s == LOWER { vec!((LOWER, 1.0), (STATE, 2.0)) }
+        else { vec!((*s, 2.0), (STATE, 1.0)) }
+    }
+}
+
+impl Method {
+    fn label(&self) -> &'static str {
+        match self {
+            Method::SemiGradientTd => "semi-gradient off-policy TD",
+            Method::SemiGradientDp => "semi-gradient DP",
+            Method::Gtd2 => "GTD2",
+            Method::Tdc => "TDC",
+            Method::ExpectedTdc => "expected TDC",
+            Method::EmphaticTd => "emphatic TD",
+            Method::ExpectedEmphaticTd => "expected emphatic TD",
+        }
+    }
+
+    //w over steps
+    fn trace(&self, w:&World, c_info:&ControlInfo, rng:&mut SeededRng) -> Vec<Vec<f64>> {
+        let mut v = LinearVfa::new(BairdFeatures {});
+        v.w = vec!(1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 10.0, 1.0);
+        let mut gtd = GradientTd::new(match self { Method::Gtd2 => gtd::Method::Gtd2, _ => gtd::Method::Tdc },
+            c_info.alpha, c_info.beta, w.gamma, v.w.len());
+        let mut trace = vec!(v.w.clone());
+        let mut etd = EmphaticTd::new(0.0, w.gamma, c_info.alpha, v.w.len());
+        let mut s = rng.gen_range(0..STATE);
+        let f = w.followon();
+        for _ in 0..c_info.max_step {
+            match self {
+                Method::SemiGradientTd => {
+                    let t = w.step(rng);
+                    let delta = w.gamma * v.value(&t.ss) - v.value(&t.s);
+                    v.update(&t.s, c_info.alpha, t.rho * delta);
+                },
+                Method::SemiGradientDp => {
+                    //the expected target of every state is γv(lower)
+                    let target = w.gamma * v.value(&LOWER);
+                    let delta:Vec<f64> = (0..STATE).map(|s| target - v.value(&s)).collect();
+                    for (s, delta) in delta.into_iter().enumerate() {
+                        v.update(&s, c_info.alpha / STATE as f64, delta);
+                    }
+                },
+                Method::Gtd2 | Method::Tdc => {
+                    let t = w.step(rng);
+                    gtd.step(&mut v, &t.s, 0.0, Some(&t.ss), t.rho);
+                },
+                Method::ExpectedTdc => {
+                    //only solid has a nonzero ratio, b·ρ = 1
+                    let target = w.gamma * v.value(&LOWER);
+                    let delta:Vec<f64> = (0..STATE).map(|s| target - v.value(&s)).collect();
+                    for (s, delta) in delta.into_iter().enumerate() {
+                        gtd.update(&mut v, &s, Some(&LOWER), 1.0, delta, 1.0 / STATE as f64);
+                    }
+                },
+                Method::EmphaticTd => {
+                    //interest 1 everywhere, one unending trajectory of b
+                    let t = w.step_from(s, rng);
+                    etd.step(&mut v, &t.s, 0.0, Some(&t.ss), t.rho, 1.0);
+                    s = t.ss;
+                },
+                Method::ExpectedEmphaticTd => {
+                    //(11.7) with the expected emphasis of each state in place of M_t
+                    let target = w.gamma * v.value(&LOWER);
+                    let delta:Vec<f64> = (0..STATE).map(|s| target - v.value(&s)).collect();
+                    for (s, delta) in delta.into_iter().enumerate() {
+                        v.update(&s, c_info.alpha / STATE as f64, f[s] * delta);
+                    }
+                },
+            }
+            trace.push(v.w.clone());
+        }
+        trace
+    }
+}
+
+fn draw_trace(label:&str, trace:&[Vec<f64>], canvas:&DrawingArea<BitMapBackend, Shift>) -> Result<(), Box<dyn Error>> {
+    let w_min = trace.iter().flatten().cloned().fold(-1.0, f64::min);
+    let w_max = trace.iter().flatten().cloned().fold(1.0, f64::max);
+    let mut chart = ChartBuilder::on(canvas)
+        .caption(label, ("sans-serif", 30))
+        .margin(5)
+        .x_label_area_size(50)
+        .y_label_area_size(50)
+        .build_cartesian_2d(0..trace.len(), w_min..w_max)?;
+    chart.configure_mesh().draw()?;
+    for k in 0..trace[0].len() {
+        let color = Palette99::pick(k).to_rgba();
+        chart.draw_series(LineSeries::new(trace.iter().map(|w| w[k]).enumerate(), color))?
+            .label(format!("w{}", k + 1))
+            .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], color));
+    }
+    chart
+        .configure_series_labels()
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
+        .draw()?;
+    Ok(())
+}
+
+pub fn run(seed:u64) -> Result<(), Box<dyn Error>> {
+    let w = World { gamma:0.99 };
+    let mut rng = rng::from_seed(seed);
+    let method = [
+        (Method::SemiGradientTd, ControlInfo { max_step:1000, alpha:0.01, beta:0.0 }),
+        (Method::SemiGradientDp, ControlInfo { max_step:1000, alpha:0.01, beta:0.0 }),
+        (Method::Gtd2, ControlInfo { max_step:1000, alpha:0.005, beta:0.05 }),
+        (Method::Tdc, ControlInfo { max_step:1000, alpha:0.005, beta:0.05 }),
+        (Method::ExpectedTdc, ControlInfo { max_step:1000, alpha:0.005, beta:0.05 }),
+        (Method::EmphaticTd, ControlInfo { max_step:1000, alpha:0.001, beta:0.0 }),
+        (Method::ExpectedEmphaticTd, ControlInfo { max_step:1000, alpha:0.03, beta:0.0 }),
+    ];
+    let canvas = BitMapBackend::new("11_2.png", (1440, 2880)).into_drawing_area();
+    canvas.fill(&WHITE)?;
+    let canvas_split = canvas.split_evenly((4, 2));
+    for (k, (m, c_info)) in method.iter().enumerate() {
+        let trace = m.trace(&w, c_info, &mut rng::fork(&mut rng));
+        let w_last = trace.last().unwrap();
+        let norm = w_last.iter().map(|w| w * w).sum::<f64>().sqrt();
+        println!("{}: |w| {:.3} after {} steps", m.label(), norm, c_info.max_step);
+        draw_trace(m.label(), &trace, &canvas_split[k])?;
+    }
+    canvas.present()?;
+    Ok(())
+}
@@ -0,0 +1,230 @@
+use std::error::Error;
+use plotters::{prelude::*, coord::Shift};
+
+use crate::policy::Behavior;
+use crate::progress;
+use crate::rng::{ self, SeededRng };
+
+struct TestbedInfo {
+    pub arms:usize,
+    pub runs:usize,
+    pub steps:usize,
+    pub q_mean:f64,//mean of the true action values
+}
+
+#[derive(Clone, Copy)]
+enum Selection {
+    EpsilonGreedy(f64),//epsilon
+    Ucb(f64),//c, degree of exploration
+    Gradient(f64, bool),//alpha, use average reward as baseline
+}
+
+//how an agent estimates the action values
+#[derive(Clone, Copy)]
+struct Estimate {
+    pub initial:f64,//Q_1 of every action, optimistic when above every q*
+    pub alpha:Option<f64>,//constant step size, sample averages when None
+}
+
+const SAMPLE_AVERAGE:Estimate = Estimate { initial:0.0, alpha:None };
+
+struct Bandit {
+    pub q_star:Vec<f64>,
+    pub optimal:usize,
+}
+
+struct Agent {
+    pub selection:Selection,
+    pub estimate:Estimate,
+    pub q:Vec<f64>,//estimated action value
+    pub n:Vec<usize>,//action count
+    pub h:Vec<f64>,//action preference
+    pub r_avg:f64,
+    pub t:usize,
+}
+
+struct Curve {
+    pub label:String,
+    pub reward:Vec<f64>,
+    pub optimal:Vec<f64>,
+}
+
+fn argmax(v:&[f64], rng:&mut SeededRng) -> usize {
+    Behavior::EpsilonGreedy(0.0).sample(v, &vec![true; v.len()], rng)
+}
+
+impl Selection {
+    fn label(&self) -> String {
+        match self {
+            Selection::EpsilonGreedy(e) => format!("Ɛ-greedy Ɛ={}", e),
+            Selection::Ucb(c) => format!("UCB c={}", c),
+            Selection::Gradient(alpha, true) => format!("gradient α={} with baseline", alpha),
+            Selection::Gradient(alpha, false) => format!("gradient α={} without baseline", alpha),
+        }
+    }
+}
+
+impl Estimate {
+    fn label(&self) -> String {
+        match self.alpha {
+            Some(alpha) => format!("Q1={} α={}", self.initial, alpha),
+            None if self.initial != 0.0 => format!("Q1={}", self.initial),
+            None => String::new(),
+        }
+    }
+}
+
+impl Bandit {
+    fn new(tb_info:&TestbedInfo, rng:&mut SeededRng) -> Self {
+        let q_star:Vec<f64> = (0..tb_info.arms).map(|_| rng::normal(rng, tb_info.q_mean, 1.0)).collect();
+        let optimal = argmax(&q_star, rng);
+        Self { q_star, optimal }
+    }
+
+    fn reward(&self, a:usize, rng:&mut SeededRng) -> f64 {
+        rng::normal(rng, self.q_star[a], 1.0)
+    }
+}
+
+impl Agent {
+    fn new(selection:Selection, estimate:Estimate, arms:usize) -> Self {
+        Self { selection, estimate, q:vec![estimate.initial; arms], n:vec![0; arms], h:vec![0.0; arms], r_avg:0.0, t:0 }
+    }
+
+    fn select(&self, rng:&mut SeededRng) -> usize {
+        let all = vec![true; self.q.len()];
+        match self.selection {
+            Selection::EpsilonGreedy(e) => Behavior::EpsilonGreedy(e).sample(&self.q, &all, rng),
+            Selection::Ucb(c) => {
+                //untried actions are considered maximizing
+                if let Some(a) = self.n.iter().position(|n| *n == 0) { return a }
+                let ln_t = (self.t as f64).ln();
+                let ucb:Vec<f64> = self.q.iter().zip(self.n.iter())
+                    .map(|(q, n)| q + c * (ln_t / *n as f64).sqrt())
+                    .collect();
+                argmax(&ucb, rng)
+            },
+            Selection::Gradient(_, _) => Behavior::Softmax(1.0).sample(&self.h, &all, rng),
+        }
+    }
+
+    fn update(&mut self, a:usize, r:f64) {
+        self.t += 1;
+        self.n[a] += 1;
+        self.q[a] += match self.estimate.alpha {
+            Some(alpha) => alpha * (r - self.q[a]),
+            //sample average
+            None => (r - self.q[a]) / self.n[a] as f64,
+        };
+        if let Selection::Gradient(alpha, baseline) = self.selection {
+            self.r_avg += (r - self.r_avg) / self.t as f64;
+            let r_base = if baseline { self.r_avg } else { 0.0 };
+            let pi = Behavior::Softmax(1.0).prob_q(&self.h, &vec![true; self.h.len()]);
+            for (k, h) in self.h.iter_mut().enumerate() {
+                let one_hot = if k == a { 1.0 } else { 0.0 };
+                *h += alpha * (r - r_base) * (one_hot - pi[k]);
+            }
+        }
+    }
+}
+
+fn testbed(tb_info:&TestbedInfo, (selection, estimate):(Selection, Estimate), rng:&mut SeededRng) -> Curve {
+    let mut reward = vec![0.0; tb_info.steps];
+    let mut optimal = vec![0.0; tb_info.steps];
+    let pb = progress::counter(tb_info.runs as u64, "run");
+    for _ in 0..tb_info.runs {
+        pb.inc(1);
+        let bandit = Bandit::new(tb_info, rng);
+        let mut agent = Agent::new(selection, estimate, tb_info.arms);
+        for t in 0..tb_info.steps {
+            let a = agent.select(rng);
+            let r = bandit.reward(a, rng);
+            agent.update(a, r);
+            reward[t] += r;
+            if a == bandit.optimal { optimal[t] += 1.0 }
+        }
+    }
+    pb.finish_and_clear();
+    let runs = tb_info.runs as f64;
+    reward.iter_mut().for_each(|r| *r /= runs);
+    optimal.iter_mut().for_each(|o| *o /= runs);
+    let label = [selection.label(), estimate.label()].join(" ").trim_end().to_string();
+    Curve { label, reward, optimal }
+}
+
+fn draw_curve(curve:&[Curve], steps:usize, optimal:bool, canvas:&DrawingArea<BitMapBackend, Shift>) -> Result<(), Box<dyn Error>> {
+    let y_range = if optimal { 0.0..1.0 }
+        else {
+            let r = curve.iter().flat_map(|c| c.reward.iter());
+            let r_min = r.clone().cloned().fold(f64::INFINITY, f64::min).min(0.0);
+            let r_max = r.cloned().fold(f64::NEG_INFINITY, f64::max);
+            r_min..r_max
+        };
+    let mut chart = ChartBuilder::on(canvas)
+        .margin(5)
+        .x_label_area_size(50)
+        .y_label_area_size(50)
+        .build_cartesian_2d(0..steps, y_range)?;
+    chart.configure_mesh().draw()?;
+    for (k, c) in curve.iter().enumerate() {
+        let color = Palette99::pick(k).to_rgba();
+        let v = if optimal { &c.optimal } else { &c.reward };
+        chart.draw_series(LineSeries::new(v.iter().cloned().enumerate(), color))?
+            .label(c.label.clone())
+            .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], color));
+    }
+    chart
+        .configure_series_labels()
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
+        .draw()?;
+    Ok(())
+}
+
+fn experiment(tb_info:&TestbedInfo, selection:&[(Selection, Estimate)], file:&str, seed:u64) -> Result<(), Box<dyn Error>> {
+    let mut rng = rng::from_seed(seed);
+    let curve:Vec<Curve> = selection.iter()
+        .map(|s| testbed(tb_info, *s, &mut rng::fork(&mut rng)))
+        .collect();
+    for c in curve.iter() {
+        let avg = c.reward.iter().sum::<f64>() / tb_info.steps as f64;
+        println!("{}: average reward {:.3}, final optimal {:.1}%", c.label, avg, c.optimal.last().unwrap() * 100.0);
+    }
+    let canvas = BitMapBackend::new(file, (1440, 1440)).into_drawing_area();
+    canvas.fill(&WHITE)?;
+    let canvas_split = canvas.split_evenly((2, 1));
+    draw_curve(&curve, tb_info.steps, false, &canvas_split[0])?;
+    draw_curve(&curve, tb_info.steps, true, &canvas_split[1])?;
+    canvas.present()?;
+    Ok(())
+}
+
+pub fn run(seed:u64) -> Result<(), Box<dyn Error>> {
+    let tb_info = TestbedInfo { arms:10, runs:2000, steps:1000, q_mean:0.0 };
+    let selection = [
+        (Selection::EpsilonGreedy(0.1), SAMPLE_AVERAGE),
+        (Selection::Ucb(2.0), SAMPLE_AVERAGE),
+    ];
+    experiment(&tb_info, &selection, "2_4.png", seed)
+}
+
+pub fn run_gradient(seed:u64) -> Result<(), Box<dyn Error>> {
+    let tb_info = TestbedInfo { arms:10, runs:2000, steps:1000, q_mean:4.0 };
+    let selection = [
+        (Selection::Gradient(0.1, true), SAMPLE_AVERAGE),
+        (Selection::Gradient(0.4, true), SAMPLE_AVERAGE),
+        (Selection::Gradient(0.1, false), SAMPLE_AVERAGE),
+        (Selection::Gradient(0.4, false), SAMPLE_AVERAGE),
+    ];
+    experiment(&tb_info, &selection, "2_5.png", seed)
+}
+
+//figure 2.3, greedy from Q1=5 against Ɛ-greedy from Q1=0, both with α=0.1
+pub fn run_optimistic(seed:u64) -> Result<(), Box<dyn Error>> {
+    let tb_info = TestbedInfo { arms:10, runs:2000, steps:1000, q_mean:0.0 };
+    let selection = [
+        (Selection::EpsilonGreedy(0.0), Estimate { initial:5.0, alpha:Some(0.1) }),
+        (Selection::EpsilonGreedy(0.1), Estimate { initial:0.0, alpha:Some(0.1) }),
+    ];
+    experiment(&tb_info, &selection, "2_3.png", seed)
+}
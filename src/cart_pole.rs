@@ -0,0 +1,66 @@
+use rand::prelude::*;
+
+use crate::env::Environment;
+use crate::rng::SeededRng;
+
+type State = [f64; 4];//cart position, cart velocity, pole angle, pole angular velocity
+type Action = i32;//push left -1 or right 1
+
+//pole balancing of example 3.4 with the dynamics of Barto, Sutton and Anderson (1983).
+//+1 every step the pole is up, the episode ends once it leans past 12° or the cart leaves the track
+const GRAVITY:f64 = 9.8;
+const CART_MASS:f64 = 1.0;
+const POLE_MASS:f64 = 0.1;
+const POLE_HALF_LENGTH:f64 = 0.5;
+const FORCE:f64 = 10.0;
+const TAU:f64 = 0.02;//seconds per step
+const ANGLE_LIMIT:f64 = 12.0 * std::f64::consts::PI / 180.0;
+const POSITION_LIMIT:f64 = 2.4;
+const ACTION:[Action; 2] = [-1, 1];
+
+pub struct World {
+    pub state:State,
+}
+
+impl Default for World {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl World {
+    pub fn new() -> Self {
+        Self { state:[0.0; 4] }
+    }
+
+    fn is_terminal(s:&State) -> bool {
+        s[0].abs() > POSITION_LIMIT || s[2].abs() > ANGLE_LIMIT
+    }
+}
+
+impl Environment for World {
+    type State = State;
+    type Action = Action;
+
+    fn reset(&mut self, rng:&mut SeededRng) -> State {
+        self.state = [0; 4].map(|_| rng.gen_range(-0.05..0.05));
+        self.state
+    }
+
+    //one euler step
+    fn step(&mut self, a:&Action, _:&mut SeededRng) -> (State, f64, bool) {
+        let [x, v, theta, omega] = self.state;
+        let force = FORCE * *a as f64;
+        let (sin, cos) = theta.sin_cos();
+        let total = CART_MASS + POLE_MASS;
+        let temp = (force + POLE_MASS * POLE_HALF_LENGTH * omega * omega * sin) / total;
+        let alpha = (GRAVITY * sin - cos * temp) / (POLE_HALF_LENGTH * (4.0 / 3.0 - POLE_MASS * cos * cos / total));
+        let acc = temp - POLE_MASS * POLE_HALF_LENGTH * alpha * cos / total;
+        self.state = [x + TAU * v, v + TAU * acc, theta + TAU * omega, omega + TAU * alpha];
+        (self.state, 1.0, World::is_terminal(&self.state))
+    }
+
+    fn actions(&self, _:&State) -> Vec<Action> {
+        ACTION.to_vec()
+    }
+}
@@ -0,0 +1,146 @@
+use std::error::Error;
+
+use crate::exercise4_7::{ DiffReport, Evaluation, Init, TieBreak };
+use crate::exercise5_12::Estimator;
+use crate::exercise6_9::Moves;
+use crate::poisson::Tail;
+use crate::mc::Visit;
+use crate::stopping::StoppingRule;
+use crate::{ deterministic, exercise4_7, registry, rng, schema };
+
+//command line of the rl_exercise binary, kept in the library so other front ends can drive it
+pub struct Args {
+    pub index:String,
+    pub seed:Option<u64>,
+    pub env:Option<String>,//command line of an external environment
+    pub map:Option<String>,//ascii map of a maze, see maze.rs
+    pub tui:bool,
+    pub output:Option<String>,//json file for the solution of 4_7
+    pub runs:Option<usize>,//independent runs averaged by the figure experiments
+    pub config:Option<String>,//json file for a grid search
+    pub f32:bool,//single precision values for 4_7
+    pub parallel:bool,//4_7 sweeps and improvement on every core
+    pub renormalize:bool,//4_7 drops poisson mass above the cap instead of lumping it
+    pub q:bool,//4_7 with action values, exercise 4.5
+    pub tie:TieBreak,//4_7 choice among equally good actions
+    pub diff:DiffReport,//4_7 policy changes printed after each improvement
+    pub init:Init,//4_7 v before the first evaluation
+    pub cold:bool,//4_7 every evaluation starts over from init
+    pub sweeps:Option<i32>,//4_7 evaluation sweeps per improvement
+    pub exact:bool,//4_7 evaluates until the stopping rule, ignoring the sweep limit
+    pub span:Option<f64>,//4_7 span seminorm stopping with this epsilon
+    pub dot:Option<String>,//4_7 writes the model as graphviz instead of solving
+    pub npy:Option<String>,//4_7 writes P and R as {prefix}_p.npy and {prefix}_r.npy instead of solving
+    pub direct:bool,//4_7 evaluates by solving the linear system, ignoring both
+    pub gammas:Option<Vec<f64>>,//4_7_gamma discounts solved on one model
+    pub ranges:Option<Vec<i32>>,//bench state_range values
+    pub mmap:Option<String>,//4_7 value and policy on files in this directory
+    pub visit:Visit,//5_12 first or every visit of a (state, action) pair
+    pub estimator:Estimator,//5_12 importance sampling of the behavior returns
+    pub moves:Moves,//6_9 action set of the windy gridworld
+    pub stochastic_wind:bool,//6_9 wind varies by ±1, exercise 6.10
+    pub deterministic:bool,//byte-identical reruns, see deterministic.rs
+    pub list:bool,//prints the exercises of the registry instead of running one
+    pub describe:Option<String>,//prints the parameters of this index as json instead of running it
+}
+
+impl Args {
+    //usage: rl_exercise [index|figure id] [--seed N] [--env "command"] [--map file.txt] [--tui] [--output file.json] [--runs N] [--config file.json] [--f32] [--parallel] [--renormalize] [--q] [--tie last|current|smallest|lexicographic] [--diff summary|list|grid] [--init zero|reward|optimistic] [--cold] [--sweeps M] [--exact] [--direct] [--gammas G1,G2,...] [--ranges R1,R2,...] [--mmap dir] [--span EPS] [--dot file.dot] [--npy prefix] [--visit first|every] [--estimator ordinary|weighted|truncated] [--moves 4|8|9] [--stochastic-wind] [--deterministic] [--list] [--describe index]
+    pub fn parse<I:Iterator<Item = String>>(mut it:I) -> Result<Self, String> {
+        let mut args = Self { index:"6_9".to_string(), seed:None, env:None, map:None, tui:false, output:None, runs:None, config:None, f32:false, parallel:false, renormalize:false, q:false, tie:TieBreak::Last, diff:DiffReport::Grid, init:Init::Zero, cold:false, sweeps:None, exact:false, span:None, dot:None, npy:None, direct:false, gammas:None, ranges:None, mmap:None, visit:Visit::Every, estimator:Estimator::WeightedTruncated, moves:Moves::KingStay, stochastic_wind:false, deterministic:false, list:false, describe:None };
+        while let Some(a) = it.next() {
+            match a.as_str() {
+                "--seed" => {
+                    let v = it.next().ok_or("missing value for --seed")?;
+                    args.seed = Some(v.parse().map_err(|_| format!("invalid seed {}", v))?);
+                },
+                "--runs" => {
+                    let v = it.next().ok_or("missing value for --runs")?;
+                    args.runs = Some(v.parse().map_err(|_| format!("invalid runs {}", v))?);
+                },
+                "--map" => args.map = Some(it.next().ok_or("missing value for --map")?),
+                "--config" => args.config = Some(it.next().ok_or("missing value for --config")?),
+                "--tui" => args.tui = true,
+                "--f32" => args.f32 = true,
+                "--parallel" => args.parallel = true,
+                "--q" => args.q = true,
+                "--exact" => args.exact = true,
+                "--direct" => args.direct = true,
+                "--dot" => args.dot = Some(it.next().ok_or("missing value for --dot")?),
+                "--mmap" => args.mmap = Some(it.next().ok_or("missing value for --mmap")?),
+                "--npy" => args.npy = Some(it.next().ok_or("missing value for --npy")?),
+                "--estimator" => args.estimator = Estimator::parse(&it.next().ok_or("missing value for --estimator")?)?,
+                "--stochastic-wind" => args.stochastic_wind = true,
+                "--deterministic" => args.deterministic = true,
+                "--list" => args.list = true,
+                "--describe" => args.describe = Some(it.next().ok_or("missing value for --describe")?),
+                "--moves" => args.moves = Moves::parse(&it.next().ok_or("missing value for --moves")?)?,
+                "--visit" => args.visit = Visit::parse(&it.next().ok_or("missing value for --visit")?)?,
+                "--span" => {
+                    let v = it.next().ok_or("missing value for --span")?;
+                    args.span = Some(v.parse().map_err(|_| format!("invalid span {}", v))?);
+                },
+                "--gammas" => {
+                    let v = it.next().ok_or("missing value for --gammas")?;
+                    let gammas:Result<Vec<f64>, _> = v.split(',').map(|g| g.trim().parse()).collect();
+                    args.gammas = Some(gammas.map_err(|_| format!("invalid gammas {}", v))?);
+                },
+                "--ranges" => {
+                    let v = it.next().ok_or("missing value for --ranges")?;
+                    let ranges:Result<Vec<i32>, _> = v.split(',').map(|r| r.trim().parse()).collect();
+                    args.ranges = Some(ranges.map_err(|_| format!("invalid ranges {}", v))?);
+                },
+                "--sweeps" => {
+                    let v = it.next().ok_or("missing value for --sweeps")?;
+                    args.sweeps = Some(v.parse().map_err(|_| format!("invalid sweeps {}", v))?);
+                },
+                "--tie" => args.tie = TieBreak::parse(&it.next().ok_or("missing value for --tie")?)?,
+                "--diff" => args.diff = DiffReport::parse(&it.next().ok_or("missing value for --diff")?)?,
+                "--init" => args.init = Init::parse(&it.next().ok_or("missing value for --init")?)?,
+                "--cold" => args.cold = true,
+                "--renormalize" => args.renormalize = true,
+                "--output" => args.output = Some(it.next().ok_or("missing value for --output")?),
+                "--env" => args.env = Some(it.next().ok_or("missing value for --env")?),
+                _ => args.index = a,
+            }
+        }
+        Ok(args)
+    }
+
+    //the car rental model and solver of the 4_7 flags, exercise 4.7 for everything else
+    pub fn car_rental(&self) -> exercise4_7::Params {
+        let defaults = exercise4_7::Params::default();
+        exercise4_7::Params {
+            max_iter:self.sweeps.unwrap_or(defaults.max_iter),
+            stopping:self.span.map_or(defaults.stopping, StoppingRule::Span),
+            evaluation:if self.direct { Evaluation::Direct } else if self.exact { Evaluation::Exact } else { Evaluation::Modified },
+            parallel:self.parallel,
+            tail:if self.renormalize { Tail::Renormalize } else { Tail::Lump },
+            action_value:self.q,
+            tie:self.tie,
+            init:self.init,
+            warm_start:!self.cold,
+            mmap:self.mmap.clone(),
+            ..defaults
+        }
+    }
+}
+
+//runs one exercise of the registry, the seed is printed first so the run can be repeated
+pub fn run(args:&Args) -> Result<(), Box<dyn Error>> {
+    if args.list {
+        print!("{}", registry::list());
+        return Ok(())
+    }
+    if let Some(index) = args.describe.as_deref() {
+        let schema = schema::describe(index).ok_or_else(|| format!("invalid index {}", index))?;
+        println!("{}", serde_json::to_string_pretty(&schema)?);
+        return Ok(())
+    }
+    let exercise = registry::find(&args.index).ok_or_else(|| format!("invalid index {}", args.index))?;
+    if args.deterministic { deterministic::enable() }
+    let seed = args.seed.unwrap_or_else(|| if args.deterministic { deterministic::SEED } else { rng::random_seed() });
+    println!("seed {}", seed);
+    println!("{}", exercise.title);
+    (exercise.run)(&registry::Context { seed, args })
+}
@@ -0,0 +1,71 @@
+use crate::afterstate::Afterstate;
+use crate::env::Environment;
+use crate::rng::SeededRng;
+
+type Vec2 = (i32, i32);
+
+const ACTION:[Vec2; 4] = [(0, 1), (1, 0), (0, -1), (-1, 0)];
+
+//example 6.6, start and goal at the bottom corners with the cliff between them,
+//stepping into it costs -100 and sends the agent back to the start
+pub struct World {
+    pub size:Vec2,
+    pub start:Vec2,
+    pub goal:Vec2,
+    pub state:Vec2,
+}
+
+impl Default for World {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl World {
+    pub fn new() -> Self {
+        Self { size:(12, 4), start:(0, 0), goal:(11, 0), state:(0, 0) }
+    }
+
+    //the move before the cliff is checked
+    fn moved(&self, s:&Vec2, a:&Vec2) -> Vec2 {
+        ((s.0 + a.0).clamp(0, self.size.0 - 1), (s.1 + a.1).clamp(0, self.size.1 - 1))
+    }
+
+    fn is_cliff(&self, p:&Vec2) -> bool {
+        p.1 == 0 && p.0 > self.start.0 && p.0 < self.goal.0
+    }
+}
+
+impl Environment for World {
+    type State = Vec2;
+    type Action = Vec2;
+
+    fn reset(&mut self, _:&mut SeededRng) -> Vec2 {
+        self.state = self.start;
+        self.state
+    }
+
+    fn step(&mut self, a:&Vec2, _:&mut SeededRng) -> (Vec2, f64, bool) {
+        let p = self.moved(&self.state, a);
+        if self.is_cliff(&p) {
+            self.state = self.start;
+            return (self.state, -100.0, false)
+        }
+        self.state = p;
+        (p, -1.0, p == self.goal)
+    }
+
+    fn actions(&self, _:&Vec2) -> Vec<Vec2> {
+        ACTION.to_vec()
+    }
+}
+
+//reward and next state only depend on the cell moved into, so entering it from any
+//neighbor or bumping a wall inside it share one value
+impl Afterstate for World {
+    type After = Vec2;
+
+    fn afterstate(&self, s:&Vec2, a:&Vec2) -> Vec2 {
+        self.moved(s, a)
+    }
+}
@@ -0,0 +1,81 @@
+use crate::vfa::{ FeatureVec, Features };
+
+//coarse coding and radial basis functions over continuous states, sections 9.5.3 and 9.5.5.
+//both put one feature at each center, the receptive field width sets how far an update generalizes
+
+//binary features, on when the state is within radius of the center (circles, spheres or intervals)
+pub struct CoarseCoding {
+    pub center:Vec<Vec<f64>>,
+    pub radius:f64,
+}
+
+//gaussian features exp(-|s - c|^2 / 2σ^2). normalized ones sum to 1, so every state keeps
+//the same total activation even between the centers
+pub struct Rbf {
+    pub center:Vec<Vec<f64>>,
+    pub sigma:f64,
+    pub normalize:bool,
+}
+
+//count[d] centers evenly from lo[d] to hi[d] in every dimension, the first dimension fastest
+pub fn grid(lo:&[f64], hi:&[f64], count:&[usize]) -> Vec<Vec<f64>> {
+    assert!(lo.len() == hi.len() && lo.len() == count.len());
+    let mut center = vec!(Vec::new());
+    for d in 0..lo.len() {
+        let step = if count[d] > 1 { (hi[d] - lo[d]) / (count[d] - 1) as f64 } else { 0.0 };
+        center = (0..count[d])
+            .flat_map(|k| center.iter().map(move |c:&Vec<f64>| {
+                let mut c = c.clone();
+                c.push(lo[d] + step * k as f64);
+                c
+            }))
+            .collect();
+    }
+    center
+}
+
+fn distance2(a:&[f64], b:&[f64]) -> f64 {
+    assert_eq!(a.len(), b.len());
+    a.iter().zip(b.iter()).map(|(a, b)| (a - b).powi(2)).sum::<f64>()
+}
+
+impl CoarseCoding {
+    pub fn new(center:Vec<Vec<f64>>, radius:f64) -> Self {
+        Self { center, radius }
+    }
+}
+
+impl Rbf {
+    pub fn new(center:Vec<Vec<f64>>, sigma:f64, normalize:bool) -> Self {
+        Self { center, sigma, normalize }
+    }
+}
+
+impl Features<Vec<f64>> for CoarseCoding {
+    fn dim(&self) -> usize {
+        self.center.len()
+    }
+
+    fn features(&self, s:&Vec<f64>) -> FeatureVec {
+        let r2 = self.radius * self.radius;
+        self.center.iter().enumerate()
+            .filter(|(_, c)| distance2(s, c) <= r2)
+            .map(|(k, _)| (k, 1.0))
+            .collect()
+    }
+}
+
+impl Features<Vec<f64>> for Rbf {
+    fn dim(&self) -> usize {
+        self.center.len()
+    }
+
+    //every center, none of them is ever exactly 0
+    fn features(&self, s:&Vec<f64>) -> FeatureVec {
+        let x:Vec<f64> = self.center.iter()
+            .map(|c| (-distance2(s, c) / (2.0 * self.sigma * self.sigma)).exp())
+            .collect();
+        let total = if self.normalize { x.iter().sum::<f64>() } else { 1.0 };
+        x.into_iter().enumerate().map(|(k, x)| (k, x / total)).collect()
+    }
+}
@@ -0,0 +1,195 @@
+use std::error::Error;
+use std::fs::File;
+use std::hash::Hash;
+use std::io::Write;
+use plotters::{prelude::*, coord::Shift};
+
+use crate::cliff_walking;
+use crate::env::Environment;
+use crate::experiment::{ Curve, Experiment };
+use crate::nstep::{ self, NStepControl, Sigma };
+use crate::policy::Behavior;
+use crate::rng::SeededRng;
+use crate::schedules::Schedule;
+use crate::td::{ Method, TdControl };
+
+//α takes any Schedule, copied into every agent built
+pub struct ControlInfo<A = f64> {
+    pub max_episode:usize,
+    pub max_step:usize,
+    pub behavior:Behavior,
+    pub alpha:A,
+    pub gamma:f64,
+}
+
+//one method's learning curves over the runs of an experiment
+pub struct Comparison {
+    pub label:String,
+    pub reward:Curve,//sum of rewards per episode
+    pub steps:Curve,
+}
+
+//learning curves of one agent built fresh for every run, episode runs it along one episode
+fn comparison<E, F, T, G, R, A>(label:&str, make_env:&mut F, mut make_agent:G, mut episode:R, c_info:&ControlInfo<A>, experiment:&Experiment) -> Comparison
+    where E:Environment, F:FnMut() -> E, G:FnMut() -> T, R:FnMut(&mut T, &mut E, &mut SeededRng) -> (f64, usize) {
+    let mut series = experiment.repeat_series(2, |rng| {
+        let mut env = make_env();
+        let mut agent = make_agent();
+        let (reward, steps):(Vec<f64>, Vec<f64>) = (0..c_info.max_episode)
+            .map(|_| {
+                let (g, step) = episode(&mut agent, &mut env, rng);
+                (g, step as f64)
+            })
+            .unzip();
+        vec!(reward, steps)
+    });
+    let steps = series.pop().unwrap();
+    let reward = series.pop().unwrap();
+    Comparison { label:label.to_string(), reward, steps }
+}
+
+//every method is repeated over the same seeds, so they only differ by their update
+pub fn curves<E, F, A:Schedule + Copy>(mut make_env:F, method:&[Method], c_info:&ControlInfo<A>, experiment:&Experiment) -> Vec<Comparison>
+    where E:Environment, E::State:Eq + Hash, F:FnMut() -> E {
+    method.iter().map(|m| {
+        comparison(m.label(), &mut make_env,
+            || TdControl::new(*m, c_info.behavior, 0.0, c_info.gamma).with_alpha(c_info.alpha),
+            |agent, env, rng| agent.episode(env, rng, c_info.max_step),
+            c_info, experiment)
+    }).collect()
+}
+
+//same for n-step methods, each with its n
+pub fn nstep_curves<E, F, A:Schedule + Copy>(mut make_env:F, method:&[(nstep::Method, usize)], c_info:&ControlInfo<A>, experiment:&Experiment) -> Vec<Comparison>
+    where E:Environment, E::State:Eq + Hash, F:FnMut() -> E {
+    method.iter().map(|(m, n)| {
+        comparison(&format!("{} n={}", m.label(), n), &mut make_env,
+            || NStepControl::new(*m, *n, c_info.behavior, 0.0, c_info.gamma).with_alpha(c_info.alpha),
+            |agent, env, rng| agent.episode(env, rng, c_info.max_step),
+            c_info, experiment)
+    }).collect()
+}
+
+//episode column followed by mean and standard error of every curve
+pub fn write_csv(path:&str, curve:&[Comparison]) -> std::io::Result<()> {
+    let mut f = File::create(path)?;
+    let header:Vec<String> = curve.iter()
+        .map(|c| format!("{0} reward,{0} reward se,{0} steps,{0} steps se", c.label))
+        .collect();
+    writeln!(f, "episode,{}", header.join(","))?;
+    for k in 0..curve[0].reward.len() {
+        let row:Vec<String> = curve.iter()
+            .map(|c| format!("{},{},{},{}", c.reward.mean[k], c.reward.stderr[k], c.steps.mean[k], c.steps.stderr[k]))
+            .collect();
+        writeln!(f, "{},{}", k, row.join(","))?;
+    }
+    Ok(())
+}
+
+//mean with its 95% interval shaded
+fn draw_curve(curve:&[Comparison], canvas:&DrawingArea<BitMapBackend, Shift>) -> Result<(), Box<dyn Error>> {
+    let n = curve[0].reward.len();
+    let mut chart = ChartBuilder::on(canvas)
+        .caption(format!("sum of rewards during episode, {} runs", curve[0].reward.runs), ("sans-serif", 30))
+        .margin(5)
+        .x_label_area_size(50)
+        .y_label_area_size(50)
+        .build_cartesian_2d(0..n, -100.0..0.0)?;
+    chart.configure_mesh().draw()?;
+    for (k, c) in curve.iter().enumerate() {
+        let color = Palette99::pick(k).to_rgba();
+        let band:Vec<(usize, f64)> = (0..n).map(|i| (i, c.reward.ci95(i).1))
+            .chain((0..n).rev().map(|i| (i, c.reward.ci95(i).0)))
+            .collect();
+        chart.draw_series(std::iter::once(Polygon::new(band, color.mix(0.2).filled())))?;
+        chart.draw_series(LineSeries::new(c.reward.mean.iter().cloned().enumerate(), color))?
+            .label(c.label.as_str())
+            .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], color));
+    }
+    chart
+        .configure_series_labels()
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
+        .draw()?;
+    Ok(())
+}
+
+//example 6.6 / figure 6.4, cliff walking with Ɛ=0.1 and α=0.5
+pub fn run(seed:u64, runs:Option<usize>) -> Result<(), Box<dyn Error>> {
+    let c_info = ControlInfo {
+        max_episode:500, max_step:100000,
+        behavior:Behavior::EpsilonGreedy(0.1), alpha:0.5, gamma:1.0,
+    };
+    let experiment = Experiment::new(runs.unwrap_or(100), seed);
+    let method = [Method::Sarsa, Method::QLearning, Method::ExpectedSarsa];
+    let curve = curves(cliff_walking::World::new, &method, &c_info, &experiment);
+    for c in curve.iter() {
+        let last = c.reward.len() - 1;
+        let (lo, hi) = c.reward.ci95(last);
+        println!("{}: last 100 episodes {:.2}, final episode {:.2} [{:.2}, {:.2}], steps {:.1}",
+            c.label, c.reward.tail(100), c.reward.mean[last], lo, hi, c.steps.tail(100));
+    }
+    write_csv("compare.csv", &curve)?;
+    let canvas = BitMapBackend::new("compare.png", (1440, 1440)).into_drawing_area();
+    canvas.fill(&WHITE)?;
+    draw_curve(&curve, &canvas)?;
+    canvas.present()?;
+    Ok(())
+}
+
+//cliff walking again, n-step SARSA against tree backup learning the greedy policy off-policy
+pub fn run_nstep(seed:u64, runs:Option<usize>) -> Result<(), Box<dyn Error>> {
+    let c_info = ControlInfo {
+        max_episode:500, max_step:100000,
+        behavior:Behavior::EpsilonGreedy(0.1), alpha:0.2, gamma:1.0,
+    };
+    let experiment = Experiment::new(runs.unwrap_or(100), seed);
+    let method = [
+        (nstep::Method::Sarsa, 1), (nstep::Method::Sarsa, 4),
+        (nstep::Method::TreeBackup, 1), (nstep::Method::TreeBackup, 4),
+    ];
+    let curve = nstep_curves(cliff_walking::World::new, &method, &c_info, &experiment);
+    for c in curve.iter() {
+        println!("{}: last 100 episodes {:.2}, steps {:.1}", c.label, c.reward.tail(100), c.steps.tail(100));
+    }
+    write_csv("compare_nstep.csv", &curve)?;
+    let canvas = BitMapBackend::new("compare_nstep.png", (1440, 1440)).into_drawing_area();
+    canvas.fill(&WHITE)?;
+    draw_curve(&curve, &canvas)?;
+    canvas.present()?;
+    Ok(())
+}
+
+//cliff walking with Q(σ) at n=3 learning the ε-greedy behavior itself, section 7.6. σ=1 is
+//sampled steps with the expectation as control variate, σ=0 is tree backup, the rest move
+//between them per step or per episode
+pub fn run_qsigma(seed:u64, runs:Option<usize>) -> Result<(), Box<dyn Error>> {
+    let c_info = ControlInfo {
+        max_episode:500, max_step:100000,
+        behavior:Behavior::EpsilonGreedy(0.1), alpha:0.2, gamma:1.0,
+    };
+    let experiment = Experiment::new(runs.unwrap_or(100), seed);
+    let n = 3;
+    let sigma = [
+        Sigma::Constant(1.0), Sigma::Constant(0.5), Sigma::Constant(0.0),
+        Sigma::Alternate, Sigma::Random(0.5), Sigma::Decay(0.95),
+    ];
+    let mut make_env = cliff_walking::World::new;
+    let curve:Vec<Comparison> = sigma.iter().map(|s| {
+        let method = nstep::Method::QSigma(*s);
+        comparison(&format!("{} n={}", method.label(), n), &mut make_env,
+            || NStepControl { target:c_info.behavior, ..NStepControl::new(method, n, c_info.behavior, 0.0, c_info.gamma) }.with_alpha(c_info.alpha),
+            |agent, env, rng| agent.episode(env, rng, c_info.max_step),
+            &c_info, &experiment)
+    }).collect();
+    for c in curve.iter() {
+        println!("{}: first 50 episodes {:.2}, last 100 episodes {:.2}, steps {:.1}",
+            c.label, c.reward.mean[..50].iter().sum::<f64>() / 50.0, c.reward.tail(100), c.steps.tail(100));
+    }
+    write_csv("compare_qsigma.csv", &curve)?;
+    let canvas = BitMapBackend::new("compare_qsigma.png", (1440, 1440)).into_drawing_area();
+    canvas.fill(&WHITE)?;
+    draw_curve(&curve, &canvas)?;
+    canvas.present()?;
+    Ok(())
+}
@@ -0,0 +1,26 @@
+use std::sync::atomic::{ AtomicBool, Ordering };
+use std::time::Instant;
+
+//--deterministic, two runs with the same arguments write the same bytes: one root seed,
+//a single rayon thread and no wall-clock readings in the output
+static ON:AtomicBool = AtomicBool::new(false);
+
+//root seed when --seed is not given
+pub const SEED:u64 = 0;
+
+//once, before any parallel work. parallel::map_index already collects in index order, the
+//single thread also pins the order of everything else run on the pool
+pub fn enable() {
+    ON.store(true, Ordering::Relaxed);
+    #[cfg(not(target_arch = "wasm32"))]
+    let _ = rayon::ThreadPoolBuilder::new().num_threads(1).build_global();
+}
+
+pub fn enabled() -> bool {
+    ON.load(Ordering::Relaxed)
+}
+
+//seconds since t, always 0 when deterministic
+pub fn elapsed(t:&Instant) -> f64 {
+    if enabled() { 0.0 } else { t.elapsed().as_secs_f64() }
+}
@@ -0,0 +1,84 @@
+use rand::prelude::*;
+
+use crate::env::Environment;
+use crate::rng::SeededRng;
+use crate::schedules::Schedule;
+use crate::vfa::{ Features, LinearVfa };
+
+//differential semi-gradient SARSA of section 10.3 for continuing tasks. returns are measured
+//against the reward rate r̄, learned from the same TD error as q. α and ε are counted by steps,
+//a continuing task has no episodes
+pub struct DifferentialSarsa<F, A = f64, X = f64> {
+    pub q:LinearVfa<F>,
+    pub epsilon:X,
+    pub alpha:A,
+    pub beta:f64,//reward rate step size
+    pub r_avg:f64,
+    pub steps:usize,
+}
+
+impl<F> DifferentialSarsa<F> {
+    pub fn new<S>(feature:F, epsilon:f64, alpha:f64, beta:f64) -> Self
+        where F:Features<S> {
+        Self { q:LinearVfa::new(feature), epsilon, alpha, beta, r_avg:0.0, steps:0 }
+    }
+}
+
+impl<F, A:Schedule, X:Schedule> DifferentialSarsa<F, A, X> {
+    pub fn with_alpha<B:Schedule>(self, alpha:B) -> DifferentialSarsa<F, B, X> {
+        let Self { q, epsilon, beta, r_avg, steps, .. } = self;
+        DifferentialSarsa { q, epsilon, alpha, beta, r_avg, steps }
+    }
+
+    pub fn with_epsilon<B:Schedule>(self, epsilon:B) -> DifferentialSarsa<F, A, B> {
+        let Self { q, alpha, beta, r_avg, steps, .. } = self;
+        DifferentialSarsa { q, epsilon, alpha, beta, r_avg, steps }
+    }
+
+    pub fn value<E:Environment>(&self, s:&E::State, a:&E::Action) -> f64
+        where F:Features<(E::State, E::Action)> {
+        self.q.value(&(s.clone(), a.clone()))
+    }
+
+    //ties go to the first of env.actions(s)
+    pub fn greedy<E:Environment>(&self, s:&E::State, env:&E) -> E::Action
+        where F:Features<(E::State, E::Action)> {
+        let q:Vec<(E::Action, f64)> = env.actions(s).into_iter().map(|a| { let q = self.value::<E>(s, &a); (a, q) }).collect();
+        q.iter().skip(1).fold(&q[0], |b, a| if a.1 > b.1 { a } else { b }).0.clone()
+    }
+
+    pub fn select<E:Environment>(&self, s:&E::State, env:&E, rng:&mut SeededRng) -> E::Action
+        where F:Features<(E::State, E::Action)> {
+        let r:f64 = rng.gen();
+        if r < self.epsilon.at(self.steps + 1) {
+            let allowed = env.actions(s);
+            return allowed[rng.gen_range(0..allowed.len())].clone()
+        }
+        self.greedy(s, env)
+    }
+
+    //δ = R - r̄ + q(S', A') - q(S, A)
+    pub fn learn<S:Clone, T:Clone>(&mut self, s:&S, a:&T, r:f64, ss:&S, aa:&T)
+        where F:Features<(S, T)> {
+        self.steps += 1;
+        let sa = (s.clone(), a.clone());
+        let delta = r - self.r_avg + self.q.value(&(ss.clone(), aa.clone())) - self.q.value(&sa);
+        self.r_avg += self.beta * delta;
+        self.q.update(&sa, self.alpha.at(self.steps), delta);
+    }
+
+    //continues from reset for max_step steps, calls observe with every reward
+    pub fn run<E:Environment, O:FnMut(f64)>(&mut self, env:&mut E, max_step:usize, rng:&mut SeededRng, mut observe:O)
+        where F:Features<(E::State, E::Action)> {
+        let mut s = env.reset(rng);
+        let mut a = self.select(&s, env, rng);
+        for _ in 0..max_step {
+            let (ss, r, _) = env.step(&a, rng);
+            let aa = self.select(&ss, env, rng);
+            self.learn(&s, &a, r, &ss, &aa);
+            observe(r);
+            s = ss;
+            a = aa;
+        }
+    }
+}
@@ -0,0 +1,281 @@
+use std::error::Error;
+use plotters::{prelude::*, coord::Shift};
+use rand::prelude::*;
+
+use crate::cart_pole;
+use crate::env::Environment;
+use crate::mountain_car;
+use crate::policy::Behavior;
+use crate::progress;
+use crate::replay::{ Experience, Replay };
+use crate::rng::{ self, SeededRng };
+use crate::schedules::{ Exponential, Schedule };
+use crate::vfa::{ QFunction, ValueFunction };
+
+//deep Q-network (Mnih et al. 2015): Q-learning with a neural network over observation vectors,
+//updated from minibatches of a replay memory against a target network copied every sync steps.
+//the network is a small fully connected one written out here, enough for the classic control tasks.
+//burn or candle would be the natural backend, but neither is in the vendored crates this builds
+//offline against, so Mlp and Adam stand in for them behind the same QFunction interface
+
+//ReLU between the layers, the last one linear with one output per action
+pub struct Mlp {
+    pub size:Vec<usize>,//inputs, hidden widths, outputs
+    pub param:Vec<f64>,//per layer the weights out × in row by row, then the biases
+}
+
+pub struct Adam {
+    pub rate:f64,
+    pub beta:(f64, f64),
+    m:Vec<f64>,
+    v:Vec<f64>,
+    t:i32,
+}
+
+pub struct Info {
+    pub hidden:Vec<usize>,
+    pub gamma:f64,
+    pub rate:f64,//adam step size
+    pub capacity:usize,//replay memory
+    pub batch:usize,
+    pub learn_start:usize,//steps of experience before the first update
+    pub sync:usize,//steps between copies to the target network
+}
+
+//actions are indices into env.actions, fixed for every state
+pub struct Dqn<X = f64> {
+    pub online:Mlp,
+    pub target:Mlp,
+    pub optimizer:Adam,
+    pub replay:Replay<Experience<Vec<f64>, usize>>,
+    pub epsilon:X,//over steps taken
+    pub info:Info,
+    pub steps:usize,
+}
+
+impl Default for Info {
+    fn default() -> Self {
+        Self { hidden:vec!(64, 64), gamma:0.99, rate:1e-3, capacity:50000, batch:32, learn_start:1000, sync:500 }
+    }
+}
+
+impl Mlp {
+    //he-uniform weights, zero biases
+    pub fn new(size:&[usize], rng:&mut SeededRng) -> Self {
+        let mut param = Vec::new();
+        for w in size.windows(2) {
+            let bound = (6.0 / w[0] as f64).sqrt();
+            param.extend((0..w[0] * w[1]).map(|_| rng.gen_range(-bound..bound)));
+            param.extend(std::iter::repeat_n(0.0, w[1]));
+        }
+        Self { size:size.to_vec(), param }
+    }
+
+    //(offset, inputs, outputs) per layer
+    fn layers(&self) -> Vec<(usize, usize, usize)> {
+        let mut offset = 0;
+        self.size.windows(2).map(|w| {
+            let l = (offset, w[0], w[1]);
+            offset += w[1] * (w[0] + 1);
+            l
+        }).collect()
+    }
+
+    pub fn forward(&self, x:&[f64]) -> Vec<f64> {
+        self.activations(x).pop().unwrap()
+    }
+
+    //the input and every layer's output
+    fn activations(&self, x:&[f64]) -> Vec<Vec<f64>> {
+        let layer = self.layers();
+        let mut a = vec!(x.to_vec());
+        for (k, (offset, n, out)) in layer.iter().cloned().enumerate() {
+            let (w, b) = self.param[offset..offset + out * (n + 1)].split_at(out * n);
+            let x = &a[k];
+            let y = (0..out).map(|j| {
+                let v = b[j] + w[j * n..(j + 1) * n].iter().zip(x.iter()).map(|(w, x)| w * x).sum::<f64>();
+                if k + 1 < layer.len() { v.max(0.0) } else { v }
+            }).collect();
+            a.push(y);
+        }
+        a
+    }
+
+    //adds to grad the gradient of Σ d_j output_j at the input of a
+    fn backward(&self, a:&[Vec<f64>], mut d:Vec<f64>, grad:&mut [f64]) {
+        for (k, (offset, n, out)) in self.layers().into_iter().enumerate().rev() {
+            let x = &a[k];
+            let mut dx = vec![0.0; n];
+            for (j, dj) in d.iter().enumerate().filter(|(_, dj)| **dj != 0.0) {
+                let row = offset + j * n;
+                for m in 0..n {
+                    grad[row + m] += dj * x[m];
+                    dx[m] += dj * self.param[row + m];
+                }
+                grad[offset + out * n + j] += dj;
+            }
+            //through the ReLU that made x
+            if k > 0 {
+                dx.iter_mut().zip(x.iter()).filter(|(_, x)| **x <= 0.0).for_each(|(d, _)| *d = 0.0);
+            }
+            d = dx;
+        }
+    }
+}
+
+//the network as a plain approximator, stepped by sgd. as a v the output is the only one,
+//as a q the actions are output indices
+impl ValueFunction<Vec<f64>> for Mlp {
+    fn predict(&self, s:&Vec<f64>) -> f64 {
+        self.forward(s)[0]
+    }
+
+    fn gradient_step(&mut self, s:&Vec<f64>, step:f64, error:f64) {
+        QFunction::gradient_step(self, s, &0, step, error)
+    }
+}
+
+impl QFunction<Vec<f64>, usize> for Mlp {
+    fn predict(&self, s:&Vec<f64>, a:&usize) -> f64 {
+        self.forward(s)[*a]
+    }
+
+    fn gradient_step(&mut self, s:&Vec<f64>, a:&usize, step:f64, error:f64) {
+        let act = self.activations(s);
+        let mut d = vec![0.0; self.size[self.size.len() - 1]];
+        d[*a] = 1.0;
+        let mut grad = vec![0.0; self.param.len()];
+        self.backward(&act, d, &mut grad);
+        self.param.iter_mut().zip(grad).for_each(|(w, g)| *w += step * error * g);
+    }
+}
+
+impl Adam {
+    pub fn new(rate:f64, n:usize) -> Self {
+        Self { rate, beta:(0.9, 0.999), m:vec![0.0; n], v:vec![0.0; n], t:0 }
+    }
+
+    pub fn step(&mut self, param:&mut [f64], grad:&[f64]) {
+        self.t += 1;
+        let (b1, b2) = self.beta;
+        let (c1, c2) = (1.0 - b1.powi(self.t), 1.0 - b2.powi(self.t));
+        for k in 0..param.len() {
+            self.m[k] = b1 * self.m[k] + (1.0 - b1) * grad[k];
+            self.v[k] = b2 * self.v[k] + (1.0 - b2) * grad[k] * grad[k];
+            param[k] -= self.rate * (self.m[k] / c1) / ((self.v[k] / c2).sqrt() + 1e-8);
+        }
+    }
+}
+
+impl<X:Schedule> Dqn<X> {
+    pub fn new(inputs:usize, actions:usize, epsilon:X, info:Info, rng:&mut SeededRng) -> Self {
+        let size:Vec<usize> = std::iter::once(inputs).chain(info.hidden.iter().cloned()).chain(std::iter::once(actions)).collect();
+        let online = Mlp::new(&size, rng);
+        let target = Mlp { size, param:online.param.clone() };
+        let optimizer = Adam::new(info.rate, online.param.len());
+        Self { online, target, optimizer, replay:Replay::new(info.capacity), epsilon, info, steps:0 }
+    }
+
+    pub fn q(&self, s:&[f64]) -> Vec<f64> {
+        self.online.forward(s)
+    }
+
+    fn select(&self, s:&[f64], rng:&mut SeededRng) -> usize {
+        let q = self.q(s);
+        Behavior::EpsilonGreedy(self.epsilon.at(self.steps)).sample(&q, &vec![true; q.len()], rng)
+    }
+
+    //one adam step on the mean huber loss of a minibatch, the td error clipped to [-1, 1]
+    fn learn(&mut self, rng:&mut SeededRng) {
+        let slot = self.replay.sample(self.info.batch, rng);
+        let mut grad = vec![0.0; self.online.param.len()];
+        for k in slot.iter() {
+            let e = self.replay.get(*k);
+            let next = if e.done { 0.0 } else { self.target.forward(&e.next).into_iter().fold(f64::NEG_INFINITY, f64::max) };
+            let a = self.online.activations(&e.state);
+            let delta = a[a.len() - 1][e.action] - (e.reward + self.info.gamma * next);
+            let mut d = vec![0.0; a[a.len() - 1].len()];
+            d[e.action] = delta.clamp(-1.0, 1.0) / slot.len() as f64;
+            self.online.backward(&a, d, &mut grad);
+        }
+        self.optimizer.step(&mut self.online.param, &grad);
+    }
+
+    //return and steps, cut after max_step without ending the episode
+    pub fn episode<E:Environment<State = Vec<f64>>>(&mut self, env:&mut E, rng:&mut SeededRng, max_step:usize) -> (f64, usize) {
+        let mut s = env.reset(rng);
+        let mut g = 0.0;
+        let mut step = 0;
+        while step < max_step {
+            step += 1;
+            self.steps += 1;
+            let a = self.select(&s, rng);
+            let action = env.actions(&s)[a].clone();
+            let (ss, r, done) = env.step(&action, rng);
+            g += r;
+            self.replay.push(Experience { state:s, action:a, reward:r, next:ss.clone(), done });
+            if self.steps >= self.info.learn_start { self.learn(rng) }
+            if self.steps.is_multiple_of(self.info.sync) { self.target.param.clone_from(&self.online.param) }
+            if done { break }
+            s = ss;
+        }
+        (g, step)
+    }
+}
+
+fn draw_return(title:&str, g:&[f64], canvas:&DrawingArea<BitMapBackend, Shift>) -> Result<(), Box<dyn Error>> {
+    let lo = g.iter().cloned().fold(f64::INFINITY, f64::min);
+    let hi = g.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    let mut chart = ChartBuilder::on(canvas)
+        .caption(title, ("sans-serif", 30))
+        .margin(5)
+        .x_label_area_size(50)
+        .y_label_area_size(50)
+        .build_cartesian_2d(0..g.len(), lo..hi + 1.0)?;
+    chart.configure_mesh().x_desc("episode").y_desc("return").draw()?;
+    chart.draw_series(LineSeries::new(g.iter().cloned().enumerate(), &BLUE))?;
+    //mean of the last 20
+    let mean:Vec<(usize, f64)> = (0..g.len()).map(|k| {
+        let w = &g[k.saturating_sub(19)..=k];
+        (k, w.iter().sum::<f64>() / w.len() as f64)
+    }).collect();
+    chart.draw_series(LineSeries::new(mean, RED.stroke_width(2)))?;
+    Ok(())
+}
+
+//return per episode of a fresh agent on cart pole (500 steps at most) and mountain car
+//(1000 steps at most), drawn to dqn.png
+pub fn run(seed:u64) -> Result<(), Box<dyn Error>> {
+    let mut rng = rng::from_seed(seed);
+    let epsilon = || Exponential { start:1.0, rate:0.999, floor:0.05 };
+    let canvas = BitMapBackend::new("dqn.png", (1600, 600)).into_drawing_area();
+    canvas.fill(&WHITE)?;
+    let canvas_split = canvas.split_evenly((1, 2));
+
+    let episodes = 300;
+    let mut env = cart_pole::World::new().map_state(|s:&[f64; 4]| s.to_vec());
+    let mut agent = Dqn::new(4, 2, epsilon(), Info::default(), &mut rng);
+    let pb = progress::counter(episodes as u64, "cart pole");
+    let g:Vec<f64> = (0..episodes).map(|_| {
+        pb.inc(1);
+        agent.episode(&mut env, &mut rng, 500).0
+    }).collect();
+    pb.finish_and_clear();
+    println!("cart pole, mean return of the last 20 episodes {:.1}", g[episodes - 20..].iter().sum::<f64>() / 20.0);
+    draw_return("cart pole", &g, &canvas_split[0])?;
+
+    let episodes = 100;
+    //position and velocity scaled to about [-1, 1]
+    let mut env = mountain_car::World::new().map_state(|s:&(f64, f64)| vec!((s.0 + 0.35) / 0.85, s.1 / 0.07));
+    let mut agent = Dqn::new(2, 3, Exponential { rate:0.9999, ..epsilon() }, Info::default(), &mut rng);
+    let pb = progress::counter(episodes as u64, "mountain car");
+    let g:Vec<f64> = (0..episodes).map(|_| {
+        pb.inc(1);
+        agent.episode(&mut env, &mut rng, 1000).0
+    }).collect();
+    pb.finish_and_clear();
+    println!("mountain car, mean return of the last 20 episodes {:.1}", g[episodes - 20..].iter().sum::<f64>() / 20.0);
+    draw_return("mountain car", &g, &canvas_split[1])?;
+    canvas.present()?;
+    Ok(())
+}
@@ -0,0 +1,307 @@
+use std::cmp::Ordering;
+use std::collections::{ BinaryHeap, HashMap };
+use std::hash::Hash;
+use rand::prelude::*;
+
+use crate::env::Environment;
+use crate::policy::Behavior;
+use crate::rng::SeededRng;
+use crate::schedules::Schedule;
+
+//tabular Dyna-Q of section 8.2: one-step Q-learning on every real step, the last outcome of
+//each (s, a) remembered as a deterministic model, then planning updates from pairs drawn from
+//the model. with kappa > 0 it is Dyna-Q+ of section 8.3, planning rewards get κ √τ for the τ
+//steps since the pair was last tried, and untried actions of a seen state are modelled as
+//staying put with reward 0. α is counted by the updates of each pair, ε by real steps
+pub struct DynaQ<S, A = f64, X = f64> {
+    pub q:HashMap<S, Vec<f64>>,
+    pub actions:usize,//indices into env.actions, the same number in every state
+    pub alpha:A,
+    pub gamma:f64,
+    pub epsilon:X,
+    pub planning:usize,//updates from the model per real step
+    pub kappa:f64,
+    pub time:usize,//real steps so far
+    pub updates:usize,//of q, real and planned
+    visits:HashMap<(S, usize), usize>,//updates of each pair
+    model:HashMap<(S, usize), Outcome<S>>,
+    key:Vec<(S, usize)>,//of model in the order first seen, what planning draws from
+}
+
+//prioritized sweeping of section 8.4 for a deterministic model: a real step only queues its
+//pair by |δ|, planning updates the most urgent pairs and queues the predecessors of each
+//updated state whose |δ| exceeds theta. no q update happens outside planning. α and ε are
+//counted as in DynaQ
+pub struct PrioritizedSweeping<S, A = f64, X = f64> {
+    pub q:HashMap<S, Vec<f64>>,
+    pub actions:usize,
+    pub alpha:A,
+    pub gamma:f64,
+    pub epsilon:X,
+    pub planning:usize,//queue pops per real step at most
+    pub theta:f64,
+    pub time:usize,//real steps so far
+    pub updates:usize,
+    visits:HashMap<(S, usize), usize>,
+    model:HashMap<(S, usize), Outcome<S>>,
+    predecessor:HashMap<S, Vec<(S, usize)>>,//pairs seen leading to the state
+    queue:BinaryHeap<Queued<S>>,
+    queued:HashMap<(S, usize), f64>,//priority of the live entry, older ones are skipped when popped
+    pushed:usize,
+}
+
+//a model-based agent stepped by episode
+pub trait Planner<S> {
+    fn q(&self) -> &HashMap<S, Vec<f64>>;
+    fn updates(&self) -> usize;
+    fn select(&mut self, s:&S, rng:&mut SeededRng) -> usize;
+    fn learn(&mut self, s:&S, a:usize, r:f64, ss:&S, done:bool, rng:&mut SeededRng);
+}
+
+//largest priority first, then the earliest pushed
+struct Queued<S> {
+    priority:f64,
+    order:usize,
+    key:(S, usize),
+}
+
+struct Outcome<S> {
+    reward:f64,
+    next:S,
+    done:bool,
+    tried:usize,//time of the last real step from the pair
+}
+
+impl<S:Clone + Eq + Hash> DynaQ<S> {
+    pub fn new(actions:usize, alpha:f64, gamma:f64, epsilon:f64, planning:usize, kappa:f64) -> Self {
+        Self {
+            q:HashMap::new(), actions, alpha, gamma, epsilon, planning, kappa, time:0, updates:0,
+            visits:HashMap::new(), model:HashMap::new(), key:Vec::new(),
+        }
+    }
+}
+
+impl<S:Clone + Eq + Hash, A:Schedule, X:Schedule> DynaQ<S, A, X> {
+    pub fn with_alpha<B:Schedule>(self, alpha:B) -> DynaQ<S, B, X> {
+        let Self { q, actions, gamma, epsilon, planning, kappa, time, updates, visits, model, key, .. } = self;
+        DynaQ { q, actions, alpha, gamma, epsilon, planning, kappa, time, updates, visits, model, key }
+    }
+
+    pub fn with_epsilon<B:Schedule>(self, epsilon:B) -> DynaQ<S, A, B> {
+        let Self { q, actions, alpha, gamma, planning, kappa, time, updates, visits, model, key, .. } = self;
+        DynaQ { q, actions, alpha, gamma, epsilon, planning, kappa, time, updates, visits, model, key }
+    }
+
+    fn entry(&mut self, s:&S) -> &mut Vec<f64> {
+        let n = self.actions;
+        self.q.entry(s.clone()).or_insert_with(|| vec![0.0; n])
+    }
+
+    //ε-greedy, ties broken at random
+    pub fn select(&mut self, s:&S, rng:&mut SeededRng) -> usize {
+        let epsilon = self.epsilon.at(self.time + 1);
+        let q = self.entry(s);
+        Behavior::EpsilonGreedy(epsilon).sample(q, &vec![true; q.len()], rng)
+    }
+
+    fn update(&mut self, s:&S, a:usize, r:f64, ss:&S, done:bool) {
+        let next = if done { 0.0 } else { self.entry(ss).iter().cloned().fold(f64::NEG_INFINITY, f64::max) };
+        let (alpha, gamma) = (step_size(&self.alpha, &mut self.visits, s, a), self.gamma);
+        let q = &mut self.entry(s)[a];
+        *q += alpha * (r + gamma * next - *q);
+        self.updates += 1;
+    }
+
+    fn remember(&mut self, s:&S, a:usize, outcome:Outcome<S>) {
+        if self.model.insert((s.clone(), a), outcome).is_none() {
+            self.key.push((s.clone(), a));
+        }
+    }
+
+    //one real step (s, a, r, s') and the planning that follows it
+    pub fn learn(&mut self, s:&S, a:usize, r:f64, ss:&S, done:bool, rng:&mut SeededRng) {
+        self.time += 1;
+        self.update(s, a, r, ss, done);
+        if self.kappa > 0.0 {
+            for b in 0..self.actions {
+                if !self.model.contains_key(&(s.clone(), b)) {
+                    self.remember(s, b, Outcome { reward:0.0, next:s.clone(), done:false, tried:0 });
+                }
+            }
+        }
+        self.remember(s, a, Outcome { reward:r, next:ss.clone(), done, tried:self.time });
+        for _ in 0..self.planning {
+            let (s, a) = self.key[rng.gen_range(0..self.key.len())].clone();
+            let m = &self.model[&(s.clone(), a)];
+            let r = m.reward + self.kappa * ((self.time - m.tried) as f64).sqrt();
+            let (ss, done) = (m.next.clone(), m.done);
+            self.update(&s, a, r, &ss, done);
+        }
+    }
+
+    //steps real steps from reset, starting over after every terminal. observe gets each reward
+    pub fn run<E:Environment<State = S>, O:FnMut(f64)>(&mut self, env:&mut E, steps:usize, rng:&mut SeededRng, mut observe:O) {
+        let mut s = env.reset(rng);
+        for _ in 0..steps {
+            let a = self.select(&s, rng);
+            let action = env.actions(&s)[a].clone();
+            let (ss, r, done) = env.step(&action, rng);
+            self.learn(&s, a, r, &ss, done, rng);
+            observe(r);
+            s = if done { env.reset(rng) } else { ss };
+        }
+    }
+}
+
+impl<S> PartialEq for Queued<S> {
+    fn eq(&self, other:&Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<S> Eq for Queued<S> {}
+
+impl<S> PartialOrd for Queued<S> {
+    fn partial_cmp(&self, other:&Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<S> Ord for Queued<S> {
+    fn cmp(&self, other:&Self) -> Ordering {
+        self.priority.total_cmp(&other.priority).then(other.order.cmp(&self.order))
+    }
+}
+
+impl<S:Clone + Eq + Hash> PrioritizedSweeping<S> {
+    pub fn new(actions:usize, alpha:f64, gamma:f64, epsilon:f64, planning:usize, theta:f64) -> Self {
+        Self {
+            q:HashMap::new(), actions, alpha, gamma, epsilon, planning, theta, time:0, updates:0, visits:HashMap::new(),
+            model:HashMap::new(), predecessor:HashMap::new(), queue:BinaryHeap::new(), queued:HashMap::new(), pushed:0,
+        }
+    }
+}
+
+impl<S:Clone + Eq + Hash, A:Schedule, X:Schedule> PrioritizedSweeping<S, A, X> {
+    pub fn with_alpha<B:Schedule>(self, alpha:B) -> PrioritizedSweeping<S, B, X> {
+        let Self { q, actions, gamma, epsilon, planning, theta, time, updates, visits, model, predecessor, queue, queued, pushed, .. } = self;
+        PrioritizedSweeping { q, actions, alpha, gamma, epsilon, planning, theta, time, updates, visits, model, predecessor, queue, queued, pushed }
+    }
+
+    pub fn with_epsilon<B:Schedule>(self, epsilon:B) -> PrioritizedSweeping<S, A, B> {
+        let Self { q, actions, alpha, gamma, planning, theta, time, updates, visits, model, predecessor, queue, queued, pushed, .. } = self;
+        PrioritizedSweeping { q, actions, alpha, gamma, epsilon, planning, theta, time, updates, visits, model, predecessor, queue, queued, pushed }
+    }
+
+    fn entry(&mut self, s:&S) -> &mut Vec<f64> {
+        let n = self.actions;
+        self.q.entry(s.clone()).or_insert_with(|| vec![0.0; n])
+    }
+
+    //|δ| of (s, a) under the model
+    fn priority(&mut self, s:&S, a:usize) -> f64 {
+        let m = &self.model[&(s.clone(), a)];
+        let (r, ss, done) = (m.reward, m.next.clone(), m.done);
+        let next = if done { 0.0 } else { self.entry(&ss).iter().cloned().fold(f64::NEG_INFINITY, f64::max) };
+        let gamma = self.gamma;
+        (r + gamma * next - self.entry(s)[a]).abs()
+    }
+
+    //kept at the larger priority if already queued
+    fn push(&mut self, s:&S, a:usize, priority:f64) {
+        if priority <= self.theta { return }
+        let key = (s.clone(), a);
+        if self.queued.get(&key).is_some_and(|p| *p >= priority) { return }
+        self.queued.insert(key.clone(), priority);
+        self.pushed += 1;
+        self.queue.push(Queued { priority, order:self.pushed, key });
+    }
+
+    fn pop(&mut self) -> Option<(S, usize)> {
+        while let Some(e) = self.queue.pop() {
+            if self.queued.get(&e.key) == Some(&e.priority) {
+                self.queued.remove(&e.key);
+                return Some(e.key)
+            }
+        }
+        None
+    }
+}
+
+impl<S:Clone + Eq + Hash, A:Schedule, X:Schedule> Planner<S> for DynaQ<S, A, X> {
+    fn q(&self) -> &HashMap<S, Vec<f64>> {
+        &self.q
+    }
+
+    fn updates(&self) -> usize {
+        self.updates
+    }
+
+    fn select(&mut self, s:&S, rng:&mut SeededRng) -> usize {
+        DynaQ::select(self, s, rng)
+    }
+
+    fn learn(&mut self, s:&S, a:usize, r:f64, ss:&S, done:bool, rng:&mut SeededRng) {
+        DynaQ::learn(self, s, a, r, ss, done, rng)
+    }
+}
+
+impl<S:Clone + Eq + Hash, A:Schedule, X:Schedule> Planner<S> for PrioritizedSweeping<S, A, X> {
+    fn q(&self) -> &HashMap<S, Vec<f64>> {
+        &self.q
+    }
+
+    fn updates(&self) -> usize {
+        self.updates
+    }
+
+    fn select(&mut self, s:&S, rng:&mut SeededRng) -> usize {
+        let epsilon = self.epsilon.at(self.time + 1);
+        let q = self.entry(s);
+        Behavior::EpsilonGreedy(epsilon).sample(q, &vec![true; q.len()], rng)
+    }
+
+    fn learn(&mut self, s:&S, a:usize, r:f64, ss:&S, done:bool, _:&mut SeededRng) {
+        self.time += 1;
+        self.model.insert((s.clone(), a), Outcome { reward:r, next:ss.clone(), done, tried:0 });
+        let from = self.predecessor.entry(ss.clone()).or_default();
+        if !from.contains(&(s.clone(), a)) { from.push((s.clone(), a)) }
+        let p = self.priority(s, a);
+        self.push(s, a, p);
+        for _ in 0..self.planning {
+            let Some((s, a)) = self.pop() else { break };
+            let m = &self.model[&(s.clone(), a)];
+            let (r, ss, done) = (m.reward, m.next.clone(), m.done);
+            let next = if done { 0.0 } else { self.entry(&ss).iter().cloned().fold(f64::NEG_INFINITY, f64::max) };
+            let (alpha, gamma) = (step_size(&self.alpha, &mut self.visits, &s, a), self.gamma);
+            let q = &mut self.entry(&s)[a];
+            *q += alpha * (r + gamma * next - *q);
+            self.updates += 1;
+            for (sp, ap) in self.predecessor.get(&s).cloned().unwrap_or_default() {
+                let p = self.priority(&sp, ap);
+                self.push(&sp, ap, p);
+            }
+        }
+    }
+}
+
+//α for the next update of (s, a)
+fn step_size<S:Clone + Eq + Hash, A:Schedule>(alpha:&A, visits:&mut HashMap<(S, usize), usize>, s:&S, a:usize) -> f64 {
+    let count = visits.entry((s.clone(), a)).or_insert(0);
+    *count += 1;
+    alpha.at(*count)
+}
+
+//one episode from reset, at most max_step steps, returns the steps taken
+pub fn episode<S, P:Planner<S>, E:Environment<State = S>>(agent:&mut P, env:&mut E, rng:&mut SeededRng, max_step:usize) -> usize {
+    let mut s = env.reset(rng);
+    for step in 1..=max_step {
+        let a = agent.select(&s, rng);
+        let action = env.actions(&s)[a].clone();
+        let (ss, r, done) = env.step(&action, rng);
+        agent.learn(&s, a, r, &ss, done, rng);
+        if done { return step }
+        s = ss;
+    }
+    max_step
+}
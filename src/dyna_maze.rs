@@ -0,0 +1,160 @@
+use std::error::Error;
+use std::fs::File;
+use std::io::Write;
+use plotters::prelude::*;
+
+use crate::dyna::{ self, DynaQ, Planner, PrioritizedSweeping };
+use crate::experiment::{ Curve, Experiment };
+use crate::maze::{ self, Changing, Maze };
+use crate::rng::SeededRng;
+use crate::schedules::Schedule;
+
+//the changing mazes of section 8.3, drawn in maps/ and built in so the figures run anywhere
+const BLOCKING:&str = include_str!("../maps/blocking_maze.txt");
+const SHORTCUT:&str = include_str!("../maps/shortcut_maze.txt");
+const DYNA:&str = include_str!("../maps/dyna_maze.txt");
+
+struct ControlInfo<A = f64, X = f64> {
+    pub steps:usize,
+    pub planning:usize,
+    pub alpha:A,
+    pub epsilon:X,
+    pub kappa:f64,//of Dyna-Q+
+}
+
+//cumulative reward after every step of a fresh agent, kappa 0 for Dyna-Q
+fn cumulative<A:Schedule + Copy, X:Schedule + Copy>(map:&str, kappa:f64, c_info:&ControlInfo<A, X>, rng:&mut SeededRng) -> Vec<f64> {
+    let mut maze = Changing::parse(map).unwrap();
+    let mut agent = DynaQ::new(4, 0.0, maze.maze.gamma, 0.0, c_info.planning, kappa)
+        .with_alpha(c_info.alpha).with_epsilon(c_info.epsilon);
+    let mut total = 0.0;
+    let mut g = Vec::with_capacity(c_info.steps);
+    agent.run(&mut maze, c_info.steps, rng, |r| {
+        total += r;
+        g.push(total);
+    });
+    g
+}
+
+fn write_csv(path:&str, curve:&[Curve]) -> std::io::Result<()> {
+    let mut f = File::create(path)?;
+    writeln!(f, "step,dyna_q,dyna_q se,dyna_q_plus,dyna_q_plus se")?;
+    for k in 0..curve[0].len() {
+        writeln!(f, "{},{},{},{},{}", k + 1, curve[0].mean[k], curve[0].stderr[k], curve[1].mean[k], curve[1].stderr[k])?;
+    }
+    Ok(())
+}
+
+fn draw(path:&str, title:&str, at:usize, curve:&[Curve]) -> Result<(), Box<dyn Error>> {
+    let canvas = BitMapBackend::new(path, (1200, 900)).into_drawing_area();
+    canvas.fill(&WHITE)?;
+    let y_max = curve.iter().map(|c| c.mean[c.len() - 1]).fold(1.0, f64::max);
+    let mut chart = ChartBuilder::on(&canvas)
+        .caption(format!("{}, {} runs", title, curve[0].runs), ("sans-serif", 30))
+        .margin(5)
+        .x_label_area_size(50)
+        .y_label_area_size(50)
+        .build_cartesian_2d(0..curve[0].len(), 0.0..y_max * 1.05)?;
+    chart.configure_mesh().x_desc("time steps").y_desc("cumulative reward").draw()?;
+    chart.draw_series(LineSeries::new(vec!((at, 0.0), (at, y_max * 1.05)), BLACK.mix(0.3)))?;
+    for (k, (label, c)) in ["Dyna-Q", "Dyna-Q+"].iter().zip(curve.iter()).enumerate() {
+        let color = Palette99::pick(k).to_rgba();
+        chart.draw_series(LineSeries::new(c.mean.iter().cloned().enumerate(), color))?
+            .label(*label)
+            .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], color));
+    }
+    chart
+        .configure_series_labels()
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
+        .draw()?;
+    canvas.present()?;
+    Ok(())
+}
+
+//Dyna-Q against Dyna-Q+ through the change, to {name}.csv and {name}.png
+fn compare<A:Schedule + Copy, X:Schedule + Copy>(name:&str, title:&str, map:&str, c_info:&ControlInfo<A, X>, experiment:&Experiment) -> Result<(), Box<dyn Error>> {
+    let curve:Vec<Curve> = [0.0, c_info.kappa].iter().map(|kappa| experiment.repeat(|rng| cumulative(map, *kappa, c_info, rng))).collect();
+    let at = Changing::parse(map)?.at;
+    for (label, c) in ["Dyna-Q", "Dyna-Q+"].iter().zip(curve.iter()) {
+        let last = c.len() - 1;
+        let (lo, hi) = c.ci95(last);
+        println!("{}: cumulative reward {:.1} at the change, {:.1} [{:.1}, {:.1}] at the end", label, c.mean[at - 1], c.mean[last], lo, hi);
+    }
+    write_csv(&format!("{}.csv", name), &curve)?;
+    draw(&format!("{}.png", name), title, at, &curve)
+}
+
+//figure 8.4, the short way around the wall is blocked after 1000 steps
+pub fn run_blocking(seed:u64, runs:Option<usize>) -> Result<(), Box<dyn Error>> {
+    let c_info = ControlInfo { steps:3000, planning:10, alpha:1.0, epsilon:0.1, kappa:1e-4 };
+    compare("8_4", "blocking maze", BLOCKING, &c_info, &Experiment::new(runs.unwrap_or(20), seed))
+}
+
+//figure 8.5, a shortcut opens after 3000 steps
+pub fn run_shortcut(seed:u64, runs:Option<usize>) -> Result<(), Box<dyn Error>> {
+    let c_info = ControlInfo { steps:6000, planning:50, alpha:1.0, epsilon:0.1, kappa:1e-3 };
+    compare("8_5", "shortcut maze", SHORTCUT, &c_info, &Experiment::new(runs.unwrap_or(20), seed))
+}
+
+//updates until the greedy path from the start is at most 1.2 times a shortest one, checked
+//after every episode. only exploration finds the last shortcuts of the bigger mazes, so an
+//exact optimum measures ε more than planning. a run that gets there in none of max_episode
+//episodes counts what it used
+fn updates_until_optimal<P:Planner<(i32, i32)>>(agent:&mut P, maze:&mut Maze, max_episode:usize, rng:&mut SeededRng) -> f64 {
+    let optimal = maze.shortest().unwrap();
+    for _ in 0..max_episode {
+        dyna::episode(agent, maze, rng, usize::MAX);
+        let path = maze::greedy_path(maze, agent.q());
+        if maze.cell[path[path.len() - 1]] == maze::Cell::Goal && (path.len() - 1) as f64 <= 1.2 * optimal as f64 { break }
+    }
+    agent.updates() as f64
+}
+
+//example 8.4, the dyna maze at growing resolution. Dyna-Q and prioritized sweeping with 5
+//planning updates per step, α = 0.5, ε = 0.1 and θ = 0.0001, to 8_4_prioritized.csv and .png
+pub fn run_prioritized(seed:u64, runs:Option<usize>) -> Result<(), Box<dyn Error>> {
+    let base = Maze::parse(DYNA)?;
+    let factor:Vec<i32> = (1..=5).collect();
+    let experiment = Experiment::new(runs.unwrap_or(10), seed);
+    let (alpha, epsilon, planning, max_episode) = (0.5, 0.1, 5, 1000);
+    let curve:Vec<Curve> = (0..2).map(|method| experiment.repeat(|rng| {
+        factor.iter().map(|f| {
+            let mut maze = base.scaled(*f);
+            match method {
+                0 => updates_until_optimal(&mut DynaQ::new(4, alpha, base.gamma, epsilon, planning, 0.0), &mut maze, max_episode, rng),
+                _ => updates_until_optimal(&mut PrioritizedSweeping::new(4, alpha, base.gamma, epsilon, planning, 1e-4), &mut maze, max_episode, rng),
+            }
+        }).collect()
+    })).collect();
+    let state:Vec<usize> = factor.iter().map(|f| base.scaled(*f).cell.iter().filter(|c| **c != maze::Cell::Wall).count()).collect();
+    let mut f = File::create("8_4_prioritized.csv")?;
+    writeln!(f, "states,dyna_q,dyna_q se,prioritized,prioritized se")?;
+    for (k, n) in state.iter().enumerate() {
+        writeln!(f, "{},{},{},{},{}", n, curve[0].mean[k], curve[0].stderr[k], curve[1].mean[k], curve[1].stderr[k])?;
+        println!("{} states: Dyna-Q {:.0}, prioritized sweeping {:.0} updates", n, curve[0].mean[k], curve[1].mean[k]);
+    }
+    let canvas = BitMapBackend::new("8_4_prioritized.png", (1200, 900)).into_drawing_area();
+    canvas.fill(&WHITE)?;
+    let y_max = curve.iter().flat_map(|c| c.mean.iter()).cloned().fold(10.0, f64::max);
+    let mut chart = ChartBuilder::on(&canvas)
+        .caption(format!("updates until optimal, {} runs", curve[0].runs), ("sans-serif", 30))
+        .margin(5)
+        .x_label_area_size(50)
+        .y_label_area_size(60)
+        .build_cartesian_2d(0..state[state.len() - 1] + 1, (10.0..y_max * 2.0).log_scale())?;
+    chart.configure_mesh().x_desc("gridworld size (states)").y_desc("updates until optimal solution").draw()?;
+    for (k, (label, c)) in ["Dyna-Q", "prioritized sweeping"].iter().zip(curve.iter()).enumerate() {
+        let color = Palette99::pick(k).to_rgba();
+        chart.draw_series(LineSeries::new(state.iter().cloned().zip(c.mean.iter().cloned()), color))?
+            .label(*label)
+            .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], color));
+    }
+    chart
+        .configure_series_labels()
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
+        .draw()?;
+    canvas.present()?;
+    Ok(())
+}
@@ -0,0 +1,58 @@
+use crate::schedules::Schedule;
+use crate::vfa::{ Features, LinearVfa };
+
+//emphatic TD(λ) of sections 11.8 and 12.11 for off-policy linear prediction. the followon
+//trace F_t = ρ_t-1 γ F_t-1 + I_t carries how much the target policy would have led to S_t,
+//the emphasis M_t = λI_t + (1 - λ)F_t weights the update there. α is counted by steps
+pub struct EmphaticTd<A = f64> {
+    pub lambda:f64,
+    pub gamma:f64,
+    pub alpha:A,
+    pub steps:usize,
+    pub f:f64,//followon trace of the last step
+    pub m:f64,//emphasis of the last step
+    rho:f64,//ρ of the last step, 0 at the start of an episode
+    z:Vec<f64>,
+}
+
+impl EmphaticTd {
+    pub fn new(lambda:f64, gamma:f64, alpha:f64, dim:usize) -> Self {
+        Self { lambda, gamma, alpha, steps:0, f:0.0, m:0.0, rho:0.0, z:vec![0.0; dim] }
+    }
+}
+
+impl<A:Schedule> EmphaticTd<A> {
+    pub fn with_alpha<B:Schedule>(self, alpha:B) -> EmphaticTd<B> {
+        let Self { lambda, gamma, steps, f, m, rho, z, .. } = self;
+        EmphaticTd { lambda, gamma, alpha, steps, f, m, rho, z }
+    }
+
+    //one transition of b with ρ = π(A|S)/b(A|S) and interest I(S). ss is None for a
+    //terminal next state, which also starts the traces over
+    pub fn step<S, F:Features<S>>(&mut self, v:&mut LinearVfa<F>, s:&S, r:f64, ss:Option<&S>, rho:f64, interest:f64) {
+        let v_next = match ss {
+            Some(ss) => v.value(ss),
+            None => 0.0,
+        };
+        let delta = r + self.gamma * v_next - v.value(s);
+        self.steps += 1;
+        let alpha = self.alpha.at(self.steps);
+        self.f = self.rho * self.gamma * self.f + interest;
+        self.m = self.lambda * interest + (1.0 - self.lambda) * self.f;
+        for z in self.z.iter_mut() {
+            *z *= rho * self.gamma * self.lambda;
+        }
+        for (k, x) in v.feature.features(s) {
+            self.z[k] += rho * self.m * x;
+        }
+        for (k, z) in self.z.iter().enumerate() {
+            v.w[k] += v.feature.step_scale(k) * alpha * delta * z;
+        }
+        self.rho = rho;
+        if ss.is_none() {
+            self.f = 0.0;
+            self.rho = 0.0;
+            self.z.iter_mut().for_each(|z| *z = 0.0);
+        }
+    }
+}
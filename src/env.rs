@@ -0,0 +1,160 @@
+use crate::rng::SeededRng;
+
+//gym-style environment, the state is held by the environment between steps
+pub trait Environment {
+    type State:Clone;
+    type Action:Clone;
+    fn reset(&mut self, rng:&mut SeededRng) -> Self::State;
+    //next state, reward, terminated; continuing tasks never terminate
+    fn step(&mut self, a:&Self::Action, rng:&mut SeededRng) -> (Self::State, f64, bool);
+    //actions available in s, never empty for a non-terminal state
+    fn actions(&self, s:&Self::State) -> Vec<Self::Action>;
+
+    //the same environment paying shaped rewards, see Shaped
+    fn shaped<P:Fn(&Self::State) -> f64>(self, potential:P, gamma:f64) -> Shaped<Self, P>
+        where Self:Sized {
+        Shaped { env:self, potential, gamma, shaping:true, state:None }
+    }
+
+    //episodes cut after steps, see TimeLimit
+    fn time_limit(self, steps:usize) -> TimeLimit<Self>
+        where Self:Sized {
+        TimeLimit { env:self, limit:steps, step:0, truncated:false }
+    }
+
+    //rewards times scale, then clipped to ±clip
+    fn scale_reward(self, scale:f64, clip:Option<f64>) -> ScaleReward<Self>
+        where Self:Sized {
+        ScaleReward { env:self, scale, clip }
+    }
+
+    //observations f(s) instead of s, see MapState
+    fn map_state<O:Clone, F:Fn(&Self::State) -> O>(self, f:F) -> MapState<Self, F>
+        where Self:Sized {
+        MapState { env:self, f, state:None }
+    }
+}
+
+//the step that reaches limit ends the episode as if it terminated, so a bootstrapping learner
+//takes the state it was cut in as terminal. truncated tells the two apart afterwards
+pub struct TimeLimit<E> {
+    pub env:E,
+    pub limit:usize,
+    pub step:usize,//taken since the last reset
+    pub truncated:bool,//the last episode ended on the limit, not on its own
+}
+
+pub struct ScaleReward<E> {
+    pub env:E,
+    pub scale:f64,
+    pub clip:Option<f64>,
+}
+
+//the agent sees f(s) and never s. actions are always those of the current state, which is
+//what a learner asks for between steps, whatever observation it passes
+pub struct MapState<E:Environment, F> {
+    pub env:E,
+    pub f:F,
+    state:Option<E::State>,
+}
+
+impl<E:Environment> Environment for TimeLimit<E> {
+    type State = E::State;
+    type Action = E::Action;
+
+    fn reset(&mut self, rng:&mut SeededRng) -> E::State {
+        self.step = 0;
+        self.truncated = false;
+        self.env.reset(rng)
+    }
+
+    fn step(&mut self, a:&E::Action, rng:&mut SeededRng) -> (E::State, f64, bool) {
+        let (ss, r, done) = self.env.step(a, rng);
+        self.step += 1;
+        self.truncated = !done && self.step >= self.limit;
+        (ss, r, done || self.truncated)
+    }
+
+    fn actions(&self, s:&E::State) -> Vec<E::Action> {
+        self.env.actions(s)
+    }
+}
+
+impl<E:Environment> Environment for ScaleReward<E> {
+    type State = E::State;
+    type Action = E::Action;
+
+    fn reset(&mut self, rng:&mut SeededRng) -> E::State {
+        self.env.reset(rng)
+    }
+
+    fn step(&mut self, a:&E::Action, rng:&mut SeededRng) -> (E::State, f64, bool) {
+        let (ss, r, done) = self.env.step(a, rng);
+        let r = r * self.scale;
+        (ss, self.clip.map_or(r, |c| r.clamp(-c, c)), done)
+    }
+
+    fn actions(&self, s:&E::State) -> Vec<E::Action> {
+        self.env.actions(s)
+    }
+}
+
+impl<E:Environment, O:Clone, F:Fn(&E::State) -> O> Environment for MapState<E, F> {
+    type State = O;
+    type Action = E::Action;
+
+    fn reset(&mut self, rng:&mut SeededRng) -> O {
+        let s = self.env.reset(rng);
+        let o = (self.f)(&s);
+        self.state = Some(s);
+        o
+    }
+
+    fn step(&mut self, a:&E::Action, rng:&mut SeededRng) -> (O, f64, bool) {
+        let (ss, r, done) = self.env.step(a, rng);
+        let o = (self.f)(&ss);
+        self.state = Some(ss);
+        (o, r, done)
+    }
+
+    fn actions(&self, _:&O) -> Vec<E::Action> {
+        self.env.actions(self.state.as_ref().expect("actions before reset"))
+    }
+}
+
+//potential-based shaping, r + γ Φ(s') - Φ(s) with Φ of a terminal state taken as 0, keeps
+//the optimal policies of the plain rewards (Ng, Harada and Russell 1999). train with shaping
+//on and turn it off to evaluate on the plain rewards, gamma should be the learner's
+pub struct Shaped<E:Environment, P> {
+    pub env:E,
+    pub potential:P,
+    pub gamma:f64,
+    pub shaping:bool,
+    state:Option<E::State>,//s of the next step
+}
+
+impl<E:Environment, P:Fn(&E::State) -> f64> Environment for Shaped<E, P> {
+    type State = E::State;
+    type Action = E::Action;
+
+    fn reset(&mut self, rng:&mut SeededRng) -> E::State {
+        let s = self.env.reset(rng);
+        self.state = Some(s.clone());
+        s
+    }
+
+    fn step(&mut self, a:&E::Action, rng:&mut SeededRng) -> (E::State, f64, bool) {
+        let (ss, mut r, done) = self.env.step(a, rng);
+        if self.shaping {
+            let s = self.state.as_ref().expect("step before reset");
+            let next = if done { 0.0 } else { (self.potential)(&ss) };
+            r += self.gamma * next - (self.potential)(s);
+        }
+        self.state = Some(ss.clone());
+        (ss, r, done)
+    }
+
+    fn actions(&self, s:&E::State) -> Vec<E::Action> {
+        self.env.actions(s)
+    }
+}
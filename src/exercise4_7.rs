@@ -1,28 +1,63 @@
-
 use std::error::Error;
-use std::collections::BTreeMap;
-use std::collections::btree_map::Entry::{ Vacant, Occupied };
 use std::cmp::{ min, max };
+use std::fs::File;
+use std::io::{ BufWriter, Write };
+use std::time::Instant;
+#[cfg(not(target_arch = "wasm32"))]
+use plotters::prelude::*;
+use serde::Serialize;
 
+use crate::alloc;
+use crate::ansi;
+use crate::deterministic;
+use crate::env::Environment;
+use crate::float::Float;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::heatmap;
+use crate::history::{ RunHistory, Timing };
 use crate::nd_vec::{ NdVec1, NdVec2 };
-use crate::poisson::Poisson;
+use crate::linear;
+use crate::lp;
+use crate::parallel;
+use crate::poisson::{ self, Tail, TruncatedPoisson };
+use crate::progress;
+use crate::rng::{ self, SeededRng };
+use crate::rollout;
+use crate::stopping::{ Change, StoppingRule };
+use crate::store::Store;
+use crate::tabular::{ Lazy, Row, Tabular };
 
 //for cyclic reference:
 //https://eli.thegreenplace.net/2021/rust-data-structures-with-circular-references/
 
-struct Graph {
-    pub state: NdVec2<State>,
-    pub action: NdVec1<Action>,
+//values, rewards and probabilities are stored as F
+struct Graph<F:Float> {
+    pub state: NdVec2<State<F>>,
+    pub action: NdVec1<Action<F>>,
+    pub value: NdVec2<F, Store<F>>,//same layout as state
+    pub action_value: Vec<F>,//q per packed row, only evaluated for exercise 4.5
+    pub packed: Packed<F>,
+}
+
+//transitions of every (state, action) pair back to back, the layout the sweeps read.
+//row state * n_action + action + move_limit spans offset[row]..offset[row + 1]
+struct Packed<F:Float> {
+    pub n_action:usize,
+    pub offset:Vec<usize>,
+    pub prob:Vec<F>,
+    pub target:Vec<usize>,//flat state index
+    pub reward:Vec<F>,//expected immediate reward per row
 }
 
 struct GraphInfo {
-    pub dist_rent_0:Poisson,
-    pub dist_rent_1:Poisson,
-    pub dist_return_0:Poisson,
-    pub dist_return_1:Poisson,
+    pub rent:(usize, usize),
+    pub ret:(usize, usize),
+    pub request:(TruncatedPoisson, TruncatedPoisson),//per location, cut at state_range
+    pub returned:(TruncatedPoisson, TruncatedPoisson),
     pub move_limit:i32,
     pub state_range:i32,
     pub rent_reward:i32,
+    pub parallel:bool,//setup builds the states on the rayon pool
 }
 
 struct GraphChange {
@@ -33,12 +68,189 @@ struct GraphChange {
 
 struct AgentInfo {
     pub discount:f64,
-    pub theta:f64,
+    pub stopping:StoppingRule,
     pub max_iter:i32,
+    pub evaluation:Evaluation,
+    pub parallel:bool,
+    pub action_value:bool,
+    pub tie:TieBreak,
+    pub init:Init,
+    pub warm_start:bool,
+}
+
+//everything the solver can be asked to change, defaults are exercise 4.7
+#[derive(Serialize, Clone)]
+pub struct Params {
+    pub discount:f64,
+    pub stopping:StoppingRule,//of each evaluation
+    pub max_iter:i32,//m, evaluation sweeps per improvement in modified mode
+    pub evaluation:Evaluation,
+    pub state_range:i32,
+    pub move_limit:i32,
+    pub rent_reward:i32,
+    pub rent:(usize, usize),//poisson λ per location
+    pub ret:(usize, usize),
+    pub change:bool,//apply the shuttle and parking rules below
+    pub free_shuttle:i32,
+    pub parking_limit:i32,
+    pub parking_cost:i32,
+    pub parallel:bool,//synchronous sweeps and improvement over the rayon pool
+    pub tail:Tail,//requests and returns above the car count
+    pub action_value:bool,//evaluate and improve from q instead of v, exercise 4.5
+    pub tie:TieBreak,
+    pub init:Init,//v before the first evaluation
+    pub warm_start:bool,//every evaluation goes on from the last v, otherwise it starts over from init
+    pub mmap:Option<String>,//directory for value.bin and policy.bin instead of RAM, cli runs on unix
+}
+
+//aperiodicity transformation of the average-reward sweeps, see Tabular::gain
+const AVERAGE_TAU:f64 = 0.5;
+
+//modified policy iteration stops evaluating after max_iter sweeps even when the
+//stopping rule is not met yet, exact evaluation runs until the rule alone.
+//direct solves (I - γ P_π) v = r_π in a single dense step, for small state spaces
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum Evaluation {
+    Modified,
+    Exact,
+    Direct,
+}
+
+//why an evaluation ended
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Stop {
+    Converged,//the stopping rule was met
+    SweepLimit,//max_iter sweeps in modified mode
+    Solved,//the linear system in direct mode
+}
+
+//which of the equally good actions improvement keeps, exercise 4.4
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum TieBreak {
+    Last,//the largest move, plain max_by
+    Current,//the current action whenever it is among the best
+    Smallest,//fewest cars moved, from the second location on a draw
+    Lexicographic,//the first in action order
+}
+
+//where v starts. any v converges, the closer to v_π the fewer sweeps
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum Init {
+    Zero,
+    Reward,//r(s), the expected rent of the state
+    Optimistic,//the largest r(s) + r(a) over 1 - γ, above every value
+}
+
+//the rental business with sampled requests and returns, what the expected-count model stands
+//in for. requests beyond the cars on hand are lost, then the move and the returns are clamped
+//to 0..=state_range like in the model. a continuing task, it never terminates
+pub struct Rental {
+    g_info:GraphInfo,
+    change:Option<GraphChange>,
+    pub start:(i32, i32),
+    pub state:(i32, i32),
+}
+
+//empirical discounted return of the converged policy from every state against its dp value,
+//flat in the order of the printed grids
+pub struct CrossValidation {
+    pub model:Vec<f64>,
+    pub mean:Vec<f64>,
+    pub stderr:Vec<f64>,
+    pub horizon:usize,//steps per episode, the return after it is cut off
+}
+
+//indexed [cars at location 0][cars at location 1]
+pub struct Solution<F:Float = f64> {
+    pub value:Vec<Vec<F>>,
+    pub policy:Vec<Vec<i32>>,
+    pub iterations:usize,
+}
+
+//what a policy does over all states at once, printed after it converged
+pub struct PolicyStats {
+    pub histogram:Vec<(i32, usize)>,//states per move, every move from -move_limit up
+    pub entropy:f64,//of the histogram in bits. a state's own policy is deterministic, so 0 within it
+    pub out:(i32, i32),//cars moved out of location 0 and out of location 1, summed over states
+    pub net:f64,//mean cars from location 0 to 1 per state
+}
+
+//--output layout, states in the same order as the printed grids
+#[derive(Serialize)]
+struct Export<'a, F:Float> {
+    pub params:&'a Params,
+    pub iterations:usize,
+    pub runtime:f64,//seconds
+    pub timing:Timing,
+    pub action:Vec<ActionExport<'a, F>>,
+    pub state:Vec<StateExport<'a, F>>,
+}
+
+#[derive(Serialize)]
+struct ActionExport<'a, F:Float> {
+    pub name:&'a str,
+    pub moved:i32,//cars from location 0 to 1
+    pub reward:F,
+}
+
+#[derive(Serialize)]
+struct StateExport<'a, F:Float> {
+    pub name:&'a str,
+    pub count:(i32, i32),
+    pub reward:F,
+    pub value:F,
+    pub policy:i32,
+    pub transition:Vec<TransitionExport<F>>,
+}
+
+#[derive(Serialize)]
+struct TransitionExport<F:Float> {
+    pub action:i32,
+    pub to:(i32, i32),
+    pub prob:F,
 }
 
 struct Policy {
-    pub state_action: NdVec2<i32>,//state index - action index
+    pub state_action: NdVec2<i32, Store<i32>>,//state index - action index
+}
+
+struct ActionChange {
+    pub state: (i32, i32),
+    pub from: i32,
+    pub to: i32,
+    pub gain: f64,//greedy value improvement under the evaluated values
+}
+
+//what Graph::validate found after setup
+pub struct Validation {
+    pub error:Vec<String>,//broken transitions, each one a construction bug
+    pub unreachable:Vec<(i32, i32)>,//no other state leads here
+}
+
+struct PolicyDiff {
+    pub change: Vec<ActionChange>,
+}
+
+//what run prints of the policy changes after each improvement
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DiffReport {
+    Summary,//the count and the gains only
+    List,//every changed state with its old and new action
+    Grid,//action delta per state
+}
+
+impl DiffReport {
+    pub fn parse(s:&str) -> Result<Self, String> {
+        match s {
+            "summary" => Ok(Self::Summary),
+            "list" => Ok(Self::List),
+            "grid" => Ok(Self::Grid),
+            _ => Err(format!("unknown diff report {}", s)),
+        }
+    }
 }
 
 struct StateDesc {
@@ -47,28 +259,28 @@ struct StateDesc {
     pub rent: (f64, f64),
 }
 
-struct State {
+struct State<F:Float> {
     pub desc: StateDesc,
-    pub reward: f64,
-    pub action: BTreeMap<i32, Vec<i32>>,
-    pub transition: Vec<Transition>,
-    pub state_v: f64,
+    pub reward: F,
+    pub action: Vec<i32>,//distinct actions, ascending
+    pub start: Vec<usize>,//transitions of action[i] are transition[start[i]..start[i + 1]]
+    pub transition: Vec<Transition<F>>,//sorted by action
 }
 
 struct ActionDesc {
     pub name: String,
 }
 
-struct Action {
+struct Action<F:Float> {
     pub desc: ActionDesc,
-    pub reward: f64,
+    pub reward: F,
 }
 
-struct Transition {
+struct Transition<F:Float> {
     pub action: i32,
     pub from: (i32, i32),
     pub to: (i32, i32),
-    pub prob: f64,
+    pub prob: F,
 }
 
 impl StateDesc {
@@ -77,9 +289,9 @@ impl StateDesc {
     }
 }
 
-impl State {
-    fn new(desc:StateDesc, reward:f64) -> Self {
-        Self { desc, reward, action: BTreeMap::new(), transition: Vec::new(), state_v: 0.0}
+impl<F:Float> State<F> {
+    fn new(desc:StateDesc, reward:F) -> Self {
+        Self { desc, reward, action: Vec::new(), start: Vec::new(), transition: Vec::new() }
     }
 
     fn name(&self) -> &str {
@@ -99,9 +311,53 @@ impl State {
         let r = self.desc.rent;
         (c.0 as f64 - r.0, c.1 as f64 - r.1)
     }
+
+    //each action with its transitions, ascending by action
+    fn actions(&self) -> impl Iterator<Item = (i32, &[Transition<F>])> {
+        self.action.iter().zip(self.start.windows(2)).map(|(a, w)| (*a, &self.transition[w[0]..w[1]]))
+    }
 }
 
-impl PartialEq for State {
+impl Environment for Rental {
+    type State = (i32, i32);
+    type Action = i32;
+
+    fn reset(&mut self, _:&mut SeededRng) -> (i32, i32) {
+        self.state = self.start;
+        self.state
+    }
+
+    //same reward terms as the model, with the rented cars counted instead of expected
+    fn step(&mut self, a:&i32, rng:&mut SeededRng) -> ((i32, i32), f64, bool) {
+        let gi = &self.g_info;
+        let (c0, c1) = self.state;
+        let rented0 = (poisson::sample(gi.rent.0, rng) as i32).min(c0);
+        let rented1 = (poisson::sample(gi.rent.1, rng) as i32).min(c1);
+        let returned0 = poisson::sample(gi.ret.0, rng) as i32;
+        let returned1 = poisson::sample(gi.ret.1, rng) as i32;
+        let sr = gi.state_range;
+        self.state = (
+            (c0 - rented0 - a + returned0).clamp(0, sr),
+            (c1 - rented1 + a + returned1).clamp(0, sr),
+        );
+        let parking = match &self.change {
+            Some(v) => (if c0 > v.parking_limit { -v.parking_cost } else { 0 }) + if c1 > v.parking_limit { -v.parking_cost } else { 0 },
+            None => 0,
+        };
+        let moved = a.abs() - match &self.change {
+            Some(v) if *a > 0 => v.free_shuttle,
+            _ => 0,
+        };
+        let reward = (rented0 + rented1) * gi.rent_reward + parking - 2 * moved;
+        (self.state, reward as f64, false)
+    }
+
+    fn actions(&self, _:&(i32, i32)) -> Vec<i32> {
+        (-self.g_info.move_limit..=self.g_info.move_limit).collect()
+    }
+}
+
+impl<F:Float> PartialEq for State<F> {
     fn eq(&self, other: &Self) -> bool {
         self.name() == other.name()
     }
@@ -113,8 +369,8 @@ impl ActionDesc {
     }
 }
 
-impl Action {
-    fn new(desc:ActionDesc, reward:f64) -> Self {
+impl<F:Float> Action<F> {
+    fn new(desc:ActionDesc, reward:F) -> Self {
         Self { desc, reward }
     }
 
@@ -123,28 +379,32 @@ impl Action {
     }
 }
 
-impl Transition {
-    fn reward(&self, g:&Graph, discount:f64) -> f64 {
-        g.state[self.from].reward + g.action[self.action].reward + discount * g.state[self.to].state_v
+impl<F:Float> Transition<F> {
+    fn reward(&self, g:&Graph<F>, discount:F) -> F {
+        g.state[self.from].reward + g.action[self.action].reward + discount * g.value[self.to]
     }
 }
 
-impl Graph {
+impl<F:Float> Packed<F> {
+    fn new() -> Self {
+        Self { n_action:0, offset:vec!(0), prob:Vec::new(), target:Vec::new(), reward:Vec::new() }
+    }
+}
+
+impl<F:Float> Graph<F> {
     fn new(gi:&GraphInfo) -> Self {
         let s = gi.state_range;
         let a = gi.move_limit;
         Self {
             state: NdVec2::new((0, s), (0, s)),
             action: NdVec1::new((-a, a)),
+            value: NdVec2::from_fn(((0, s), (0, s)), |_| F::ZERO).into_store(),
+            action_value: Vec::new(),
+            packed: Packed::new(),
         }
     }
 
-    fn add_state(&mut self, desc:StateDesc, reward:f64) {
-        let state = State::new(desc, reward);
-        self.state.push(state);
-    }
-
-    fn add_action(&mut self, desc:ActionDesc, reward:f64) {
+    fn add_action(&mut self, desc:ActionDesc, reward:F) {
         let action = Action::new(desc, reward);
         self.action.push(action);
     }
@@ -157,86 +417,207 @@ impl Graph {
         format!("{:+}", v)
     }
 
-    fn expected_count(v:i32, dist:&Poisson) -> f64 {
-        let v = v as usize;
-        let mut r:f64 = 0.0;
-        r += (0..=v).map(|n| dist.pmf(n) * n as f64).sum::<f64>();
-        r += (1.0 - dist.cdf(v)) * v as f64;
-        r
+    //(m, n) with its expected rentals and reward, parking costs included
+    fn state_desc(m:i32, n:i32, gi:&GraphInfo, c:Option<&GraphChange>) -> (StateDesc, f64) {
+        let rent0 = gi.request.0.mean_below(m as usize);
+        let rent1 = gi.request.1.mean_below(n as usize);
+        let desc = StateDesc::new(Self::state_name(m, n), (m, n), (rent0, rent1));
+        let state_reward = (rent0 + rent1) * gi.rent_reward as f64
+            + match c {
+                Some(v) => {
+                    //possible parking costs
+                    (if m > v.parking_limit { -v.parking_cost } else { 0 }) +
+                    if n > v.parking_limit { -v.parking_cost } else { 0 }
+                }
+                None => 0,
+            } as f64;
+        (desc, state_reward)
+    }
+
+    fn action_reward(k:i32, c:Option<&GraphChange>) -> f64 {
+        (k.abs() - match c {
+            Some(v) if k > 0 => v.free_shuttle,
+            _ => 0,
+        }) as f64 * -2.0
     }
 
-    fn add_transition_for_move(s:&mut State, k:i32, gi:&GraphInfo) {
-        let (c0, c1) = s.expected_count();
-        let dist0 = &gi.dist_return_0;
-        let dist1 = &gi.dist_return_1;
+    //where moving k cars leads from the counts left after the expected rentals, with the
+    //expected returns
+    fn move_target((c0, c1):(f64, f64), k:i32, gi:&GraphInfo) -> (i32, i32) {
         let sr = gi.state_range;
-        let c0 = c0 as f64;
-        let c1 = c1 as f64;
         let kf = k as f64;
-        let return0 = Graph::expected_count(sr, dist0);
-        let return1 = Graph::expected_count(sr, dist1);
-        let to = (
-            max(min(sr, (c0 - kf + return0).round() as i32), 0), 
+        let (return0, return1) = (gi.returned.0.mean(), gi.returned.1.mean());
+        (
+            max(min(sr, (c0 - kf + return0).round() as i32), 0),
             max(min(sr, (c1 + kf + return1).round() as i32), 0)
-        );
-        s.transition.push(Transition { action:k, from:s.count(), to, prob:1.0 });
+        )
+    }
+
+    fn add_transition_for_move(s:&mut State<F>, k:i32, gi:&GraphInfo) {
+        let to = Self::move_target(s.expected_count(), k, gi);
+        s.transition.push(Transition { action:k, from:s.count(), to, prob:F::ONE });
     }
 
-    fn parse_action(s:&mut State) {
-        let mut map:BTreeMap<i32, Vec<i32>> = BTreeMap::new();
-        let mut i = 0;
-        for t in s.transition.iter() {
-            let list = match map.entry(t.action) {
-                Vacant(v) => v.insert(Vec::new()),
-                Occupied(v) => v.into_mut(),
-            };
-            list.push(i);
-            i += 1;
+    //stable, transitions of one action keep the order they were added in
+    fn parse_action(s:&mut State<F>) {
+        s.transition.sort_by_key(|t| t.action);
+        s.action.clear();
+        s.start.clear();
+        for (i, t) in s.transition.iter().enumerate() {
+            if s.action.last() != Some(&t.action) {
+                s.action.push(t.action);
+                s.start.push(i);
+            }
         }
-        s.action = map;
+        s.start.push(s.transition.len());
     }
 
+    //every state is built with its transitions on its own, in parallel when asked, and
+    //they are put in place in flat index order afterwards
     fn setup(&mut self, gi:&GraphInfo, c:Option<&GraphChange>) {
-        for n in 0..=gi.state_range {
-            for m in 0..=gi.state_range {
-                let rent0 = Graph::expected_count(m, &gi.dist_rent_0);
-                let rent1 = Graph::expected_count(n, &gi.dist_rent_1);
-                let desc = StateDesc::new(Graph::state_name(m, n), (m, n), (rent0, rent1));
-                let state_reward = (rent0 + rent1) * gi.rent_reward as f64
-                    + match c {
-                        Some(v) => {
-                            //possible parking costs
-                            (if m > v.parking_limit { -v.parking_cost } else { 0 }) +
-                            if n > v.parking_limit { -v.parking_cost } else { 0 }
-                        }
-                        None => 0,
-                    } as f64;
-                self.add_state(desc, state_reward);
+        let w = gi.state_range + 1;
+        self.state.data = parallel::map_index((w * w) as usize, gi.parallel, |k| {
+            let (m, n) = (k as i32 % w, k as i32 / w);
+            let (desc, state_reward) = Self::state_desc(m, n, gi, c);
+            let mut s = State::new(desc, F::from_f64(state_reward));
+            //self transition
+            Self::add_transition_for_move(&mut s, 0, gi);
+            //move out
+            for k in 1..=gi.move_limit {
+                Self::add_transition_for_move(&mut s, k, gi);
             }
-        }
+            //move in
+            for k in 1..=gi.move_limit {
+                Self::add_transition_for_move(&mut s, -k, gi);
+            }
+            Self::parse_action(&mut s);
+            s
+        });
         let m = gi.move_limit;
         for k in -m..=m {
-            let desc = ActionDesc::new(Graph::action_name(k));
-            let action_reward = (k.abs() - match c {
-                Some(v) => if k > 0 { v.free_shuttle } else { 0 },
-                None => 0,
-            }) as f64 * -2.0;
-            self.add_action(desc, action_reward);
+            let desc = ActionDesc::new(Self::action_name(k));
+            self.add_action(desc, F::from_f64(Self::action_reward(k, c)));
         }
-        let m = gi.move_limit;
-        for s in self.state.iter_mut() {
-            //self transition
-            Graph::add_transition_for_move(s, 0, gi);
-            //move out
-            for k in 1..=m {
-                Graph::add_transition_for_move(s, k, gi);
+        self.pack(gi);
+    }
+
+    //every (state, action) has to be a distribution over states in range. unreachable states
+    //are legal but show how much the expected-count approximation leaves out of the model
+    fn validate(&self, gi:&GraphInfo) -> Validation {
+        let sr = gi.state_range;
+        let in_range = |c:(i32, i32)| c.0 >= 0 && c.0 <= sr && c.1 >= 0 && c.1 <= sr;
+        let mut error = Vec::new();
+        let mut reached = NdVec2::from_fn(((0, sr), (0, sr)), |_| false);
+        for s in self.state.iter() {
+            if s.action.len() != self.action.data.len() {
+                error.push(format!("state {}: {} of {} actions", s.name(), s.action.len(), self.action.data.len()));
             }
-            //move in
-            for k in 1..=m {
-                Graph::add_transition_for_move(s, -k, gi);
+            for (a, transition) in s.actions() {
+                let mut total = 0.0;
+                for t in transition.iter() {
+                    let prob = t.prob.to_f64();
+                    if !(0.0..=1.0).contains(&prob) {
+                        error.push(format!("state {} action {:+}: probability {} to {:?}", s.name(), a, prob, t.to));
+                    }
+                    if !in_range(t.to) {
+                        error.push(format!("state {} action {:+}: {:?} out of range", s.name(), a, t.to));
+                        continue
+                    }
+                    total += prob;
+                    if t.to != s.count() { reached[t.to] = true }
+                }
+                if (total - 1.0).abs() > 1e-6 {
+                    error.push(format!("state {} action {:+}: probabilities sum to {}", s.name(), a, total));
+                }
+            }
+        }
+        let unreachable = self.state.iter().map(|s| s.count()).filter(|c| !reached[*c]).collect();
+        Validation { error, unreachable }
+    }
+
+    //every state lists all moves, clamped ones included
+    fn pack(&mut self, gi:&GraphInfo) {
+        let n_action = (2 * gi.move_limit + 1) as usize;
+        let (state, action) = (&self.state, &self.action);
+        //each state's rows apart, then appended in state order
+        let rows = parallel::map_index(state.data.len(), gi.parallel, |k| {
+            let s = &state.data[k];
+            assert_eq!(s.action.len(), n_action);
+            let mut p = Packed::new();
+            for (a, transition) in s.actions() {
+                let mut reward = F::ZERO;
+                for t in transition.iter() {
+                    p.prob.push(t.prob);
+                    p.target.push(state.index(&t.to));
+                    reward += t.prob * (s.reward + action[a].reward);
+                }
+                p.reward.push(reward);
+                p.offset.push(p.prob.len());
+            }
+            p
+        });
+        let p = &mut self.packed;
+        p.n_action = n_action;
+        for r in rows {
+            let base = p.prob.len();
+            p.offset.extend(r.offset[1..].iter().map(|o| base + o));
+            p.prob.extend(r.prob);
+            p.target.extend(r.target);
+            p.reward.extend(r.reward);
+        }
+        self.action_value = vec![F::ZERO; p.reward.len()];
+    }
+
+    //Σ p(s'|s,a) [r + γ v(s')] from the packed row of a flat state index
+    fn q(&self, k:usize, a:i32, discount:F) -> F {
+        let p = &self.packed;
+        let row = k * p.n_action + (a + self.action.offset) as usize;
+        let range = p.offset[row]..p.offset[row + 1];
+        p.reward[row] + discount * expectation(&p.prob[range.clone()], &p.target[range], &self.value.data)
+    }
+
+    //v back to where an evaluation starts, see Init
+    fn init_value(&mut self, init:Init, discount:f64) {
+        match init {
+            Init::Zero => self.value.iter_mut().for_each(|v| *v = F::ZERO),
+            Init::Reward => {
+                for (v, s) in self.value.iter_mut().zip(self.state.iter()) {
+                    *v = s.reward;
+                }
+            },
+            Init::Optimistic => {
+                let best = |r:F, x:F| if x > r { x } else { r };
+                let state = self.state.iter().map(|s| s.reward).reduce(best).unwrap_or(F::ZERO);
+                let action = self.action.iter().map(|a| a.reward).reduce(best).unwrap_or(F::ZERO);
+                let top = (state + action) / F::from_f64(1.0 - discount);
+                self.value.iter_mut().for_each(|v| *v = top);
+            },
+        }
+    }
+
+    //graphviz digraph, one node per state and one edge per transition labelled with
+    //the action, its probability and the expected reward r(s) + r(a)
+    fn to_dot(&self, path:&str) -> std::io::Result<()> {
+        let mut f = BufWriter::new(File::create(path)?);
+        writeln!(f, "digraph car_rental {{")?;
+        for s in self.state.iter() {
+            writeln!(f, "    \"{}\" [label=\"{}\\nr {:.2}\"];", s.name(), s.name(), s.reward)?;
+        }
+        for s in self.state.iter() {
+            for t in s.transition.iter() {
+                let reward = s.reward + self.action[t.action].reward;
+                writeln!(f, "    \"{}\" -> \"{}\" [label=\"{:+} p {:.3} r {:.2}\"];",
+                    s.name(), self.state[t.to].name(), t.action, t.prob, reward)?;
             }
-            Graph::parse_action(s);
         }
+        writeln!(f, "}}")
+    }
+
+    fn print_validation(v:&Validation) {
+        for e in v.error.iter() {
+            println!("model: {}", e);
+        }
+        println!("model: {} errors, {} unreachable states", v.error.len(), v.unreachable.len());
     }
 
     fn print_info(&self, gi:&GraphInfo, p:&Policy, discount:f64) {
@@ -245,174 +626,826 @@ impl Graph {
             println!("\t{}:{}", a.name(), a.reward);
         }
         println!("state:");
-        let sr = gi.state_range;
+        let (return0, return1) = (gi.returned.0.mean(), gi.returned.1.mean());
         for s in self.state.iter() {
             let (r0, r1) = s.rent();
-            let return0 = Graph::expected_count(sr, &gi.dist_return_0);
-            let return1 = Graph::expected_count(sr, &gi.dist_return_1);
             let a = p.state_action[s.count()];
             println!("\t{}|{:+}:{:.1} | {:.1} {:.1} | {:.1} {:.1}", s.name(), a, s.reward, r0, r1, return0, return1);
             for t in s.transition.iter() {
-                println!("\t\t{:+}:->{:?} {:.1}|{:.1} {:.2}", t.action, t.to, t.reward(self, discount), self.state[t.to].state_v, t.prob);
+                println!("\t\t{:+}:->{:?} {:.1}|{:.1} {:.2}", t.action, t.to, t.reward(self, F::from_f64(discount)), self.value[t.to], t.prob);
             }
         }
     }
 
-    // fn print_state(&self, gi:&GraphInfo) {
-    //     let limit = gi.state_range;
-    //     let mut count = 0;
-    //     for s in self.state.iter() {
-    //         print!("\t{:.1}", s.state_v);
-    //         count += 1;
-    //         if count > limit {
-    //             count = 0;
-    //             println!();
-    //         }
-    //     }
-    //     println!();
-    // }
+    //one row per count at location 1, coloured from lowest to highest value
+    fn print_grid(&self, gi:&GraphInfo, width:usize, value:&[f64]) {
+        let lo = value.iter().cloned().fold(f64::INFINITY, f64::min);
+        let hi = value.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        let s = gi.state_range;
+        let grid = NdVec2::from_fn(((0, s), (0, s)), |(m, n)| value[(m + n * (s + 1)) as usize]);
+        print!("{}", grid.grid(width, |_, v| ansi::cell(&format!("{:>width$.1}", v), ansi::sequential(ansi::scale(*v, lo, hi)))));
+    }
+
+    fn print_state(&self, gi:&GraphInfo) {
+        let value:Vec<f64> = self.value.iter().map(|v| v.to_f64()).collect();
+        self.print_grid(gi, 6, &value);
+    }
 
     fn print_reward(&self, gi:&GraphInfo) {
-        let limit = gi.state_range;
-        let mut count = 0;
-        for s in self.state.iter() {
-            print!("\t{:.1}", s.reward);
-            count += 1;
-            if count > limit {
-                count = 0;
-                println!();
-            }
-        }
-        println!();
+        let reward:Vec<f64> = self.state.iter().map(|s| s.reward.to_f64()).collect();
+        self.print_grid(gi, 6, &reward);
     }
 
+    //blue moves cars from location 0 to 1, red the other way
     fn print_policy(&self, p:&Policy, gi:&GraphInfo) {
-        let limit = gi.state_range;
-        let mut count = 0;
-        for s in self.state.iter() {
-            let sn = s.count();
-            let a = p.state_action[sn];
-            // print!("{:?} {}|{:+} ", sn, self.state.index(sn), a);
-            print!("{:+} ", a);
-            count += 1;
-            if count > limit {
-                count = 0;
-                println!();
-            }
+        let limit = gi.move_limit.max(1) as f64;
+        print!("{}", p.state_action.grid(3, |_, a| ansi::cell(&format!("{:+3}", a), ansi::diverging(*a as f64 / limit))));
+    }
+}
+
+impl PolicyDiff {
+    fn print(&self, report:DiffReport, gi:&GraphInfo) {
+        let gain_max = self.change.iter().map(|c| c.gain).fold(0.0, f64::max);
+        let gain_sum = self.change.iter().map(|c| c.gain).sum::<f64>();
+        println!("changed {} states, gain max {:.2} total {:.2}", self.change.len(), gain_max, gain_sum);
+        match report {
+            DiffReport::Summary => {},
+            DiffReport::List => {
+                for c in self.change.iter() {
+                    println!("\t{:?} {:+}->{:+} {:.2}", c.state, c.from, c.to, c.gain);
+                }
+            },
+            DiffReport::Grid => {
+                //action delta per state, '.' for unchanged
+                let s = gi.state_range;
+                let mut grid:NdVec2<Option<i32>> = NdVec2::from_fn(((0, s), (0, s)), |_| None);
+                for c in self.change.iter() {
+                    grid[c.state] = Some(c.to - c.from);
+                }
+                print!("{}", grid.grid(3, |_, d| d.map_or_else(|| "  .".to_string(), |v| format!("{:+3}", v))));
+            },
+        }
+    }
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Self {
+            discount:0.9, stopping:StoppingRule::MaxNorm(0.1), max_iter:16,
+            evaluation:Evaluation::Modified,
+            state_range:20, move_limit:5, rent_reward:10,
+            rent:(3, 4), ret:(3, 2),
+            change:true,
+            free_shuttle:1, parking_limit:10, parking_cost:4,
+            parallel:false,
+            tail:Tail::Lump,
+            action_value:false,
+            tie:TieBreak::Last,
+            init:Init::Zero,
+            warm_start:true,
+            mmap:None,
+        }
+    }
+}
+
+impl Params {
+    fn info(&self) -> (AgentInfo, GraphInfo) {
+        let agent_info = AgentInfo { discount:self.discount, stopping:self.stopping, max_iter:self.max_iter,
+            evaluation:self.evaluation, parallel:self.parallel, action_value:self.action_value, tie:self.tie,
+            init:self.init, warm_start:self.warm_start };
+        //no more than state_range cars are ever rented or returned
+        let cut = |l| TruncatedPoisson::new(l, self.state_range as usize, self.tail);
+        let g_info = GraphInfo {
+            move_limit:self.move_limit, state_range:self.state_range,
+            rent_reward:self.rent_reward,
+            rent:self.rent, ret:self.ret,
+            request:(cut(self.rent.0), cut(self.rent.1)),
+            returned:(cut(self.ret.0), cut(self.ret.1)),
+            parallel:self.parallel,
+        };
+        (agent_info, g_info)
+    }
+
+    fn graph_change(&self) -> Option<GraphChange> {
+        if !self.change { return None }
+        Some(GraphChange {
+            free_shuttle:self.free_shuttle,
+            parking_limit:self.parking_limit,
+            parking_cost:self.parking_cost,
+        })
+    }
+}
+
+impl Stop {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Converged => "converged",
+            Self::SweepLimit => "sweep limit",
+            Self::Solved => "solved",
+        }
+    }
+}
+
+impl AgentInfo {
+    //None while the evaluation should go on
+    fn stop(&self, change:&Change, sweeps:usize) -> Option<Stop> {
+        if self.evaluation == Evaluation::Direct { return Some(Stop::Solved) }
+        if self.stopping.met(change, self.discount) { return Some(Stop::Converged) }
+        match self.evaluation {
+            Evaluation::Modified if sweeps as i32 >= self.max_iter => Some(Stop::SweepLimit),
+            _ => None,
+        }
+    }
+}
+
+impl PolicyStats {
+    //action holds the move of every state in any order
+    pub fn new(action:&[i32], move_limit:i32) -> Self {
+        let mut histogram:Vec<(i32, usize)> = (-move_limit..=move_limit).map(|a| (a, 0)).collect();
+        for a in action.iter() {
+            histogram[(a + move_limit) as usize].1 += 1;
+        }
+        let n = action.len().max(1) as f64;
+        let entropy = histogram.iter().filter(|(_, c)| *c > 0).map(|(_, c)| *c as f64 / n).map(|p| -p * p.log2()).sum();
+        let out = (action.iter().filter(|a| **a > 0).sum(), -action.iter().filter(|a| **a < 0).sum::<i32>());
+        let net = (out.0 - out.1) as f64 / n;
+        Self { histogram, entropy, out, net }
+    }
+
+    pub fn print(&self) {
+        let n:usize = self.histogram.iter().map(|(_, c)| c).sum();
+        let top = self.histogram.iter().map(|(_, c)| *c).max().unwrap_or(0).max(1);
+        println!("policy: moves over {} states", n);
+        for (a, c) in self.histogram.iter() {
+            println!("	{:+3} {:5} {:5.1}% {}", a, c, 100.0 * *c as f64 / n.max(1) as f64, "#".repeat(c * 40 / top));
+        }
+        println!("entropy {:.3} bits, {} cars out of location 0, {} out of location 1, net {:+.2} per state",
+            self.entropy, self.out.0, self.out.1, self.net);
+    }
+}
+
+impl Init {
+    pub fn parse(s:&str) -> Result<Self, String> {
+        match s {
+            "zero" => Ok(Self::Zero),
+            "reward" => Ok(Self::Reward),
+            "optimistic" => Ok(Self::Optimistic),
+            _ => Err(format!("unknown value init {}", s)),
+        }
+    }
+}
+
+impl TieBreak {
+    pub fn parse(s:&str) -> Result<Self, String> {
+        match s {
+            "last" => Ok(Self::Last),
+            "current" => Ok(Self::Current),
+            "smallest" => Ok(Self::Smallest),
+            "lexicographic" => Ok(Self::Lexicographic),
+            _ => Err(format!("unknown tie break {}", s)),
+        }
+    }
+
+    //action_v is in action order. everything within TIE of the best counts as equal,
+    //so rounding noise cannot flip the policy back and forth
+    fn pick<F:Float>(&self, action_v:&[(i32, F)], a_old:i32) -> (i32, F) {
+        const TIE:f64 = 1e-9;
+        let best = *action_v.iter().max_by(|(_, x), (_, y)| x.total_cmp(y)).unwrap();
+        if *self == Self::Last { return best }
+        let eps = F::from_f64(TIE * best.1.to_f64().abs().max(1.0));
+        let mut ties = action_v.iter().filter(|(_, v)| *v >= best.1 - eps);
+        *match self {
+            Self::Current => action_v.iter().find(|(a, v)| *a == a_old && *v >= best.1 - eps)
+                .unwrap_or_else(|| ties.next().unwrap()),
+            Self::Smallest => ties.min_by_key(|(a, _)| a.abs()).unwrap(),
+            _ => ties.next().unwrap(),
         }
-        println!();
     }
 }
 
 impl Policy {
     fn new(gi:&GraphInfo) -> Self {
         let s = gi.state_range;
-        let mut v =  NdVec2::new((0, s), (0, s));
-        let s = s + 1;
-        v.resize((s * s) as usize, 0);
-        Self { state_action: v}
+        Self { state_action:NdVec2::from_fn(((0, s), (0, s)), |_| 0).into_store() }
+    }
+}
+
+//moves v and the policy into {dir}/value.bin and {dir}/policy.bin, keeping their contents.
+//the files hold the raw elements in state order and stay after the run
+fn map_storage<F:Float>(g:&mut Graph<F>, p:&mut Policy, dir:&str) -> std::io::Result<()> {
+    let dir = std::path::Path::new(dir);
+    std::fs::create_dir_all(dir)?;
+    g.value.data = Store::mapped(&dir.join("value.bin"), &g.value.data)?;
+    p.state_action.data = Store::mapped(&dir.join("policy.bin"), &p.state_action.data)?;
+    Ok(())
+}
+
+//prob · value[target] in four independent lanes, which the compiler can vectorise
+fn expectation<F:Float>(prob:&[F], target:&[usize], value:&[F]) -> F {
+    let mut lane = [F::ZERO; 4];
+    let mut p = prob.chunks_exact(4);
+    let mut t = target.chunks_exact(4);
+    for (p, t) in (&mut p).zip(&mut t) {
+        for k in 0..4 {
+            lane[k] += p[k] * value[t[k]];
+        }
+    }
+    let rest = p.remainder().iter().zip(t.remainder().iter()).map(|(p, t)| *p * value[*t]).sum::<F>();
+    lane[0] + lane[1] + lane[2] + lane[3] + rest
+}
+
+//one evaluation sweep, returns the range of v' - v. in place by default, the parallel
+//sweep is synchronous and backs up from the previous values only
+fn evaluate_sweep<F:Float>(g:&mut Graph<F>, p:&Policy, info:&AgentInfo) -> Change {
+    if info.evaluation == Evaluation::Direct { return evaluate_direct(g, p, info) }
+    if info.action_value { return evaluate_q_sweep(g, p, info) }
+    let discount = F::from_f64(info.discount);
+    let mut change = Change::new();
+    if info.parallel {
+        let gs = &*g;
+        let value = parallel::map_index(g.value.data.len(), true, |k| gs.q(k, p.state_action.data[k], discount));
+        for (v_old, v_new) in g.value.data.iter().zip(value.iter()) {
+            change.add((*v_new - *v_old).to_f64());
+        }
+        g.value.data.copy_from_slice(&value);
+        return change
+    }
+    for k in 0..g.value.data.len() {
+        let v_old = g.value.data[k];
+        let v_new = g.q(k, p.state_action.data[k], discount);
+        g.value.data[k] = v_new;
+        change.add((v_new - v_old).to_f64());
+    }
+    change
+}
+
+//v = r_π + γ P_π v as one dense system in f64, the "sweep" of direct mode.
+//q is backed up once from the solved v, so improvement on q sees the same values
+fn evaluate_direct<F:Float>(g:&mut Graph<F>, p:&Policy, info:&AgentInfo) -> Change {
+    let pk = &g.packed;
+    let n = g.value.data.len();
+    let mut a = vec![0.0; n * n];
+    let mut b = vec![0.0; n];
+    for (k, act) in p.state_action.data.iter().enumerate() {
+        let row = k * pk.n_action + (act + g.action.offset) as usize;
+        a[k * n + k] += 1.0;
+        for t in pk.offset[row]..pk.offset[row + 1] {
+            a[k * n + pk.target[t]] -= info.discount * pk.prob[t].to_f64();
+        }
+        b[k] = pk.reward[row].to_f64();
+    }
+    let v = linear::solve(a, b).expect("I - γ P_π is singular, direct evaluation needs discount < 1");
+    let mut change = Change::new();
+    for (v_old, v_new) in g.value.data.iter_mut().zip(v) {
+        change.add(v_new - v_old.to_f64());
+        *v_old = F::from_f64(v_new);
+    }
+    if info.action_value {
+        let discount = F::from_f64(info.discount);
+        for row in 0..pk.reward.len() {
+            let range = pk.offset[row]..pk.offset[row + 1];
+            g.action_value[row] = pk.reward[row] + discount * expectation(&pk.prob[range.clone()], &pk.target[range], &g.value.data);
+        }
+    }
+    change
+}
+
+//value = q(s, π(s)), what the q backup reads and what gets printed
+fn read_policy_value<F:Float>(g:&mut Graph<F>, p:&Policy) {
+    for (k, a) in p.state_action.data.iter().enumerate() {
+        g.value.data[k] = g.action_value[k * g.packed.n_action + (a + g.action.offset) as usize];
     }
 }
 
-fn evaluate_policy(g:&mut Graph, p:&Policy, info:&AgentInfo) {
+//q(s, a) = r(s, a) + γ Σ p(s'|s,a) q(s', π(s')), every row from the previous sweep.
+//value is read again first since the policy may have changed since the last sweep
+fn evaluate_q_sweep<F:Float>(g:&mut Graph<F>, p:&Policy, info:&AgentInfo) -> Change {
+    let discount = F::from_f64(info.discount);
+    read_policy_value(g, p);
+    let pk = &g.packed;
+    let q = parallel::map_index(pk.reward.len(), info.parallel, |row| {
+        let range = pk.offset[row]..pk.offset[row + 1];
+        pk.reward[row] + discount * expectation(&pk.prob[range.clone()], &pk.target[range], &g.value.data)
+    });
+    let mut change = Change::new();
+    for (q_old, q_new) in g.action_value.iter().zip(q.iter()) {
+        change.add((*q_new - *q_old).to_f64());
+    }
+    g.action_value = q;
+    read_policy_value(g, p);
+    change
+}
+
+fn evaluate_policy<F:Float>(g:&mut Graph<F>, p:&Policy, info:&AgentInfo, history:&mut RunHistory) -> (Stop, usize, f64) {
     let mut i = 0;
-    let pg:*const Graph = g;
-    //hack to grant shared access to graph
-    let gs = unsafe { &(*pg) };
+    let pb = match info.evaluation {
+        Evaluation::Modified => progress::sweep(info.max_iter as u64),
+        Evaluation::Exact => progress::sweep_unbounded(),
+        Evaluation::Direct => progress::sweep(1),
+    };
     loop {
-        let mut delta:f64 = 0.0;
-        for s in g.state.iter_mut() {
-            let v_old = s.state_v;
-            let a = p.state_action[s.count()];
-            let t_index = &s.action[&a];
-            let v_new = t_index.iter()
-                .map(|t| &s.transition[*t as usize] )
-                .map(|t| t.prob * t.reward(gs, info.discount))
-                .sum::<f64>();
-            s.state_v = v_new;
-            // println!("{} {} {}", s.name(), v_old, v_new);
-            delta = delta.max((v_new - v_old).abs());
-        }
+        let t = Instant::now();
+        let change = evaluate_sweep(g, p, info);
+        let seconds = deterministic::elapsed(&t);
+        let delta = info.stopping.measure(&change);
         i += 1;
         // println!("{}:{}", i, delta);
-        if delta <= info.theta || i >= info.max_iter { break }
-    }
-}
-
-fn improve_policy(p:&mut Policy, g:&Graph, info:&AgentInfo, _gi:&GraphInfo) -> bool {
-    println!("improvement:");
-    let mut policy_stable = true;
-    for s in g.state.iter() {
-        let sn = s.count();
-        let a_old = p.state_action[sn];
-        let (a_new, _) = s.action.iter()
-            .map(|(a, vec_t)| (*a, vec_t))
-            .map(|(a, vec_t)|
-                (a, vec_t.iter()
-                    .map(|t| &s.transition[*t as usize])
-                    .map(|t| t.prob * t.reward(g, info.discount))
-                    .sum::<f64>()))
-            .max_by(|(_, x), (_, y)| x.total_cmp(y)).unwrap();
-        let state_stable = a_old == a_new;
-        // if !state_stable {
-        //     println!("{:?} {:+} {:+}", sn, a_old, a_new);
-        //     s.action.iter()
-        //     .map(|(a, vec_t)|
-        //         (a, vec_t.iter()
-        //         .map(|t| &s.transition[*t as usize])
-        //         .map(|t| t.prob * t.reward(g, info.discount))
-        //         .sum::<f64>()))
-        //     .for_each(|(a, v)| println!("{:+} {:.1}", a, v));
-        //     s.action.iter()
-        //     .map(|(a, vec_t)|
-        //         (a, vec_t.iter()
-        //         .map(|t| &s.transition[*t as usize])
-        //         .map(|t| g.state[t.to].state_v)
-        //         .sum::<f64>()))
-        //     .for_each(|(a, v)| println!("{:+} {:.1}", a, v));
-        // }
-        // println!("{} {}", sn, a_new);
-        p.state_action[sn] = a_new;
-        policy_stable = policy_stable && state_stable;
-        // g.print_policy(p, _gi);
-    }
-    policy_stable
-}
-
-pub fn run() -> Result<(), Box<dyn Error>> {
-    let agent_info = AgentInfo { discount:0.9, theta:0.1, max_iter:16 };
-    let state_range:usize = 20;
-    let g_info = GraphInfo { 
-        move_limit:5, state_range:state_range as i32,
-        rent_reward:10,
-        dist_rent_0:Poisson::new(3, state_range),
-        dist_rent_1:Poisson::new(4, state_range),
-        dist_return_0:Poisson::new(3, state_range),
-        dist_return_1:Poisson::new(2, state_range),
+        history.record_sweep(delta, seconds);
+        pb.inc(1);
+        pb.set_message(progress::delta_msg(delta, info.stopping.threshold(info.discount)));
+        if let Some(stop) = info.stop(&change, i) {
+            pb.finish_and_clear();
+            return (stop, i, delta)
+        }
+    }
+}
+
+//the argmax of every state is independent, in parallel when asked.
+//the policy is stable once no state reports a change
+fn improve_policy<F:Float>(p:&mut Policy, g:&Graph<F>, info:&AgentInfo, _gi:&GraphInfo) -> PolicyDiff {
+    let discount = F::from_f64(info.discount);
+    let policy = &*p;
+    let n_action = g.packed.n_action;
+    let greedy = parallel::map_index(g.state.data.len(), info.parallel, |k| {
+        let s = &g.state.data[k];
+        let a_old = policy.state_action.data[k];
+        //from the stored q when it was evaluated, otherwise a one step lookahead on v
+        let action_v:Vec<(i32, F)> = s.action.iter()
+            .map(|a| (*a, if info.action_value {
+                g.action_value[k * n_action + (*a + g.action.offset) as usize]
+            } else {
+                g.q(k, *a, discount)
+            }))
+            .collect();
+        let (a_new, v_new) = info.tie.pick(&action_v, a_old);
+        if a_old == a_new { return (a_new, None) }
+        let (_, v_old) = action_v.iter().find(|(a, _)| *a == a_old).unwrap();
+        (a_new, Some(ActionChange { state:s.count(), from:a_old, to:a_new, gain:(v_new - *v_old).to_f64() }))
+    });
+    let mut diff = PolicyDiff { change: Vec::new() };
+    for (k, (a, change)) in greedy.into_iter().enumerate() {
+        p.state_action.data[k] = a;
+        diff.change.extend(change);
+    }
+    diff
+}
+
+//policy iteration one step at a time and without printing, for callers outside the cli
+//no progress bar or timing either, neither works on wasm32
+pub struct Solver<F:Float = f64> {
+    g:Graph<F>,
+    p:Policy,
+    agent_info:AgentInfo,
+    g_info:GraphInfo,
+    pub iterations:usize,
+    pub sweeps:usize,//within the current evaluation
+    pub stop:Option<Stop>,//how the last finished evaluation ended
+}
+
+impl<F:Float> Solver<F> {
+    pub fn new(params:&Params) -> Self {
+        let (agent_info, g_info) = params.info();
+        let graph_change = params.graph_change();
+        let mut g = Graph::new(&g_info);
+        g.setup(&g_info, graph_change.as_ref());
+        g.init_value(agent_info.init, agent_info.discount);
+        let p = Policy::new(&g_info);
+        Self { g, p, agent_info, g_info, iterations:0, sweeps:0, stop:None }
+    }
+
+    //value and policy on files in dir from here on, see Params::mmap
+    pub fn map_to(&mut self, dir:&str) -> std::io::Result<()> {
+        map_storage(&mut self.g, &mut self.p, dir)
+    }
+
+    //starts over from the initial v and no moves under another discount, keeping the built model
+    pub fn reset(&mut self, discount:f64) {
+        self.agent_info.discount = discount;
+        self.g.init_value(self.agent_info.init, discount);
+        self.g.action_value.iter_mut().for_each(|q| *q = F::ZERO);
+        self.p.state_action.iter_mut().for_each(|a| *a = 0);
+        self.iterations = 0;
+        self.sweeps = 0;
+        self.stop = None;
+    }
+
+    //a single evaluation sweep, the change the stopping rule measured and true once done
+    pub fn sweep(&mut self) -> (f64, bool) {
+        if self.sweeps == 0 && !self.agent_info.warm_start {
+            self.g.init_value(self.agent_info.init, self.agent_info.discount);
+        }
+        let change = evaluate_sweep(&mut self.g, &self.p, &self.agent_info);
+        self.sweeps += 1;
+        let stop = self.agent_info.stop(&change, self.sweeps);
+        if stop.is_some() { self.stop = stop }
+        (self.agent_info.stopping.measure(&change), stop.is_some())
+    }
+
+    //greedy improvement, returns the number of changed states
+    pub fn improve(&mut self) -> usize {
+        let diff = improve_policy(&mut self.p, &self.g, &self.agent_info, &self.g_info);
+        self.iterations += 1;
+        self.sweeps = 0;
+        diff.change.len()
+    }
+
+    //evaluation then improvement, returns the number of changed states
+    pub fn iterate(&mut self) -> usize {
+        while !self.sweep().1 {}
+        self.improve()
+    }
+
+    pub fn value(&self) -> Vec<Vec<F>> {
+        let range = 0..=self.g_info.state_range;
+        range.clone().map(|m| range.clone().map(|n| self.g.value[(m, n)]).collect()).collect()
+    }
+
+    pub fn policy(&self) -> Vec<Vec<i32>> {
+        let range = 0..=self.g_info.state_range;
+        range.clone().map(|m| range.clone().map(|n| self.p.state_action[(m, n)]).collect()).collect()
+    }
+}
+
+pub fn solve(params:&Params) -> Solution {
+    solve_as(params)
+}
+
+//same as solve, values kept as F throughout
+pub fn solve_as<F:Float>(params:&Params) -> Solution<F> {
+    let mut solver = Solver::new(params);
+    while solver.iterate() > 0 {}
+    Solution { value:solver.value(), policy:solver.policy(), iterations:solver.iterations }
+}
+
+//one solution per discount from a single built model, params.discount is ignored
+pub fn solve_discounts(params:&Params, discount:&[f64]) -> Vec<Solution> {
+    let mut solver = Solver::new(params);
+    discount.iter().map(|d| {
+        solver.reset(*d);
+        while solver.iterate() > 0 {}
+        Solution { value:solver.value(), policy:solver.policy(), iterations:solver.iterations }
+    }).collect()
+}
+
+//model checks on the graph these params build
+pub fn validate(params:&Params) -> Validation {
+    let (_, g_info) = params.info();
+    let mut g = Graph::<f64>::new(&g_info);
+    g.setup(&g_info, params.graph_change().as_ref());
+    g.validate(&g_info)
+}
+
+//writes the graph these params build as graphviz dot, e.g. `dot -Tsvg` on a small state_range
+pub fn write_dot(params:&Params, path:&str) -> Result<(), Box<dyn Error>> {
+    let (_, g_info) = params.info();
+    let mut g = Graph::<f64>::new(&g_info);
+    g.setup(&g_info, params.graph_change().as_ref());
+    g.to_dot(path)?;
+    println!("graph written to {}", path);
+    Ok(())
+}
+
+//P and R of the graph these params build as numpy arrays, see Tabular::dense.
+//actions run from -move_limit to move_limit
+pub fn write_npy(params:&Params, prefix:&str) -> Result<(), Box<dyn Error>> {
+    tabular(params).write_npy(prefix)?;
+    println!("model written to {}_p.npy and {}_r.npy", prefix, prefix);
+    Ok(())
+}
+
+//the same mdp for the lp, one row per packed (state, action) pair
+pub fn tabular(params:&Params) -> Tabular {
+    let (_, g_info) = params.info();
+    let mut g = Graph::<f64>::new(&g_info);
+    g.setup(&g_info, params.graph_change().as_ref());
+    let pk = &g.packed;
+    let mut mdp = Tabular::new(g.state.data.len());
+    for row in 0..pk.reward.len() {
+        let range = pk.offset[row]..pk.offset[row + 1];
+        let next = pk.target[range.clone()].iter().copied().zip(pk.prob[range].iter().copied()).collect();
+        mdp.push(row / pk.n_action, pk.reward[row], next);
+    }
+    mdp
+}
+
+//the rows of tabular(params) one state at a time, built when a solver first reads them,
+//for rtdp and other solvers that only visit part of the states. cache keeps them
+pub fn lazy_model(params:&Params, cache:bool) -> Lazy<impl FnMut(usize) -> Vec<Row>> {
+    let (_, gi) = params.info();
+    let change = params.graph_change();
+    let w = (gi.state_range + 1) as usize;
+    Lazy::new(w * w, cache, move |s| {
+        let (m, n) = ((s % w) as i32, (s / w) as i32);
+        let (desc, state_reward) = Graph::<f64>::state_desc(m, n, &gi, change.as_ref());
+        let expected = (m as f64 - desc.rent.0, n as f64 - desc.rent.1);
+        (-gi.move_limit..=gi.move_limit).map(|k| {
+            let to = Graph::<f64>::move_target(expected, k, &gi);
+            let reward = state_reward + Graph::<f64>::action_reward(k, change.as_ref());
+            Row { state:s, reward, next:vec!((to.0 as usize + to.1 as usize * w, 1.0)) }
+        }).collect()
+    })
+}
+
+//discounted on-policy distribution of the solution's policy from a uniform start, laid out
+//like Solution::value. the tabular states run with location 0 fastest
+pub fn occupancy(params:&Params, solution:&Solution) -> Vec<Vec<f64>> {
+    let mdp = tabular(params);
+    let w = (params.state_range + 1) as usize;
+    let action:Vec<usize> = (0..mdp.states)
+        .map(|k| (solution.policy[k % w][k / w] + params.move_limit) as usize)
+        .collect();
+    let start = vec![1.0 / mdp.states as f64; mdp.states];
+    let mu = mdp.occupancy(&action, &start, params.discount, StoppingRule::MaxNorm(1e-12));
+    (0..w).map(|m| (0..w).map(|n| mu[n * w + m]).collect()).collect()
+}
+
+//gain-optimal policy of the undiscounted continuing task, the footnote to example 4.2.
+//value holds differential values relative to (0, 0), the discount of params is ignored.
+//max_iter is the sweeps per evaluation, the stopping tolerance the span of Tv - v
+pub fn solve_average(params:&Params) -> (f64, Solution) {
+    let mdp = tabular(params);
+    let avg = mdp.average_policy_iteration(0, params.max_iter.max(1) as usize, AVERAGE_TAU, params.stopping.tolerance());
+    let w = (params.state_range + 1) as usize;
+    let value = (0..w).map(|m| (0..w).map(|n| avg.value[n * w + m]).collect()).collect();
+    let policy = (0..w).map(|m| (0..w).map(|n| avg.action[n * w + m] as i32 - params.move_limit).collect()).collect();
+    (avg.rate, Solution { value, policy, iterations:avg.iterations })
+}
+
+//reward rate of a policy laid out like Solution::policy from every state, laid out the same
+pub fn average_reward(params:&Params, policy:&[Vec<i32>]) -> Vec<Vec<f64>> {
+    let mdp = tabular(params);
+    let w = (params.state_range + 1) as usize;
+    let action:Vec<usize> = (0..mdp.states)
+        .map(|k| (policy[k % w][k / w] + params.move_limit) as usize)
+        .collect();
+    let gain = mdp.gain(&action, AVERAGE_TAU, StoppingRule::MaxNorm(1e-9));
+    (0..w).map(|m| (0..w).map(|n| gain[n * w + m]).collect()).collect()
+}
+
+//exact optimal values by linear programming, laid out like Solution::value
+pub fn solve_lp(params:&Params) -> Result<Vec<Vec<f64>>, Box<dyn Error>> {
+    let s = params.state_range;
+    let mut value = NdVec2::new((0, s), (0, s));
+    value.data = lp::solve(&tabular(params), params.discount)?;
+    Ok((0..=s).map(|m| (0..=s).map(|n| value[(m, n)]).collect()).collect())
+}
+
+//episodes from every state under the policy iteration result, each cut at the step where
+//γ^t falls below 1e-3. needs discount < 1
+pub fn cross_validate(params:&Params, episodes:usize, seed:u64) -> CrossValidation {
+    assert!(params.discount < 1.0, "cross validation needs discount < 1");
+    let solution = solve(params);
+    let (_, g_info) = params.info();
+    let horizon = (1e-3f64.ln() / params.discount.ln()).ceil() as usize;
+    let mut env = Rental { g_info, change:params.graph_change(), start:(0, 0), state:(0, 0) };
+    let mut rng = rng::from_seed(seed);
+    let mut cv = CrossValidation { model:Vec::new(), mean:Vec::new(), stderr:Vec::new(), horizon };
+    let range = 0..=params.state_range;
+    for n in range.clone() {
+        for m in range.clone() {
+            env.start = (m, n);
+            let policy = |s:&(i32, i32), _:&mut SeededRng| solution.policy[s.0 as usize][s.1 as usize];
+            let (mean, stderr) = rollout::mc_return(&mut env, policy, &mut rng, episodes, horizon, params.discount);
+            cv.model.push(solution.value[m as usize][n as usize]);
+            cv.mean.push(mean);
+            cv.stderr.push(stderr);
+        }
+    }
+    cv
+}
+
+fn write_json<F:Float>(path:&str, g:&Graph<F>, p:&Policy, params:&Params, history:&RunHistory) -> Result<(), Box<dyn Error>> {
+    let export = Export {
+        params,
+        iterations:history.iteration.len(),
+        runtime:history.iteration.last().map_or(0.0, |r| r.elapsed),
+        timing:history.timing(),
+        action:g.action.iter().zip(-params.move_limit..)
+            .map(|(a, moved)| ActionExport { name:a.name(), moved, reward:a.reward })
+            .collect(),
+        state:g.state.iter().map(|s| StateExport {
+            name:s.name(), count:s.count(), reward:s.reward, value:g.value[s.count()],
+            policy:p.state_action[s.count()],
+            transition:s.transition.iter()
+                .map(|t| TransitionExport { action:t.action, to:t.to, prob:t.prob })
+                .collect(),
+        }).collect(),
     };
-    let graph_change = GraphChange {
-        free_shuttle:1,
-        parking_limit:10,
-        parking_cost:4,
+    serde_json::to_writer_pretty(BufWriter::new(File::create(path)?), &export)?;
+    Ok(())
+}
+
+//how far policy iteration with these params lands from the exact optimum
+pub fn run_lp(params:&Params) -> Result<(), Box<dyn Error>> {
+    let exact = solve_lp(params)?;
+    let s = solve(params);
+    let error = exact.iter().zip(s.value.iter()).map(|(e, v)| lp::max_error(e, v)).fold(0.0, f64::max);
+    println!("{} iterations, max |v - v*| {:.6}", s.iterations, error);
+    Ok(())
+}
+
+//how far the expected-count model is from sampled rentals and returns under its own policy
+pub fn run_mc(params:&Params, episodes:usize, seed:u64) -> Result<(), Box<dyn Error>> {
+    let cv = cross_validate(params, episodes, seed);
+    let (_, g_info) = params.info();
+    let g = Graph::<f64>::new(&g_info);
+    let error:Vec<f64> = cv.mean.iter().zip(cv.model.iter()).map(|(m, v)| m - v).collect();
+    println!("sampled return - dp value, {} episodes of {} steps per state:", episodes, cv.horizon);
+    g.print_grid(&g_info, 7, &error);
+    let n = error.len() as f64;
+    let beyond = error.iter().zip(cv.stderr.iter()).filter(|(e, se)| e.abs() > 3.0 * *se).count();
+    println!("mean |error| {:.2}, max |error| {:.2}, mean error {:+.2}, mean stderr {:.2}",
+        error.iter().map(|e| e.abs()).sum::<f64>() / n,
+        error.iter().map(|e| e.abs()).fold(0.0, f64::max),
+        error.iter().sum::<f64>() / n,
+        cv.stderr.iter().sum::<f64>() / n);
+    println!("{} of {} states differ by more than 3 standard errors", beyond, error.len());
+    Ok(())
+}
+
+//the average-reward policy next to the discounted one, and the reward rate each earns
+pub fn run_average(params:&Params) -> Result<(), Box<dyn Error>> {
+    let (rate, avg) = solve_average(params);
+    let discounted = solve(params);
+    let (_, g_info) = params.info();
+    let mut g = Graph::<f64>::new(&g_info);
+    g.setup(&g_info, params.graph_change().as_ref());
+    let w = avg.value.len();
+    let flat = |grid:&[Vec<i32>]| {
+        let mut p = Policy::new(&g_info);
+        for k in 0..w * w {
+            p.state_action.data[k] = grid[k % w][k / w];
+        }
+        p
     };
-    //changes switch
-    let option_change = 
-        Some(&graph_change);
-        // None;
-    let mut g = Graph::new(&g_info);
-    g.setup(&g_info, option_change);
+    println!("differential values, relative to (0, 0):");
+    let value:Vec<f64> = (0..w * w).map(|k| avg.value[k % w][k / w]).collect();
+    g.print_grid(&g_info, 7, &value);
+    println!("average-reward policy, {} iterations:", avg.iterations);
+    g.print_policy(&flat(&avg.policy), &g_info);
+    println!("discounted policy, γ = {}:", params.discount);
+    g.print_policy(&flat(&discounted.policy), &g_info);
+    let differ = (0..w * w).filter(|k| avg.policy[k % w][k / w] != discounted.policy[k % w][k / w]).count();
+    println!("{} of {} states act differently", differ, w * w);
+    println!("reward rate of the average-reward policy {:.4}", rate);
+    let gain:Vec<f64> = average_reward(params, &discounted.policy).into_iter().flatten().collect();
+    let lo = gain.iter().cloned().fold(f64::INFINITY, f64::min);
+    let hi = gain.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    println!("reward rate of the discounted policy {:.4} to {:.4} depending on the start", lo, hi);
+    Ok(())
+}
+
+//the optimal policy for every discount in turn and how much of it each step in γ changes
+pub fn run_discounts(params:&Params, discount:&[f64]) -> Result<(), Box<dyn Error>> {
+    let t = Instant::now();
+    let mut solver = Solver::<f64>::new(params);
+    if let Some(dir) = &params.mmap { solver.map_to(dir)? }
+    println!("model built in {:.1}ms", deterministic::elapsed(&t) * 1e3);
+    let (_, g_info) = params.info();
+    let w = (params.state_range + 1) as usize;
+    let mut last:Option<Vec<Vec<i32>>> = None;
+    for d in discount {
+        let t = Instant::now();
+        solver.reset(*d);
+        while solver.iterate() > 0 {}
+        let value = solver.value();
+        let mean = value.iter().flatten().sum::<f64>() / (w * w) as f64;
+        println!("γ = {}: {} iterations in {:.1}ms, mean value {:.1}", d, solver.iterations, deterministic::elapsed(&t) * 1e3, mean);
+        solver.g.print_policy(&solver.p, &g_info);
+        let policy = solver.policy();
+        if let Some(last) = &last {
+            let changed = (0..w * w).filter(|k| policy[k / w][k % w] != last[k / w][k % w]).count();
+            println!("{} of {} states act differently than at the previous γ", changed, w * w);
+        }
+        last = Some(policy);
+    }
+    Ok(())
+}
+
+//time and allocations of one phase of the solver
+struct Phase {
+    pub seconds:f64,
+    pub allocations:usize,
+    pub bytes:usize,
+}
+
+impl Phase {
+    fn new() -> Self {
+        Self { seconds:0.0, allocations:0, bytes:0 }
+    }
+
+    //runs f and adds what it took
+    fn time<T>(&mut self, f:impl FnOnce() -> T) -> T {
+        let (allocations, bytes) = alloc::count();
+        let t = Instant::now();
+        let v = f();
+        self.seconds += deterministic::elapsed(&t);
+        let (a, b) = alloc::count();
+        self.allocations += a - allocations;
+        self.bytes += b - bytes;
+        v
+    }
+
+    //states per second over `states` state visits, 0 when no time was measured
+    fn print(&self, label:&str, states:usize) {
+        let rate = if self.seconds > 0.0 { states as f64 / self.seconds } else { 0.0 };
+        println!("  {:<12}{:>10.1}ms{:>14.0} states/s{:>10} allocations{:>12.1}KiB",
+            label, self.seconds * 1e3, rate, self.allocations, self.bytes as f64 / 1024.0);
+    }
+}
+
+//graph construction, evaluation sweeps and improvement timed apart over a full policy
+//iteration for every state_range, allocations only count in the rl_exercise binary
+pub fn run_bench(params:&Params, state_range:&[i32]) -> Result<(), Box<dyn Error>> {
+    for range in state_range {
+        let params = Params { state_range:*range, ..params.clone() };
+        let states = ((range + 1) * (range + 1)) as usize;
+        let (mut build, mut sweep, mut improve) = (Phase::new(), Phase::new(), Phase::new());
+        let mut solver = build.time(|| Solver::<f64>::new(&params));
+        if let Some(dir) = &params.mmap { solver.map_to(dir)? }
+        let mut sweeps = 0;
+        loop {
+            while !sweep.time(|| solver.sweep()).1 {}
+            sweeps += solver.sweeps;
+            if improve.time(|| solver.improve()) == 0 { break }
+        }
+        println!("state_range {}: {} states, {} iterations, {} sweeps", range, states, solver.iterations, sweeps);
+        build.print("build", states);
+        sweep.print("evaluation", states * sweeps);
+        improve.print("improvement", states * solver.iterations);
+    }
+    Ok(())
+}
+
+//value and on-policy distribution of the policy iteration result as heatmaps in 4_7_occupancy.png,
+//cars at location 0 along x
+#[cfg(not(target_arch = "wasm32"))]
+pub fn run_occupancy(params:&Params) -> Result<(), Box<dyn Error>> {
+    let solution = solve(params);
+    let mu = occupancy(params, &solution);
+    let (_, g_info) = params.info();
+    let g = Graph::<f64>::new(&g_info);
+    let w = mu.len();
+    //printed like the value grids, location 1 by row
+    let flat:Vec<f64> = (0..w * w).map(|k| 100.0 * mu[k % w][k / w]).collect();
+    println!("on-policy distribution, %:");
+    g.print_grid(&g_info, 6, &flat);
+    let canvas = BitMapBackend::new("4_7_occupancy.png", (2160, 1080)).into_drawing_area();
+    canvas.fill(&WHITE)?;
+    let canvas_split = canvas.split_evenly((1, 2));
+    heatmap::draw("value", &solution.value, &canvas_split[0])?;
+    heatmap::draw("on-policy distribution", &mu, &canvas_split[1])?;
+    canvas.present()?;
+    Ok(())
+}
+
+//output is an optional json file with the whole solved graph
+pub fn run(params:&Params, diff_report:DiffReport, output:Option<&str>) -> Result<(), Box<dyn Error>> {
+    run_as::<f64>(params, diff_report, output)
+}
+
+pub fn run_as<F:Float>(params:&Params, diff_report:DiffReport, output:Option<&str>) -> Result<(), Box<dyn Error>> {
+    let (agent_info, g_info) = params.info();
+    let graph_change = params.graph_change();
+    let t = Instant::now();
+    let mut g:Graph<F> = Graph::new(&g_info);
+    g.setup(&g_info, graph_change.as_ref());
+    let setup = deterministic::elapsed(&t);
+    Graph::<F>::print_validation(&g.validate(&g_info));
     g.print_reward(&g_info);
     // g.print_info(&graph_info, &p);
     let mut p = Policy::new(&g_info);
+    g.init_value(agent_info.init, agent_info.discount);
+    if let Some(dir) = &params.mmap {
+        map_storage(&mut g, &mut p, dir)?;
+        println!("value and policy mapped to {}", dir);
+    }
+    let mut history = RunHistory::new();
+    history.setup = setup;
     loop {
-        evaluate_policy(&mut g, &p, &agent_info);
+        if !agent_info.warm_start && !history.iteration.is_empty() {
+            g.init_value(agent_info.init, agent_info.discount);
+        }
+        let (stop, sweeps, delta) = evaluate_policy(&mut g, &p, &agent_info, &mut history);
+        println!("evaluation: {} after {} sweeps, delta {:.4}", stop.label(), sweeps, delta);
         // g.print_state();
-        let stable = improve_policy(&mut p, &g, &agent_info, &g_info);
-        // g.print_state(&g_info);
+        println!("improvement:");
+        let t = Instant::now();
+        let diff = improve_policy(&mut p, &g, &agent_info, &g_info);
+        history.record_iteration(diff.change.len(), deterministic::elapsed(&t));
+        g.print_state(&g_info);
         g.print_policy(&p, &g_info);
-        if stable { break }
+        diff.print(diff_report, &g_info);
+        if diff.change.is_empty() { break }
     }
     println!("finish");
     g.print_info(&g_info, &p, agent_info.discount);
+    PolicyStats::new(&p.state_action.data, g_info.move_limit).print();
+    history.print_summary();
+    history.write_csv("4_7_history")?;
+    if let Some(path) = output {
+        write_json(path, &g, &p, params, &history)?;
+        println!("solution written to {}", path);
+    }
     Ok(())
-}
\ No newline at end of file
+}
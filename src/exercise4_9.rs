@@ -1,13 +1,25 @@
+//only solve() is built for wasm32, the rest backs the png-writing run()
+#![cfg_attr(target_arch = "wasm32", allow(dead_code, unused_imports))]
+
 use std::cmp::{ min, max };
 use std::error::Error;
 use plotters::{prelude::*, coord::Shift};
 
+use rand::prelude::*;
+
+use crate::progress;
+use crate::rng::{ self, SeededRng };
+use crate::env::Environment;
+use crate::tabular::Tabular;
+use crate::rollout;
+use crate::stopping::{ Change, StoppingRule };
+
 struct Graph {
     pub state:Vec<State>,
 }
 
 struct GraphInfo {
-    pub theta:f64,
+    pub stopping:StoppingRule,
     pub p_win:f64,
     pub state_range:i32,
     pub state_active:(i32, i32),
@@ -26,6 +38,14 @@ struct Policy {
     pub action_max:i32,
 }
 
+//the coin flips themselves, to check the DP solution by simulation
+struct Game {
+    pub p_win:f64,
+    pub goal:i32,
+    pub start:i32,
+    pub capital:i32,
+}
+
 impl State {
     fn new(capital:i32, reward:f64) -> Self {
         Self { capital, reward, state_v:0.0 }
@@ -66,9 +86,8 @@ impl Graph {
     // }
 
     fn print_policy(&self, p:&Policy, gi:&GraphInfo) {
-        let p_v = &p.state_action;
-        for k in 0..p_v.len() {
-            print!("\t{}: {}", k, p_v[k]);
+        for (k, a) in p.state_action.iter().enumerate() {
+            print!("\t{}: {}", k, a);
             if k> 0 && k % gi.print_per_line == 0 { println!(); }
         }
         println!();
@@ -91,16 +110,58 @@ impl Graph {
 
 impl Policy {
     fn new(gi:&GraphInfo) -> Self {
-        let mut state_action:Vec<i32> = Vec::new();
-        state_action.resize((gi.state_range + 1) as usize, 0);
+        let state_action:Vec<i32> = vec![0; (gi.state_range + 1) as usize];
         Self { state_action, state_v_max:0.0, action_max:0 }
     }
 }
 
+impl Environment for Game {
+    type State = i32;
+    type Action = i32;
+
+    fn reset(&mut self, _:&mut SeededRng) -> i32 {
+        self.capital = self.start;
+        self.capital
+    }
+
+    fn step(&mut self, a:&i32, rng:&mut SeededRng) -> (i32, f64, bool) {
+        self.capital = if rng.gen::<f64>() < self.p_win { self.capital + a } else { self.capital - a };
+        let win = self.capital >= self.goal;
+        (self.capital, if win { 1.0 } else { 0.0 }, win || self.capital <= 0)
+    }
+
+    fn actions(&self, s:&i32) -> Vec<i32> {
+        (1..=min(*s, self.goal - *s)).collect()
+    }
+}
+
+//one in-place sweep over the active states, returns the range of v' - v. each backup reads
+//the graph before its own write, so later states already see the new values of earlier ones
+fn value_sweep(g:&mut Graph, gi:&GraphInfo) -> Change {
+    let (s_min, s_max) = gi.state_active;
+    //capital 0 and the goal never change, which the span has to see
+    let mut change = Change::new();
+    change.add(0.0);
+    for k in s_min..=s_max {
+        let s = &g.state[k as usize];
+        let v_old = s.state_v;
+        let bet_max = min(s.capital, gi.state_range - s.capital);
+        let v_new = (1..=bet_max).map(|a| g.expected_reward(s, a, gi))
+            .max_by(|x, y| x.total_cmp(y)).unwrap();
+        g.state[k as usize].state_v = v_new;
+        change.add(v_new - v_old);
+    }
+    change
+}
+
+//ties go to the smallest bet
+fn greedy_bet(g:&Graph, s:&State, gi:&GraphInfo) -> (i32, f64) {
+    let bet_max = min(s.capital, gi.state_range - s.capital);
+    (1..=bet_max).rev().map(|a| (a, g.expected_reward(s, a, gi)))
+        .max_by(|(_, x), (_, y)| x.total_cmp(y)).unwrap()
+}
+
 fn value_iteration(g:&mut Graph, gi:&GraphInfo, canvas:&DrawingArea<BitMapBackend, Shift>) -> Result<(), Box<dyn Error>> {
-    let pg:*const Graph = g;
-    //hack to grant shared access to graph
-    let gs = unsafe { &(*pg) };
     let (s_min, s_max) = gi.state_active;
     let mut chart = ChartBuilder::on(canvas)
         .margin(5)
@@ -110,20 +171,12 @@ fn value_iteration(g:&mut Graph, gi:&GraphInfo, canvas:&DrawingArea<BitMapBacken
     chart.configure_mesh().draw()?;
     let mut sweep = 0;
     let sweep_band = 10;
+    let pb = progress::sweep_unbounded();
     loop {
-        let mut delta:f64 = 0.0;
-        for k in s_min..=s_max {
-            let s = &mut g.state[k as usize];
-            let v_old = s.state_v;
-            let bet_max = min(s.capital, gi.state_range - s.capital);
-            let v_new = (1..=bet_max).map(|a| gs.expected_reward(s, a, gi))
-                .max_by(|x, y| x.total_cmp(y)).unwrap();
-            s.state_v = v_new;
-            delta = delta.max((v_new - v_old).abs());
-        }
+        let change = value_sweep(g, gi);
         let sweepf = ((1.0f32).min(sweep as f32 / sweep_band as f32) * 255.0).round() as u8;
-        let color = if sweep % 2 == 0 { RGBColor { 0:sweepf, 1:128, 2:128 } }
-            else { RGBColor { 0:128, 1:sweepf, 2:128 } };
+        let color = if sweep % 2 == 0 { RGBColor(sweepf, 128, 128) }
+            else { RGBColor(128, sweepf, 128) };
         let line = chart.draw_series(LineSeries::new(
             (s_min..s_max).map(|i| (i, g.state[i as usize].state_v))
             , &color))?;
@@ -131,14 +184,17 @@ fn value_iteration(g:&mut Graph, gi:&GraphInfo, canvas:&DrawingArea<BitMapBacken
             line.label(format!("sweep {}", sweep))
             .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], color));
         }
-        // gs.print_state(&gi);
+        // g.print_state(&gi);
         sweep += 1;
-        if delta < gi.theta { break }
+        pb.inc(1);
+        pb.set_message(progress::delta_msg(gi.stopping.measure(&change), gi.stopping.threshold(1.0)));
+        if gi.stopping.met(&change, 1.0) { break }
     }
+    pb.finish_and_clear();
     chart
         .configure_series_labels()
-        .background_style(&WHITE.mix(0.8))
-        .border_style(&BLACK)
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
         .draw()?;
     Ok(())
 }
@@ -151,8 +207,7 @@ fn check_policy(p:&mut Policy, g:&Graph, gi:&GraphInfo) {
         let ki = k as usize;
         let s = &g.state[ki];
         let bet_max = min(s.capital, gi.state_range - s.capital);
-        let (a, v) = (1..=bet_max).rev().map(|a| (a, g.expected_reward(s, a, gi)))
-            .max_by(|(_, x), (_, y)| x.total_cmp(y)).unwrap();
+        let (a, v) = greedy_bet(g, s, gi);
         println!("{} {}|{:.4}", k, a, s.state_v);
         (1..=bet_max).map(|a| (a, g.expected_reward(s, a, gi)))
             .for_each(|(a, v)| println!("{}:{}", a, v));
@@ -164,10 +219,52 @@ fn check_policy(p:&mut Policy, g:&Graph, gi:&GraphInfo) {
     p.action_max = a_max;
 }
 
-pub fn run() -> Result<(), Box<dyn Error>> {
+//the sampled win rate under the greedy policy should match v(s)
+fn validate_policy(p:&Policy, g:&Graph, gi:&GraphInfo, rng:&mut SeededRng) {
+    let episode = 10000;
+    for start in [25, 50, 75] {
+        let mut game = Game { p_win:gi.p_win, goal:gi.state_range, start, capital:start };
+        let (mean, err) = rollout::mc_return(&mut game, |s, _| p.state_action[*s as usize], rng, episode, usize::MAX, 1.0);
+        println!("capital {}: v {:.4} simulated {:.4} ± {:.4}", start, g.state[start as usize].state_v, mean, err);
+    }
+}
+
+//value iteration without plotting, value and greedy bet indexed by capital 0..=goal
+pub fn solve(p_win:f64, stopping:StoppingRule, goal:i32) -> (Vec<f64>, Vec<i32>) {
+    let gi = GraphInfo {
+        stopping, p_win,
+        state_range:goal,
+        state_active:(1, goal - 1),
+        print_per_line:10,
+    };
+    let mut g = Graph::new();
+    g.setup(&gi);
+    //undiscounted
+    while !gi.stopping.met(&value_sweep(&mut g, &gi), 1.0) {}
+    let value = g.state.iter().map(|s| s.state_v).collect();
+    let (s_min, s_max) = gi.state_active;
+    let policy = (0..=goal).map(|k| if k < s_min || k > s_max { 0 } else { greedy_bet(&g, &g.state[k as usize], &gi).0 }).collect();
+    (value, policy)
+}
+
+//for the lp, indexed by capital 0..=goal. 0 and the goal get no row, so reaching the
+//goal pays 1 as a reward instead of as its value
+pub fn tabular(p_win:f64, goal:i32) -> Tabular {
+    let mut mdp = Tabular::new(goal as usize + 1);
+    for s in 1..goal {
+        for a in 1..=min(s, goal - s) {
+            let reward = if s + a == goal { p_win } else { 0.0 };
+            mdp.push(s as usize, reward, vec!(((s + a) as usize, p_win), ((s - a) as usize, 1.0 - p_win)));
+        }
+    }
+    mdp
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn run(seed:u64) -> Result<(), Box<dyn Error>> {
     let mut g = Graph::new();
     let g_info = GraphInfo {
-        theta: 0.001,
+        stopping: StoppingRule::MaxNorm(0.001),
         p_win: 0.55,
         state_range: 100,
         state_active: (1, 99),
@@ -183,6 +280,7 @@ pub fn run() -> Result<(), Box<dyn Error>> {
     check_policy(&mut p, &g, &g_info);
     g.print_policy(&p, &g_info);
     g.draw_policy(&p, &g_info, &canvas_split[1])?;
+    validate_policy(&p, &g, &g_info, &mut rng::from_seed(seed));
     canvas.present()?;
     Ok(())
 }
\ No newline at end of file
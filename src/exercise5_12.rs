@@ -1,10 +1,20 @@
 use std::cmp::{ min, max };
-use std::collections::{BTreeMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::collections::btree_map::Entry::{ Vacant, Occupied };
 use std::error::Error;
 use std::time::Instant;
 use rand::prelude::*;
 
+use crate::deterministic;
+use crate::env::Environment;
+use crate::mc::Visit;
+use crate::progress;
+use crate::rng::{ self, SeededRng };
+use crate::rollout;
+use crate::rtdp::Rtdp;
+use crate::stopping::StoppingRule;
+use crate::tabular::Tabular;
+
 type Vec2 = (i32, i32);
 type State = (Vec2, Vec2);
 type Action = Vec2;
@@ -24,8 +34,17 @@ struct ControlInfo {
     pub epsilon:f64,
     pub gamma:f64,
     pub horizon:usize,
-    pub estimator:i32,
+    pub estimator:Estimator,
     pub field:i32,
+    pub visit:Visit,//of a (state, action) pair within an episode
+}
+
+//how q weighs the returns of the behavior policy
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Estimator {
+    Ordinary,
+    Weighted,
+    WeightedTruncated,//discounting-aware, section 5.8
 }
 
 struct AgentInfo {
@@ -42,8 +61,18 @@ struct Agent<'a> {
     pub position:Vec2,
 }
 
+//the car on the track as an environment, step_reward per step. leaving the track puts the car
+//back on a random start cell without ending the episode, crash is the step that last did
+struct Track<'a> {
+    pub field:&'a Field,
+    pub agent:Agent<'a>,
+    pub start:usize,//cell of the next reset, see Field::indexed_start
+    pub step:usize,
+    pub crash:usize,
+}
+
+//the steps from the last crash on
 struct Episode {
-    rng:ThreadRng,
     pub state:Vec<State>,
     pub action:Vec<Action>,
 }
@@ -110,7 +139,7 @@ impl Field {
         self.corner = 20;
     }
 
-    fn random_start(&self, rng:&mut ThreadRng) -> Vec2 {
+    fn random_start(&self, rng:&mut SeededRng) -> Vec2 {
         let start = &self.boundary[self.start_line as usize];
         let r = rng.gen_range(start.0..=start.1);
         (r, self.start_line)
@@ -151,7 +180,12 @@ impl Field {
         }
     }
 
-    fn reset_to_start(&self, p:&mut Vec2, v:&mut Vec2, rng:&mut ThreadRng) {
+    fn start_cells(&self) -> Vec<Vec2> {
+        let start = &self.boundary[self.start_line as usize];
+        (start.0..=start.1).map(|x| (x, self.start_line)).collect()
+    }
+
+    fn reset_to_start(&self, p:&mut Vec2, v:&mut Vec2, rng:&mut SeededRng) {
         *v = (0, 0);
         *p = self.random_start(rng);
     }
@@ -188,8 +222,8 @@ impl<'a> Agent<'a> {
         v.0 = min(max(v.0 + v_a.0, v_min), v_max);
         v.1 = min(max(v.1 + v_a.1, v_min), v_max);
         let p = &mut self.position;
-        p.0 = p.0 + v.0;
-        p.1 = p.1 + v.1;
+        p.0 += v.0;
+        p.1 += v.1;
         (p, v)
     }
 
@@ -198,76 +232,81 @@ impl<'a> Agent<'a> {
     }
 }
 
-impl Episode {
-    fn new() -> Self {
-        Self { state:Vec::new(), action:Vec::new(), rng:rand::thread_rng() }
+impl Environment for Track<'_> {
+    type State = State;
+    type Action = Action;
+
+    fn reset(&mut self, _:&mut SeededRng) -> State {
+        self.agent.velocity = (0, 0);
+        self.agent.position = self.field.indexed_start(self.start);
+        (self.step, self.crash) = (0, 0);
+        self.agent.state()
     }
 
-    fn step(&mut self, b:&mut Policy, f:&Field, a:&mut Agent, c_info:&ControlInfo) -> (State, Action, bool) {
-        let rng = &mut self.rng;
-        let s = a.state();
-        let info = a.info;
+    fn step(&mut self, act:&Action, rng:&mut SeededRng) -> (State, f64, bool) {
+        let p_s = self.agent.position;
+        let (p, v) = self.agent.action(act);
+        let (outside, finish) = self.field.intersect(&p_s, p, v);
+        if outside && !finish {
+            self.field.reset_to_start(p, v, rng);
+            self.crash = self.step;
+        }
+        self.step += 1;
+        (self.agent.state(), self.agent.info.step_reward, finish)
+    }
+
+    fn actions(&self, _:&State) -> Vec<Action> {
+        let (lo, hi) = self.agent.info.action;
+        (lo..=hi).flat_map(|y| (lo..=hi).map(move |x| (x, y))).collect()
+    }
+}
+
+impl Episode {
+    //ε-greedy on b, untried states head up the track then right past the corner. a moving car
+    //keeps its velocity with p_vel_inc0 whatever is picked, and that is the action recorded
+    fn select(s:&State, b:&Policy, f:&Field, info:&AgentInfo, c_info:&ControlInfo, rng:&mut SeededRng) -> Action {
         let a_min = info.action.0;
-        let (act_r, act_s)= info.a_space;
+        let (act_r, act_s) = info.a_space;
         let v0 = -(s.1).0;
         let v1 = -(s.1).1;
         let v_zero = v0 == 0 && v1 == 0;
-        let act:Action;
         let r:f64 = rng.gen();
-        if r < info.p_vel_inc0 && !v_zero {
-            act = (0, 0);
-        }
-        else {
-            let r:f64 = rng.gen();
-            if r < c_info.epsilon {
-                //equiprobable explore
-                let mut aa = (rng.gen::<f32>() * act_s) as i32;
-                let skip = v0 - a_min + (v1 - a_min) * act_r;
-                if aa >= skip {//velocity will become (0, 0)
-                    aa += 1;
-                }
-                act = (aa % act_r + a_min, aa / act_r + a_min);
-            }
-            else {
-                //greedy with policy
-                act = match b.state_action.get(&s) {
-                    Some(v) => *v,
-                    None => {
-                        let y = (s.0).1;
-                        if y > f.corner { (1, 0) }
-                        else { (0, 1) }
-                    },
-                };
+        if r < info.p_vel_inc0 && !v_zero { return (0, 0) }
+        let r:f64 = rng.gen();
+        if r < c_info.epsilon {
+            //equiprobable explore
+            let mut aa = (rng.gen::<f32>() * act_s) as i32;
+            let skip = v0 - a_min + (v1 - a_min) * act_r;
+            if aa >= skip {//velocity will become (0, 0)
+                aa += 1;
             }
+            return (aa % act_r + a_min, aa / act_r + a_min)
         }
-        let p_s = a.position;
-        let (p, v) = a.action(&act);
-        let (outside, finish) = f.intersect(&p_s, p, v);
-        if outside && !finish {
-            f.reset_to_start(p, v, rng);
-            //clear previous failed trajectory
-            //but keeps the boundary state for feedback
-            self.state.clear();
-            self.action.clear();
-        }
-        (s, act, finish)
-    }
-
-    fn generate(&mut self, i:usize, b:&mut Policy, f:&Field, a:&mut Agent, c_info:&ControlInfo) {
-        self.state.clear();
-        self.action.clear();
-        a.velocity = (0, 0);
-        a.position = f.indexed_start(i);
-        let mut finish = false;
-        while !finish {
-            let s:State;
-            let act:Action;
-            (s, act, finish) = self.step(b, f, a, c_info);
-            self.state.push(s);
-            self.action.push(act);
-            // println!("{:?}:{:?}->{:?}", s, act, a.state());
+        //greedy with policy
+        match b.state_action.get(s) {
+            Some(v) => *v,
+            None => {
+                let y = (s.0).1;
+                if y > f.corner { (1, 0) }
+                else { (0, 1) }
+            },
         }
-        // println!("episode generated");
+    }
+
+    //from start cell i until the finish line, only the steps after the last crash are kept,
+    //the crash itself included for its feedback
+    fn generate(i:usize, b:&Policy, track:&mut Track, c_info:&ControlInfo, rng:&mut SeededRng) -> Self {
+        track.start = i;
+        let (f, info) = (track.field, track.agent.info);
+        let trajectory = rollout::simulate_episode(track, |s, rng| Self::select(s, b, f, info, c_info, rng), rng, usize::MAX);
+        let crash = track.crash;
+        Self { state:trajectory.state[crash..trajectory.steps()].to_vec(), action:trajectory.action[crash..].to_vec() }
+    }
+
+    //which steps update their (state, action) pair
+    fn visit_mask(&self, visit:Visit) -> Vec<bool> {
+        let key:Vec<(State, Action)> = self.state.iter().copied().zip(self.action.iter().copied()).collect();
+        visit.mask(&key)
     }
 }
 
@@ -280,40 +319,57 @@ impl<'a> Graph<'a> {
         self.g = 0.0;
         self.w = 1.0;
         match c_info.estimator {
-            //weighted importance sampling
-            0 => self.mc_control_wis(ep, a_info, c_info, b),
-            //weighted truncated importance sampling
-            1 => self.mc_control_wtis(ep, a_info, c_info, b),
-            _ => todo!()
+            Estimator::Ordinary => self.mc_control_ois(ep, a_info, c_info, b),
+            Estimator::Weighted => self.mc_control_wis(ep, a_info, c_info, b),
+            Estimator::WeightedTruncated => self.mc_control_wtis(ep, a_info, c_info, b),
         }
     }
 
-    fn mc_control_wis(&mut self, ep:&Episode, a_info:&AgentInfo, c_info:&ControlInfo, b:Option<&Graph>) {
+    //q.w counts the returns, the average is over ρ G
+    fn mc_control_ois(&mut self, ep:&Episode, a_info:&AgentInfo, c_info:&ControlInfo, b:Option<&Graph>) {
+        let counted = ep.visit_mask(c_info.visit);
         for k in (0..ep.state.len()).rev() {
             let s = &ep.state[k];
             let a = &ep.action[k];
             let r = k as f64 * a_info.step_reward;
             self.g += r;
-            let a_map = match self.q.entry(*s) {
-                Vacant(v) => v.insert(BTreeMap::new()),
-                Occupied(v) => v.into_mut(),
-            };
-            let q = match a_map.entry(*a) {
-                Vacant(v) => v.insert(ActionValue::new()),
-                Occupied(v) => v.into_mut(),
+            if counted[k] {
+                let (w, g) = (self.w, self.g);
+                let q = self.entry(s, a);
+                q.w += 1.0;
+                q.v += (w * g - q.v) / q.w;
+            }
+            let a_match = match self.improve_policy(s) {
+                Some(v) => v == a,
+                None => false,
             };
-            q.w += self.w;
-            q.v += self.w * (self.g - q.v) / q.w;
+            if let Some(v) = b {
+                if !a_match { return }
+                self.w *= 1.0 / v.p_epsilon(s, a, c_info);
+            }
+        }
+    }
+
+    fn mc_control_wis(&mut self, ep:&Episode, a_info:&AgentInfo, c_info:&ControlInfo, b:Option<&Graph>) {
+        let counted = ep.visit_mask(c_info.visit);
+        for k in (0..ep.state.len()).rev() {
+            let s = &ep.state[k];
+            let a = &ep.action[k];
+            let r = k as f64 * a_info.step_reward;
+            self.g += r;
+            if counted[k] {
+                let (w, g) = (self.w, self.g);
+                let q = self.entry(s, a);
+                q.w += w;
+                q.v += w * (g - q.v) / q.w;
+            }
             let a_match = match self.improve_policy(s) {
                 Some(v) => v == a,
                 None => false,
             };
-            match b {
-                Some(v) => {
-                    if !a_match { return }
-                    self.w *= 1.0 / v.p_epsilon(s, a, c_info);
-                },
-                None => {}
+            if let Some(v) = b {
+                if !a_match { return }
+                self.w *= 1.0 / v.p_epsilon(s, a, c_info);
             }
         }
     }
@@ -329,57 +385,58 @@ impl<'a> Graph<'a> {
         let gamma_v = c_info.gamma;
         let mut gamma = 1.0;
         let neg_gamma = 1.0 - gamma;
+        let counted = ep.visit_mask(c_info.visit);
         for k in (0..tt).rev() {
             let s = &ep.state[k];
             let a = &ep.action[k];
             let r = k as f64 * a_info.step_reward;
             self.g += r;
-            let a_map = match self.q.entry(*s) {
-                Vacant(v) => v.insert(BTreeMap::new()),
-                Occupied(v) => v.into_mut(),
-            };
-            let q = match a_map.entry(*a) {
-                Vacant(v) => v.insert(ActionValue::new()),
-                Occupied(v) => v.into_mut(),
-            };
             let mut w = self.w * gamma;
             let mut g = w * self.g;
             let mut w_h = 1.0;
             let mut g_h = 0.0;
             let mut gamma_h = 1.0;
-            for j in k..min(k + h, tt) {
+            for (j, p_j) in p_vec.iter().enumerate().take(min(k + h, tt)).skip(k) {
                 let r = j as f64 * a_info.step_reward;
                 g_h += r;
                 let w_step = neg_gamma * gamma_h * w_h;
                 w += w_step;
                 g += w_step * g_h;
-                w_h *= p_vec[j];
+                w_h *= p_j;
                 gamma_h *= gamma_v;
             }
             //TODO NaN value appears, check needed
-            q.w += w;
-            q.v += w * (g - q.v) / q.w;
+            if counted[k] {
+                let q = self.entry(s, a);
+                q.w += w;
+                q.v += w * (g - q.v) / q.w;
+            }
             let a_match = match self.improve_policy(s) {
                 Some(v) => v == a,
                 None => false,
             };
-            match b {
-                Some(v) => {
-                    if !a_match { return }
-                    self.w *= 1.0 / v.p_epsilon(s, a, c_info);
-                },
-                None => {}
+            if let Some(v) = b {
+                if !a_match { return }
+                self.w *= 1.0 / v.p_epsilon(s, a, c_info);
             }
             gamma *= gamma_v;
         }
     }
 
+    fn entry(&mut self, s:&State, a:&Action) -> &mut ActionValue {
+        let a_map = match self.q.entry(*s) {
+            Vacant(v) => v.insert(BTreeMap::new()),
+            Occupied(v) => v.into_mut(),
+        };
+        match a_map.entry(*a) {
+            Vacant(v) => v.insert(ActionValue::new()),
+            Occupied(v) => v.into_mut(),
+        }
+    }
+
     fn improve_policy(&mut self, s:&State) -> Option<&Action> {
         let p = &mut *self.p_ref;
-        let a_map = match self.q.get(s) {
-            Some(v) => v,
-            None => return None,
-        };
+        let a_map = self.q.get(s)?;
         let (a, _) = a_map.iter().max_by(|(_, q0), (_, q1)| q0.v.total_cmp(&q1.v)).unwrap();
         p.state_action.insert(*s, *a);
         Some(a)
@@ -396,7 +453,7 @@ impl<'a> Graph<'a> {
                 if v == a { 1.0 - ep + ep / c_a }
                 else { ep / c_a }
             },
-            None => return 0.0,
+            None => 0.0,
         }
     }
 
@@ -416,7 +473,7 @@ impl<'a> Graph<'a> {
                     break (false, *s)
                 }
             };
-            agent.action(&act);
+            agent.action(act);
             let p = &agent.position;
             if visit.contains(p) {
                 println!("position visited (loop) {:?} {:?}", s, p);
@@ -432,18 +489,14 @@ impl<'a> Graph<'a> {
         };
         if !finish {
             println!("sample steps {}", visit.len());
-            match self.q.get(&s) {
-                Some(v) => {
-                    for (a, q) in v {
-                        println!("{:?} {:?}", a, q.v);
-                    }
-                },
-                None => {},
-            };
-            match map.get(&s) {
-                Some(v) => { println!("{:?}", v) },
-                None => {},
-            };
+            if let Some(v) = self.q.get(&s) {
+                for (a, q) in v {
+                    println!("{:?} {:?}", a, q.v);
+                }
+            }
+            if let Some(v) = map.get(&s) {
+                println!("{:?}", v)
+            }
             return
         }
         let empty = "   ";
@@ -467,6 +520,17 @@ impl<'a> Graph<'a> {
     }
 }
 
+impl Estimator {
+    pub fn parse(s:&str) -> Result<Self, String> {
+        match s {
+            "ordinary" => Ok(Self::Ordinary),
+            "weighted" => Ok(Self::Weighted),
+            "truncated" => Ok(Self::WeightedTruncated),
+            _ => Err(format!("invalid estimator {}, expected ordinary|weighted|truncated", s)),
+        }
+    }
+}
+
 impl ActionValue {
     fn new() -> Self {
         Self { v:0.0, w:0.0 }
@@ -479,34 +543,136 @@ impl Policy {
     }
 }
 
-fn iteration(c_info:&ControlInfo, a:&mut Agent, f:&Field, b:&mut Graph, pi:&mut Graph) {
-    let mut ep = Episode::new();
+fn iteration(c_info:&ControlInfo, track:&mut Track, b:&mut Graph, pi:&mut Graph, mut rng:SeededRng) {
     let mut ep_c = 0;
-    let a_info = a.info;
+    let (f, a_info) = (track.field, track.agent.info);
     let now = Instant::now();
     let interval = c_info.max_episode / c_info.episode_check;
+    let pb = progress::counter(c_info.max_episode as u64, "episode");
     while ep_c < c_info.max_episode {
         let mut ep_cc = 0;
         while ep_cc < interval {
             ep_cc += 1;
-            ep.generate(ep_c + ep_cc, b.p_ref, f, a, c_info);
-            b.mc_control_wis(&ep, a.info, c_info, None);
-            pi.mc_control(&ep, a.info, c_info, Some(b));
+            let ep = Episode::generate(ep_c + ep_cc, b.p_ref, track, c_info, &mut rng);
+            b.mc_control_wis(&ep, a_info, c_info, None);
+            pi.mc_control(&ep, a_info, c_info, Some(b));
+            pb.inc(1);
         }
-        let elapsed = now.elapsed().as_secs();
-        println!("elapsed:{}", elapsed);
-        let sample_start = f.random_start(&mut ep.rng);
-        b.print_policy_sample(f, a_info, "b:", sample_start);
-        pi.print_policy_sample(f, a_info, "pi:", sample_start);
+        let sample_start = f.random_start(&mut rng);
+        pb.suspend(|| {
+            let elapsed = deterministic::elapsed(&now) as u64;
+            println!("elapsed:{}", elapsed);
+            b.print_policy_sample(f, a_info, "b:", sample_start);
+            pi.print_policy_sample(f, a_info, "pi:", sample_start);
+        });
         ep_c += ep_cc;
     }
+    pb.finish_and_clear();
+}
+
+//the track as a tabular mdp over the states reachable from the start line, -1 per step.
+//an action may not stop the car, with p_vel_inc0 the velocity is left unchanged, leaving the
+//track restarts uniformly on the start line and crossing the finish line reaches the terminal
+//state 0. returns the start states along with it
+fn tabular(f:&Field, info:&AgentInfo) -> (Tabular, Vec<usize>) {
+    let mut index:HashMap<State, usize> = HashMap::new();
+    let mut state:Vec<State> = Vec::new();
+    //0 is the terminal
+    fn id(s:State, index:&mut HashMap<State, usize>, state:&mut Vec<State>) -> usize {
+        *index.entry(s).or_insert_with(|| { state.push(s); state.len() })
+    }
+    let start:Vec<usize> = f.start_cells().into_iter()
+        .map(|p| id((p, (0, 0)), &mut index, &mut state))
+        .collect();
+    let mut row = Vec::new();
+    let mut k = 0;
+    while k < state.len() {
+        let s = state[k];
+        k += 1;
+        for ax in info.action.0..=info.action.1 {
+            for ay in info.action.0..=info.action.1 {
+                let mut agent = Agent::new(info);
+                (agent.position, agent.velocity) = s;
+                agent.action(&(ax, ay));
+                if agent.velocity == (0, 0) { continue }
+                let mut outcome = vec!(((ax, ay), 1.0));
+                if s.1 != (0, 0) {
+                    outcome = vec!(((ax, ay), 1.0 - info.p_vel_inc0), ((0, 0), info.p_vel_inc0));
+                }
+                let mut next = Vec::new();
+                for (act, p) in outcome {
+                    let mut agent = Agent::new(info);
+                    (agent.position, agent.velocity) = s;
+                    let (pp, v) = agent.action(&act);
+                    let (outside, finish) = f.intersect(&s.0, pp, v);
+                    if finish { next.push((0, p)) }
+                    else if outside {
+                        next.extend(start.iter().map(|ss| (*ss, p / start.len() as f64)));
+                    }
+                    else {
+                        let ss = agent.state();
+                        next.push((id(ss, &mut index, &mut state), p));
+                    }
+                }
+                row.push((index[&s], next));
+            }
+        }
+    }
+    let mut mdp = Tabular::new(state.len() + 1);
+    for (s, next) in row {
+        mdp.push(s, info.step_reward, next);
+    }
+    (mdp, start)
+}
+
+//real-time dynamic programming on the track against value iteration over every reachable state
+pub fn run_rtdp(seed:u64) -> Result<(), Box<dyn Error>> {
+    let mut f = Field::new();
+    f.setup_v1();
+    let mut a_info = AgentInfo {
+        velocity_max:5, action:(-1, 1), step_reward:-1.0,
+        p_vel_inc0:0.1, a_space:(0, 0.0),
+    };
+    a_info.setup();
+    let (mdp, start) = tabular(&f, &a_info);
+    let theta = 1e-4;
+    let t = Instant::now();
+    let (v_dp, sweeps) = mdp.value_iteration_sweeps(1.0, StoppingRule::MaxNorm(theta));
+    println!("value iteration: {} states, {} sweeps, {} backups, {:.3}s", mdp.states - 1, sweeps, sweeps * (mdp.states - 1), deterministic::elapsed(&t));
+    let mut rng = rng::from_seed(seed);
+    let t = Instant::now();
+    let mut rtdp = Rtdp::new(mdp.by_state(), 1.0, 0.0);
+    let check = 100;
+    let max_trial = 100000;
+    println!("{:>7} {:>10} {:>8} {:>10} {:>12} {:>10}", "trials", "backups", "visited", "mean steps", "start error", "residual");
+    //over the last check trials
+    let (mut steps, mut residual) = (0, 0.0f64);
+    for trial in 1..=max_trial {
+        let s = start[rng.gen_range(0..start.len())];
+        let result = rtdp.trial(s, &mut rng, 10000);
+        steps += result.steps;
+        residual = residual.max(result.residual);
+        if trial % check != 0 { continue }
+        let error = start.iter().map(|s| (rtdp.v[*s] - v_dp[*s]).abs()).fold(0.0, f64::max);
+        let visited = rtdp.visited.iter().filter(|v| **v).count();
+        println!("{:>7} {:>10} {:>7.2}% {:>10.2} {:>12.4} {:>10.2e}", trial, rtdp.backups,
+            100.0 * visited as f64 / (mdp.states - 1) as f64, steps as f64 / check as f64, error, residual);
+        if residual < theta { break }
+        (steps, residual) = (0, 0.0);
+    }
+    println!("rtdp: {} backups, {:.3}s, {:.1}% of value iteration's", rtdp.backups, deterministic::elapsed(&t),
+        100.0 * rtdp.backups as f64 / (sweeps * (mdp.states - 1)) as f64);
+    let v_start = start.iter().map(|s| rtdp.v[*s]).sum::<f64>() / start.len() as f64;
+    let v_dp_start = start.iter().map(|s| v_dp[*s]).sum::<f64>() / start.len() as f64;
+    println!("mean start value: rtdp {:.4}, value iteration {:.4}", v_start, v_dp_start);
+    Ok(())
 }
 
-pub fn run() -> Result<(), Box<dyn Error>> {
+pub fn run(seed:u64, visit:Visit, estimator:Estimator) -> Result<(), Box<dyn Error>> {
     let c_info = ControlInfo {
         max_episode:100000000, episode_check:20,
         epsilon:0.55, gamma:0.2, horizon:4,
-        estimator:1, field:1,
+        estimator, field:1, visit,
     };
     let mut f = Field::new();
     match c_info.field {
@@ -520,11 +686,11 @@ pub fn run() -> Result<(), Box<dyn Error>> {
         p_vel_inc0:0.1, a_space:(0, 0.0),
     };
     a_info.setup();
-    let mut agent = Agent::new(&a_info);
+    let mut track = Track { field:&f, agent:Agent::new(&a_info), start:0, step:0, crash:0 };
     let mut b = Policy::new();
     let mut pi = Policy::new();
     let mut g_b = Graph::new(&mut b);
     let mut g_pi = Graph::new(&mut pi);
-    iteration(&c_info, &mut agent, &f, &mut g_b, &mut g_pi);
+    iteration(&c_info, &mut track, &mut g_b, &mut g_pi, rng::from_seed(seed));
     Ok(())
 }
\ No newline at end of file
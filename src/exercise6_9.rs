@@ -1,10 +1,15 @@
 use std::cmp::{ min, max };
 use std::collections::HashSet;
 use std::error::Error;
-use std::time::Instant;
 use rand::prelude::*;
 
+use crate::env::Environment;
 use crate::nd_vec::NdVec2;
+use crate::policy::{ Behavior, StochasticPolicy };
+use crate::progress;
+use crate::rng::{ self, SeededRng };
+use crate::rollout;
+use crate::schedules::Schedule;
 
 type Vec2 = (i32, i32);
 type State = Vec2;
@@ -19,28 +24,43 @@ struct World {
     pub wind:Vec<i32>,
 }
 
-struct ControlInfo {
+struct ControlInfo<A = f64> {
     pub max_episode:usize,
     pub episode_check:usize,
-    pub epsilon:f64,
-    pub alpha:f64,
-    pub stochastic_wind:bool,
+    pub behavior:Behavior,
+    pub alpha:A,//by the updates of (s, a) so far
+}
+
+//action set of example 6.5 and exercise 6.9, all of them taken from the 3x3 neighborhood
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Moves {
+    Rook,//up, down, left, right
+    King,//the rook moves and the diagonals
+    KingStay,//king's moves and staying put, only the wind moves the agent then
 }
 
 struct AgentInfo {
     pub action:NdVec2<i32>,
-    pub no_stay:bool,
+    pub moves:Moves,
     pub step_reward:f64,
 }
 
 struct Agent<'a> {
     pub info:&'a AgentInfo,
     pub position:Vec2,
-    pub rng:ThreadRng,
+}
+
+//the agent on the world as an environment, step_reward on every step until the goal
+struct Windy<'a> {
+    pub world:&'a World,
+    pub agent:Agent<'a>,
+    pub stochastic_wind:bool,//exercise 6.10, one more or one less than a windy column's wind a third of the time each
 }
 
 struct Graph<'a> {
     pub q:NdVec2<NdVec2<f64>>,//<state:<Action: ActionValue>>
+    pub updates:NdVec2<NdVec2<usize>>,//of every (s, a), what alpha counts
+    pub b:StochasticPolicy<Action>,//behavior, Ɛ-greedy with respect to q
     pub p_ref:&'a mut Policy,
 }
 
@@ -54,9 +74,43 @@ impl World {
     }
 }
 
+impl Moves {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Rook => "4",
+            Self::King => "8",
+            Self::KingStay => "9",
+        }
+    }
+
+    pub fn parse(s:&str) -> Result<Self, String> {
+        match s {
+            "4" => Ok(Self::Rook),
+            "8" => Ok(Self::King),
+            "9" => Ok(Self::KingStay),
+            _ => Err(format!("unknown moves {}, expected 4, 8 or 9", s)),
+        }
+    }
+
+    fn contains(&self, a:&Action) -> bool {
+        match self {
+            Self::Rook => a.0.abs() + a.1.abs() == 1,
+            Self::King => *a != (0, 0),
+            Self::KingStay => true,
+        }
+    }
+}
+
+impl AgentInfo {
+    fn allowed(&self) -> Vec<bool> {
+        let (x, y) = self.action.dimension;
+        (0..(x * y) as usize).map(|k| self.moves.contains(&self.action.rev_index(k))).collect()
+    }
+}
+
 impl<'a> Agent<'a> {
     fn new(info:&'a AgentInfo) -> Self {
-        Self { info, position:(0, 0), rng:rand::thread_rng() }
+        Self { info, position:(0, 0) }
     }
 
     fn state(&self) -> State {
@@ -67,56 +121,78 @@ impl<'a> Agent<'a> {
         self.position = *p;
     }
 
-    fn action(&mut self, a:&Vec2, w:&World, c_info:Option<&ControlInfo>) -> (State, f64, State) {
+    //gust is added to the wind of the column
+    fn action(&mut self, a:&Vec2, w:&World, gust:i32) -> State {
         let ss = &mut self.position;
-        let s = *ss;
         let x_max = (w.size.0 - 1) as i32;
         let y_max = (w.size.1 - 1) as i32;
-        let wind = w.wind[s.0 as usize] + 
-            match c_info {
-                Some(v) => {
-                    if v.stochastic_wind {
-                        let r:f64 = self.rng.gen();
-                        if r < 0.33 { -1 }
-                        else if r < 0.66 { 1 }
-                        else { 0 }
-                    }
-                    else { 0 }
-                },
-                None => { 0 }
-            };
+        let wind = w.wind[ss.0 as usize] + gust;
         ss.0 = max(0, min(ss.0 + a.0, x_max));
         ss.1 = max(0, min(ss.1 + a.1 + wind, y_max));
-        (s, self.info.step_reward, *ss)
+        *ss
     }
 }
 
-impl<'a> Graph<'a> {
-    fn new(p_ref:&'a mut Policy, w:&World) -> Self {
-        Self { q:NdVec2::from_size(w.size), p_ref }
+impl Environment for Windy<'_> {
+    type State = State;
+    type Action = Action;
+
+    fn reset(&mut self, _:&mut SeededRng) -> State {
+        self.agent.reset(&self.world.start);
+        self.agent.state()
     }
 
-    fn fill_q(&mut self, w:&World) {
-        let q = &mut self.q;
-        let c = w.size.0 * w.size.1;
-        for _ in 0..c {
-            let mut a:NdVec2<f64> = NdVec2::new((-1, 1), (-1, 1));
-            a.fill(0.0);
-            q.push(a);
-        }
+    //calm columns stay calm under the stochastic wind
+    fn step(&mut self, a:&Action, rng:&mut SeededRng) -> (State, f64, bool) {
+        let windy = self.world.wind[self.agent.position.0 as usize] != 0;
+        let gust = if self.stochastic_wind && windy { rng.gen_range(-1..=1) } else { 0 };
+        let ss = self.agent.action(a, self.world, gust);
+        (ss, self.agent.info.step_reward, self.world.is_terminal(&ss))
+    }
+
+    fn actions(&self, _:&State) -> Vec<Action> {
+        let info = self.agent.info;
+        info.allowed().into_iter().enumerate().filter(|(_, v)| *v).map(|(k, _)| info.action.rev_index(k)).collect()
+    }
+}
+
+impl<'a> Graph<'a> {
+    fn new(p_ref:&'a mut Policy, w:&World, a_info:&AgentInfo) -> Self {
+        let (x, y) = a_info.action.dimension;
+        let action:Vec<Action> = (0..(x * y) as usize).map(|k| a_info.action.rev_index(k)).collect();
+        let range = ((0, w.size.0 as i32 - 1), (0, w.size.1 as i32 - 1));
+        let q = NdVec2::from_fn(range, |_| NdVec2::from_fn(((-1, 1), (-1, 1)), |_| 0.0));
+        let updates = NdVec2::from_fn(range, |_| NdVec2::from_fn(((-1, 1), (-1, 1)), |_| 0));
+        Self { q, updates, b:StochasticPolicy::new(w.size, action), p_ref }
     }
 
-    fn update(&mut self, s:&State, a:&Action, r:f64, ss:&State, aa:&Action, c_info:&ControlInfo) {
-        // println!("{:?} {:?} {} {:?} {:?}", s, a, r, ss, aa);
-        let qq = self.q[ss][aa];
+    //next is (S', A'), None past the goal
+    fn update<A:Schedule>(&mut self, s:&State, a:&Action, r:f64, next:Option<(&State, &Action)>, c_info:&ControlInfo<A>) {
+        let qq = next.map_or(0.0, |(ss, aa)| self.q[ss][aa]);
+        let n = &mut self.updates[s][a];
+        *n += 1;
+        let alpha = c_info.alpha.at(*n);
         let q = &mut self.q[s][a];
-        *q += c_info.alpha * (r + qq - *q);
+        *q += alpha * (r + qq - *q);
+    }
+
+    fn fill_behavior<A>(&mut self, w:&World, a_info:&AgentInfo, c_info:&ControlInfo<A>) {
+        let allowed = a_info.allowed();
+        for k in 0..w.size.0 * w.size.1 {
+            let s = self.p_ref.p.rev_index(k);
+            let a = a_info.action.index(&self.p_ref.p[s]);
+            self.b.set(&s, c_info.behavior.prob_greedy(a, &allowed));
+        }
     }
 
-    fn update_policy(&mut self, s:&State, a_info:&AgentInfo) {
-        let (a, _) = self.q[s].iter().enumerate()
+    //greedy among the allowed moves only, the others keep their initial 0 above every learned value
+    fn update_policy<A>(&mut self, s:&State, a_info:&AgentInfo, c_info:&ControlInfo<A>) {
+        let allowed = a_info.allowed();
+        let (a, _) = self.q[s].iter().enumerate().filter(|(k, _)| allowed[*k])
             .max_by(|(_, q0), (_, q1)| q0.total_cmp(q1)).unwrap();
         self.p_ref.p[s] = a_info.action.rev_index(a);
+        let prob = c_info.behavior.prob_q(&self.q[s].data, &allowed);
+        self.b.set(s, prob);
     }
 
     fn print_policy_sample(&self, w:&World, a_info:&AgentInfo) {
@@ -124,12 +200,13 @@ impl<'a> Graph<'a> {
         let map = &self.p_ref.p;
         let mut visit:HashSet<Vec2> = HashSet::new();
         visit.insert(w.start);
+        //steady wind for the sample
         let mut agent = Agent::new(a_info);
         agent.reset(&w.start);
         let (finish, s) = loop {
             let s = agent.state();
             let act = map[s];
-            agent.action(&act, w, None);
+            agent.action(&act, w, 0);
             let p = &agent.position;
             if visit.contains(p) {
                 println!("position visited (loop) {:?} {:?}", s, p);
@@ -139,9 +216,12 @@ impl<'a> Graph<'a> {
             if w.is_terminal(p) { break (true, s) }
         };
         println!("sample steps {}", visit.len());
+        let q_start = &self.q[w.start];
+        println!("start value {:.2}", self.b.expectation(&w.start, |a| q_start[a]));
         if !finish {
             for (a, q) in self.q[s].iter().enumerate() {
-                println!("{:?} {:?}", a, q);
+                let a = a_info.action.rev_index(a);
+                println!("{:?} {:?} {:.2}", a, q, self.b.prob(&s, &a));
             }
             println!("{:?}", map[s]);
             return
@@ -162,78 +242,130 @@ impl Policy {
         Self { p:NdVec2::from_size(w.size) }
     }
 
-    fn fill_random(&mut self, w:&mut World, agent:&mut Agent) {
+    fn fill_random(&mut self, w:&World, a_info:&AgentInfo, rng:&mut SeededRng) {
         let c = w.size.0 * w.size.1;
-        let rng = &mut agent.rng;
         let p = &mut self.p;
         for _ in 0..c {
-            let a = Policy::random_action(agent.info, rng);
+            let a = Policy::random_action(a_info, rng);
             // println!(" {} {:?}", k, a);
             p.push(a);
         }
     }
 
-    fn random_action(a_info:&AgentInfo, rng:&mut ThreadRng) -> Action {
-        let r = if a_info.no_stay { 8.0 } else { 9.0 };//8-dir move
-        let rn:f64 = rng.gen();
-        let mut r = (rn * r).floor() as i32;
-        if a_info.no_stay && r >= 4 { r += 1 }//skip (0, 0)
-        a_info.action.rev_index(r as usize)
-    }
-
-    fn select_action(&self, s:&State, c_info:&ControlInfo, a_info:&AgentInfo, rng:&mut ThreadRng) -> Action {
+    fn random_action(a_info:&AgentInfo, rng:&mut SeededRng) -> Action {
+        let allowed:Vec<usize> = a_info.allowed().iter().enumerate().filter(|(_, v)| **v).map(|(k, _)| k).collect();
         let rn:f64 = rng.gen();
-        if rn < c_info.epsilon { Policy::random_action(a_info, rng) }
-        else { self.p[*s] }
+        let r = (rn * allowed.len() as f64).floor() as usize;
+        a_info.action.rev_index(allowed[r])
     }
 }
 
-fn episode(c_info:&ControlInfo, agent:&mut Agent, w:&mut World, g:&mut Graph) {
-    agent.reset(&w.start);
-    let mut a = g.p_ref.select_action(&agent.position, c_info, agent.info, &mut agent.rng);
-    loop {
-        let (s, r, ss) = agent.action(&a, w, Some(c_info));
-        let aa = g.p_ref.select_action(&ss, c_info, agent.info, &mut agent.rng);
-        g.update(&s, &a, r, &ss, &aa, c_info);
-        g.update_policy(&s, agent.info);
-        a = aa;
-        if w.is_terminal(&ss) { break }
+//SARSA over one episode, returns the steps taken. every step pays the same step_reward, so the
+//update of the step that led to S' runs as soon as A' is drawn there, and the step into the
+//goal is updated once the episode is over
+fn episode<A:Schedule>(c_info:&ControlInfo<A>, env:&mut Windy, g:&mut Graph, rng:&mut SeededRng) -> usize {
+    let (info, r) = (env.agent.info, env.agent.info.step_reward);
+    let mut last:Option<(State, Action)> = None;
+    let trajectory = rollout::simulate_episode(env, |ss, rng| {
+        let aa = g.b.sample(ss, rng);
+        if let Some((s, a)) = last {
+            g.update(&s, &a, r, Some((ss, &aa)), c_info);
+            g.update_policy(&s, info, c_info);
+        }
+        last = Some((*ss, aa));
+        aa
+    }, rng, usize::MAX);
+    if let Some((s, a)) = last {
+        g.update(&s, &a, r, None, c_info);
+        g.update_policy(&s, info, c_info);
     }
+    trajectory.steps()
 }
 
-fn iteration(c_info:&ControlInfo, agent:&mut Agent, w:&mut World, g:&mut Graph) {
+//the learners side by side, each on its own environment and generator. after every check the
+//policy sample of each and their mean episode lengths are printed
+fn iteration<A:Schedule>(c_info:&ControlInfo<A>, env:&mut [Windy], g:&mut [Graph], rng:&mut [SeededRng]) {
     let mut ep_c = 0;
     let interval = c_info.max_episode / c_info.episode_check;
+    let pb = progress::counter((c_info.max_episode * env.len()) as u64, "episode");
     while ep_c < c_info.max_episode {
-        let mut ep_cc = 0;
-        while ep_cc < interval {
-            episode(c_info, agent, w, g);
-            ep_cc += 1;
+        let mut steps = vec![0; env.len()];
+        for (k, steps) in steps.iter_mut().enumerate() {
+            for _ in 0..interval {
+                *steps += episode(c_info, &mut env[k], &mut g[k], &mut rng[k]);
+                pb.inc(1);
+            }
         }
-        g.print_policy_sample(w, agent.info);
-        ep_c += ep_cc;
+        ep_c += interval;
+        pb.suspend(|| {
+            for (env, g) in env.iter().zip(g.iter()) {
+                println!("{} wind:", wind_label(env.stochastic_wind));
+                g.print_policy_sample(env.world, env.agent.info);
+            }
+            let mean:Vec<String> = env.iter().zip(steps.iter())
+                .map(|(env, steps)| format!("{} {:.1}", wind_label(env.stochastic_wind), *steps as f64 / interval as f64))
+                .collect();
+            println!("episode {}, {} moves: mean steps of the last {} episodes, {}", ep_c, env[0].agent.info.moves.label(), interval, mean.join(", "));
+        });
     }
+    pb.finish_and_clear();
 }
 
-pub fn run() -> Result<(), Box<dyn Error>> {
-    let c_info = ControlInfo {
+fn wind_label(stochastic_wind:bool) -> &'static str {
+    if stochastic_wind { "stochastic" } else { "steady" }
+}
+
+fn control_info() -> ControlInfo {
+    ControlInfo {
         max_episode:2000, episode_check:50,
-        epsilon:0.1, alpha:0.5,
-        stochastic_wind:true,
-    };
-    let mut w = World {
+        behavior:Behavior::EpsilonGreedy(0.1), alpha:0.5,
+    }
+}
+
+fn world() -> World {
+    World {
         size:(10, 7), start:(0, 3), goal:(7, 3),
         wind:vec!(0, 0, 0, 1, 1, 1, 2, 2, 1, 0),
-    };
-    let a_info = AgentInfo {
+    }
+}
+
+fn agent_info(moves:Moves) -> AgentInfo {
+    AgentInfo {
         action:NdVec2::new((-1, 1), (-1, 1)),
-        step_reward:-1.0, no_stay:false,
-    };
-    let mut agent = Agent::new(&a_info);
+        step_reward:-1.0, moves,
+    }
+}
+
+//the greedy action of every state, x-major, after episodes of SARSA under the steady wind
+pub fn greedy_actions(seed:u64, moves:Moves, episodes:usize) -> Vec<(i32, i32)> {
+    let (c_info, w, a_info) = (control_info(), world(), agent_info(moves));
+    let mut rng = rng::from_seed(seed);
     let mut pi = Policy::new(&w);
-    pi.fill_random(&mut w, &mut agent);
-    let mut g = Graph::new(&mut pi, &w);
-    g.fill_q(&w);
-    iteration(&c_info, &mut agent, &mut w, &mut g);
+    pi.fill_random(&w, &a_info, &mut rng);
+    let mut g = Graph::new(&mut pi, &w, &a_info);
+    g.fill_behavior(&w, &a_info, &c_info);
+    let mut env = Windy { world:&w, agent:Agent::new(&a_info), stochastic_wind:false };
+    for _ in 0..episodes {
+        episode(&c_info, &mut env, &mut g, &mut rng);
+    }
+    pi.p.data
+}
+
+//with stochastic_wind a second learner runs on the stochastic wind next to the steady one,
+//both from the same seed
+pub fn run(seed:u64, moves:Moves, stochastic_wind:bool) -> Result<(), Box<dyn Error>> {
+    let (c_info, w, a_info) = (control_info(), world(), agent_info(moves));
+    let wind = if stochastic_wind { vec!(false, true) } else { vec!(false) };
+    let mut rng:Vec<SeededRng> = wind.iter().map(|_| rng::from_seed(seed)).collect();
+    let mut pi:Vec<Policy> = wind.iter().map(|_| Policy::new(&w)).collect();
+    for (pi, rng) in pi.iter_mut().zip(rng.iter_mut()) {
+        pi.fill_random(&w, &a_info, rng);
+    }
+    let mut g:Vec<Graph> = pi.iter_mut().map(|pi| Graph::new(pi, &w, &a_info)).collect();
+    for g in g.iter_mut() {
+        g.fill_behavior(&w, &a_info, &c_info);
+    }
+    let mut env:Vec<Windy> = wind.iter().map(|stochastic_wind| Windy { world:&w, agent:Agent::new(&a_info), stochastic_wind:*stochastic_wind }).collect();
+    iteration(&c_info, &mut env, &mut g, &mut rng);
     Ok(())
-}
\ No newline at end of file
+}
@@ -0,0 +1,216 @@
+use std::fs::File;
+use std::io::{ self, BufReader, BufWriter, Read, Write };
+use std::marker::PhantomData;
+
+use crate::env::Environment;
+use crate::replay::Experience;
+use crate::rng::SeededRng;
+
+//steps streamed to a file as they happen, read back with Reader. after the header every step
+//is state, action, reward as f64, next state and done as one byte, all little-endian and
+//without padding. the types are not stored, read with the ones that were written
+const MAGIC:&[u8; 5] = b"RLXP\x01";
+
+//fixed-size little-endian encoding of a state or an action
+pub trait Record:Sized {
+    fn write<W:Write>(&self, w:&mut W) -> io::Result<()>;
+    fn read<R:Read>(r:&mut R) -> io::Result<Self>;
+}
+
+pub struct Writer<S, A, W:Write> {
+    out:W,
+    pub steps:u64,//logged so far
+    step:PhantomData<(S, A)>,
+}
+
+pub struct Reader<S, A, R:Read> {
+    input:R,
+    step:PhantomData<(S, A)>,
+}
+
+//an environment that logs every step it takes. an Environment cannot fail, so the first
+//write error is kept in error and nothing is logged after it
+pub struct Logged<E:Environment, W:Write> {
+    pub env:E,
+    pub log:Writer<E::State, E::Action, W>,
+    pub error:Option<io::Error>,
+    state:Option<E::State>,
+}
+
+macro_rules! record_number {
+    ($($t:ty),*) => {$(
+        impl Record for $t {
+            fn write<W:Write>(&self, w:&mut W) -> io::Result<()> {
+                w.write_all(&self.to_le_bytes())
+            }
+
+            fn read<R:Read>(r:&mut R) -> io::Result<Self> {
+                let mut b = [0; std::mem::size_of::<$t>()];
+                r.read_exact(&mut b)?;
+                Ok(<$t>::from_le_bytes(b))
+            }
+        }
+    )*};
+}
+
+record_number!(u8, i8, u32, i32, u64, i64, f32, f64);
+
+//as u64, so files move between 32 and 64 bit builds
+impl Record for usize {
+    fn write<W:Write>(&self, w:&mut W) -> io::Result<()> {
+        (*self as u64).write(w)
+    }
+
+    fn read<R:Read>(r:&mut R) -> io::Result<Self> {
+        Ok(u64::read(r)? as usize)
+    }
+}
+
+impl Record for bool {
+    fn write<W:Write>(&self, w:&mut W) -> io::Result<()> {
+        (*self as u8).write(w)
+    }
+
+    fn read<R:Read>(r:&mut R) -> io::Result<Self> {
+        Ok(u8::read(r)? != 0)
+    }
+}
+
+impl Record for () {
+    fn write<W:Write>(&self, _:&mut W) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn read<R:Read>(_:&mut R) -> io::Result<Self> {
+        Ok(())
+    }
+}
+
+impl<T:Record, U:Record> Record for (T, U) {
+    fn write<W:Write>(&self, w:&mut W) -> io::Result<()> {
+        self.0.write(w)?;
+        self.1.write(w)
+    }
+
+    fn read<R:Read>(r:&mut R) -> io::Result<Self> {
+        Ok((T::read(r)?, U::read(r)?))
+    }
+}
+
+impl<T:Record, const N:usize> Record for [T; N] {
+    fn write<W:Write>(&self, w:&mut W) -> io::Result<()> {
+        self.iter().try_for_each(|v| v.write(w))
+    }
+
+    fn read<R:Read>(r:&mut R) -> io::Result<Self> {
+        let v = (0..N).map(|_| T::read(r)).collect::<io::Result<Vec<T>>>()?;
+        Ok(v.try_into().unwrap_or_else(|_| unreachable!()))
+    }
+}
+
+//a u32 length first, e.g. the observation vectors of the function approximation tasks
+impl<T:Record> Record for Vec<T> {
+    fn write<W:Write>(&self, w:&mut W) -> io::Result<()> {
+        (self.len() as u32).write(w)?;
+        self.iter().try_for_each(|v| v.write(w))
+    }
+
+    fn read<R:Read>(r:&mut R) -> io::Result<Self> {
+        let n = u32::read(r)?;
+        (0..n).map(|_| T::read(r)).collect()
+    }
+}
+
+impl<S:Record, A:Record> Writer<S, A, BufWriter<File>> {
+    pub fn create(path:&str) -> io::Result<Self> {
+        Self::new(BufWriter::new(File::create(path)?))
+    }
+}
+
+impl<S:Record, A:Record, W:Write> Writer<S, A, W> {
+    pub fn new(mut out:W) -> io::Result<Self> {
+        out.write_all(MAGIC)?;
+        Ok(Self { out, steps:0, step:PhantomData })
+    }
+
+    pub fn log(&mut self, e:&Experience<S, A>) -> io::Result<()> {
+        e.state.write(&mut self.out)?;
+        e.action.write(&mut self.out)?;
+        e.reward.write(&mut self.out)?;
+        e.next.write(&mut self.out)?;
+        e.done.write(&mut self.out)?;
+        self.steps += 1;
+        Ok(())
+    }
+
+    //also done on drop for a BufWriter, but then errors go unseen
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+impl<S:Record, A:Record> Reader<S, A, BufReader<File>> {
+    pub fn open(path:&str) -> io::Result<Self> {
+        Self::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<S:Record, A:Record, R:Read> Reader<S, A, R> {
+    pub fn new(mut input:R) -> io::Result<Self> {
+        let mut magic = [0; 5];
+        input.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "not an experience log"))
+        }
+        Ok(Self { input, step:PhantomData })
+    }
+
+    //None at the end of the file, a step cut short is an error
+    fn next_step(&mut self) -> io::Result<Option<Experience<S, A>>> {
+        let mut first = [0; 1];
+        if self.input.read(&mut first)? == 0 { return Ok(None) }
+        let mut r = first.chain(&mut self.input);
+        Ok(Some(Experience { state:S::read(&mut r)?, action:A::read(&mut r)?, reward:f64::read(&mut r)?, next:S::read(&mut r)?, done:bool::read(&mut r)? }))
+    }
+}
+
+impl<S:Record, A:Record, R:Read> Iterator for Reader<S, A, R> {
+    type Item = io::Result<Experience<S, A>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_step().transpose()
+    }
+}
+
+impl<E:Environment, W:Write> Logged<E, W>
+    where E::State:Record, E::Action:Record {
+    pub fn new(env:E, log:Writer<E::State, E::Action, W>) -> Self {
+        Self { env, log, error:None, state:None }
+    }
+}
+
+impl<E:Environment, W:Write> Environment for Logged<E, W>
+    where E::State:Record, E::Action:Record {
+    type State = E::State;
+    type Action = E::Action;
+
+    fn reset(&mut self, rng:&mut SeededRng) -> E::State {
+        let s = self.env.reset(rng);
+        self.state = Some(s.clone());
+        s
+    }
+
+    fn step(&mut self, a:&E::Action, rng:&mut SeededRng) -> (E::State, f64, bool) {
+        let (ss, r, done) = self.env.step(a, rng);
+        let state = self.state.replace(ss.clone()).expect("step before reset");
+        if self.error.is_none() {
+            let e = Experience { state, action:a.clone(), reward:r, next:ss.clone(), done };
+            self.error = self.log.log(&e).err();
+        }
+        (ss, r, done)
+    }
+
+    fn actions(&self, s:&E::State) -> Vec<E::Action> {
+        self.env.actions(s)
+    }
+}
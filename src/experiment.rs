@@ -0,0 +1,108 @@
+use indicatif::ProgressBar;
+use rand::prelude::*;
+
+use crate::progress;
+use crate::rng::{ self, SeededRng };
+
+//per-index statistics over independent runs, usually one index per episode
+pub struct Curve {
+    pub mean:Vec<f64>,
+    pub stderr:Vec<f64>,
+    pub runs:usize,
+}
+
+//welford accumulation, runs may be of different length
+struct Accumulator {
+    pub n:Vec<usize>,
+    pub mean:Vec<f64>,
+    pub m2:Vec<f64>,
+}
+
+//runs a closure once per derived seed. the seed list only depends on the experiment
+//seed, so run k sees the same generator across every method it is repeated for
+pub struct Experiment {
+    pub runs:usize,
+    pub seed:u64,
+    pub progress:bool,//off when experiments run side by side
+}
+
+impl Curve {
+    pub fn len(&self) -> usize {
+        self.mean.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.mean.is_empty()
+    }
+
+    //normal approximation of the 95% interval
+    pub fn ci95(&self, k:usize) -> (f64, f64) {
+        let h = 1.96 * self.stderr[k];
+        (self.mean[k] - h, self.mean[k] + h)
+    }
+
+    //mean over the last n indices
+    pub fn tail(&self, n:usize) -> f64 {
+        let tail = &self.mean[self.len().saturating_sub(n)..];
+        tail.iter().sum::<f64>() / tail.len() as f64
+    }
+}
+
+impl Accumulator {
+    fn new() -> Self {
+        Self { n:Vec::new(), mean:Vec::new(), m2:Vec::new() }
+    }
+
+    fn add(&mut self, v:&[f64]) {
+        if v.len() > self.n.len() {
+            self.n.resize(v.len(), 0);
+            self.mean.resize(v.len(), 0.0);
+            self.m2.resize(v.len(), 0.0);
+        }
+        for (k, x) in v.iter().enumerate() {
+            self.n[k] += 1;
+            let d = x - self.mean[k];
+            self.mean[k] += d / self.n[k] as f64;
+            self.m2[k] += d * (x - self.mean[k]);
+        }
+    }
+
+    fn curve(self, runs:usize) -> Curve {
+        let stderr = self.n.iter().zip(self.m2.iter())
+            .map(|(n, m2)| if *n > 1 { (m2 / (*n - 1) as f64 / *n as f64).sqrt() } else { 0.0 })
+            .collect();
+        Curve { mean:self.mean, stderr, runs }
+    }
+}
+
+impl Experiment {
+    pub fn new(runs:usize, seed:u64) -> Self {
+        Self { runs, seed, progress:true }
+    }
+
+    pub fn seeds(&self) -> Vec<u64> {
+        let mut rng = rng::from_seed(self.seed);
+        (0..self.runs).map(|_| rng.gen()).collect()
+    }
+
+    //one series per run
+    pub fn repeat<F:FnMut(&mut SeededRng) -> Vec<f64>>(&self, mut run:F) -> Curve {
+        self.repeat_series(1, |rng| vec!(run(rng))).pop().unwrap()
+    }
+
+    //several series per run, e.g. return and steps per episode
+    pub fn repeat_series<F:FnMut(&mut SeededRng) -> Vec<Vec<f64>>>(&self, series:usize, mut run:F) -> Vec<Curve> {
+        let mut acc:Vec<Accumulator> = (0..series).map(|_| Accumulator::new()).collect();
+        let pb = if self.progress { progress::counter(self.runs as u64, "run") } else { ProgressBar::hidden() };
+        for seed in self.seeds() {
+            let v = run(&mut rng::from_seed(seed));
+            assert_eq!(v.len(), series);
+            for (a, v) in acc.iter_mut().zip(v.iter()) {
+                a.add(v);
+            }
+            pb.inc(1);
+        }
+        pb.finish_and_clear();
+        acc.into_iter().map(|a| a.curve(self.runs)).collect()
+    }
+}
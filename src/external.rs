@@ -0,0 +1,217 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::error::Error;
+use std::io::{ BufRead, BufReader, Write };
+use std::marker::PhantomData;
+use std::process::{ Child, ChildStdin, ChildStdout, Command, Stdio };
+use rand::prelude::*;
+use serde::{ Deserialize, Serialize, de::DeserializeOwned };
+use serde_json::Value;
+
+use crate::env::Environment;
+use crate::progress;
+use crate::rng::{ self, SeededRng };
+use crate::schedules::Schedule;
+
+//an environment living in another process, one JSON object per line each way:
+//  -> {"cmd":"reset","seed":N}       <- {"state":S}
+//  -> {"cmd":"step","action":A}      <- {"state":S,"reward":R,"done":B}
+//  -> {"cmd":"actions","state":S}    <- {"actions":[A, ...]}
+//  -> {"cmd":"close"}
+//the process is expected to exit after close or once its stdin is closed
+pub struct ExternalEnv<S, A> {
+    child:Child,
+    //requests go out behind a shared reference so actions() can ask too
+    pipe:RefCell<(ChildStdin, BufReader<ChildStdout>)>,
+    kind:PhantomData<(S, A)>,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "cmd", rename_all = "lowercase")]
+enum Request<'a, S, A> {
+    Reset { seed:u64 },
+    Step { action:&'a A },
+    Actions { state:&'a S },
+    Close,
+}
+
+#[derive(Deserialize)]
+struct ResetReply<S> {
+    pub state:S,
+}
+
+#[derive(Deserialize)]
+struct StepReply<S> {
+    pub state:S,
+    pub reward:f64,
+    pub done:bool,
+}
+
+#[derive(Deserialize)]
+struct ActionsReply<A> {
+    pub actions:Vec<A>,
+}
+
+struct ControlInfo<A = f64, X = f64> {
+    pub max_episode:usize,
+    pub max_step:usize,
+    pub epsilon:X,//by episode
+    pub alpha:A,//by the updates of (s, a) so far
+    pub gamma:f64,
+    pub report:usize,//episodes per printed average
+}
+
+//tabular Q-learning keyed by the JSON text of the state
+struct Agent {
+    pub q:HashMap<String, (Vec<Value>, Vec<f64>)>,
+    pub updates:HashMap<String, Vec<usize>>,//of every (s, a), what alpha counts
+    pub episodes:usize,//what epsilon counts
+}
+
+impl<S:Serialize, A:Serialize> ExternalEnv<S, A> {
+    //command line split on whitespace, e.g. "python3 external/short_corridor.py"
+    pub fn spawn(command:&str) -> Result<Self, Box<dyn Error>> {
+        let mut arg = command.split_whitespace();
+        let program = arg.next().ok_or("empty environment command")?;
+        let mut child = Command::new(program)
+            .args(arg)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()?;
+        let stdin = child.stdin.take().ok_or("no stdin")?;
+        let stdout = BufReader::new(child.stdout.take().ok_or("no stdout")?);
+        Ok(Self { child, pipe:RefCell::new((stdin, stdout)), kind:PhantomData })
+    }
+
+    fn send(&self, req:&Request<S, A>) -> Result<(), Box<dyn Error>> {
+        let mut pipe = self.pipe.borrow_mut();
+        writeln!(pipe.0, "{}", serde_json::to_string(req)?)?;
+        pipe.0.flush()?;
+        Ok(())
+    }
+
+    fn request<T:DeserializeOwned>(&self, req:&Request<S, A>) -> Result<T, Box<dyn Error>> {
+        self.send(req)?;
+        let mut line = String::new();
+        if self.pipe.borrow_mut().1.read_line(&mut line)? == 0 {
+            return Err("environment process closed its output".into())
+        }
+        Ok(serde_json::from_str(&line).map_err(|e| format!("bad reply {:?}: {}", line.trim_end(), e))?)
+    }
+}
+
+//the trait has no error channel, a broken pipe or malformed reply ends the run
+impl<S, A> Environment for ExternalEnv<S, A>
+    where S:Clone + Serialize + DeserializeOwned, A:Clone + Serialize + DeserializeOwned {
+    type State = S;
+    type Action = A;
+
+    fn reset(&mut self, rng:&mut SeededRng) -> S {
+        let reply:ResetReply<S> = self.request(&Request::Reset { seed:rng.gen() })
+            .unwrap_or_else(|e| panic!("external reset: {}", e));
+        reply.state
+    }
+
+    fn step(&mut self, a:&A, _:&mut SeededRng) -> (S, f64, bool) {
+        let reply:StepReply<S> = self.request(&Request::Step { action:a })
+            .unwrap_or_else(|e| panic!("external step: {}", e));
+        (reply.state, reply.reward, reply.done)
+    }
+
+    fn actions(&self, s:&S) -> Vec<A> {
+        let reply:ActionsReply<A> = self.request(&Request::Actions { state:s })
+            .unwrap_or_else(|e| panic!("external actions: {}", e));
+        reply.actions
+    }
+}
+
+impl<S, A> Drop for ExternalEnv<S, A> {
+    fn drop(&mut self) {
+        let pipe = self.pipe.get_mut();
+        if let Ok(s) = serde_json::to_string(&Request::<(), ()>::Close) {
+            let _ = writeln!(pipe.0, "{}", s);
+            let _ = pipe.0.flush();
+        }
+        let _ = self.child.wait();
+    }
+}
+
+impl Agent {
+    //action list of a state is asked for once, on the first visit
+    fn entry(&mut self, s:&Value, env:&ExternalEnv<Value, Value>) -> &mut (Vec<Value>, Vec<f64>) {
+        self.q.entry(s.to_string()).or_insert_with(|| {
+            let action = env.actions(s);
+            let q = vec![0.0; action.len()];
+            (action, q)
+        })
+    }
+
+    fn greedy(q:&[f64], rng:&mut SeededRng) -> usize {
+        let q_max = q.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        let best:Vec<usize> = (0..q.len()).filter(|k| q[*k] == q_max).collect();
+        best[rng.gen_range(0..best.len())]
+    }
+
+    fn select<A, X:Schedule>(&mut self, s:&Value, env:&ExternalEnv<Value, Value>, c_info:&ControlInfo<A, X>, rng:&mut SeededRng) -> usize {
+        let epsilon = c_info.epsilon.at(self.episodes);
+        let (_, q) = self.entry(s, env);
+        let r:f64 = rng.gen();
+        if r < epsilon { return rng.gen_range(0..q.len()) }
+        Self::greedy(q, rng)
+    }
+
+    //α for the next update of (s, a), the entry of s is already there
+    fn step_size<A:Schedule, X>(&mut self, s:&Value, a:usize, c_info:&ControlInfo<A, X>) -> f64 {
+        let n = self.q[&s.to_string()].1.len();
+        let count = &mut self.updates.entry(s.to_string()).or_insert_with(|| vec![0; n])[a];
+        *count += 1;
+        c_info.alpha.at(*count)
+    }
+
+    fn episode<A:Schedule, X:Schedule>(&mut self, env:&mut ExternalEnv<Value, Value>, c_info:&ControlInfo<A, X>, rng:&mut SeededRng) -> f64 {
+        self.episodes += 1;
+        let mut s = env.reset(rng);
+        let mut g = 0.0;
+        for _ in 0..c_info.max_step {
+            let a = self.select(&s, env, c_info, rng);
+            let action = self.entry(&s, env).0[a].clone();
+            let (ss, r, done) = env.step(&action, rng);
+            g += r;
+            let q_next = if done { 0.0 }
+                else { self.entry(&ss, env).1.iter().cloned().fold(f64::NEG_INFINITY, f64::max) };
+            let alpha = self.step_size(&s, a, c_info);
+            let q = &mut self.entry(&s, env).1[a];
+            *q += alpha * (r + c_info.gamma * q_next - *q);
+            if done { break }
+            s = ss;
+        }
+        g
+    }
+}
+
+pub fn run(command:&str, seed:u64) -> Result<(), Box<dyn Error>> {
+    let c_info = ControlInfo {
+        max_episode:500, max_step:10000,
+        epsilon:0.1, alpha:0.1, gamma:1.0,
+        report:50,
+    };
+    let mut rng = rng::from_seed(seed);
+    let mut env:ExternalEnv<Value, Value> = ExternalEnv::spawn(command)?;
+    let mut agent = Agent { q:HashMap::new(), updates:HashMap::new(), episodes:0 };
+    let mut g_sum = 0.0;
+    let pb = progress::counter(c_info.max_episode as u64, "episode");
+    for ep in 1..=c_info.max_episode {
+        g_sum += agent.episode(&mut env, &c_info, &mut rng);
+        pb.inc(1);
+        if ep % c_info.report == 0 {
+            pb.suspend(|| println!("episode {}: average return {:.2}", ep, g_sum / c_info.report as f64));
+            g_sum = 0.0;
+        }
+    }
+    pb.finish_and_clear();
+    println!("states seen {}", agent.q.len());
+    let greedy = ControlInfo { epsilon:0.0, alpha:0.0, ..c_info };
+    let g = (0..100).map(|_| agent.episode(&mut env, &greedy, &mut rng)).sum::<f64>() / 100.0;
+    println!("greedy policy average return {:.2}", g);
+    Ok(())
+}
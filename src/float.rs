@@ -0,0 +1,53 @@
+use std::fmt::{ Debug, Display };
+use std::iter::Sum;
+use std::ops::{ Add, AddAssign, Div, Mul, Neg, Sub };
+use serde::Serialize;
+
+use crate::store::Plain;
+
+//value type of the dp solvers, f32 halves the memory of big state spaces.
+//setup math stays in f64 and is converted once when stored, Plain lets --mmap put it in a file
+pub trait Float:
+    Copy + Default + Debug + Display + PartialOrd + Serialize + Send + Sync + Plain + 'static
+    + Add<Output = Self> + Sub<Output = Self> + Mul<Output = Self> + Div<Output = Self>
+    + Neg<Output = Self> + AddAssign + Sum {
+    const ZERO:Self;
+    const ONE:Self;
+    fn from_f64(v:f64) -> Self;
+    fn to_f64(self) -> f64;
+    fn abs(self) -> Self;
+    fn max(self, other:Self) -> Self;
+    fn total_cmp(&self, other:&Self) -> std::cmp::Ordering;
+}
+
+macro_rules! impl_float {
+    ($t:ty) => {
+        impl Float for $t {
+            const ZERO:Self = 0.0;
+            const ONE:Self = 1.0;
+
+            fn from_f64(v:f64) -> Self {
+                v as $t
+            }
+
+            fn to_f64(self) -> f64 {
+                self as f64
+            }
+
+            fn abs(self) -> Self {
+                <$t>::abs(self)
+            }
+
+            fn max(self, other:Self) -> Self {
+                <$t>::max(self, other)
+            }
+
+            fn total_cmp(&self, other:&Self) -> std::cmp::Ordering {
+                <$t>::total_cmp(self, other)
+            }
+        }
+    };
+}
+
+impl_float!(f32);
+impl_float!(f64);
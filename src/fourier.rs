@@ -0,0 +1,54 @@
+use std::f64::consts::PI;
+
+use crate::vfa::{ FeatureVec, Features };
+
+//order-n fourier cosine basis of section 9.5.2, x_i(s) = cos(π c_i · s) for every c_i in
+//{0, ..., n}^k over a state already scaled into [0, 1]^k, (n + 1)^k features.
+//feature i learns at α / |c_i|, the constant one at α, after Konidaris et al. (2011)
+pub struct Fourier {
+    pub order:usize,
+    pub c:Vec<Vec<f64>>,
+    scale:Vec<f64>,
+}
+
+//every vector in {0, ..., n}^k, the first entry fastest
+pub fn coefficients(order:usize, dims:usize) -> Vec<Vec<f64>> {
+    let mut c = vec!(Vec::new());
+    for _ in 0..dims {
+        c = (0..=order)
+            .flat_map(|k| c.iter().map(move |c:&Vec<f64>| {
+                let mut c = c.clone();
+                c.push(k as f64);
+                c
+            }))
+            .collect();
+    }
+    c
+}
+
+impl Fourier {
+    pub fn new(order:usize, dims:usize) -> Self {
+        let c = coefficients(order, dims);
+        let scale = c.iter()
+            .map(|c| c.iter().map(|v| v * v).sum::<f64>().sqrt())
+            .map(|norm| if norm > 0.0 { 1.0 / norm } else { 1.0 })
+            .collect();
+        Self { order, c, scale }
+    }
+}
+
+impl Features<Vec<f64>> for Fourier {
+    fn dim(&self) -> usize {
+        self.c.len()
+    }
+
+    fn features(&self, s:&Vec<f64>) -> FeatureVec {
+        self.c.iter().enumerate()
+            .map(|(k, c)| (k, (PI * c.iter().zip(s.iter()).map(|(c, s)| c * s).sum::<f64>()).cos()))
+            .collect()
+    }
+
+    fn step_scale(&self, k:usize) -> f64 {
+        self.scale[k]
+    }
+}
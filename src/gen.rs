@@ -0,0 +1,24 @@
+use rand::prelude::*;
+use rand::seq::index;
+
+use crate::rng;
+use crate::tabular::Tabular;
+
+//random finite mdps for benchmarking the solvers and sanity checks between them.
+//every (state, action) leads to `branching` distinct states with random probabilities,
+//rewards are uniform in [-1, 1). no state is terminal, so solve them with γ < 1
+pub fn random_mdp(num_states:usize, num_actions:usize, branching:usize, seed:u64) -> Tabular {
+    assert!(branching >= 1 && branching <= num_states, "branching must be in 1..=num_states");
+    let mut rng = rng::from_seed(seed);
+    let mut mdp = Tabular::new(num_states);
+    for s in 0..num_states {
+        for _ in 0..num_actions {
+            let target = index::sample(&mut rng, num_states, branching);
+            let weight:Vec<f64> = (0..branching).map(|_| rng.gen::<f64>() + f64::EPSILON).collect();
+            let total:f64 = weight.iter().sum();
+            let next = target.iter().zip(weight).map(|(t, w)| (t, w / total)).collect();
+            mdp.push(s, rng.gen_range(-1.0..1.0), next);
+        }
+    }
+    mdp
+}
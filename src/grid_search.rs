@@ -0,0 +1,244 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs::{ self, File };
+use std::io::Write;
+use rayon::prelude::*;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::cliff_walking;
+use crate::experiment::{ Curve, Experiment };
+use crate::lstd::Lstd;
+use crate::mountain_car;
+use crate::nstep::{ Correction, NStepPrediction };
+use crate::policy::Behavior;
+use crate::progress;
+use crate::td::{ Method, TdControl };
+use crate::vfa::{ FeatureVec, Features };
+use crate::walk::RandomWalk;
+
+//parameter name -> candidate values, every combination is one setting, e.g.
+//{"task":"cliff_walking","runs":20,"episode":200,"grid":{"alpha":[0.1,0.5],"method":["sarsa"]}}
+//or {"task":"random_walk","runs":100,"episode":10,"grid":{"method":["nstep_td"],"n":[1,2,4],"alpha":[0.2,0.4]}}
+#[derive(Deserialize)]
+pub struct GridConfig {
+    pub task:String,
+    pub runs:usize,
+    pub episode:usize,
+    #[serde(default)]
+    pub parallel:bool,
+    #[serde(default = "default_output")]
+    pub output:String,
+    pub grid:BTreeMap<String, Vec<Value>>,
+}
+
+pub type Setting = BTreeMap<String, Value>;
+
+pub struct Outcome {
+    pub setting:Setting,
+    pub last:f64,//mean of the final episode
+    pub last_stderr:f64,
+    pub average:f64,//over every episode of every run
+}
+
+//what a task learns and how its curve is read
+struct Task {
+    pub metric:&'static str,
+    pub higher_is_better:bool,
+    pub param:&'static [&'static str],
+}
+
+//one-hot of the states of the walk, terminals included, i.e. a table for LSTD
+struct OneHot {
+    pub n:usize,
+}
+
+fn default_output() -> String {
+    "grid_search.csv".to_string()
+}
+
+fn task(name:&str) -> Result<Task, String> {
+    match name {
+        "cliff_walking" => Ok(Task { metric:"reward", higher_is_better:true, param:&["method", "alpha", "epsilon", "gamma"] }),
+        "mountain_car" => Ok(Task { metric:"steps", higher_is_better:false, param:&["alpha", "epsilon", "tilings"] }),
+        //19-state walk of example 7.1, n-step TD reads n and alpha, LSTD(λ) reads lambda
+        "random_walk" => Ok(Task { metric:"rms error", higher_is_better:false, param:&["method", "alpha", "n", "lambda"] }),
+        _ => Err(format!("unknown task {}, expected cliff_walking, mountain_car or random_walk", name)),
+    }
+}
+
+//cartesian product in key order
+pub fn settings(grid:&BTreeMap<String, Vec<Value>>) -> Vec<Setting> {
+    grid.iter().fold(vec!(Setting::new()), |acc, (name, values)| {
+        acc.iter().flat_map(|s| values.iter().map(move |v| {
+            let mut s = s.clone();
+            s.insert(name.clone(), v.clone());
+            s
+        })).collect()
+    })
+}
+
+fn number(s:&Setting, name:&str, default:f64) -> Result<f64, String> {
+    match s.get(name) {
+        None => Ok(default),
+        Some(v) => v.as_f64().ok_or(format!("{} should be a number, got {}", name, v)),
+    }
+}
+
+fn method(s:&Setting) -> Result<Method, String> {
+    match s.get("method").map(|v| v.as_str()) {
+        None => Ok(Method::Sarsa),
+        Some(Some("sarsa")) => Ok(Method::Sarsa),
+        Some(Some("q_learning")) => Ok(Method::QLearning),
+        Some(Some("expected_sarsa")) => Ok(Method::ExpectedSarsa),
+        Some(_) => Err(format!("unknown method {}", s["method"])),
+    }
+}
+
+impl Features<usize> for OneHot {
+    fn dim(&self) -> usize {
+        self.n + 2
+    }
+
+    fn features(&self, s:&usize) -> FeatureVec {
+        vec!((*s, 1.0))
+    }
+}
+
+//rms error over the non-terminal states of the walk after every episode
+fn walk_curve(s:&Setting, episode:usize, experiment:&Experiment) -> Result<Curve, String> {
+    let method = s.get("method").map_or(Some("nstep_td"), |v| v.as_str());
+    let read:&[&str] = match method {
+        Some("nstep_td") => &["method", "alpha", "n"],
+        Some("lstd") => &["method", "lambda"],
+        _ => return Err(format!("unknown method {}, expected nstep_td or lstd", s["method"])),
+    };
+    if let Some(name) = s.keys().find(|k| !read.contains(&k.as_str())) {
+        return Err(format!("{} does not read {}", method.unwrap_or_default(), name))
+    }
+    let n = number(s, "n", 1.0)?;
+    if n < 1.0 || n.fract() != 0.0 { return Err(format!("n should be a positive integer, got {}", n)) }
+    let (n, alpha, lambda) = (n as usize, number(s, "alpha", 0.1)?, number(s, "lambda", 0.0)?);
+    let mut w = RandomWalk::new(19, -1.0, 1.0);
+    let v_true = w.true_value(1e-12);
+    let states = w.n;
+    let rms = move |v:&dyn Fn(usize) -> f64| ((1..=states).map(|k| (v(k) - v_true[k]).powi(2)).sum::<f64>() / states as f64).sqrt();
+    Ok(experiment.repeat(|rng| {
+        match method {
+            Some("lstd") => {
+                let mut v = Lstd::new(OneHot { n:states }, 1.0, lambda, 1e-3);
+                (0..episode).map(|_| {
+                    let t = w.episode(rng);
+                    let trajectory:Vec<(usize, f64)> = t.state.into_iter().zip(t.reward).collect();
+                    v.episode(&trajectory);
+                    let weight = v.weights();
+                    rms(&|k| v.value(&weight, &k))
+                }).collect()
+            },
+            _ => {
+                let mut v = NStepPrediction::new(n, alpha, 1.0, Correction::Ordinary);
+                (0..episode).map(|_| {
+                    let trajectory = w.episode(rng);
+                    v.episode(&trajectory, &vec![1.0; trajectory.steps()]);
+                    rms(&|k| v.value(&k))
+                }).collect()
+            },
+        }
+    }))
+}
+
+fn learn(task:&str, s:&Setting, episode:usize, experiment:&Experiment) -> Result<Curve, String> {
+    match task {
+        "cliff_walking" => {
+            let method = method(s)?;
+            let behavior = Behavior::EpsilonGreedy(number(s, "epsilon", 0.1)?);
+            let (alpha, gamma) = (number(s, "alpha", 0.5)?, number(s, "gamma", 1.0)?);
+            Ok(experiment.repeat(|rng| {
+                let mut w = cliff_walking::World::new();
+                let mut agent = TdControl::new(method, behavior, alpha, gamma);
+                (0..episode).map(|_| agent.episode(&mut w, rng, 100000).0).collect()
+            }))
+        },
+        "mountain_car" => {
+            let (alpha, epsilon) = (number(s, "alpha", 0.5)?, number(s, "epsilon", 0.0)?);
+            let tilings = number(s, "tilings", 8.0)? as usize;
+            Ok(experiment.repeat(|rng| mountain_car::steps_curve(alpha, epsilon, tilings, episode, rng)))
+        },
+        "random_walk" => walk_curve(s, episode, experiment),
+        _ => Err(format!("unknown task {}", task)),
+    }
+}
+
+//every setting sees the same seeds
+pub fn search(config:&GridConfig, seed:u64) -> Result<Vec<Outcome>, Box<dyn Error>> {
+    let t = task(&config.task)?;
+    if let Some(name) = config.grid.keys().find(|k| !t.param.contains(&k.as_str())) {
+        return Err(format!("{} has no parameter {}, expected one of {:?}", config.task, name, t.param).into())
+    }
+    //the last episode and the average need one episode of one run at least
+    if config.episode == 0 || config.runs == 0 {
+        return Err(format!("episode and runs should be at least 1, got {} and {}", config.episode, config.runs).into())
+    }
+    let setting = settings(&config.grid);
+    let mut experiment = Experiment::new(config.runs, seed);
+    experiment.progress = false;
+    let pb = progress::counter(setting.len() as u64, "setting");
+    let evaluate = |s:&Setting| -> Result<Outcome, String> {
+        let curve = learn(&config.task, s, config.episode, &experiment)?;
+        pb.inc(1);
+        let last = curve.len() - 1;
+        Ok(Outcome {
+            setting:s.clone(),
+            last:curve.mean[last], last_stderr:curve.stderr[last],
+            average:curve.tail(curve.len()),
+        })
+    };
+    let outcome:Result<Vec<Outcome>, String> = if config.parallel {
+        setting.par_iter().map(evaluate).collect()
+    } else {
+        setting.iter().map(evaluate).collect()
+    };
+    pb.finish_and_clear();
+    Ok(outcome?)
+}
+
+fn cell(v:&Value) -> String {
+    match v {
+        Value::String(s) => s.clone(),
+        v => v.to_string(),
+    }
+}
+
+//one row per setting, in grid order
+pub fn write_csv(path:&str, name:&[&String], metric:&str, outcome:&[Outcome]) -> std::io::Result<()> {
+    let mut f = File::create(path)?;
+    let header:Vec<&str> = name.iter().map(|n| n.as_str()).collect();
+    writeln!(f, "{},last {1},last {1} se,average {1}", header.join(","), metric)?;
+    for o in outcome.iter() {
+        let row:Vec<String> = o.setting.values().map(cell).collect();
+        writeln!(f, "{},{},{},{}", row.join(","), o.last, o.last_stderr, o.average)?;
+    }
+    Ok(())
+}
+
+pub fn run(config:&str, seed:u64) -> Result<(), Box<dyn Error>> {
+    let config:GridConfig = serde_json::from_str(&fs::read_to_string(config)?)?;
+    let t = task(&config.task)?;
+    let mut outcome = search(&config, seed)?;
+    let name:Vec<&String> = config.grid.keys().collect();
+    write_csv(&config.output, &name, t.metric, &outcome)?;
+    //best first in the printed table
+    outcome.sort_by(|a, b| if t.higher_is_better { b.average.total_cmp(&a.average) } else { a.average.total_cmp(&b.average) });
+    for n in name.iter() {
+        print!("{:>16}", n);
+    }
+    println!("{:>20}{:>20}", format!("last {}", t.metric), format!("average {}", t.metric));
+    for o in outcome.iter() {
+        for v in o.setting.values() {
+            print!("{:>16}", cell(v));
+        }
+        println!("{:>20}{:>20.2}", format!("{:.2}±{:.2}", o.last, 1.96 * o.last_stderr), o.average);
+    }
+    println!("results written to {}", config.output);
+    Ok(())
+}
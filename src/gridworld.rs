@@ -0,0 +1,120 @@
+use std::error::Error;
+
+use crate::tabular::Tabular;
+use crate::stopping::{ Change, StoppingRule };
+
+type Action = (i32, i32);
+
+const ACTION:[Action; 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];//up right down left
+
+//example 4.1, states numbered row by row, the two shaded corners are terminal
+pub struct World {
+    pub size:i32,
+}
+
+impl Default for World {
+    fn default() -> Self {
+        Self::new(4)
+    }
+}
+
+impl World {
+    pub fn new(size:i32) -> Self {
+        Self { size }
+    }
+
+    pub fn states(&self) -> usize {
+        (self.size * self.size) as usize
+    }
+
+    fn is_terminal(&self, s:usize) -> bool {
+        s == 0 || s == self.states() - 1
+    }
+
+    //moves off the grid leave the state unchanged, every move costs 1
+    fn transition(&self, s:usize, a:&Action) -> (usize, f64) {
+        let (x, y) = (s as i32 % self.size, s as i32 / self.size);
+        let (xx, yy) = (x + a.0, y + a.1);
+        if xx < 0 || yy < 0 || xx >= self.size || yy >= self.size { return (s, -1.0) }
+        ((yy * self.size + xx) as usize, -1.0)
+    }
+
+    fn backup(&self, v:&[f64], s:usize, a:&Action, gamma:f64) -> f64 {
+        let (ss, r) = self.transition(s, a);
+        r + gamma * v[ss]
+    }
+
+    //in-place iterative evaluation of the equiprobable random policy, figure 4.1
+    pub fn evaluate_random(&self, stopping:StoppingRule, gamma:f64) -> Vec<f64> {
+        let mut v = vec![0.0; self.states()];
+        loop {
+            //terminal states never change, which the span has to see
+            let mut change = Change::new();
+            change.add(0.0);
+            for s in (0..self.states()).filter(|s| !self.is_terminal(*s)) {
+                let vv = ACTION.iter().map(|a| self.backup(&v, s, a, gamma)).sum::<f64>() / ACTION.len() as f64;
+                change.add(vv - v[s]);
+                v[s] = vv;
+            }
+            if stopping.met(&change, gamma) { break v }
+        }
+    }
+
+    //value iteration, greedy action index into up right down left, first of the ties
+    pub fn solve(&self, stopping:StoppingRule, gamma:f64) -> (Vec<f64>, Vec<usize>) {
+        let mut v = vec![0.0; self.states()];
+        loop {
+            //terminal states never change, which the span has to see
+            let mut change = Change::new();
+            change.add(0.0);
+            for s in (0..self.states()).filter(|s| !self.is_terminal(*s)) {
+                let vv = ACTION.iter().map(|a| self.backup(&v, s, a, gamma)).fold(f64::NEG_INFINITY, f64::max);
+                change.add(vv - v[s]);
+                v[s] = vv;
+            }
+            if stopping.met(&change, gamma) { break }
+        }
+        let policy = (0..self.states()).map(|s| {
+            if self.is_terminal(s) { return 0 }
+            let q:Vec<f64> = ACTION.iter().map(|a| self.backup(&v, s, a, gamma)).collect();
+            let q_max = q.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+            q.iter().position(|q| *q == q_max).unwrap()
+        }).collect();
+        (v, policy)
+    }
+
+    //for the lp, the terminal corners get no row
+    pub fn tabular(&self) -> Tabular {
+        let mut mdp = Tabular::new(self.states());
+        for s in (0..self.states()).filter(|s| !self.is_terminal(*s)) {
+            for a in ACTION.iter() {
+                let (ss, r) = self.transition(s, a);
+                mdp.push(s, r, vec!((ss, 1.0)));
+            }
+        }
+        mdp
+    }
+
+    fn print(&self, v:&[f64]) {
+        for row in v.chunks(self.size as usize) {
+            for v in row.iter() {
+                print!("{:>8.1}", v);
+            }
+            println!();
+        }
+    }
+}
+
+pub fn run() -> Result<(), Box<dyn Error>> {
+    let w = World::new(4);
+    println!("random policy");
+    w.print(&w.evaluate_random(StoppingRule::MaxNorm(1e-6), 1.0));
+    let (v, p) = w.solve(StoppingRule::MaxNorm(1e-6), 1.0);
+    println!("optimal");
+    w.print(&v);
+    for row in p.chunks(w.size as usize) {
+        let arrow:Vec<&str> = row.iter().map(|a| ["↑", "→", "↓", "←"][*a]).collect();
+        println!("{}", arrow.join(" "));
+    }
+    Ok(())
+}
@@ -0,0 +1,83 @@
+use crate::schedules::Schedule;
+use crate::vfa::{ FeatureVec, Features, LinearVfa };
+
+//gradient-TD methods of section 11.7. both descend the projected Bellman error with a
+//secondary vector u ≈ E[xxᵀ]⁻¹E[ρδx] learned on the faster timescale, β > α.
+//α takes any Schedule counted by updates, a number for a constant one
+#[derive(Clone, Copy)]
+pub enum Method {
+    Gtd2,//w += αρ(x - γx')xᵀu
+    Tdc,//w += αρ(δx - γx'xᵀu), TD(0) plus a gradient correction
+}
+
+pub struct GradientTd<A = f64> {
+    pub method:Method,
+    pub alpha:A,
+    pub beta:f64,
+    pub gamma:f64,
+    pub u:Vec<f64>,
+    pub updates:usize,
+}
+
+impl Method {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Method::Gtd2 => "GTD2",
+            Method::Tdc => "TDC",
+        }
+    }
+}
+
+fn dot(x:&FeatureVec, w:&[f64]) -> f64 {
+    x.iter().map(|(k, x)| w[*k] * x).sum::<f64>()
+}
+
+impl GradientTd {
+    pub fn new(method:Method, alpha:f64, beta:f64, gamma:f64, dim:usize) -> Self {
+        Self { method, alpha, beta, gamma, u:vec![0.0; dim], updates:0 }
+    }
+}
+
+impl<A:Schedule> GradientTd<A> {
+    pub fn with_alpha<B:Schedule>(self, alpha:B) -> GradientTd<B> {
+        let Self { method, beta, gamma, u, updates, .. } = self;
+        GradientTd { method, alpha, beta, gamma, u, updates }
+    }
+
+    //one transition of b with ρ = π(A|S)/b(A|S), ss is None for a terminal next state
+    pub fn step<S, F:Features<S>>(&mut self, v:&mut LinearVfa<F>, s:&S, r:f64, ss:Option<&S>, rho:f64) {
+        let v_next = match ss {
+            Some(ss) => v.value(ss),
+            None => 0.0,
+        };
+        let delta = r + self.gamma * v_next - v.value(s);
+        self.update(v, s, ss, rho, delta, 1.0);
+    }
+
+    //both updates for a given δ, with α and β scaled, for expected updates that sum over states
+    pub fn update<S, F:Features<S>>(&mut self, v:&mut LinearVfa<F>, s:&S, ss:Option<&S>, rho:f64, delta:f64, scale:f64) {
+        let x = v.feature.features(s);
+        let xx = ss.map(|ss| v.feature.features(ss)).unwrap_or_default();
+        let xu = dot(&x, &self.u);
+        self.updates += 1;
+        let alpha = scale * self.alpha.at(self.updates) * rho;
+        match self.method {
+            Method::Gtd2 => {
+                for (k, x) in x.iter() {
+                    v.w[*k] += alpha * xu * x;
+                }
+            },
+            Method::Tdc => {
+                for (k, x) in x.iter() {
+                    v.w[*k] += alpha * delta * x;
+                }
+            },
+        }
+        for (k, x) in xx.iter() {
+            v.w[*k] -= alpha * self.gamma * xu * x;
+        }
+        for (k, x) in x.iter() {
+            self.u[*k] += scale * self.beta * rho * (delta - xu) * x;
+        }
+    }
+}
@@ -0,0 +1,25 @@
+use std::error::Error;
+use plotters::{prelude::*, coord::Shift};
+
+use crate::ansi;
+
+//one cell per grid[x][y], coloured from the lowest to the highest value like the printed grids
+pub fn draw(title:&str, grid:&[Vec<f64>], canvas:&DrawingArea<BitMapBackend, Shift>) -> Result<(), Box<dyn Error>> {
+    let lo = grid.iter().flatten().cloned().fold(f64::INFINITY, f64::min);
+    let hi = grid.iter().flatten().cloned().fold(f64::NEG_INFINITY, f64::max);
+    let (w, h) = (grid.len(), grid[0].len());
+    let mut chart = ChartBuilder::on(canvas)
+        .caption(format!("{} [{:.3e}, {:.3e}]", title, lo, hi), ("sans-serif", 30))
+        .margin(5)
+        .x_label_area_size(50)
+        .y_label_area_size(50)
+        .build_cartesian_2d(0..w, 0..h)?;
+    chart.configure_mesh().disable_mesh().draw()?;
+    chart.draw_series(grid.iter().enumerate().flat_map(|(x, column)| {
+        column.iter().enumerate().map(move |(y, v)| {
+            let (r, g, b) = ansi::sequential(ansi::scale(*v, lo, hi));
+            Rectangle::new([(x, y), (x + 1, y + 1)], RGBColor(r, g, b).filled())
+        })
+    }))?;
+    Ok(())
+}
@@ -0,0 +1,107 @@
+use std::fs::File;
+use std::io::{ self, Write };
+use std::time::Instant;
+use serde::Serialize;
+
+use crate::deterministic;
+
+//one evaluation sweep
+pub struct SweepRecord {
+    pub iteration:usize,
+    pub sweep:usize,
+    pub delta:f64,
+    pub seconds:f64,//the sweep alone
+}
+
+//one policy evaluation + improvement round
+pub struct IterationRecord {
+    pub iteration:usize,
+    pub sweeps:usize,
+    pub policy_changes:usize,
+    pub elapsed:f64,//seconds since the run started
+    pub improvement:f64,//seconds of the improvement pass alone
+}
+
+//time per phase over a whole run, with how often each ran
+#[derive(Serialize)]
+pub struct Timing {
+    pub setup:f64,//seconds
+    pub sweeps:usize,
+    pub evaluation:f64,
+    pub improvements:usize,
+    pub improvement:f64,
+}
+
+pub struct RunHistory {
+    pub sweep:Vec<SweepRecord>,
+    pub iteration:Vec<IterationRecord>,
+    pub setup:f64,//seconds building the model before the first sweep
+    start:Instant,
+}
+
+impl Default for RunHistory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RunHistory {
+    pub fn new() -> Self {
+        Self { sweep:Vec::new(), iteration:Vec::new(), setup:0.0, start:Instant::now() }
+    }
+
+    fn current_iteration(&self) -> usize {
+        self.iteration.len()
+    }
+
+    pub fn record_sweep(&mut self, delta:f64, seconds:f64) {
+        let iteration = self.current_iteration();
+        let sweep = self.sweep.iter().filter(|s| s.iteration == iteration).count();
+        self.sweep.push(SweepRecord { iteration, sweep, delta, seconds });
+    }
+
+    pub fn record_iteration(&mut self, policy_changes:usize, improvement:f64) {
+        let iteration = self.current_iteration();
+        let sweeps = self.sweep.iter().filter(|s| s.iteration == iteration).count();
+        let elapsed = deterministic::elapsed(&self.start);
+        self.iteration.push(IterationRecord { iteration, sweeps, policy_changes, elapsed, improvement });
+    }
+
+    pub fn timing(&self) -> Timing {
+        Timing {
+            setup:self.setup,
+            sweeps:self.sweep.len(),
+            evaluation:self.sweep.iter().map(|s| s.seconds).sum(),
+            improvements:self.iteration.len(),
+            improvement:self.iteration.iter().map(|r| r.improvement).sum(),
+        }
+    }
+
+    pub fn print_summary(&self) {
+        println!("iteration sweeps changes elapsed(s)");
+        for r in self.iteration.iter() {
+            println!("\t{} {} {} {:.3}", r.iteration, r.sweeps, r.policy_changes, r.elapsed);
+        }
+        let t = self.timing();
+        let mean = |seconds:f64, n:usize| if n == 0 { 0.0 } else { seconds / n as f64 };
+        println!("time per phase:");
+        println!("\tsetup {:.1}ms", t.setup * 1e3);
+        println!("\tevaluation {:.1}ms over {} sweeps, {:.3}ms each", t.evaluation * 1e3, t.sweeps, mean(t.evaluation, t.sweeps) * 1e3);
+        println!("\timprovement {:.1}ms over {} passes, {:.3}ms each", t.improvement * 1e3, t.improvements, mean(t.improvement, t.improvements) * 1e3);
+    }
+
+    //<prefix>_sweep.csv and <prefix>_iteration.csv
+    pub fn write_csv(&self, prefix:&str) -> io::Result<()> {
+        let mut f = File::create(format!("{}_sweep.csv", prefix))?;
+        writeln!(f, "iteration,sweep,delta,seconds")?;
+        for r in self.sweep.iter() {
+            writeln!(f, "{},{},{},{}", r.iteration, r.sweep, r.delta, r.seconds)?;
+        }
+        let mut f = File::create(format!("{}_iteration.csv", prefix))?;
+        writeln!(f, "iteration,sweeps,policy_changes,elapsed,improvement")?;
+        for r in self.iteration.iter() {
+            writeln!(f, "{},{},{},{},{}", r.iteration, r.sweeps, r.policy_changes, r.elapsed, r.improvement)?;
+        }
+        Ok(())
+    }
+}
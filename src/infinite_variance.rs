@@ -0,0 +1,97 @@
+use std::error::Error;
+use plotters::{prelude::*, coord::Shift};
+
+use rand::prelude::*;
+
+use crate::env::Environment;
+use crate::experiment::Experiment;
+use crate::mc::{ OffPolicyMc, Sampling, Visit };
+use crate::rollout;
+use crate::rng::{ self, SeededRng };
+
+const LEFT:usize = 0;
+const RIGHT:usize = 1;
+
+//example 5.5, a single nonterminal state. right ends the episode with 0, left comes back
+//with probability 0.9 and ends with +1 otherwise. π always goes left so v_π(s) = 1
+pub struct World {
+    pub p_back:f64,
+}
+
+impl Environment for World {
+    type State = ();
+    type Action = usize;
+
+    fn reset(&mut self, _:&mut SeededRng) {}
+
+    fn step(&mut self, a:&usize, rng:&mut SeededRng) -> ((), f64, bool) {
+        if *a == RIGHT { return ((), 0.0, true) }
+        if rng.gen::<f64>() < self.p_back { ((), 0.0, false) } else { ((), 1.0, true) }
+    }
+
+    fn actions(&self, _:&()) -> Vec<usize> {
+        vec!(LEFT, RIGHT)
+    }
+}
+
+//π(a|s) / b(a|s) with b equiprobable
+fn rho(_:&(), a:&usize) -> f64 {
+    if *a == LEFT { 2.0 } else { 0.0 }
+}
+
+//the estimate of v(s) after every episode of one run
+fn estimate(sampling:Sampling, episode:usize, rng:&mut SeededRng) -> (Vec<f64>, Vec<f64>) {
+    let mut world = World { p_back:0.9 };
+    let mut mc = OffPolicyMc::new(sampling, Visit::First, 1.0);
+    let v = (0..episode).map(|_| {
+        let trajectory = rollout::simulate_episode(&mut world, |_, rng| rng.gen_range(0..2), rng, usize::MAX);
+        mc.update(&trajectory, rho);
+        mc.v.get(&()).copied().unwrap_or(0.0)
+    }).collect();
+    (v, mc.ratio)
+}
+
+//every run as its own line over log episodes, figure 5.4
+fn draw_runs(run:&[Vec<f64>], sampling:Sampling, canvas:&DrawingArea<BitMapBackend, Shift>) -> Result<(), Box<dyn Error>> {
+    let n = run[0].len();
+    let mut chart = ChartBuilder::on(canvas)
+        .caption(format!("{}, {} runs", sampling.label(), run.len()), ("sans-serif", 30))
+        .margin(5)
+        .x_label_area_size(50)
+        .y_label_area_size(50)
+        .build_cartesian_2d((1..n).log_scale(), 0.0..3.0)?;
+    chart.configure_mesh().x_desc("episodes").y_desc("v(s)").draw()?;
+    for (k, v) in run.iter().enumerate() {
+        let color = Palette99::pick(k).to_rgba();
+        chart.draw_series(LineSeries::new(v.iter().cloned().enumerate().skip(1), color))?;
+    }
+    Ok(())
+}
+
+pub fn run(seed:u64, runs:Option<usize>) -> Result<(), Box<dyn Error>> {
+    let episode = 100000;
+    let experiment = Experiment::new(runs.unwrap_or(10), seed);
+    let canvas = BitMapBackend::new("5_5.png", (1440, 1440)).into_drawing_area();
+    canvas.fill(&WHITE)?;
+    let canvas_split = canvas.split_evenly((2, 1));
+    for (sampling, area) in [Sampling::Ordinary, Sampling::Weighted].into_iter().zip(canvas_split.iter()) {
+        let mut run = Vec::new();
+        let mut ratio = Vec::new();
+        for seed in experiment.seeds() {
+            let (v, r) = estimate(sampling, episode, &mut rng::from_seed(seed));
+            run.push(v);
+            ratio.extend(r);
+        }
+        //ρ is 2^T on the all-left episodes and 0 otherwise
+        let nonzero:Vec<f64> = ratio.iter().cloned().filter(|r| *r > 0.0).collect();
+        let last:Vec<String> = run.iter().map(|v| format!("{:.3}", v[v.len() - 1])).collect();
+        println!("{}: final v(s) {}", sampling.label(), last.join(" "));
+        println!("\tρ nonzero in {:.2}% of episodes, max {:.3e}, mean {:.3}",
+            100.0 * nonzero.len() as f64 / ratio.len() as f64,
+            nonzero.iter().cloned().fold(0.0, f64::max),
+            ratio.iter().sum::<f64>() / ratio.len() as f64);
+        draw_runs(&run, sampling, area)?;
+    }
+    canvas.present()?;
+    Ok(())
+}
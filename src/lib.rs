@@ -0,0 +1,107 @@
+//figure runners write png files and are left out of wasm32 builds
+pub mod afterstate;
+pub mod alloc;
+pub mod ansi;
+pub mod cart_pole;
+pub mod cliff_walking;
+pub mod coarse;
+pub mod deterministic;
+pub mod differential;
+pub mod dyna;
+pub mod emphatic;
+pub mod env;
+pub mod experience_log;
+pub mod experiment;
+pub mod float;
+pub mod fourier;
+pub mod gen;
+pub mod gtd;
+pub mod history;
+pub mod linear;
+pub mod lp;
+pub mod lstd;
+pub mod maze;
+pub mod mc;
+pub mod nd_vec;
+pub mod npy;
+pub mod offline;
+pub mod nstep;
+pub mod parallel;
+pub mod poisson;
+pub mod policy;
+pub mod polynomial;
+pub mod progress;
+pub mod replay;
+pub mod rollout;
+pub mod rng;
+pub mod rtdp;
+pub mod schedules;
+pub mod stopping;
+pub mod store;
+pub mod tabular;
+pub mod td;
+pub mod tiles;
+pub mod vfa;
+pub mod walk;
+pub mod access_control;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod baird;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod bandit;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod cli;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod compare;
+#[cfg(all(feature = "dqn", not(target_arch = "wasm32")))]
+pub mod dqn;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod dyna_maze;
+pub mod exercise4_7;
+pub mod exercise4_9;
+pub mod exercise5_12;
+pub mod exercise6_9;
+pub mod external;
+pub mod gridworld;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod heatmap;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod infinite_variance;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod grid_search;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod mountain_car;
+pub mod policy_gradient;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod random_walk;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod registry;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod sample_update;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod schema;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod short_corridor;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod square_wave;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod tic_tac_toe;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod tui;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod walk19;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod walk5;
+
+pub use env::Environment;
+pub use float::Float;
+pub use nd_vec::{ NdVec1, NdVec2 };
+pub use poisson::Poisson;
+pub use rng::SeededRng;
+pub use stopping::StoppingRule;
+
+#[cfg(feature = "ndarray")]
+mod nd_array;
+#[cfg(feature = "python")]
+mod python;
+#[cfg(target_arch = "wasm32")]
+mod wasm;
@@ -0,0 +1,31 @@
+//dense a x = b by gaussian elimination with partial pivoting, a is n x n row by row.
+//None when a pivot vanishes, i.e. a is singular
+pub fn solve(mut a:Vec<f64>, mut b:Vec<f64>) -> Option<Vec<f64>> {
+    let n = b.len();
+    assert_eq!(a.len(), n * n);
+    for col in 0..n {
+        let pivot = (col..n).max_by(|i, j| a[i * n + col].abs().total_cmp(&a[j * n + col].abs())).unwrap();
+        if a[pivot * n + col].abs() < 1e-12 { return None }
+        if pivot != col {
+            for k in 0..n {
+                a.swap(pivot * n + k, col * n + k);
+            }
+            b.swap(pivot, col);
+        }
+        let d = a[col * n + col];
+        for i in col + 1..n {
+            let f = a[i * n + col] / d;
+            if f == 0.0 { continue }
+            for k in col..n {
+                a[i * n + k] -= f * a[col * n + k];
+            }
+            b[i] -= f * b[col];
+        }
+    }
+    let mut x = vec![0.0; n];
+    for i in (0..n).rev() {
+        let s:f64 = (i + 1..n).map(|k| a[i * n + k] * x[k]).sum();
+        x[i] = (b[i] - s) / a[i * n + i];
+    }
+    Some(x)
+}
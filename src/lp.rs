@@ -0,0 +1,37 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+
+use microlp::{ ComparisonOp, LinearExpr, OptimizationDirection, Problem };
+
+use crate::tabular::Tabular;
+
+//primal value lp: minimize Σ v(s) subject to v(s) >= r(s,a) + γ Σ p(s'|s,a) v(s') for every row.
+//the smallest v meeting every constraint is v*. terminal states are held at 0,
+//so γ = 1 only works when every policy the lp can lean on reaches one
+pub fn solve(mdp:&Tabular, gamma:f64) -> Result<Vec<f64>, Box<dyn Error>> {
+    let mut problem = Problem::new(OptimizationDirection::Minimize);
+    let mut terminal = vec![true; mdp.states];
+    for r in mdp.row.iter() {
+        terminal[r.state] = false;
+    }
+    let v:Vec<_> = terminal.iter()
+        .map(|t| problem.add_var(1.0, if *t { (0.0, 0.0) } else { (f64::NEG_INFINITY, f64::INFINITY) }))
+        .collect();
+    for r in mdp.row.iter() {
+        //self transitions fold into the coefficient of v(s)
+        let mut coeff = BTreeMap::new();
+        *coeff.entry(r.state).or_insert(0.0) += 1.0;
+        for (s, p) in r.next.iter() {
+            *coeff.entry(*s).or_insert(0.0) -= gamma * p;
+        }
+        let expr:LinearExpr = coeff.into_iter().map(|(s, c)| (v[s], c)).collect();
+        problem.add_constraint(expr, ComparisonOp::Ge, r.reward);
+    }
+    let solution = problem.solve()?;
+    Ok(v.iter().map(|x| solution[*x]).collect())
+}
+
+//largest |a - b|, how far an iterative solver landed from the lp
+pub fn max_error(a:&[f64], b:&[f64]) -> f64 {
+    a.iter().zip(b.iter()).map(|(a, b)| (a - b).abs()).fold(0.0, f64::max)
+}
@@ -0,0 +1,81 @@
+use crate::vfa::Features;
+
+//least-squares TD of section 9.8 with the accumulating trace of LSTD(λ). A = Σ z (x - γx')ᵀ
+//and b = Σ r z are kept, A through its inverse updated by Sherman-Morrison in O(d²) per
+//step. A starts as εI so the inverse exists from the first step
+pub struct Lstd<F> {
+    pub feature:F,
+    pub gamma:f64,
+    pub lambda:f64,
+    pub a_inv:Vec<Vec<f64>>,
+    pub b:Vec<f64>,
+    z:Vec<f64>,
+}
+
+impl<F> Lstd<F> {
+    pub fn new<S>(feature:F, gamma:f64, lambda:f64, epsilon:f64) -> Self
+        where F:Features<S> {
+        let d = feature.dim();
+        let a_inv = (0..d).map(|i| (0..d).map(|j| if i == j { 1.0 / epsilon } else { 0.0 }).collect()).collect();
+        Self { feature, gamma, lambda, a_inv, b:vec![0.0; d], z:vec![0.0; d] }
+    }
+
+    fn dense<S>(&self, s:&S) -> Vec<f64>
+        where F:Features<S> {
+        let mut x = vec![0.0; self.b.len()];
+        for (k, v) in self.feature.features(s) {
+            x[k] += v;
+        }
+        x
+    }
+
+    //one transition, ss is None for a terminal next state, which also clears the trace
+    pub fn step<S>(&mut self, s:&S, r:f64, ss:Option<&S>)
+        where F:Features<S> {
+        let x = self.dense(s);
+        let x_next = match ss {
+            Some(ss) => self.dense(ss),
+            None => vec![0.0; x.len()],
+        };
+        for (z, x) in self.z.iter_mut().zip(x.iter()) {
+            *z = self.gamma * self.lambda * *z + x;
+        }
+        let v:Vec<f64> = x.iter().zip(x_next.iter()).map(|(x, xx)| x - self.gamma * xx).collect();
+        //A⁻¹ -= (A⁻¹z)(vᵀA⁻¹) / (1 + vᵀA⁻¹z)
+        let u:Vec<f64> = self.a_inv.iter().map(|row| dot(row, &self.z)).collect();
+        let vt:Vec<f64> = (0..v.len()).map(|j| self.a_inv.iter().zip(v.iter()).map(|(row, v)| v * row[j]).sum()).collect();
+        let denom = 1.0 + dot(&vt, &self.z);
+        for (row, u) in self.a_inv.iter_mut().zip(u.iter()) {
+            for (a, vt) in row.iter_mut().zip(vt.iter()) {
+                *a -= u * vt / denom;
+            }
+        }
+        for (b, z) in self.b.iter_mut().zip(self.z.iter()) {
+            *b += r * z;
+        }
+        if ss.is_none() { self.z.iter_mut().for_each(|z| *z = 0.0) }
+    }
+
+    //every step of an episode of (state, following reward) pairs
+    pub fn episode<S>(&mut self, trajectory:&[(S, f64)])
+        where F:Features<S> {
+        for (t, (s, r)) in trajectory.iter().enumerate() {
+            self.step(s, *r, trajectory.get(t + 1).map(|(ss, _)| ss));
+        }
+    }
+
+    //w = A⁻¹b
+    pub fn weights(&self) -> Vec<f64> {
+        self.a_inv.iter().map(|row| dot(row, &self.b)).collect()
+    }
+
+    //v(s) from weights already solved for
+    pub fn value<S>(&self, w:&[f64], s:&S) -> f64
+        where F:Features<S> {
+        self.feature.features(s).iter().map(|(k, x)| w[*k] * x).sum()
+    }
+}
+
+fn dot(a:&[f64], b:&[f64]) -> f64 {
+    a.iter().zip(b.iter()).map(|(a, b)| a * b).sum()
+}
@@ -1,26 +1,16 @@
+use rl_exercise::alloc::Counting;
+use rl_exercise::cli::{ self, Args };
 
-mod nd_vec;
-mod poisson;
-mod exercise4_7;
-mod exercise4_9;
-mod exercise5_12;
-mod exercise6_9;
+//counts allocations for the bench mode
+#[global_allocator]
+static ALLOC:Counting = Counting;
 
 fn main() {
-    let index = "6_9";
-    let ret = match index {
-        //policy iteraction: policy evaluation -> policy improvement, equiprobable
-        "4_7" => exercise4_7::run(),
-        //value iteraction -> policy, equiprobable
-        "4_9" => exercise4_9::run(),
-        //monte carlo off-policy b:Ɛ-soft
-        "5_12" => exercise5_12::run(),
-        //SARSA
-        "6_9" => exercise6_9::run(),
-        _ => Err(format!("invalid index {}", index).into()),
-    };
-    match ret {
-        Ok(_) => {},
-        Err(e) => { println!("{}", e.to_string()) }
+    let args = match Args::parse(std::env::args().skip(1)) {
+        Ok(v) => v,
+        Err(e) => { println!("{}", e); return }
     };
+    if let Err(e) = cli::run(&args) {
+        println!("{}", e)
+    }
 }
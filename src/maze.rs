@@ -0,0 +1,308 @@
+use std::collections::{ HashMap, VecDeque };
+use std::error::Error;
+use std::fs;
+
+use crate::afterstate::Afterstate;
+use crate::env::Environment;
+use crate::nd_vec::NdVec2;
+use crate::policy::Behavior;
+use crate::rng::{ self, SeededRng };
+use crate::td::{ Method, TdControl };
+
+type Vec2 = (i32, i32);
+
+const ACTION:[Vec2; 4] = [(0, 1), (1, 0), (0, -1), (-1, 0)];
+
+//a gridworld read from an ascii map, one character per cell with the first line at the top:
+//  .  open        #  wall
+//  S  start       G  goal, ends the episode
+//  C  cliff, back to the start
+//lines of a keyword and numbers set everything else, in any order around the map:
+//  step -1        reward of every move, -1 unless given
+//  goal 0         reward for reaching a goal on top of nothing else, 0 unless given
+//  cliff -100     reward for stepping into a cliff, -100 unless given
+//  wind 0 0 1 2   upward push of every column, applied after the move like example 6.5
+//  gamma 0.95     discount of the task, 1 unless given
+//blank lines and lines from ; on are skipped. moves off the map or into a wall stay put,
+//the wind stops below a wall
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Cell {
+    Open,
+    Wall,
+    Cliff,
+    Goal,
+}
+
+pub struct Maze {
+    pub cell:NdVec2<Cell>,//(x, y) with y = 0 the bottom line
+    pub start:Vec2,
+    pub wind:Vec<i32>,//per column
+    pub step_reward:f64,
+    pub goal_reward:f64,
+    pub cliff_reward:f64,
+    pub gamma:f64,
+    pub state:Vec2,
+}
+
+//a maze whose walls become those of a second map once at real steps have been taken, counted
+//across episodes, like the blocking and shortcut mazes of section 8.3. the agent stays where it is
+pub struct Changing {
+    pub maze:Maze,
+    pub after:NdVec2<Cell>,
+    pub at:usize,
+    pub steps:usize,
+}
+
+impl Cell {
+    fn parse(c:char) -> Option<Self> {
+        match c {
+            '.' | 'S' => Some(Self::Open),
+            '#' => Some(Self::Wall),
+            'C' => Some(Self::Cliff),
+            'G' => Some(Self::Goal),
+            _ => None,
+        }
+    }
+
+    fn symbol(&self) -> char {
+        match self {
+            Self::Open => '.',
+            Self::Wall => '#',
+            Self::Cliff => 'C',
+            Self::Goal => 'G',
+        }
+    }
+}
+
+impl Maze {
+    pub fn parse(text:&str) -> Result<Self, String> {
+        let (mut step_reward, mut goal_reward, mut cliff_reward, mut gamma) = (-1.0, 0.0, -100.0, 1.0);
+        let mut wind = None;
+        let mut row:Vec<(usize, Vec<char>)> = Vec::new();
+        for (k, line) in text.lines().enumerate() {
+            let line = line.split(';').next().unwrap().trim_end();
+            if line.trim().is_empty() { continue }
+            let mut word = line.split_whitespace();
+            let key = word.next().unwrap();
+            if key.chars().all(|c| c.is_ascii_lowercase()) {
+                let value = word.map(|v| v.parse::<f64>().map_err(|_| format!("line {}: invalid number {}", k + 1, v))).collect::<Result<Vec<f64>, String>>()?;
+                let single = || match value[..] {
+                    [v] => Ok(v),
+                    _ => Err(format!("line {}: {} takes one number", k + 1, key)),
+                };
+                match key {
+                    "step" => step_reward = single()?,
+                    "goal" => goal_reward = single()?,
+                    "cliff" => cliff_reward = single()?,
+                    "gamma" => gamma = single()?,
+                    "wind" => wind = Some(value.iter().map(|v| *v as i32).collect::<Vec<i32>>()),
+                    _ => return Err(format!("line {}: unknown keyword {}", k + 1, key)),
+                }
+                continue
+            }
+            row.push((k + 1, line.trim().chars().collect()));
+        }
+        if row.is_empty() { return Err("no map".to_string()) }
+        let width = row[0].1.len();
+        let height = row.len();
+        let mut cell = NdVec2::from_size((width, height));
+        cell.fill(Cell::Open);
+        let mut start = None;
+        for (y, (line, c)) in row.iter().rev().enumerate() {
+            if c.len() != width { return Err(format!("line {}: {} cells, the first map line has {}", line, c.len(), width)) }
+            for (x, c) in c.iter().enumerate() {
+                let p = (x as i32, y as i32);
+                cell[p] = Cell::parse(*c).ok_or_else(|| format!("line {}: unknown cell {}", line, c))?;
+                if *c == 'S' {
+                    if start.is_some() { return Err(format!("line {}: a second start", line)) }
+                    start = Some(p);
+                }
+            }
+        }
+        let start = start.ok_or("no start S")?;
+        if !cell.iter().any(|c| *c == Cell::Goal) { return Err("no goal G".to_string()) }
+        let wind = wind.unwrap_or_else(|| vec![0; width]);
+        if wind.len() != width { return Err(format!("wind has {} columns, the map {}", wind.len(), width)) }
+        Ok(Self { cell, start, wind, step_reward, goal_reward, cliff_reward, gamma, state:start })
+    }
+
+    pub fn load(path:&str) -> Result<Self, Box<dyn Error>> {
+        Ok(Self::parse(&fs::read_to_string(path)?).map_err(|e| format!("{}: {}", path, e))?)
+    }
+
+    pub fn size(&self) -> Vec2 {
+        self.cell.dimension
+    }
+
+    fn blocked(&self, p:&Vec2) -> bool {
+        self.cell.get(*p).is_none_or(|c| *c == Cell::Wall)
+    }
+
+    //where a leaves s, wind included
+    fn moved(&self, s:&Vec2, a:&Vec2) -> Vec2 {
+        let p = (s.0 + a.0, s.1 + a.1);
+        let mut p = if self.blocked(&p) { *s } else { p };
+        let w = self.wind[s.0 as usize];
+        for _ in 0..w.abs() {
+            let q = (p.0, p.1 + w.signum());
+            if self.blocked(&q) { break }
+            p = q;
+        }
+        p
+    }
+
+    //every cell as factor x factor cells, the start in the bottom left one of its block. the
+    //dyna maze at several resolutions is example 8.4
+    pub fn scaled(&self, factor:i32) -> Self {
+        let (w, h) = self.size();
+        let mut cell = NdVec2::from_size(((w * factor) as usize, (h * factor) as usize));
+        cell.fill(Cell::Open);
+        for y in 0..h * factor {
+            for x in 0..w * factor {
+                cell[(x, y)] = self.cell[(x / factor, y / factor)];
+            }
+        }
+        let wind = self.wind.iter().flat_map(|v| std::iter::repeat_n(*v, factor as usize)).collect();
+        let start = (self.start.0 * factor, self.start.1 * factor);
+        Self { cell, start, wind, state:start, ..*self }
+    }
+
+    //fewest steps from the start to a goal without touching a cliff, breadth first
+    pub fn shortest(&self) -> Option<usize> {
+        let mut distance = HashMap::from([(self.start, 0)]);
+        let mut open = VecDeque::from([self.start]);
+        while let Some(s) = open.pop_front() {
+            let d = distance[&s];
+            if self.cell[s] == Cell::Goal { return Some(d) }
+            for a in ACTION.iter() {
+                let p = self.moved(&s, a);
+                if self.cell[p] != Cell::Cliff && !distance.contains_key(&p) {
+                    distance.insert(p, d + 1);
+                    open.push_back(p);
+                }
+            }
+        }
+        None
+    }
+
+    //the map as it was read, path cells marked *
+    pub fn render(&self, path:&[Vec2]) -> String {
+        let (w, h) = self.size();
+        (0..h).rev().map(|y| {
+            (0..w).map(|x| {
+                let p = (x, y);
+                if p == self.start { 'S' } else if path.contains(&p) && self.cell[p] == Cell::Open { '*' } else { self.cell[p].symbol() }
+            }).collect::<String>() + "\n"
+        }).collect()
+    }
+}
+
+impl Environment for Maze {
+    type State = Vec2;
+    type Action = Vec2;
+
+    fn reset(&mut self, _:&mut SeededRng) -> Vec2 {
+        self.state = self.start;
+        self.state
+    }
+
+    fn step(&mut self, a:&Vec2, _:&mut SeededRng) -> (Vec2, f64, bool) {
+        let p = self.moved(&self.state, a);
+        match self.cell[p] {
+            Cell::Cliff => {
+                self.state = self.start;
+                (self.state, self.cliff_reward, false)
+            },
+            Cell::Goal => {
+                self.state = p;
+                (p, self.step_reward + self.goal_reward, true)
+            },
+            _ => {
+                self.state = p;
+                (p, self.step_reward, false)
+            },
+        }
+    }
+
+    fn actions(&self, _:&Vec2) -> Vec<Vec2> {
+        ACTION.to_vec()
+    }
+}
+
+impl Changing {
+    //a maze, a line change N and the map from step N on, of the same size and start. keywords
+    //only count before the change
+    pub fn parse(text:&str) -> Result<Self, String> {
+        let line:Vec<&str> = text.lines().collect();
+        let k = line.iter().position(|l| l.split_whitespace().next() == Some("change")).ok_or("no change line")?;
+        let at = line[k].split_whitespace().nth(1).and_then(|v| v.parse().ok())
+            .ok_or_else(|| format!("line {}: change takes a step count", k + 1))?;
+        let maze = Maze::parse(&line[..k].join("\n"))?;
+        let after = Maze::parse(&line[k + 1..].join("\n")).map_err(|e| format!("after the change, {}", e))?;
+        if after.size() != maze.size() || after.start != maze.start {
+            return Err("the map after the change has another size or start".to_string())
+        }
+        Ok(Self { maze, after:after.cell, at, steps:0 })
+    }
+}
+
+impl Environment for Changing {
+    type State = Vec2;
+    type Action = Vec2;
+
+    fn reset(&mut self, rng:&mut SeededRng) -> Vec2 {
+        self.maze.reset(rng)
+    }
+
+    fn step(&mut self, a:&Vec2, rng:&mut SeededRng) -> (Vec2, f64, bool) {
+        if self.steps == self.at { std::mem::swap(&mut self.maze.cell, &mut self.after) }
+        self.steps += 1;
+        self.maze.step(a, rng)
+    }
+
+    fn actions(&self, s:&Vec2) -> Vec<Vec2> {
+        self.maze.actions(s)
+    }
+}
+
+//the cell moved into decides everything, as on the cliff
+impl Afterstate for Maze {
+    type After = Vec2;
+
+    fn afterstate(&self, s:&Vec2, a:&Vec2) -> Vec2 {
+        self.moved(s, a)
+    }
+}
+
+//the cells a greedy walk on q visits from the start, until a goal, a cliff or a repeat
+pub fn greedy_path(maze:&Maze, q:&HashMap<Vec2, Vec<f64>>) -> Vec<Vec2> {
+    let mut path = vec!(maze.start);
+    let mut s = maze.start;
+    while let Some(q) = q.get(&s) {
+        let a = (0..q.len()).fold(0, |b, k| if q[k] > q[b] { k } else { b });
+        let p = maze.moved(&s, &ACTION[a]);
+        if path.contains(&p) || maze.cell[p] == Cell::Cliff { break }
+        path.push(p);
+        if maze.cell[p] == Cell::Goal { break }
+        s = p;
+    }
+    path
+}
+
+//Q-learning on the map of a file with its discount, ε = 0.1 and α = 0.5, then the greedy path drawn over it
+pub fn run(path:&str, seed:u64, episodes:usize) -> Result<(), Box<dyn Error>> {
+    let mut maze = Maze::load(path)?;
+    let mut rng = rng::from_seed(seed);
+    let mut agent = TdControl::new(Method::QLearning, Behavior::EpsilonGreedy(0.1), 0.5, maze.gamma);
+    let (w, h) = maze.size();
+    let max_step = 100 * (w * h) as usize;
+    let mut last = (0.0, 0);
+    for _ in 0..episodes {
+        last = agent.episode(&mut maze, &mut rng, max_step);
+    }
+    println!("{} {}x{}, {} episodes of Q-learning, the last one {} steps with return {}", path, w, h, episodes, last.1, last.0);
+    let path = greedy_path(&maze, &agent.q);
+    print!("{}", maze.render(&path));
+    println!("greedy path {} steps", path.len() - 1);
+    Ok(())
+}
@@ -0,0 +1,154 @@
+use std::collections::{ HashMap, HashSet };
+use std::hash::Hash;
+
+use crate::env::Environment;
+use crate::rng::SeededRng;
+use crate::rollout::{ self, Trajectory };
+use crate::schedules::{ Harmonic, Schedule };
+
+//which visits to a state within one episode contribute their return, section 5.1
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Visit {
+    First,
+    Every,
+}
+
+impl Visit {
+    pub fn parse(s:&str) -> Result<Self, String> {
+        match s {
+            "first" => Ok(Self::First),
+            "every" => Ok(Self::Every),
+            _ => Err(format!("invalid visit {}, expected first|every", s)),
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::First => "first-visit",
+            Self::Every => "every-visit",
+        }
+    }
+
+    //true for the steps whose key gets a return, keys in time order
+    pub fn mask<K:Eq + Hash>(&self, key:&[K]) -> Vec<bool> {
+        match self {
+            Self::Every => vec![true; key.len()],
+            Self::First => {
+                let mut seen = HashSet::new();
+                key.iter().map(|k| seen.insert(k)).collect()
+            },
+        }
+    }
+}
+
+//tabular monte carlo prediction of v_π, α counted by the returns of each state. the default
+//1/n is the sample average, a constant α the recency-weighted one of section 2.5
+pub struct McPrediction<S, A = Harmonic> {
+    pub visit:Visit,
+    pub alpha:A,
+    pub gamma:f64,
+    pub v:HashMap<S, f64>,
+    pub count:HashMap<S, usize>,//returns averaged into v
+}
+
+impl<S:Clone + Eq + Hash> McPrediction<S> {
+    pub fn new(visit:Visit, gamma:f64) -> Self {
+        Self { visit, alpha:Harmonic, gamma, v:HashMap::new(), count:HashMap::new() }
+    }
+}
+
+impl<S:Clone + Eq + Hash, A:Schedule> McPrediction<S, A> {
+    pub fn with_alpha<B:Schedule>(self, alpha:B) -> McPrediction<S, B> {
+        let Self { visit, gamma, v, count, .. } = self;
+        McPrediction { visit, alpha, gamma, v, count }
+    }
+
+    //S0..S(T-1) get their G_t, the final state has no return of its own
+    pub fn update<T>(&mut self, trajectory:&Trajectory<S, T>) {
+        let g = trajectory.returns(self.gamma);
+        let state = &trajectory.state[..trajectory.steps()];
+        for ((s, g), counted) in state.iter().zip(g).zip(self.visit.mask(state)) {
+            if !counted { continue }
+            let n = self.count.entry(s.clone()).or_insert(0);
+            *n += 1;
+            let alpha = self.alpha.at(*n);
+            let v = self.v.entry(s.clone()).or_insert(0.0);
+            *v += alpha * (g - *v);
+        }
+    }
+
+    pub fn episode<E, P>(&mut self, env:&mut E, policy:P, rng:&mut SeededRng, max_step:usize)
+        where E:Environment<State = S>, P:FnMut(&S, &mut SeededRng) -> E::Action {
+        let trajectory = rollout::simulate_episode(env, policy, rng, max_step);
+        self.update(&trajectory);
+    }
+}
+
+//how returns under the behavior policy are reweighted toward the target, section 5.5
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Sampling {
+    Ordinary,//Σ ρ G / n, unbiased but the variance can be infinite, example 5.5
+    Weighted,//Σ ρ G / Σ ρ, biased toward b early on
+}
+
+impl Sampling {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Ordinary => "ordinary importance sampling",
+            Self::Weighted => "weighted importance sampling",
+        }
+    }
+}
+
+//off-policy monte carlo prediction of v_π from episodes of b. α steps the ordinary estimate
+//by the returns of each state, weighted keeps W / C, the step that makes it a weighted average
+pub struct OffPolicyMc<S, A = Harmonic> {
+    pub sampling:Sampling,
+    pub visit:Visit,
+    pub alpha:A,
+    pub gamma:f64,
+    pub v:HashMap<S, f64>,
+    pub weight:HashMap<S, f64>,//returns averaged for ordinary, Σ ρ for weighted
+    pub ratio:Vec<f64>,//ρ_0:T-1 of every episode so far
+}
+
+impl<S:Clone + Eq + Hash> OffPolicyMc<S> {
+    pub fn new(sampling:Sampling, visit:Visit, gamma:f64) -> Self {
+        Self { sampling, visit, alpha:Harmonic, gamma, v:HashMap::new(), weight:HashMap::new(), ratio:Vec::new() }
+    }
+}
+
+impl<S:Clone + Eq + Hash, A:Schedule> OffPolicyMc<S, A> {
+    pub fn with_alpha<B:Schedule>(self, alpha:B) -> OffPolicyMc<S, B> {
+        let Self { sampling, visit, gamma, v, weight, ratio, .. } = self;
+        OffPolicyMc { sampling, visit, alpha, gamma, v, weight, ratio }
+    }
+
+    //rho(s, a) = π(a|s) / b(a|s). backward over the episode, so the weight of G_t is ρ_t:T-1
+    pub fn update<T, R:Fn(&S, &T) -> f64>(&mut self, trajectory:&Trajectory<S, T>, rho:R) {
+        let state = &trajectory.state[..trajectory.steps()];
+        let counted = self.visit.mask(state);
+        let mut g = 0.0;
+        let mut w = 1.0;
+        for t in (0..trajectory.steps()).rev() {
+            let s = &state[t];
+            g = self.gamma * g + trajectory.reward[t];
+            w *= rho(s, &trajectory.action[t]);
+            if !counted[t] { continue }
+            let c = self.weight.entry(s.clone()).or_insert(0.0);
+            let v = self.v.entry(s.clone()).or_insert(0.0);
+            match self.sampling {
+                Sampling::Ordinary => {
+                    *c += 1.0;
+                    *v += self.alpha.at(*c as usize) * (w * g - *v);
+                },
+                Sampling::Weighted => {
+                    if w == 0.0 { continue }
+                    *c += w;
+                    *v += w / *c * (g - *v);
+                },
+            }
+        }
+        self.ratio.push(w);
+    }
+}
@@ -0,0 +1,331 @@
+use std::error::Error;
+use std::fs::File;
+use std::io::Write;
+use plotters::{prelude::*, coord::Shift};
+use rand::prelude::*;
+
+use crate::env::Environment;
+use crate::experiment::{ Curve, Experiment };
+use crate::progress;
+use crate::rng::{ self, SeededRng };
+use crate::schedules::Schedule;
+use crate::tiles::TileCoder;
+use crate::vfa::{ self, FeatureVec, Features, LinearVfa };
+
+type State = (f64, f64);//position, velocity
+type Action = i32;//throttle: -1, 0, 1
+
+const POSITION:(f64, f64) = (-1.2, 0.5);
+const VELOCITY:(f64, f64) = (-0.07, 0.07);
+const ACTION:[Action; 3] = [-1, 0, 1];
+
+pub struct World {
+    pub state:State,
+}
+
+struct ControlInfo {
+    pub max_episode:usize,
+    pub max_step:usize,//cap per episode, the first episodes of a fresh agent are long
+    pub snapshot:Vec<usize>,//episodes after which the cost-to-go is drawn
+}
+
+//tile-coded state, the action is an extra tile coordinate
+struct CarFeatures {
+    pub tile:TileCoder,
+}
+
+//α counted by updates, ε by episode
+struct Agent<A = f64, X = f64> {
+    pub q:LinearVfa<CarFeatures>,
+    pub alpha:A,//per tiling, divided by the number of tilings
+    pub epsilon:X,
+    pub updates:usize,
+    pub episodes:usize,
+}
+
+impl Default for World {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl World {
+    pub fn new() -> Self {
+        Self { state:(0.0, 0.0) }
+    }
+
+    fn is_terminal(s:&State) -> bool {
+        s.0 >= POSITION.1
+    }
+}
+
+impl Environment for World {
+    type State = State;
+    type Action = Action;
+
+    fn reset(&mut self, rng:&mut SeededRng) -> State {
+        self.state = (rng.gen_range(-0.6..-0.4), 0.0);
+        self.state
+    }
+
+    fn step(&mut self, a:&Action, _:&mut SeededRng) -> (State, f64, bool) {
+        let (x, v) = self.state;
+        let v = (v + 0.001 * *a as f64 - 0.0025 * (3.0 * x).cos()).clamp(VELOCITY.0, VELOCITY.1);
+        let x = (x + v).clamp(POSITION.0, POSITION.1);
+        //inelastic left bound
+        let v = if x == POSITION.0 { 0.0 } else { v };
+        self.state = (x, v);
+        (self.state, -1.0, World::is_terminal(&self.state))
+    }
+
+    fn actions(&self, _:&State) -> Vec<Action> {
+        ACTION.to_vec()
+    }
+}
+
+impl Features<(State, Action)> for CarFeatures {
+    fn dim(&self) -> usize {
+        self.tile.size()
+    }
+
+    fn features(&self, (s, a):&(State, Action)) -> FeatureVec {
+        self.tile.active(&[s.0, s.1], &[*a as i64]).into_iter().map(|k| (k, 1.0)).collect()
+    }
+}
+
+impl Agent {
+    //tiles per dimension over the state range, greedy with the α of figure 10.1 until changed
+    fn new(tilings:usize, tiles:usize, size:usize) -> Self {
+        let width = vec!(
+            (POSITION.1 - POSITION.0) / tiles as f64,
+            (VELOCITY.1 - VELOCITY.0) / tiles as f64,
+        );
+        let tile = TileCoder::new(size, tilings, width, vec!(None, None));
+        Self { q:LinearVfa::new(CarFeatures { tile }), alpha:0.5, epsilon:0.0, updates:0, episodes:0 }
+    }
+}
+
+impl<A:Schedule, X:Schedule> Agent<A, X> {
+    fn with_alpha<B:Schedule>(self, alpha:B) -> Agent<B, X> {
+        let Self { q, epsilon, updates, episodes, .. } = self;
+        Agent { q, alpha, epsilon, updates, episodes }
+    }
+
+    fn with_epsilon<B:Schedule>(self, epsilon:B) -> Agent<A, B> {
+        let Self { q, alpha, updates, episodes, .. } = self;
+        Agent { q, alpha, epsilon, updates, episodes }
+    }
+
+    fn q(&self, s:&State, a:Action) -> f64 {
+        self.q.value(&(*s, a))
+    }
+
+    fn step_size(&mut self) -> f64 {
+        self.updates += 1;
+        self.alpha.at(self.updates) / self.q.feature.tile.tilings as f64
+    }
+
+    fn select(&self, s:&State, action:&[Action], rng:&mut SeededRng) -> Action {
+        let r:f64 = rng.gen();
+        if r < self.epsilon.at(self.episodes) { return action[rng.gen_range(0..action.len())] }
+        let q:Vec<f64> = action.iter().map(|a| self.q(s, *a)).collect();
+        let q_max = q.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        let best:Vec<Action> = action.iter().zip(q.iter())
+            .filter(|(_, q)| **q == q_max)
+            .map(|(a, _)| *a)
+            .collect();
+        best[rng.gen_range(0..best.len())]
+    }
+
+    fn cost_to_go(&self, s:&State) -> f64 {
+        -ACTION.iter().map(|a| self.q(s, *a)).fold(f64::NEG_INFINITY, f64::max)
+    }
+}
+
+//episodic semi-gradient SARSA, i.e. TD(0) on state-action values, returns steps taken. only
+//the terminal bootstraps from 0, cut at the step cap the last update still takes q(S', A')
+fn episode<A:Schedule, X:Schedule>(agent:&mut Agent<A, X>, w:&mut World, c_info:&ControlInfo, rng:&mut SeededRng) -> usize {
+    agent.episodes += 1;
+    let mut s = w.reset(rng);
+    let mut a = agent.select(&s, &w.actions(&s), rng);
+    let mut step = 0;
+    loop {
+        let (ss, r, done) = w.step(&a, rng);
+        step += 1;
+        let alpha = agent.step_size();
+        if done {
+            vfa::td0(&mut agent.q, &(s, a), r, None, alpha, 1.0);
+            break step
+        }
+        let aa = agent.select(&ss, &w.actions(&ss), rng);
+        vfa::td0(&mut agent.q, &(s, a), r, Some(&(ss, aa)), alpha, 1.0);
+        if step >= c_info.max_step { break step }
+        s = ss;
+        a = aa;
+    }
+}
+
+//n-step semi-gradient SARSA of section 10.2, returns steps taken. the last n - 1 updates
+//after a terminal bootstrap from nothing, after the step cap from q(S_T, A_T)
+fn nstep_episode<A:Schedule, X:Schedule>(agent:&mut Agent<A, X>, w:&mut World, n:usize, c_info:&ControlInfo, rng:&mut SeededRng) -> usize {
+    agent.episodes += 1;
+    let s = w.reset(rng);
+    let mut state = vec!(s);
+    let mut action = vec!(agent.select(&s, &w.actions(&s), rng));
+    let mut reward:Vec<f64> = Vec::new();
+    //T once the episode has ended
+    let mut end = usize::MAX;
+    let mut truncated = false;
+    let mut t = 0;
+    loop {
+        if t < end {
+            let (ss, r, done) = w.step(&action[t], rng);
+            reward.push(r);
+            state.push(ss);
+            if done { end = t + 1 } else {
+                action.push(agent.select(&ss, &w.actions(&ss), rng));
+                if t + 1 >= c_info.max_step { (end, truncated) = (t + 1, true) }
+            }
+        }
+        if t + 1 >= n {
+            let tau = t + 1 - n;
+            let h = (tau + n).min(end);
+            let mut g:f64 = reward[tau..h].iter().sum();
+            if h < end || truncated { g += agent.q(&state[h], action[h]) }
+            let sa = (state[tau], action[tau]);
+            let error = g - agent.q.value(&sa);
+            let alpha = agent.step_size();
+            agent.q.update(&sa, alpha, error);
+            if tau + 1 == end { break end }
+        }
+        t += 1;
+    }
+}
+
+//steps per episode of a fresh agent with 8x8 tiles per tiling, α counted by updates and ε by episode
+pub fn steps_curve<A:Schedule, X:Schedule>(alpha:A, epsilon:X, tilings:usize, max_episode:usize, rng:&mut SeededRng) -> Vec<f64> {
+    let c_info = ControlInfo { max_episode, max_step:100000, snapshot:Vec::new() };
+    let mut w = World::new();
+    let mut agent = Agent::new(tilings, 8, 4096).with_alpha(alpha).with_epsilon(epsilon);
+    (0..c_info.max_episode).map(|_| episode(&mut agent, &mut w, &c_info, rng) as f64).collect()
+}
+
+fn draw_cost_to_go<A:Schedule, X:Schedule>(agent:&Agent<A, X>, ep:usize, canvas:&DrawingArea<BitMapBackend, Shift>) -> Result<(), Box<dyn Error>> {
+    let n = 40;
+    let x_step = (POSITION.1 - POSITION.0) / n as f64;
+    let v_step = (VELOCITY.1 - VELOCITY.0) / n as f64;
+    let x_axis:Vec<f64> = (0..=n).map(|k| POSITION.0 + k as f64 * x_step).collect();
+    let v_axis:Vec<f64> = (0..=n).map(|k| VELOCITY.0 + k as f64 * v_step).collect();
+    let c_max = x_axis.iter()
+        .flat_map(|x| v_axis.iter().map(move |v| (*x, *v)))
+        .map(|s| agent.cost_to_go(&s))
+        .fold(1.0, f64::max);
+    let mut chart = ChartBuilder::on(canvas)
+        .caption(format!("episode {}", ep), ("sans-serif", 30))
+        .margin(5)
+        .build_cartesian_3d(POSITION.0..POSITION.1, 0.0..c_max, VELOCITY.0..VELOCITY.1)?;
+    chart.configure_axes().draw()?;
+    chart.draw_series(SurfaceSeries::xoz(x_axis.into_iter(), v_axis.into_iter(),
+        |x, v| agent.cost_to_go(&(x, v)))
+        .style(BLUE.mix(0.4).filled()))?;
+    Ok(())
+}
+
+fn draw_steps(steps:&[usize], canvas:&DrawingArea<BitMapBackend, Shift>) -> Result<(), Box<dyn Error>> {
+    let s_max = *steps.iter().max().unwrap();
+    let mut chart = ChartBuilder::on(canvas)
+        .caption("steps per episode", ("sans-serif", 30))
+        .margin(5)
+        .x_label_area_size(50)
+        .y_label_area_size(50)
+        .build_cartesian_2d(0..steps.len(), (1..s_max).log_scale())?;
+    chart.configure_mesh().draw()?;
+    chart.draw_series(LineSeries::new(steps.iter().cloned().enumerate(), &BLUE))?;
+    Ok(())
+}
+
+//one line per n over α, points past y_max dropped
+fn draw_parameter(alpha:&[f64], step:&[usize], curve:&[Curve], episode:usize, canvas:&DrawingArea<BitMapBackend, Shift>) -> Result<(), Box<dyn Error>> {
+    let (y_min, y_max) = (200.0, 300.0);
+    let mut chart = ChartBuilder::on(canvas)
+        .caption(format!("first {} episodes, {} runs", episode, curve[0].runs), ("sans-serif", 30))
+        .margin(5)
+        .x_label_area_size(50)
+        .y_label_area_size(50)
+        .build_cartesian_2d(0.0..alpha[alpha.len() - 1], y_min..y_max)?;
+    chart.configure_mesh().x_desc("α × number of tilings (8)").y_desc("steps per episode").draw()?;
+    for (k, (n, c)) in step.iter().zip(curve.iter()).enumerate() {
+        let color = Palette99::pick(k).to_rgba();
+        let point:Vec<(f64, f64)> = alpha.iter().cloned().zip(c.mean.iter().cloned())
+            .filter(|(_, v)| *v <= y_max)
+            .collect();
+        chart.draw_series(LineSeries::new(point, color))?
+            .label(format!("n={}", n))
+            .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], color));
+    }
+    chart
+        .configure_series_labels()
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
+        .draw()?;
+    Ok(())
+}
+
+pub fn run(seed:u64) -> Result<(), Box<dyn Error>> {
+    let c_info = ControlInfo { max_episode:9000, max_step:100000, snapshot:vec!(1, 12, 104, 1000, 9000) };
+    let mut rng = rng::from_seed(seed);
+    let mut w = World::new();
+    let mut agent = Agent::new(8, 8, 4096);
+    let canvas = BitMapBackend::new("10_1.png", (1800, 1200)).into_drawing_area();
+    canvas.fill(&WHITE)?;
+    let canvas_split = canvas.split_evenly((2, 3));
+    let mut steps:Vec<usize> = Vec::new();
+    let pb = progress::counter(c_info.max_episode as u64, "episode");
+    for ep in 1..=c_info.max_episode {
+        steps.push(episode(&mut agent, &mut w, &c_info, &mut rng));
+        pb.inc(1);
+        if let Some(k) = c_info.snapshot.iter().position(|v| *v == ep) {
+            pb.suspend(|| println!("episode {} steps {}", ep, steps.last().unwrap()));
+            draw_cost_to_go(&agent, ep, &canvas_split[k])?;
+        }
+    }
+    pb.finish_and_clear();
+    let tile = &agent.q.feature.tile;
+    println!("tiles {}/{} overflow {}", tile.iht.count(), tile.size(), tile.iht.overflow());
+    draw_steps(&steps, &canvas_split[5])?;
+    canvas.present()?;
+    Ok(())
+}
+
+//figure 10.4, steps per episode over the first episodes of n-step SARSA for every (n, α).
+//the whole surface goes to 10_4.csv, the plot cuts it at 300 like the book
+pub fn run_nstep(seed:u64, runs:Option<usize>) -> Result<(), Box<dyn Error>> {
+    let c_info = ControlInfo { max_episode:50, max_step:5000, snapshot:Vec::new() };
+    let step = [1, 2, 4, 8, 16];
+    let alpha:Vec<f64> = (1..=8).map(|k| k as f64 * 0.2).collect();
+    let experiment = Experiment::new(runs.unwrap_or(20), seed);
+    let mut w = World::new();
+    let curve:Vec<Curve> = step.iter().map(|n| {
+        experiment.repeat(|rng| {
+            alpha.iter().map(|a| {
+                let mut agent = Agent::new(8, 8, 4096).with_alpha(*a);
+                let total:usize = (0..c_info.max_episode).map(|_| nstep_episode(&mut agent, &mut w, *n, &c_info, rng)).sum();
+                total as f64 / c_info.max_episode as f64
+            }).collect()
+        })
+    }).collect();
+    let mut f = File::create("10_4.csv")?;
+    writeln!(f, "n,alpha,steps,steps se")?;
+    for (n, c) in step.iter().zip(curve.iter()) {
+        for (k, a) in alpha.iter().enumerate() {
+            writeln!(f, "{},{:.2},{},{}", n, a, c.mean[k], c.stderr[k])?;
+        }
+        let (k, best) = c.mean.iter().enumerate().fold((0, f64::INFINITY), |b, (k, v)| if *v < b.1 { (k, *v) } else { b });
+        println!("n={}: best α×8 {:.1} steps {:.1}", n, alpha[k], best);
+    }
+    let canvas = BitMapBackend::new("10_4.png", (1440, 960)).into_drawing_area();
+    canvas.fill(&WHITE)?;
+    draw_parameter(&alpha, &step, &curve, c_info.max_episode, &canvas)?;
+    canvas.present()?;
+    Ok(())
+}
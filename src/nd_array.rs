@@ -0,0 +1,46 @@
+use ndarray::{ Array1, Array2, ArrayView2, ShapeBuilder, ShapeError };
+
+use crate::nd_vec::{ NdVec1, NdVec2 };
+
+//arrays index from 0, the offsets are dropped on the way out and start at 0 on the way in.
+//a[[i, j]] is v[(i - offset.0, j - offset.1)], the data of NdVec2 is column major in ndarray
+
+impl<T> From<NdVec1<T>> for Array1<T> {
+    fn from(v:NdVec1<T>) -> Self {
+        Array1::from(v.data)
+    }
+}
+
+impl<T> From<Array1<T>> for NdVec1<T> {
+    fn from(a:Array1<T>) -> Self {
+        let data = a.into_raw_vec_and_offset().0;
+        NdVec1 { offset:0, dimension:data.len() as i32 - 1, data }
+    }
+}
+
+//fails when data does not cover both ranges
+impl<T> TryFrom<NdVec2<T>> for Array2<T> {
+    type Error = ShapeError;
+
+    fn try_from(v:NdVec2<T>) -> Result<Self, ShapeError> {
+        let shape = (v.dimension.0 as usize, v.dimension.1 as usize);
+        Array2::from_shape_vec(shape.f(), v.data)
+    }
+}
+
+impl<T> From<Array2<T>> for NdVec2<T> {
+    fn from(a:Array2<T>) -> Self {
+        let (x, y) = a.dim();
+        //the transpose iterates x fastest
+        let data:Vec<T> = a.reversed_axes().into_iter().collect();
+        NdVec2::from_size((x, y)).with_data(data)
+    }
+}
+
+impl<T> NdVec2<T> {
+    //borrowed, without copying the data
+    pub fn view(&self) -> Result<ArrayView2<'_, T>, ShapeError> {
+        let shape = (self.dimension.0 as usize, self.dimension.1 as usize);
+        ArrayView2::from_shape(shape.f(), &self.data)
+    }
+}
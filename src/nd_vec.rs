@@ -1,4 +1,6 @@
-use std::ops::{Index, IndexMut};
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut, Index, IndexMut};
 
 type Vec2 = (i32, i32);
 
@@ -20,11 +22,31 @@ impl<T> NdVec1<T> {
         (i + self.offset) as usize
     }
 
+    //position in data, None below the range or past the pushed data
+    pub fn checked_index(&self, i:i32) -> Option<usize> {
+        let k = i + self.offset;
+        (k >= 0 && (k as usize) < self.data.len()).then_some(k as usize)
+    }
+
+    pub fn get(&self, i:i32) -> Option<&T> {
+        self.checked_index(i).map(|k| &self.data[k])
+    }
+
+    pub fn get_mut(&mut self, i:i32) -> Option<&mut T> {
+        self.checked_index(i).map(|k| &mut self.data[k])
+    }
+
+    //for Index, the panic names the index and the valid range
+    fn expect_index(&self, i:i32) -> usize {
+        self.checked_index(i).unwrap_or_else(|| panic!("NdVec1 index {} out of range {}..{}",
+            i, -self.offset, self.data.len() as i32 - self.offset))
+    }
+
     pub fn push(&mut self, v:T) {
         self.data.push(v);
     }
 
-    pub fn iter(&self) -> std::slice::Iter<T> {
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
         self.data.iter()
     }
 }
@@ -33,21 +55,23 @@ impl<T> Index<i32> for NdVec1<T> {
     type Output = T;
 
     fn index(&self, index:i32) -> &Self::Output {
-        &self.data[self.index(index)]
+        &self.data[self.expect_index(index)]
     }
 }
 
 impl<T> IndexMut<i32> for NdVec1<T> {
     fn index_mut(&mut self, index:i32) -> &mut Self::Output {
-        let i = self.index(index);
+        let i = self.expect_index(index);
         &mut self.data[i]
     }
 }
 
-pub struct NdVec2<T> {
+//data is a Vec unless moved to another store with with_data
+pub struct NdVec2<T, S = Vec<T>> {
     pub offset: Vec2,
     pub dimension: Vec2,
-    pub data: Vec<T>,
+    pub data: S,
+    element: PhantomData<T>,
 }
 
 impl<T> NdVec2<T> {
@@ -55,24 +79,50 @@ impl<T> NdVec2<T> {
         assert!(x.1 >= x.0 && y.1 >= y.0);
         let offset = (-x.0, -y.0);
         let dimension = (x.1 - x.0 + 1, y.1 - y.0 + 1);
-        Self { offset, dimension, data: Vec::new() }
+        Self { offset, dimension, data: Vec::new(), element: PhantomData }
     }
 
     pub fn from_size(size: (usize, usize)) -> Self {
         NdVec2::new((0, size.0 as i32 - 1), (0, size.1 as i32 - 1))
     }
 
+    //every element of the ranges from its coordinates, called in data order (x fastest)
+    pub fn from_fn(range:(Vec2, Vec2), mut f:impl FnMut(Vec2) -> T) -> Self {
+        let mut v = Self::new(range.0, range.1);
+        let (x, y) = range;
+        v.data = (y.0..=y.1).flat_map(|j| (x.0..=x.1).map(move |i| (i, j))).map(&mut f).collect();
+        v
+    }
+
     pub fn resize(&mut self, new_len:usize, value:T)
         where T: Clone {
         self.data.resize(new_len, value);
     }
 
+    //sizes to the ranges with every element set to value
     pub fn fill(&mut self, value:T)
         where T: Clone {
         let l = self.dimension.0 * self.dimension.1;
+        self.data.clear();
         self.resize(l as usize, value);
     }
 
+    pub fn push(&mut self, v:T) {
+        self.data.push(v);
+    }
+}
+
+impl<T, S> NdVec2<T, S> {
+    //same ranges over other storage, e.g. a memory-mapped file
+    pub fn with_data<D>(self, data:D) -> NdVec2<T, D> {
+        NdVec2 { offset:self.offset, dimension:self.dimension, data, element:PhantomData }
+    }
+
+    //the same data moved into another kind of storage
+    pub fn into_store<D:From<S>>(self) -> NdVec2<T, D> {
+        NdVec2 { offset:self.offset, dimension:self.dimension, data:D::from(self.data), element:PhantomData }
+    }
+
     pub fn index(&self, i:&Vec2) -> usize {
         (i.0 + self.offset.0 + (i.1 + self.offset.1) * self.dimension.0) as usize
     }
@@ -81,46 +131,108 @@ impl<T> NdVec2<T> {
         let i = i as i32;
         (i % self.dimension.0 - self.offset.0, i / self.dimension.0 - self.offset.1)
     }
+}
 
-    pub fn push(&mut self, v:T) {
-        self.data.push(v);
+impl<T, S:Deref<Target = [T]>> NdVec2<T, S> {
+    //position in data, None outside the declared ranges or past the pushed data
+    pub fn checked_index(&self, i:&Vec2) -> Option<usize> {
+        let (x, y) = (i.0 + self.offset.0, i.1 + self.offset.1);
+        if x < 0 || y < 0 || x >= self.dimension.0 || y >= self.dimension.1 { return None }
+        let k = (x + y * self.dimension.0) as usize;
+        (k < self.data.len()).then_some(k)
+    }
+
+    pub fn get(&self, i:Vec2) -> Option<&T> {
+        self.checked_index(&i).map(|k| &self.data[k])
+    }
+
+    //for Index, the panic names the coordinates, the ranges and how much data there is
+    fn expect_index(&self, i:&Vec2) -> usize {
+        self.checked_index(i).unwrap_or_else(|| panic!("NdVec2 index {:?} out of range x {}..{} y {}..{} ({} of {} elements)",
+            i, -self.offset.0, self.dimension.0 - self.offset.0, -self.offset.1, self.dimension.1 - self.offset.1,
+            self.data.len(), self.dimension.0 * self.dimension.1))
     }
 
-    pub fn iter(&self) -> std::slice::Iter<T> {
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
         self.data.iter()
     }
 
-    pub fn iter_mut(&mut self) -> std::slice::IterMut<T> {
+    //same ranges, every element through f
+    pub fn map<U>(&self, f:impl FnMut(&T) -> U) -> NdVec2<U> {
+        NdVec2 { offset:self.offset, dimension:self.dimension, data:self.data.iter().map(f).collect(), element:PhantomData }
+    }
+
+    //one line per y from the lowest, x labels above and y labels to the left. cell returns
+    //the element already padded to width, so it can colour it. missing data is left blank
+    pub fn grid(&self, width:usize, cell:impl Fn(Vec2, &T) -> String) -> String {
+        let (x0, y0) = (-self.offset.0, -self.offset.1);
+        let (x1, y1) = (x0 + self.dimension.0, y0 + self.dimension.1);
+        let label = y0.to_string().len().max((y1 - 1).to_string().len());
+        let mut s = format!("{:label$} ", "");
+        for x in x0..x1 {
+            s += &format!("{:>width$}", x);
+        }
+        s += "\n";
+        for y in y0..y1 {
+            s += &format!("{:>label$} ", y);
+            for x in x0..x1 {
+                s += &self.get((x, y)).map_or_else(|| " ".repeat(width), |v| cell((x, y), v));
+            }
+            s += "\n";
+        }
+        s
+    }
+}
+
+impl<T, S:DerefMut<Target = [T]>> NdVec2<T, S> {
+    pub fn get_mut(&mut self, i:Vec2) -> Option<&mut T> {
+        self.checked_index(&i).map(|k| &mut self.data[k])
+    }
+
+    pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, T> {
         self.data.iter_mut()
     }
 }
 
-impl<T> Index<Vec2> for NdVec2<T> {
+impl<T, S:Deref<Target = [T]>> Index<Vec2> for NdVec2<T, S> {
     type Output = T;
 
     fn index(&self, index:Vec2) -> &Self::Output {
-        &self.data[self.index(&index)]
+        &self.data[self.expect_index(&index)]
     }
 }
 
-impl<T> Index<&Vec2> for NdVec2<T> {
+impl<T, S:Deref<Target = [T]>> Index<&Vec2> for NdVec2<T, S> {
     type Output = T;
 
     fn index(&self, index:&Vec2) -> &Self::Output {
-        &self.data[self.index(index)]
+        &self.data[self.expect_index(index)]
     }
 }
 
-impl<T> IndexMut<Vec2> for NdVec2<T> {
+impl<T, S:DerefMut<Target = [T]>> IndexMut<Vec2> for NdVec2<T, S> {
     fn index_mut(&mut self, index:Vec2) -> &mut Self::Output {
-        let i = self.index(&index);
+        let i = self.expect_index(&index);
         &mut self.data[i]
     }
 }
 
-impl<T> IndexMut<&Vec2> for NdVec2<T> {
+impl<T, S:DerefMut<Target = [T]>> IndexMut<&Vec2> for NdVec2<T, S> {
     fn index_mut(&mut self, index:&Vec2) -> &mut Self::Output {
-        let i = self.index(index);
+        let i = self.expect_index(index);
         &mut self.data[i]
     }
-}
\ No newline at end of file
+}
+
+//the grid of NdVec2::grid, {:6.1} pads every element to 6 with 1 decimal. without a width
+//the widest element sets it
+impl<T:fmt::Display, S:Deref<Target = [T]>> fmt::Display for NdVec2<T, S> {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        let text = |v:&T| match f.precision() {
+            Some(p) => format!("{:.*}", p, v),
+            None => v.to_string(),
+        };
+        let width = f.width().unwrap_or_else(|| self.data.iter().map(|v| text(v).len() + 1).max().unwrap_or(1));
+        write!(f, "{}", self.grid(width, |_, v| format!("{:>width$}", text(v))))
+    }
+}
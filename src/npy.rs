@@ -0,0 +1,23 @@
+use std::fs::File;
+use std::io::{ self, BufWriter, Write };
+
+//numpy .npy version 1.0, little-endian f64 in C order, readable with np.load
+pub fn write(path:&str, shape:&[usize], data:&[f64]) -> io::Result<()> {
+    assert_eq!(shape.iter().product::<usize>(), data.len());
+    let dims:Vec<String> = shape.iter().map(|d| d.to_string()).collect();
+    //a 1-d shape still needs the trailing comma to be a tuple
+    let shape = if dims.len() == 1 { format!("({},)", dims[0]) } else { format!("({})", dims.join(", ")) };
+    let mut header = format!("{{'descr': '<f8', 'fortran_order': False, 'shape': {}, }}", shape);
+    //magic, version and length take 10 bytes, the whole header ends on a 64 byte boundary
+    let pad = 64 - (10 + header.len() + 1) % 64;
+    header.push_str(&" ".repeat(pad % 64));
+    header.push('\n');
+    let mut f = BufWriter::new(File::create(path)?);
+    f.write_all(b"\x93NUMPY\x01\x00")?;
+    f.write_all(&(header.len() as u16).to_le_bytes())?;
+    f.write_all(header.as_bytes())?;
+    for v in data.iter() {
+        f.write_all(&v.to_le_bytes())?;
+    }
+    f.flush()
+}
@@ -0,0 +1,282 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use rand::prelude::*;
+
+use crate::env::Environment;
+use crate::policy::{ self, Behavior };
+use crate::rng::SeededRng;
+use crate::rollout::Trajectory;
+use crate::schedules::{ self, Schedule };
+
+//n-step control targets, sections 7.2, 7.5 and 7.6
+#[derive(Clone, Copy)]
+pub enum Method {
+    Sarsa,//on-policy, the sampled actions all the way to the bootstrap
+    TreeBackup,//off-policy without ratios, every step also backs up the actions not taken under π
+    QSigma(Sigma),//per step between sampling with ρ (σ = 1) and tree backup (σ = 0), (7.17)
+}
+
+//σ_t of Q(σ), drawn when S_t enters the buffer
+#[derive(Clone, Copy)]
+pub enum Sigma {
+    Constant(f64),
+    Alternate,//1 on even steps, 0 on odd ones
+    Random(f64),//1 with this probability, 0 otherwise
+    Decay(f64),//starts at 1 and is multiplied by this after every episode
+}
+
+//tabular action values over env.actions(s) learned n steps behind the behavior. the episode
+//so far is kept in a Trajectory, S_t, A_t and R_t+1 sharing index t. schedules as in TdControl
+pub struct NStepControl<S, A = f64, X = f64> {
+    pub method:Method,
+    pub n:usize,
+    pub behavior:Behavior,
+    pub target:Behavior,//π of tree backup, EpsilonGreedy(0.0) for greedy
+    pub alpha:A,//by the updates of (s, a) so far
+    pub epsilon:Option<X>,//of behavior by episode, target is left alone
+    pub gamma:f64,
+    pub q:HashMap<S, Vec<f64>>,
+    pub initial:f64,//of every unseen (s, a), optimistic above every return
+    pub episodes:usize,//learned so far, what Sigma::Decay counts
+    pub visits:HashMap<S, usize>,//steps taken from each state over all episodes
+    pub updates:HashMap<S, Vec<usize>>,//of every (s, a), what alpha counts
+}
+
+//how n-step TD prediction corrects for following b instead of π, sections 7.3 - 7.4
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Correction {
+    Ordinary,//ρ_t:t+n-1 scales the whole update, (7.9)
+    PerDecision,//G_t:h = ρ_t (R_t+1 + γ G_t+1:h), every reward only by the ratios before it
+    ControlVariate,//per-decision plus (1 - ρ_t) V(S_t), (7.13)
+}
+
+impl Correction {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Ordinary => "ordinary ρ",
+            Self::PerDecision => "per-decision ρ",
+            Self::ControlVariate => "per-decision ρ with control variate",
+        }
+    }
+}
+
+//n-step TD prediction of v_π from episodes of b, rho[t] = π(A_t|S_t) / b(A_t|S_t). α takes
+//any Schedule counted by the updates of each state, a number for a constant one
+pub struct NStepPrediction<S, A = f64> {
+    pub n:usize,
+    pub alpha:A,
+    pub gamma:f64,
+    pub correction:Correction,
+    pub v:HashMap<S, f64>,
+    pub updates:HashMap<S, usize>,//of every state, what alpha counts
+}
+
+impl<S:Clone + Eq + Hash> NStepPrediction<S> {
+    pub fn new(n:usize, alpha:f64, gamma:f64, correction:Correction) -> Self {
+        Self { n, alpha, gamma, correction, v:HashMap::new(), updates:HashMap::new() }
+    }
+}
+
+impl<S:Clone + Eq + Hash, A:Schedule> NStepPrediction<S, A> {
+    pub fn with_alpha<B:Schedule>(self, alpha:B) -> NStepPrediction<S, B> {
+        let Self { n, gamma, correction, v, updates, .. } = self;
+        NStepPrediction { n, alpha, gamma, correction, v, updates }
+    }
+
+    pub fn value(&self, s:&S) -> f64 {
+        self.v.get(s).copied().unwrap_or(0.0)
+    }
+
+    //the update for S_τ only reads values at S_τ..S_τ+n and b does not depend on v, so
+    //updating in τ order after the episode matches updating online n steps late
+    pub fn episode<T>(&mut self, trajectory:&Trajectory<S, T>, rho:&[f64]) {
+        let (tt, gamma) = (trajectory.steps(), self.gamma);
+        assert_eq!(rho.len(), tt);
+        for tau in 0..tt {
+            let h = (tau + self.n).min(tt);
+            let value = |v:&HashMap<S, f64>, k:usize| {
+                if k == tt && trajectory.terminal { 0.0 } else { v.get(&trajectory.state[k]).copied().unwrap_or(0.0) }
+            };
+            let v_tau = value(&self.v, tau);
+            let mut g = value(&self.v, h);
+            let mut ratio = 1.0;
+            for k in (tau..h).rev() {
+                let r = trajectory.reward[k];
+                g = match self.correction {
+                    Correction::Ordinary => r + gamma * g,
+                    Correction::PerDecision => rho[k] * (r + gamma * g),
+                    Correction::ControlVariate => rho[k] * (r + gamma * g) + (1.0 - rho[k]) * value(&self.v, k),
+                };
+                ratio *= rho[k];
+            }
+            let s = &trajectory.state[tau];
+            let count = self.updates.entry(s.clone()).or_insert(0);
+            *count += 1;
+            let alpha = self.alpha.at(*count);
+            let step = match self.correction {
+                Correction::Ordinary => alpha * ratio * (g - v_tau),
+                _ => alpha * (g - v_tau),
+            };
+            *self.v.entry(s.clone()).or_insert(0.0) += step;
+        }
+    }
+}
+
+impl Method {
+    pub fn label(&self) -> String {
+        match self {
+            Method::Sarsa => "n-step SARSA".to_string(),
+            Method::TreeBackup => "tree backup".to_string(),
+            Method::QSigma(sigma) => format!("Q(σ) {}", sigma.label()),
+        }
+    }
+}
+
+impl Sigma {
+    pub fn label(&self) -> String {
+        match self {
+            Sigma::Constant(v) => format!("σ={}", v),
+            Sigma::Alternate => "σ alternating".to_string(),
+            Sigma::Random(p) => format!("σ~Bernoulli({})", p),
+            Sigma::Decay(f) => format!("σ decaying ×{}", f),
+        }
+    }
+
+    fn draw(&self, t:usize, episodes:usize, rng:&mut SeededRng) -> f64 {
+        match *self {
+            Sigma::Constant(v) => v,
+            Sigma::Alternate => if t.is_multiple_of(2) { 1.0 } else { 0.0 },
+            Sigma::Random(p) => if rng.gen::<f64>() < p { 1.0 } else { 0.0 },
+            Sigma::Decay(f) => f.powi(episodes as i32),
+        }
+    }
+}
+
+impl<S:Clone + Eq + Hash> NStepControl<S> {
+    pub fn new(method:Method, n:usize, behavior:Behavior, alpha:f64, gamma:f64) -> Self {
+        Self { method, n, behavior, target:Behavior::EpsilonGreedy(0.0), alpha, epsilon:None, gamma, q:HashMap::new(), initial:0.0, episodes:0, visits:HashMap::new(), updates:HashMap::new() }
+    }
+}
+
+impl<S:Clone + Eq + Hash, A:Schedule, X:Schedule> NStepControl<S, A, X> {
+    pub fn with_alpha<B:Schedule>(self, alpha:B) -> NStepControl<S, B, X> {
+        let Self { method, n, behavior, target, epsilon, gamma, q, initial, episodes, visits, updates, .. } = self;
+        NStepControl { method, n, behavior, target, alpha, epsilon, gamma, q, initial, episodes, visits, updates }
+    }
+
+    pub fn with_epsilon<B:Schedule>(self, epsilon:B) -> NStepControl<S, A, B> {
+        let Self { method, n, behavior, target, alpha, gamma, q, initial, episodes, visits, updates, .. } = self;
+        NStepControl { method, n, behavior, target, alpha, epsilon:Some(epsilon), gamma, q, initial, episodes, visits, updates }
+    }
+
+    //α for the next update of (s, a)
+    fn step_size(&mut self, s:&S, a:usize) -> f64 {
+        let n = self.q[s].len();
+        let count = &mut self.updates.entry(s.clone()).or_insert_with(|| vec![0; n])[a];
+        *count += 1;
+        self.alpha.at(*count)
+    }
+
+    //unseen states start at initial for every action
+    fn entry<E:Environment<State = S>>(&mut self, s:&S, env:&E) -> &mut Vec<f64> {
+        let initial = self.initial;
+        self.q.entry(s.clone()).or_insert_with(|| vec![initial; env.actions(s).len()])
+    }
+
+    //the action and b(A|s) at the time it was drawn
+    fn select<E:Environment<State = S>>(&mut self, s:&S, env:&E, rng:&mut SeededRng) -> (usize, f64) {
+        let behavior = self.behavior;
+        let q = self.entry(s, env);
+        let p = behavior.prob_q(q, &vec![true; q.len()]);
+        let a = policy::sample_index(&p, rng);
+        (a, p[a])
+    }
+
+    //π(.|s) and q(s, .) of a state already in the table
+    fn target_prob(&self, s:&S) -> (Vec<f64>, &[f64]) {
+        let q = &self.q[s];
+        (self.target.prob_q(q, &vec![true; q.len()]), q)
+    }
+
+    //G_τ:h backward from Q(S_h, A_h), or from 0 past the end of the episode. tree backup and
+    //Q(σ) share one recursion, V̄(S_k) + w (G_k:h - Q(S_k, A_k)), with w = π(A_k|S_k) for tree
+    //backup and σ_k ρ_k + (1 - σ_k) π(A_k|S_k) for Q(σ). step holds (σ_t, b(A_t|S_t))
+    fn target_return(&self, buf:&Trajectory<S, usize>, step:&[(f64, f64)], tau:usize, h:usize, end:usize) -> f64 {
+        let mut g = if h < end { self.q[&buf.state[h]][buf.action[h]] } else { 0.0 };
+        for i in (tau..h.min(end)).rev() {
+            let k = i + 1;
+            let x = if k == end { 0.0 } else {
+                match self.method {
+                    Method::Sarsa => g,
+                    Method::TreeBackup | Method::QSigma(_) => {
+                        let (p, q) = self.target_prob(&buf.state[k]);
+                        let a = buf.action[k];
+                        let v_bar = p.iter().zip(q.iter()).map(|(p, q)| p * q).sum::<f64>();
+                        let w = match self.method {
+                            Method::QSigma(_) => {
+                                let (sigma, b) = step[k];
+                                sigma * p[a] / b + (1.0 - sigma) * p[a]
+                            },
+                            _ => p[a],
+                        };
+                        v_bar + w * (g - q[a])
+                    },
+                }
+            };
+            g = buf.reward[i] + self.gamma * x;
+        }
+        g
+    }
+
+    //learns along one episode, returns the sum of rewards and the steps taken. an episode cut
+    //off at max_step keeps its last n - 1 pairs un-updated rather than treating the cut as an end
+    pub fn episode<E:Environment<State = S>>(&mut self, env:&mut E, rng:&mut SeededRng, max_step:usize) -> (f64, usize) {
+        self.behavior = schedules::behavior(self.behavior, self.epsilon.as_ref(), self.episodes + 1);
+        let s = env.reset(rng);
+        let (a, b) = self.select(&s, env, rng);
+        let mut buf = Trajectory { state:vec!(s), action:vec!(a), reward:Vec::new(), terminal:false };
+        let mut step = vec!((self.sigma(0, rng), b));
+        //T once the episode has terminated
+        let mut end = usize::MAX;
+        let mut t = 0;
+        loop {
+            if t < end {
+                *self.visits.entry(buf.state[t].clone()).or_insert(0) += 1;
+                let action = env.actions(&buf.state[t])[buf.action[t]].clone();
+                let (ss, r, done) = env.step(&action, rng);
+                buf.reward.push(r);
+                buf.state.push(ss.clone());
+                if done {
+                    end = t + 1;
+                    buf.terminal = true;
+                } else {
+                    let (aa, b) = self.select(&ss, env, rng);
+                    buf.action.push(aa);
+                    step.push((self.sigma(t + 1, rng), b));
+                }
+            }
+            if t + 1 >= self.n {
+                let tau = t + 1 - self.n;
+                let g = self.target_return(&buf, &step, tau, tau + self.n, end);
+                let sa = buf.action[tau];
+                let alpha = self.step_size(&buf.state[tau], sa);
+                let q = &mut self.entry(&buf.state[tau], env)[sa];
+                *q += alpha * (g - *q);
+                if tau + 1 == end { break }
+            }
+            if end == usize::MAX && t + 1 >= max_step { break }
+            t += 1;
+        }
+        self.episodes += 1;
+        (buf.reward.iter().sum(), buf.steps())
+    }
+
+    //only Q(σ) draws, the other methods leave the generator alone
+    fn sigma(&self, t:usize, rng:&mut SeededRng) -> f64 {
+        match self.method {
+            Method::QSigma(sigma) => sigma.draw(t, self.episodes, rng),
+            _ => 0.0,
+        }
+    }
+}
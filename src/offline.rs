@@ -0,0 +1,99 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::io;
+
+use crate::experience_log::{ Reader, Record };
+use crate::mc::Sampling;
+use crate::replay::Experience;
+use crate::rollout::Trajectory;
+use crate::stopping::{ Change, StoppingRule };
+
+//evaluation of a target policy π from logged steps of some behavior b, without the environment.
+//both estimates are of the expected return from the logged episode starts
+
+//the steps back into episodes, each ending at a done. steps after the last done make a last
+//episode that is not terminal
+pub fn episodes<S:Clone, A>(steps:impl IntoIterator<Item = Experience<S, A>>) -> Vec<Trajectory<S, A>> {
+    let mut episode = Vec::new();
+    let mut current:Option<Trajectory<S, A>> = None;
+    for e in steps {
+        let t = current.get_or_insert_with(|| Trajectory { state:vec!(e.state.clone()), action:Vec::new(), reward:Vec::new(), terminal:false });
+        t.action.push(e.action);
+        t.reward.push(e.reward);
+        t.state.push(e.next);
+        if e.done {
+            t.terminal = true;
+            episode.extend(current.take());
+        }
+    }
+    episode.extend(current);
+    episode
+}
+
+//every episode of a file written by experience_log::Writer
+pub fn read<S:Record + Clone, A:Record>(path:&str) -> io::Result<Vec<Trajectory<S, A>>> {
+    let steps = Reader::<S, A, _>::open(path)?.collect::<io::Result<Vec<_>>>()?;
+    Ok(episodes(steps))
+}
+
+//ρ_0:T-1 G_0 per episode with rho(s, a) = π(a|s) / b(a|s), averaged as in section 5.5
+pub fn importance_sampling<S, A>(episode:&[Trajectory<S, A>], sampling:Sampling, gamma:f64, rho:impl Fn(&S, &A) -> f64) -> f64 {
+    let (mut sum, mut weight) = (0.0, 0.0);
+    for t in episode.iter() {
+        let w:f64 = t.action.iter().enumerate().map(|(k, a)| rho(&t.state[k], a)).product();
+        sum += w * t.total(gamma);
+        weight += w;
+    }
+    match sampling {
+        Sampling::Ordinary => sum / episode.len().max(1) as f64,
+        Sampling::Weighted => if weight > 0.0 { sum / weight } else { 0.0 },
+    }
+}
+
+//fitted q evaluation in a table: every sweep sets q(s, a) to the mean over the logged steps
+//from (s, a) of r + γ Σ π(a'|s') q(s', a'), 0 after a done. target(s) lists π(.|s), pairs
+//never logged count 0. stopping measures the change of q between sweeps
+pub fn fitted_q<S, A>(episode:&[Trajectory<S, A>], gamma:f64, stopping:StoppingRule, target:impl Fn(&S) -> Vec<(A, f64)>) -> (f64, HashMap<(S, A), f64>)
+    where S:Clone + Eq + Hash, A:Clone + Eq + Hash {
+    //(s, a) index of every step, its reward and the successor's π-weighted pairs
+    let mut index:HashMap<(S, A), usize> = HashMap::new();
+    let mut step = Vec::new();
+    for t in episode.iter() {
+        for (k, a) in t.action.iter().enumerate() {
+            let n = index.len();
+            let sa = *index.entry((t.state[k].clone(), a.clone())).or_insert(n);
+            let last = k + 1 == t.steps();
+            step.push((sa, t.reward[k], last && t.terminal, k + 1, t));
+        }
+    }
+    let mut next:Vec<Vec<(Option<usize>, f64)>> = Vec::with_capacity(step.len());
+    for (_, _, done, k, t) in step.iter() {
+        if *done { next.push(Vec::new()); continue }
+        let ss = &t.state[*k];
+        next.push(target(ss).into_iter().map(|(aa, p)| (index.get(&(ss.clone(), aa)).copied(), p)).collect());
+    }
+    let mut count = vec![0; index.len()];
+    for (sa, ..) in step.iter() {
+        count[*sa] += 1;
+    }
+    let mut q = vec![0.0; index.len()];
+    loop {
+        let mut sum = vec![0.0; q.len()];
+        for ((sa, r, ..), next) in step.iter().zip(next.iter()) {
+            let v:f64 = next.iter().map(|(k, p)| p * k.map_or(0.0, |k| q[k])).sum();
+            sum[*sa] += r + gamma * v;
+        }
+        let mut change = Change::new();
+        for (k, s) in sum.into_iter().enumerate() {
+            let v = s / count[k] as f64;
+            change.add(v - q[k]);
+            q[k] = v;
+        }
+        if stopping.met(&change, gamma) { break }
+    }
+    let q:HashMap<(S, A), f64> = index.into_iter().map(|(sa, k)| (sa, q[k])).collect();
+    let start = episode.iter().map(|t| {
+        target(&t.state[0]).into_iter().map(|(a, p)| p * q.get(&(t.state[0].clone(), a)).copied().unwrap_or(0.0)).sum::<f64>()
+    }).sum::<f64>() / episode.len().max(1) as f64;
+    (start, q)
+}
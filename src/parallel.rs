@@ -0,0 +1,14 @@
+//f over 0..n, on the rayon pool when asked. wasm32 has no threads and always runs in order
+#[cfg(not(target_arch = "wasm32"))]
+pub fn map_index<T, F>(n:usize, parallel:bool, f:F) -> Vec<T>
+    where T:Send, F:Fn(usize) -> T + Sync + Send {
+    use rayon::prelude::*;
+    if parallel { (0..n).into_par_iter().map(f).collect() }
+    else { (0..n).map(f).collect() }
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn map_index<T, F>(n:usize, _:bool, f:F) -> Vec<T>
+    where T:Send, F:Fn(usize) -> T + Sync + Send {
+    (0..n).map(f).collect()
+}
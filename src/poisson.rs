@@ -1,16 +1,20 @@
+use rand::Rng;
+use serde::Serialize;
 
+use crate::float::Float;
 
-pub struct Poisson {
-    pmf_v: Vec<f64>,
-    cdf_v: Vec<f64>,
+//truncated at range, probabilities are computed in f64 and stored as F
+pub struct Poisson<F:Float = f64> {
+    pmf_v: Vec<F>,
+    cdf_v: Vec<F>,
     pub l:usize,
 }
 
-impl Poisson {
+impl<F:Float> Poisson<F> {
     pub fn new(l:usize, range:usize) -> Self {
-        let mut pmf_v:Vec<f64> = Vec::new();
-        let mut cdf_v:Vec<f64> = Vec::new();
-        let e:f64 = 2.7182818284;
+        let mut pmf_v:Vec<F> = Vec::new();
+        let mut cdf_v:Vec<F> = Vec::new();
+        let e:f64 = std::f64::consts::E;
         let lf = l as f64;
         let mut n_rank:f64 = 1.0;
         let mut cdf:f64 = 0.0;
@@ -18,18 +22,98 @@ impl Poisson {
             let nf = n as f64;
             if n > 0 { n_rank *= nf }
             let p = e.powf(-lf) * lf.powf(nf) / n_rank;
-            pmf_v.push(p);
+            pmf_v.push(F::from_f64(p));
             cdf += p;
-            cdf_v.push(cdf);
+            cdf_v.push(F::from_f64(cdf));
         }
         Self { l, pmf_v, cdf_v }
     }
 
-    pub fn pmf(&self, v:usize) -> f64 {
+    pub fn pmf(&self, v:usize) -> F {
         self.pmf_v[v]
     }
 
-    pub fn cdf(&self, v:usize) -> f64 {
+    pub fn cdf(&self, v:usize) -> F {
         self.cdf_v[v]
     }
-}
\ No newline at end of file
+}
+
+//a draw by multiplying uniforms until they fall below e^-λ, fine for small λ
+pub fn sample<R:Rng + ?Sized>(l:usize, rng:&mut R) -> usize {
+    let limit = (-(l as f64)).exp();
+    let mut p:f64 = rng.gen();
+    let mut n = 0;
+    while p > limit {
+        n += 1;
+        p *= rng.gen::<f64>();
+    }
+    n
+}
+
+//what happens to the mass above the cap of a truncated distribution
+#[derive(Clone, Copy, PartialEq, Debug, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Tail {
+    Lump,//piled onto the cap, i.e. the distribution of min(X, max)
+    Renormalize,//dropped, the rest is scaled back to 1
+}
+
+//poisson restricted to 0..=max
+pub struct TruncatedPoisson<F:Float = f64> {
+    pmf_v: Vec<F>,
+    cdf_v: Vec<F>,
+    mean_v: F,
+    pub l:usize,
+    pub max:usize,
+    pub tail:Tail,
+}
+
+impl<F:Float> TruncatedPoisson<F> {
+    pub fn new(l:usize, max:usize, tail:Tail) -> Self {
+        let dist:Poisson<f64> = Poisson::new(l, max);
+        let mut pmf:Vec<f64> = (0..=max).map(|n| dist.pmf(n)).collect();
+        match tail {
+            Tail::Lump => pmf[max] += 1.0 - dist.cdf(max),
+            Tail::Renormalize => {
+                let mass = dist.cdf(max);
+                pmf.iter_mut().for_each(|p| *p /= mass);
+            },
+        }
+        let mean = pmf.iter().enumerate().map(|(n, p)| p * n as f64).sum::<f64>();
+        let mut cdf = 0.0;
+        let cdf_v = pmf.iter().map(|p| { cdf += p; F::from_f64(cdf) }).collect();
+        Self {
+            pmf_v:pmf.into_iter().map(F::from_f64).collect(), cdf_v,
+            mean_v:F::from_f64(mean),
+            l, max, tail,
+        }
+    }
+
+    pub fn pmf(&self, v:usize) -> F {
+        self.pmf_v[v]
+    }
+
+    pub fn cdf(&self, v:usize) -> F {
+        self.cdf_v[v]
+    }
+
+    pub fn mean(&self) -> F {
+        self.mean_v
+    }
+
+    //mean of the same poisson cut at v instead, with the same tail, so one distribution per λ
+    //serves every car count. below the cap the lumped pmf is the plain one and the
+    //renormalized pmf a multiple of it, which cancels
+    pub fn mean_below(&self, v:usize) -> F {
+        if v >= self.max { return self.mean_v }
+        let pmf = &self.pmf_v[..=v];
+        let head = pmf.iter().enumerate().map(|(n, p)| p.to_f64() * n as f64).sum::<f64>();
+        F::from_f64(match self.tail {
+            Tail::Lump => {
+                let below = pmf[..v].iter().map(|p| p.to_f64()).sum::<f64>();
+                head + v as f64 * (1.0 - below - pmf[v].to_f64())
+            },
+            Tail::Renormalize => head / pmf.iter().map(|p| p.to_f64()).sum::<f64>(),
+        })
+    }
+}
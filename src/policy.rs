@@ -0,0 +1,106 @@
+use rand::prelude::*;
+
+use crate::nd_vec::NdVec2;
+
+type Vec2 = (i32, i32);
+
+//behavior policy derived from action values or from a greedy action
+#[derive(Clone, Copy)]
+pub enum Behavior {
+    EpsilonGreedy(f64),//epsilon
+    Softmax(f64),//temperature
+}
+
+//probability distribution over a shared action set, per state
+pub struct StochasticPolicy<A> {
+    pub action:Vec<A>,
+    pub prob:NdVec2<Vec<f64>>,//state - action index - probability
+}
+
+pub fn sample_index<R:Rng + ?Sized>(p:&[f64], rng:&mut R) -> usize {
+    let r:f64 = rng.gen();
+    let mut acc = 0.0;
+    //fall back to the last action with non-zero probability on rounding error
+    p.iter().position(|v| { acc += v; r < acc })
+        .unwrap_or_else(|| p.iter().rposition(|v| *v > 0.0).unwrap())
+}
+
+impl Behavior {
+    //action probabilities, disallowed actions get none
+    pub fn prob_q(&self, q:&[f64], allowed:&[bool]) -> Vec<f64> {
+        assert_eq!(q.len(), allowed.len());
+        let c = allowed.iter().filter(|v| **v).count();
+        let q_max = q.iter().zip(allowed.iter())
+            .filter(|(_, v)| **v)
+            .map(|(q, _)| *q)
+            .fold(f64::NEG_INFINITY, f64::max);
+        match *self {
+            Behavior::EpsilonGreedy(epsilon) => {
+                //greedy share is split among tied actions
+                let greedy = q.iter().zip(allowed.iter()).filter(|(q, v)| **v && **q == q_max).count();
+                q.iter().zip(allowed.iter())
+                    .map(|(q, v)| {
+                        if !*v { 0.0 }
+                        else if *q == q_max { epsilon / c as f64 + (1.0 - epsilon) / greedy as f64 }
+                        else { epsilon / c as f64 }
+                    })
+                    .collect()
+            },
+            Behavior::Softmax(temperature) => {
+                let e:Vec<f64> = q.iter().zip(allowed.iter())
+                    .map(|(q, v)| if *v { ((q - q_max) / temperature).exp() } else { 0.0 })
+                    .collect();
+                let sum = e.iter().sum::<f64>();
+                e.iter().map(|v| v / sum).collect()
+            },
+        }
+    }
+
+    //treats the greedy action as the only valued one
+    pub fn prob_greedy(&self, a:usize, allowed:&[bool]) -> Vec<f64> {
+        let q:Vec<f64> = (0..allowed.len()).map(|k| if k == a { 1.0 } else { 0.0 }).collect();
+        self.prob_q(&q, allowed)
+    }
+
+    pub fn sample<R:Rng + ?Sized>(&self, q:&[f64], allowed:&[bool], rng:&mut R) -> usize {
+        sample_index(&self.prob_q(q, allowed), rng)
+    }
+}
+
+impl<A:Copy + PartialEq> StochasticPolicy<A> {
+    //equiprobable over all actions
+    pub fn new(size:(usize, usize), action:Vec<A>) -> Self {
+        let mut prob = NdVec2::from_size(size);
+        let p = 1.0 / action.len() as f64;
+        prob.fill(vec![p; action.len()]);
+        Self { action, prob }
+    }
+
+    pub fn action_index(&self, a:&A) -> Option<usize> {
+        self.action.iter().position(|v| v == a)
+    }
+
+    pub fn set(&mut self, s:&Vec2, p:Vec<f64>) {
+        assert_eq!(p.len(), self.action.len());
+        self.prob[s] = p;
+    }
+
+    pub fn prob(&self, s:&Vec2, a:&A) -> f64 {
+        match self.action_index(a) {
+            Some(k) => self.prob[s][k],
+            None => 0.0,
+        }
+    }
+
+    pub fn sample<R:Rng + ?Sized>(&self, s:&Vec2, rng:&mut R) -> A {
+        self.action[sample_index(&self.prob[s], rng)]
+    }
+
+    //sum of π(a|s) * f(a)
+    pub fn expectation<F:FnMut(&A) -> f64>(&self, s:&Vec2, mut f:F) -> f64 {
+        self.prob[s].iter().zip(self.action.iter())
+            .filter(|(p, _)| **p > 0.0)
+            .map(|(p, a)| p * f(a))
+            .sum::<f64>()
+    }
+}
@@ -0,0 +1,186 @@
+use crate::policy::{ self, Behavior };
+use crate::rng::SeededRng;
+use crate::rollout::Trajectory;
+use crate::schedules::Schedule;
+use crate::vfa::{ FeatureVec, Features, ValueFunction };
+
+//policy gradient methods, chapter 13. a softmax explores on its own so there is no ε, the step
+//sizes are schedules: P of the actor, W of the baseline or critic
+
+//π(a|s) = softmax over θ·x(s, a)
+pub struct SoftmaxPolicy<A, F> {
+    pub action:Vec<A>,
+    pub theta:Vec<f64>,
+    pub feature:F,
+    pub floor:f64,//minimum action probability, 0 for a plain softmax
+}
+
+pub struct Baseline<V, W = f64> {
+    pub v:V,
+    pub alpha:W,
+}
+
+//monte carlo policy gradient, chapter 13.3/13.4, both step sizes counted by episode
+pub struct Reinforce<A, F, V, P = f64, W = f64> {
+    pub policy:SoftmaxPolicy<A, F>,
+    pub alpha:P,
+    pub gamma:f64,
+    pub baseline:Option<Baseline<V, W>>,
+    pub episodes:usize,
+}
+
+impl<A:Copy, F> SoftmaxPolicy<A, F> {
+    pub fn new<S>(action:Vec<A>, feature:F, theta:Vec<f64>) -> Self
+        where F:Features<(S, A)> {
+        assert_eq!(theta.len(), feature.dim());
+        Self { action, theta, feature, floor:0.0 }
+    }
+
+    fn preference<S:Copy>(&self, s:&S, a:&A) -> f64
+        where F:Features<(S, A)> {
+        self.feature.features(&(*s, *a)).iter().map(|(k, x)| self.theta[*k] * x).sum::<f64>()
+    }
+
+    //the softmax before the floor
+    fn softmax<S:Copy>(&self, s:&S) -> Vec<f64>
+        where F:Features<(S, A)> {
+        let h:Vec<f64> = self.action.iter().map(|a| self.preference(s, a)).collect();
+        Behavior::Softmax(1.0).prob_q(&h, &vec![true; h.len()])
+    }
+
+    //max(p, floor) / Z with p the softmax and Z the sum of the raised probabilities
+    pub fn prob<S:Copy>(&self, s:&S) -> Vec<f64>
+        where F:Features<(S, A)> {
+        let prob = self.softmax(s);
+        if self.floor <= 0.0 { return prob }
+        let prob:Vec<f64> = prob.into_iter().map(|p| p.max(self.floor)).collect();
+        let total = prob.iter().sum::<f64>();
+        prob.into_iter().map(|p| p / total).collect()
+    }
+
+    //action index
+    pub fn sample<S:Copy>(&self, s:&S, rng:&mut SeededRng) -> usize
+        where F:Features<(S, A)> {
+        policy::sample_index(&self.prob(s), rng)
+    }
+
+    //∇ln π(a|s) of the floored policy. an action held at the floor has no gradient of its own,
+    //the others have ∇p_b = p_b (x(s, b) - x̄) with x̄ = Σ_b p_b x(s, b), so with U the actions
+    //above the floor
+    //  ∇ln π(a|s) = [a ∈ U] (x(s, a) - x̄) - Σ_{b ∈ U} p_b (x(s, b) - x̄) / Z
+    //which is x(s, a) - x̄ of the plain softmax when the floor is 0
+    pub fn grad_ln<S:Copy>(&self, s:&S, a:usize) -> FeatureVec
+        where F:Features<(S, A)> {
+        let prob = self.softmax(s);
+        let free:Vec<bool> = prob.iter().map(|p| self.floor <= 0.0 || *p > self.floor).collect();
+        let z = prob.iter().map(|p| p.max(self.floor)).sum::<f64>();
+        let p_free = prob.iter().zip(free.iter()).filter(|(_, f)| **f).map(|(p, _)| p).sum::<f64>();
+        let own = if free[a] { 1.0 } else { 0.0 };
+        let mut grad = FeatureVec::new();
+        for (b, (action, p)) in self.action.iter().zip(prob.iter()).enumerate() {
+            //coefficient of x(s, b) once x̄ is expanded
+            let mut c = p * (p_free / z - own);
+            if b == a { c += own }
+            if free[b] { c -= p / z }
+            grad.extend(self.feature.features(&(*s, *action)).into_iter().map(|(k, x)| (k, c * x)));
+        }
+        grad
+    }
+
+    pub fn update<S:Copy>(&mut self, s:&S, a:usize, step:f64)
+        where F:Features<(S, A)> {
+        for (k, g) in self.grad_ln(s, a) {
+            self.theta[k] += step * g;
+        }
+    }
+}
+
+impl<A:Copy, F, V> Reinforce<A, F, V> {
+    pub fn new(policy:SoftmaxPolicy<A, F>, alpha:f64, gamma:f64, baseline:Option<Baseline<V>>) -> Self {
+        Self { policy, alpha, gamma, baseline, episodes:0 }
+    }
+}
+
+impl<A:Copy, F, V, P:Schedule, W:Schedule> Reinforce<A, F, V, P, W> {
+    pub fn with_alpha<B:Schedule>(self, alpha:B) -> Reinforce<A, F, V, B, W> {
+        let Self { policy, gamma, baseline, episodes, .. } = self;
+        Reinforce { policy, alpha, gamma, baseline, episodes }
+    }
+
+    //of the baseline, if any
+    pub fn with_alpha_w<B:Schedule>(self, alpha_w:B) -> Reinforce<A, F, V, P, B> {
+        let Self { policy, alpha, gamma, baseline, episodes } = self;
+        let baseline = baseline.map(|b| Baseline { v:b.v, alpha:alpha_w });
+        Reinforce { policy, alpha, gamma, baseline, episodes }
+    }
+
+    //actions are indices into the policy's action list
+    pub fn learn<S:Copy>(&mut self, episode:&Trajectory<S, usize>)
+        where F:Features<(S, A)>, V:ValueFunction<S> {
+        self.episodes += 1;
+        let alpha = self.alpha.at(self.episodes);
+        //returns are computed up front, θ changes during the pass
+        let g = episode.returns(self.gamma);
+        let mut discount = 1.0;
+        for (t, (s, a)) in episode.state.iter().zip(episode.action.iter()).enumerate() {
+            let delta = match &mut self.baseline {
+                Some(b) => {
+                    let delta = g[t] - b.v.predict(s);
+                    b.v.gradient_step(s, b.alpha.at(self.episodes), delta);
+                    delta
+                },
+                None => g[t],
+            };
+            self.policy.update(s, *a, alpha * discount * delta);
+            discount *= self.gamma;
+        }
+    }
+}
+
+//one-step actor-critic, chapter 13.5, the critic any approximator. step sizes counted by steps
+pub struct ActorCritic<A, F, V, P = f64, W = f64> {
+    pub policy:SoftmaxPolicy<A, F>,
+    pub v:V,
+    pub alpha:P,//actor
+    pub alpha_w:W,//critic
+    pub gamma:f64,
+    pub steps:usize,
+    discount:f64,//γ^t within the current episode
+}
+
+impl<A:Copy, F, V> ActorCritic<A, F, V> {
+    pub fn new<S>(policy:SoftmaxPolicy<A, F>, v:V, alpha:f64, alpha_w:f64, gamma:f64) -> Self
+        where V:ValueFunction<S> {
+        Self { policy, v, alpha, alpha_w, gamma, steps:0, discount:1.0 }
+    }
+}
+
+impl<A:Copy, F, V, P:Schedule, W:Schedule> ActorCritic<A, F, V, P, W> {
+    pub fn with_alpha<B:Schedule>(self, alpha:B) -> ActorCritic<A, F, V, B, W> {
+        let Self { policy, v, alpha_w, gamma, steps, discount, .. } = self;
+        ActorCritic { policy, v, alpha, alpha_w, gamma, steps, discount }
+    }
+
+    pub fn with_alpha_w<B:Schedule>(self, alpha_w:B) -> ActorCritic<A, F, V, P, B> {
+        let Self { policy, v, alpha, gamma, steps, discount, .. } = self;
+        ActorCritic { policy, v, alpha, alpha_w, gamma, steps, discount }
+    }
+
+    pub fn reset(&mut self) {
+        self.discount = 1.0;
+    }
+
+    //ss is None for a terminal next state
+    pub fn step<S:Copy>(&mut self, s:&S, a:usize, r:f64, ss:Option<&S>)
+        where F:Features<(S, A)>, V:ValueFunction<S> {
+        let v_next = match ss {
+            Some(ss) => self.v.predict(ss),
+            None => 0.0,
+        };
+        self.steps += 1;
+        let delta = r + self.gamma * v_next - self.v.predict(s);
+        self.v.gradient_step(s, self.alpha_w.at(self.steps), delta);
+        self.policy.update(s, a, self.alpha.at(self.steps) * self.discount * delta);
+        self.discount *= self.gamma;
+    }
+}
@@ -0,0 +1,34 @@
+use crate::fourier;
+use crate::vfa::{ FeatureVec, Features };
+
+//order-n polynomial basis of section 9.5.1, x_i(s) = Π_j s_j^c_ij for every c_i in
+//{0, ..., n}^k, (n + 1)^k features. s is first scaled from [lo, hi] into [0, 1] per
+//dimension so high powers neither vanish nor blow up
+pub struct Polynomial {
+    pub order:usize,
+    pub c:Vec<Vec<f64>>,
+    pub lo:Vec<f64>,
+    pub hi:Vec<f64>,
+}
+
+impl Polynomial {
+    pub fn new(order:usize, lo:&[f64], hi:&[f64]) -> Self {
+        assert_eq!(lo.len(), hi.len());
+        Self { order, c:fourier::coefficients(order, lo.len()), lo:lo.to_vec(), hi:hi.to_vec() }
+    }
+}
+
+impl Features<Vec<f64>> for Polynomial {
+    fn dim(&self) -> usize {
+        self.c.len()
+    }
+
+    fn features(&self, s:&Vec<f64>) -> FeatureVec {
+        let u:Vec<f64> = s.iter().zip(self.lo.iter().zip(self.hi.iter()))
+            .map(|(s, (lo, hi))| (s - lo) / (hi - lo))
+            .collect();
+        self.c.iter().enumerate()
+            .map(|(k, c)| (k, c.iter().zip(u.iter()).map(|(c, u)| u.powi(*c as i32)).product()))
+            .collect()
+    }
+}
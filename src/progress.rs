@@ -0,0 +1,31 @@
+use indicatif::{ ProgressBar, ProgressStyle };
+
+//bars draw to stderr and hide themselves when it is not a terminal
+
+//evaluation sweeps bounded by max_iter
+pub fn sweep(max_iter:u64) -> ProgressBar {
+    let pb = ProgressBar::new(max_iter);
+    pb.set_style(ProgressStyle::with_template("sweep {pos}/{len} [{bar:40}] {msg} eta {eta}")
+        .unwrap().progress_chars("=> "));
+    pb
+}
+
+//sweeps running until convergence
+pub fn sweep_unbounded() -> ProgressBar {
+    let pb = ProgressBar::new_spinner();
+    pb.set_style(ProgressStyle::with_template("{spinner} sweep {pos} {msg} {elapsed}").unwrap());
+    pb
+}
+
+//episodes, runs, or any other counted loop
+pub fn counter(len:u64, prefix:&'static str) -> ProgressBar {
+    let pb = ProgressBar::new(len);
+    pb.set_style(ProgressStyle::with_template("{prefix} {pos}/{len} [{bar:40}] {per_sec} eta {eta}")
+        .unwrap().progress_chars("=> "));
+    pb.set_prefix(prefix);
+    pb
+}
+
+pub fn delta_msg(delta:f64, theta:f64) -> String {
+    format!("delta {:.4} theta {}", delta, theta)
+}
@@ -0,0 +1,146 @@
+use pyo3::exceptions::{ PyIndexError, PyValueError };
+use pyo3::prelude::*;
+
+use crate::env::Environment;
+use crate::exercise4_7::{ self, Evaluation, Init, Params, TieBreak };
+use crate::exercise4_9;
+use crate::poisson::{ Poisson, Tail };
+use crate::rng::{ self, SeededRng };
+use crate::stopping::StoppingRule;
+use crate::{ access_control, mountain_car, short_corridor };
+use crate::walk::RandomWalk;
+
+//python module, `maturin develop --features python` then `import rl_exercise`
+
+//theta is the span epsilon with span=True
+fn stopping_rule(theta:f64, span:bool) -> StoppingRule {
+    if span { StoppingRule::Span(theta) } else { StoppingRule::MaxNorm(theta) }
+}
+
+//exercise 4.7 policy iteration, returns (value, policy) indexed [cars at 0][cars at 1]
+#[pyfunction]
+#[pyo3(signature = (discount=0.9, theta=0.1, max_iter=16, state_range=20, move_limit=5, rent_reward=10,
+    rent=(3, 4), ret=(3, 2), change=true, free_shuttle=1, parking_limit=10, parking_cost=4, span=false, parallel=false, renormalize=false, action_value=false, tie="last", exact=false, direct=false, init="zero", warm_start=true))]
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn car_rental(discount:f64, theta:f64, max_iter:i32, state_range:i32, move_limit:i32, rent_reward:i32,
+    rent:(usize, usize), ret:(usize, usize), change:bool, free_shuttle:i32, parking_limit:i32, parking_cost:i32, span:bool, parallel:bool, renormalize:bool, action_value:bool, tie:&str, exact:bool, direct:bool, init:&str, warm_start:bool)
+    -> PyResult<(Vec<Vec<f64>>, Vec<Vec<i32>>)> {
+    let params = Params {
+        discount, stopping:stopping_rule(theta, span), max_iter, state_range, move_limit, rent_reward,
+        rent, ret, change, free_shuttle, parking_limit, parking_cost, parallel,
+        tail:if renormalize { Tail::Renormalize } else { Tail::Lump },
+        action_value,
+        tie:TieBreak::parse(tie).map_err(PyValueError::new_err)?,
+        evaluation:if direct { Evaluation::Direct } else if exact { Evaluation::Exact } else { Evaluation::Modified },
+        init:Init::parse(init).map_err(PyValueError::new_err)?,
+        warm_start,
+        mmap:None,
+    };
+    let s = exercise4_7::solve(&params);
+    Ok((s.value, s.policy))
+}
+
+//gambler's problem value iteration, returns (value, bet) indexed by capital
+#[pyfunction]
+#[pyo3(signature = (p_win=0.4, theta=1e-9, goal=100, span=false))]
+fn gambler(p_win:f64, theta:f64, goal:i32, span:bool) -> (Vec<f64>, Vec<i32>) {
+    exercise4_9::solve(p_win, stopping_rule(theta, span), goal)
+}
+
+#[pyclass(name = "Poisson")]
+struct PyPoisson {
+    dist:Poisson,
+    range:usize,
+}
+
+#[pymethods]
+impl PyPoisson {
+    #[new]
+    fn new(l:usize, range:usize) -> Self {
+        Self { dist:Poisson::new(l, range), range }
+    }
+
+    fn pmf(&self, v:usize) -> PyResult<f64> {
+        if v > self.range { return Err(PyIndexError::new_err(format!("{} outside 0..={}", v, self.range))) }
+        Ok(self.dist.pmf(v))
+    }
+
+    fn cdf(&self, v:usize) -> PyResult<f64> {
+        if v > self.range { return Err(PyIndexError::new_err(format!("{} outside 0..={}", v, self.range))) }
+        Ok(self.dist.cdf(v))
+    }
+}
+
+//reset() -> state, step(action) -> (state, reward, done), actions(state) -> [action]
+macro_rules! py_env {
+    ($name:ident, $py:literal, $env:ty, ($($arg:ident:$t:ty = $d:expr),*), $new:expr) => {
+        #[pyclass(name = $py, unsendable)]
+        struct $name {
+            env:$env,
+            rng:SeededRng,
+        }
+
+        #[pymethods]
+        impl $name {
+            #[new]
+            #[pyo3(signature = (seed=None, $($arg=$d),*))]
+            fn new(seed:Option<u64>, $($arg:$t),*) -> Self {
+                Self { env:$new, rng:rng::from_seed(seed.unwrap_or_else(rng::random_seed)) }
+            }
+
+            fn reset(&mut self) -> <$env as Environment>::State {
+                self.env.reset(&mut self.rng)
+            }
+
+            fn step(&mut self, action:<$env as Environment>::Action) -> (<$env as Environment>::State, f64, bool) {
+                self.env.step(&action, &mut self.rng)
+            }
+
+            fn actions(&self, state:<$env as Environment>::State) -> Vec<<$env as Environment>::Action> {
+                self.env.actions(&state)
+            }
+        }
+    };
+}
+
+py_env!(PyMountainCar, "MountainCar", mountain_car::World, (), mountain_car::World::new());
+py_env!(PyShortCorridor, "ShortCorridor", short_corridor::World, (), short_corridor::World::new());
+py_env!(PyAccessControl, "AccessControl", access_control::World, (servers:usize = 10, p_free:f64 = 0.06),
+    access_control::World::new(servers, p_free));
+
+//the walk takes no actions, step() has no argument and actions() is not needed
+#[pyclass(name = "RandomWalk", unsendable)]
+struct PyRandomWalk {
+    env:RandomWalk,
+    rng:SeededRng,
+}
+
+#[pymethods]
+impl PyRandomWalk {
+    #[new]
+    #[pyo3(signature = (seed=None, n=1000, jump=100, start=500, left=-1.0, right=1.0))]
+    fn new(seed:Option<u64>, n:usize, jump:usize, start:usize, left:f64, right:f64) -> Self {
+        let env = RandomWalk { jump, start, ..RandomWalk::new(n, left, right) };
+        Self { env, rng:rng::from_seed(seed.unwrap_or_else(rng::random_seed)) }
+    }
+
+    fn reset(&mut self) -> usize {
+        self.env.reset(&mut self.rng)
+    }
+
+    fn step(&mut self) -> (usize, f64, bool) {
+        self.env.step(&(), &mut self.rng)
+    }
+}
+
+#[pymodule]
+fn rl_exercise(m:&Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(car_rental, m)?)?;
+    m.add_function(wrap_pyfunction!(gambler, m)?)?;
+    m.add_class::<PyPoisson>()?;
+    m.add_class::<PyMountainCar>()?;
+    m.add_class::<PyShortCorridor>()?;
+    m.add_class::<PyRandomWalk>()?;
+    m.add_class::<PyAccessControl>()?;
+    Ok(())
+}
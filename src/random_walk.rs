@@ -0,0 +1,267 @@
+use std::error::Error;
+use plotters::{prelude::*, coord::Shift};
+
+use crate::experiment::{ Curve, Experiment };
+use crate::fourier::Fourier;
+use crate::lstd::Lstd;
+use crate::polynomial::Polynomial;
+use crate::progress;
+use crate::rng::{ self, SeededRng };
+use crate::vfa::{ self, FeatureVec, Features, LinearVfa };
+use crate::walk::RandomWalk;
+
+type State = usize;
+
+struct ControlInfo {
+    pub max_episode:usize,
+    pub alpha_mc:f64,
+    pub alpha_td:f64,
+    pub group:usize,//states per aggregated group
+}
+
+struct Aggregation {
+    pub n:usize,
+    pub group:usize,
+}
+
+//a basis over [0, 1] applied to the states 1..=n
+struct Scaled<F> {
+    pub n:usize,
+    pub basis:F,
+}
+
+//example 9.1, 1000 states from the middle, jumps of up to 100
+fn world() -> RandomWalk {
+    RandomWalk { jump:100, ..RandomWalk::new(1000, -1.0, 1.0) }
+}
+
+//(state, following reward) pairs until termination
+fn transitions(w:&mut RandomWalk, rng:&mut SeededRng) -> Vec<(State, f64)> {
+    let t = w.episode(rng);
+    t.state.into_iter().zip(t.reward).collect()
+}
+
+impl Features<State> for Aggregation {
+    fn dim(&self) -> usize {
+        self.n.div_ceil(self.group)
+    }
+
+    fn features(&self, s:&State) -> FeatureVec {
+        vec!(((s - 1) / self.group, 1.0))
+    }
+}
+
+impl<F:Features<Vec<f64>>> Features<State> for Scaled<F> {
+    fn dim(&self) -> usize {
+        self.basis.dim()
+    }
+
+    fn features(&self, s:&State) -> FeatureVec {
+        self.basis.features(&vec!((s - 1) as f64 / (self.n - 1) as f64))
+    }
+
+    fn step_scale(&self, k:usize) -> f64 {
+        self.basis.step_scale(k)
+    }
+}
+
+//rms error over the states of one value estimate
+fn rms_error<F:Features<State>>(w:&RandomWalk, v:&LinearVfa<F>, v_true:&[f64]) -> f64 {
+    ((1..=w.n).map(|s| (v.value(&s) - v_true[s]).powi(2)).sum::<f64>() / w.n as f64).sqrt()
+}
+
+fn draw_curve(episode:&[usize], curve:&[(String, Curve)], canvas:&DrawingArea<BitMapBackend, Shift>) -> Result<(), Box<dyn Error>> {
+    let mut chart = ChartBuilder::on(canvas)
+        .caption(format!("1000-state walk, {} runs", curve[0].1.runs), ("sans-serif", 30))
+        .margin(5)
+        .x_label_area_size(50)
+        .y_label_area_size(50)
+        .build_cartesian_2d(0..episode[episode.len() - 1], 0.0..0.5)?;
+    chart.configure_mesh().x_desc("episodes").y_desc("rms error").draw()?;
+    for (k, (label, c)) in curve.iter().enumerate() {
+        let color = Palette99::pick(k).to_rgba();
+        chart.draw_series(LineSeries::new(episode.iter().cloned().zip(c.mean.iter().cloned()), color))?
+            .label(label.as_str())
+            .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], color));
+    }
+    chart
+        .configure_series_labels()
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
+        .draw()?;
+    Ok(())
+}
+
+fn draw_value(w:&RandomWalk, v_true:&[f64], estimate:&[(&str, Vec<f64>)], canvas:&DrawingArea<BitMapBackend, Shift>) -> Result<(), Box<dyn Error>> {
+    let mut chart = ChartBuilder::on(canvas)
+        .margin(5)
+        .x_label_area_size(50)
+        .y_label_area_size(50)
+        .build_cartesian_2d(1..w.n, -1.0..1.0)?;
+    chart.configure_mesh().draw()?;
+    chart.draw_series(LineSeries::new((1..=w.n).map(|s| (s, v_true[s])), &RED))?
+        .label("true value")
+        .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], RED));
+    for (k, (label, v)) in estimate.iter().enumerate() {
+        let color = Palette99::pick(k + 1).to_rgba();
+        chart.draw_series(LineSeries::new((1..=w.n).map(|s| (s, v[s - 1])), color))?
+            .label(*label)
+            .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], color));
+    }
+    chart
+        .configure_series_labels()
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
+        .draw()?;
+    Ok(())
+}
+
+fn draw_distribution(w:&RandomWalk, mu:&[f64], canvas:&DrawingArea<BitMapBackend, Shift>) -> Result<(), Box<dyn Error>> {
+    let mu_max = mu.iter().cloned().fold(0.0, f64::max);
+    let mut chart = ChartBuilder::on(canvas)
+        .margin(5)
+        .x_label_area_size(50)
+        .y_label_area_size(50)
+        .build_cartesian_2d(1..w.n, 0.0..mu_max * 1.1)?;
+    chart.configure_mesh().draw()?;
+    chart.draw_series((1..=w.n).map(|s| {
+        Rectangle::new([(s, 0.0), (s + 1, mu[s - 1])], BLUE.mix(0.5).filled())
+    }))?;
+    Ok(())
+}
+
+pub fn run(seed:u64) -> Result<(), Box<dyn Error>> {
+    let c_info = ControlInfo {
+        max_episode:100000,
+        alpha_mc:2e-5, alpha_td:2e-4,
+        group:100,
+    };
+    let mut w = world();
+    let mut rng = rng::from_seed(seed);
+    let v_true = w.true_value(1e-9);
+    let mut v_mc = LinearVfa::new(Aggregation { n:w.n, group:c_info.group });
+    let mut v_td = LinearVfa::new(Aggregation { n:w.n, group:c_info.group });
+    let mut visit = vec![0usize; w.n];
+    let pb = progress::counter(c_info.max_episode as u64, "episode");
+    for _ in 0..c_info.max_episode {
+        let trajectory = transitions(&mut w, &mut rng);
+        for (s, _) in trajectory.iter() {
+            visit[*s - 1] += 1;
+        }
+        vfa::gradient_mc(&mut v_mc, &trajectory, c_info.alpha_mc, 1.0);
+        let trajectory = transitions(&mut w, &mut rng);
+        for (t, (s, r)) in trajectory.iter().enumerate() {
+            let ss = trajectory.get(t + 1).map(|(ss, _)| ss);
+            vfa::td0(&mut v_td, s, *r, ss, c_info.alpha_td, 1.0);
+        }
+        pb.inc(1);
+    }
+    pb.finish_and_clear();
+    let total = visit.iter().sum::<usize>() as f64;
+    let mu:Vec<f64> = visit.iter().map(|c| *c as f64 / total).collect();
+    let estimate = [
+        ("gradient MC", (1..=w.n).map(|s| v_mc.value(&s)).collect::<Vec<f64>>()),
+        ("semi-gradient TD(0)", (1..=w.n).map(|s| v_td.value(&s)).collect::<Vec<f64>>()),
+    ];
+    for (label, v) in estimate.iter() {
+        let rms = ((1..=w.n).map(|s| (v[s - 1] - v_true[s]).powi(2)).sum::<f64>() / w.n as f64).sqrt();
+        println!("{}: rms error {:.4}", label, rms);
+    }
+    let canvas = BitMapBackend::new("9_1.png", (1440, 1440)).into_drawing_area();
+    canvas.fill(&WHITE)?;
+    let canvas_split = canvas.split_evenly((2, 1));
+    draw_value(&w, &v_true, &estimate, &canvas_split[0])?;
+    draw_distribution(&w, &mu, &canvas_split[1])?;
+    canvas.present()?;
+    Ok(())
+}
+
+//one gradient MC run of a basis, rms error every interval episodes
+fn basis_error<F:Features<Vec<f64>>>(w:&mut RandomWalk, v_true:&[f64], basis:F, alpha:f64, max_episode:usize, interval:usize, rng:&mut SeededRng) -> Vec<f64> {
+    let mut v = LinearVfa::new(Scaled { n:w.n, basis });
+    let mut error = vec!(rms_error(w, &v, v_true));
+    for k in 1..=max_episode {
+        let trajectory = transitions(w, rng);
+        vfa::gradient_mc(&mut v, &trajectory, alpha, 1.0);
+        if k % interval == 0 { error.push(rms_error(w, &v, v_true)) }
+    }
+    error
+}
+
+//figure 9.5, gradient MC with polynomial and fourier bases of orders 5, 10 and 20
+pub fn run_basis(seed:u64, runs:Option<usize>) -> Result<(), Box<dyn Error>> {
+    let (max_episode, interval) = (5000, 50);
+    //α of each basis as in the figure, the fourier one further scaled per feature
+    let (alpha_polynomial, alpha_fourier) = (1e-4, 5e-5);
+    let experiment = Experiment::new(runs.unwrap_or(30), seed);
+    let mut w = world();
+    let v_true = w.true_value(1e-9);
+    let episode:Vec<usize> = (0..=max_episode).step_by(interval).collect();
+    let order = [5, 10, 20];
+    let mut curve:Vec<(String, Curve)> = Vec::new();
+    for n in order {
+        let c = experiment.repeat(|rng| basis_error(&mut w, &v_true, Polynomial::new(n, &[0.0], &[1.0]), alpha_polynomial, max_episode, interval, rng));
+        curve.push((format!("polynomial order {}", n), c));
+    }
+    for n in order {
+        let c = experiment.repeat(|rng| basis_error(&mut w, &v_true, Fourier::new(n, 1), alpha_fourier, max_episode, interval, rng));
+        curve.push((format!("fourier order {}", n), c));
+    }
+    for (label, c) in curve.iter() {
+        println!("{}: rms error after {} episodes {:.4}", label, max_episode, c.mean[c.len() - 1]);
+    }
+    let canvas = BitMapBackend::new("9_5.png", (1440, 960)).into_drawing_area();
+    canvas.fill(&WHITE)?;
+    draw_curve(&episode, &curve, &canvas)?;
+    canvas.present()?;
+    Ok(())
+}
+
+//section 9.8, LSTD(0) and LSTD(λ) against semi-gradient TD(0) over the first episodes,
+//all on state aggregation
+pub fn run_lstd(seed:u64, runs:Option<usize>) -> Result<(), Box<dyn Error>> {
+    let (max_episode, interval, group, alpha) = (200, 5, 100, 0.01);
+    let experiment = Experiment::new(runs.unwrap_or(30), seed);
+    let mut w = world();
+    let v_true = w.true_value(1e-9);
+    let n = w.n;
+    let episode:Vec<usize> = (0..=max_episode).step_by(interval).collect();
+    let mut curve:Vec<(String, Curve)> = Vec::new();
+    let c = experiment.repeat(|rng| {
+        let mut v = LinearVfa::new(Aggregation { n, group });
+        let mut error = vec!(rms_error(&w, &v, &v_true));
+        for k in 1..=max_episode {
+            let trajectory = transitions(&mut w, rng);
+            for (t, (s, r)) in trajectory.iter().enumerate() {
+                vfa::td0(&mut v, s, *r, trajectory.get(t + 1).map(|(ss, _)| ss), alpha, 1.0);
+            }
+            if k % interval == 0 { error.push(rms_error(&w, &v, &v_true)) }
+        }
+        error
+    });
+    curve.push((format!("semi-gradient TD(0) α={}", alpha), c));
+    for lambda in [0.0, 0.8] {
+        let c = experiment.repeat(|rng| {
+            let mut v = Lstd::new(Aggregation { n, group }, 1.0, lambda, 1e-3);
+            let rms = |v:&Lstd<Aggregation>| {
+                let weight = v.weights();
+                ((1..=n).map(|s| (v.value(&weight, &s) - v_true[s]).powi(2)).sum::<f64>() / n as f64).sqrt()
+            };
+            let mut error = vec!(rms(&v));
+            for k in 1..=max_episode {
+                v.episode(&transitions(&mut w, rng));
+                if k % interval == 0 { error.push(rms(&v)) }
+            }
+            error
+        });
+        curve.push((format!("LSTD(λ={})", lambda), c));
+    }
+    for (label, c) in curve.iter() {
+        println!("{}: rms error after {} episodes {:.4}, after {} {:.4}", label, episode[2], c.mean[2], max_episode, c.mean[c.len() - 1]);
+    }
+    let canvas = BitMapBackend::new("9_lstd.png", (1440, 960)).into_drawing_area();
+    canvas.fill(&WHITE)?;
+    draw_curve(&episode, &curve, &canvas)?;
+    canvas.present()?;
+    Ok(())
+}
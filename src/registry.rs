@@ -0,0 +1,218 @@
+use std::error::Error;
+use serde_json::{ json, Value };
+
+use crate::cli::Args;
+use crate::exercise4_7::{ self, DiffReport, Params };
+use crate::exercise5_12::Estimator;
+use crate::exercise6_9::Moves;
+use crate::mc::Visit;
+use crate::schema::{ self, Kind, Param };
+use crate::{
+    access_control, baird, bandit, compare, dyna_maze,
+    exercise4_9, exercise5_12, exercise6_9, external, grid_search, gridworld, infinite_variance, maze,
+    mountain_car, random_walk, sample_update, short_corridor, square_wave, tic_tac_toe, tui, walk19, walk5,
+};
+
+//what an exercise may take from the command line. the book figures read the runs at most,
+//everything else of theirs is fixed by the book
+pub struct Context<'a> {
+    pub seed:u64,
+    pub args:&'a Args,
+}
+
+//one runnable exercise, either a cli index with its flags or a figure of the book by number
+pub struct Exercise {
+    pub id:&'static str,//"6_9" for an index, "fig4.2" for figure 4.2, "ex6.6" for example or exercise 6.6
+    pub chapter:Option<u32>,//None for the tools outside the book
+    pub title:&'static str,
+    pub run:fn(&Context) -> Result<(), Box<dyn Error>>,
+    pub params:fn() -> Vec<Param>,//the flags run reads besides --seed and --deterministic, what --describe prints
+}
+
+//the car rental solver the flags ask for, --dot and --npy export instead of solving
+fn car_rental(c:&Context) -> Result<(), Box<dyn Error>> {
+    let args = c.args;
+    let params = args.car_rental();
+    if let Some(path) = args.dot.as_deref() { return exercise4_7::write_dot(&params, path) }
+    if let Some(prefix) = args.npy.as_deref() { return exercise4_7::write_npy(&params, prefix) }
+    if args.tui { return tui::policy_iteration(&params) }
+    if args.f32 { return exercise4_7::run_as::<f32>(&params, args.diff, args.output.as_deref()) }
+    exercise4_7::run(&params, args.diff, args.output.as_deref())
+}
+
+//nothing beyond the seed
+fn fixed() -> Vec<Param> {
+    Vec::new()
+}
+
+fn runs() -> Vec<Param> {
+    vec!(schema::runs(None))
+}
+
+fn car_rental_params() -> Vec<Param> {
+    let mut params = schema::car_rental();
+    params.extend(vec!(
+        Param::new("--output", Kind::Path, Value::Null, "json file for the solution"),
+        Param::flag("--tui", "interactive policy iteration"),
+        Param::flag("--f32", "single precision values"),
+        Param::new("--diff", Kind::Choice, json!("grid"), "policy changes printed after each improvement").choices(&["summary", "list", "grid"]),
+        Param::new("--dot", Kind::Path, Value::Null, "writes the model as graphviz instead of solving"),
+        Param::new("--npy", Kind::Path, Value::Null, "writes P and R as {prefix}_p.npy and {prefix}_r.npy instead of solving"),
+    ));
+    params
+}
+
+fn sampled_params() -> Vec<Param> {
+    let mut params = schema::car_rental();
+    params.push(schema::runs(Some(200)));
+    params
+}
+
+fn discounts_params() -> Vec<Param> {
+    let mut params = schema::car_rental();
+    params.push(Param::new("--gammas", Kind::NumberList, json!([0.5, 0.8, 0.9, 0.95]), "discounts solved on one model").range(Some(0.0), Some(1.0)));
+    params
+}
+
+fn bench_params() -> Vec<Param> {
+    let mut params = schema::car_rental();
+    params.push(Param::new("--ranges", Kind::IntegerList, json!([10, 20, 30]), "state_range values timed").range(Some(1.0), None));
+    params
+}
+
+fn racetrack_params() -> Vec<Param> {
+    vec!(
+        Param::new("--visit", Kind::Choice, json!("every"), "first or every visit of a (state, action) pair").choices(&["first", "every"]),
+        Param::new("--estimator", Kind::Choice, json!("truncated"), "importance sampling of the behavior returns")
+            .choices(&["ordinary", "weighted", "truncated"]),
+    )
+}
+
+fn windy_params() -> Vec<Param> {
+    vec!(
+        Param::new("--moves", Kind::Choice, json!("9"), "action set, rook, king or king with staying put").choices(&["4", "8", "9"]),
+        Param::flag("--stochastic-wind", "wind varies by ±1, exercise 6.10"),
+    )
+}
+
+//in book order, every index next to the figures it draws, the tools last
+pub fn exercises() -> Vec<Exercise> {
+    let exercise = vec!(
+        Exercise { id:"1_1", chapter:Some(1), title:"tic-tac-toe, TD self-play on afterstates", run:|c| tic_tac_toe::run(c.seed, c.args.runs), params:runs },
+        Exercise { id:"2_3", chapter:Some(2), title:"10-armed testbed, optimistic greedy vs realistic Ɛ-greedy", run:|c| bandit::run_optimistic(c.seed), params:fixed },
+        Exercise { id:"fig2.3", chapter:Some(2), title:"10-armed testbed, optimistic initial values", run:|c| bandit::run_optimistic(c.seed), params:fixed },
+        Exercise { id:"2_4", chapter:Some(2), title:"10-armed testbed, UCB vs Ɛ-greedy", run:|c| bandit::run(c.seed), params:fixed },
+        Exercise { id:"fig2.4", chapter:Some(2), title:"10-armed testbed, UCB vs Ɛ-greedy", run:|c| bandit::run(c.seed), params:fixed },
+        Exercise { id:"2_5", chapter:Some(2), title:"10-armed testbed, gradient bandit with/without baseline", run:|c| bandit::run_gradient(c.seed), params:fixed },
+        Exercise { id:"fig2.5", chapter:Some(2), title:"10-armed testbed, gradient bandit with and without baseline", run:|c| bandit::run_gradient(c.seed), params:fixed },
+        Exercise { id:"4_1", chapter:Some(4), title:"4x4 gridworld, random policy evaluation and value iteration", run:|_| gridworld::run(), params:fixed },
+        Exercise { id:"fig4.1", chapter:Some(4), title:"4x4 gridworld, random policy evaluation", run:|_| gridworld::run(), params:fixed },
+        Exercise { id:"4_7", chapter:Some(4), title:"jack's car rental, policy iteration with the flags of the command line", run:car_rental, params:car_rental_params },
+        Exercise { id:"fig4.2", chapter:Some(4), title:"jack's car rental, policy iteration", run:|_| exercise4_7::run(&Params { change:false, ..Default::default() }, DiffReport::Grid, None), params:fixed },
+        Exercise { id:"ex4.7", chapter:Some(4), title:"jack's car rental with the free shuttle and parking cost", run:|_| exercise4_7::run(&Params::default(), DiffReport::Grid, None), params:fixed },
+        Exercise { id:"4_7_lp", chapter:Some(4), title:"car rental, policy iteration against the linear-programming optimum", run:|c| exercise4_7::run_lp(&c.args.car_rental()), params:schema::car_rental },
+        Exercise { id:"4_7_occupancy", chapter:Some(4), title:"car rental, value and on-policy distribution of the solved policy as heatmaps", run:|c| exercise4_7::run_occupancy(&c.args.car_rental()), params:schema::car_rental },
+        Exercise { id:"4_7_mc", chapter:Some(4), title:"car rental, dp values against returns under sampled rentals, --runs episodes per state",
+            run:|c| exercise4_7::run_mc(&c.args.car_rental(), c.args.runs.unwrap_or(200), c.seed), params:sampled_params },
+        Exercise { id:"4_7_gamma", chapter:Some(4), title:"car rental, the optimal policy over several discounts on one model",
+            run:|c| exercise4_7::run_discounts(&c.args.car_rental(), c.args.gammas.as_deref().unwrap_or(&[0.5, 0.8, 0.9, 0.95])), params:discounts_params },
+        Exercise { id:"4_7_average", chapter:Some(4), title:"car rental, average-reward policy iteration against the discounted policy", run:|c| exercise4_7::run_average(&c.args.car_rental()), params:schema::car_rental },
+        Exercise { id:"bench", chapter:Some(4), title:"car rental solver throughput, construction, sweeps and improvement timed apart",
+            run:|c| exercise4_7::run_bench(&c.args.car_rental(), c.args.ranges.as_deref().unwrap_or(&[10, 20, 30])), params:bench_params },
+        Exercise { id:"4_9", chapter:Some(4), title:"gambler's problem, value iteration", run:|c| exercise4_9::run(c.seed), params:fixed },
+        Exercise { id:"fig4.3", chapter:Some(4), title:"gambler's problem, value iteration", run:|c| exercise4_9::run(c.seed), params:fixed },
+        Exercise { id:"5_5", chapter:Some(5), title:"one-state mdp, ordinary vs weighted importance sampling", run:|c| infinite_variance::run(c.seed, c.args.runs), params:runs },
+        Exercise { id:"fig5.4", chapter:Some(5), title:"one-state mdp, ordinary importance sampling", run:|c| infinite_variance::run(c.seed, c.args.runs), params:runs },
+        Exercise { id:"5_12", chapter:Some(5), title:"racetrack, off-policy monte carlo control with an Ɛ-soft behavior", run:|c| exercise5_12::run(c.seed, c.args.visit, c.args.estimator), params:racetrack_params },
+        Exercise { id:"ex5.12", chapter:Some(5), title:"racetrack, off-policy monte carlo control", run:|c| exercise5_12::run(c.seed, Visit::Every, Estimator::WeightedTruncated), params:fixed },
+        Exercise { id:"5_12_rtdp", chapter:Some(5), title:"racetrack, real-time dynamic programming vs value iteration", run:|c| exercise5_12::run_rtdp(c.seed), params:fixed },
+        Exercise { id:"6_2", chapter:Some(6), title:"5-state walk, TD(0) vs constant-α MC, online and batch updating", run:|c| walk5::run(c.seed, c.args.runs), params:runs },
+        Exercise { id:"ex6.2", chapter:Some(6), title:"5-state random walk, TD(0) vs constant-α MC", run:|c| walk5::run(c.seed, c.args.runs), params:runs },
+        Exercise { id:"6_9", chapter:Some(6), title:"windy gridworld, SARSA with rook or king moves, optionally stochastic wind",
+            run:|c| exercise6_9::run(c.seed, c.args.moves, c.args.stochastic_wind), params:windy_params },
+        Exercise { id:"ex6.5", chapter:Some(6), title:"windy gridworld, SARSA", run:|c| exercise6_9::run(c.seed, Moves::Rook, false), params:fixed },
+        Exercise { id:"ex6.9", chapter:Some(6), title:"windy gridworld with king's moves", run:|c| exercise6_9::run(c.seed, Moves::King, false), params:fixed },
+        Exercise { id:"ex6.10", chapter:Some(6), title:"windy gridworld with king's moves and stochastic wind", run:|c| exercise6_9::run(c.seed, Moves::King, true), params:fixed },
+        Exercise { id:"compare", chapter:Some(6), title:"cliff walking, SARSA vs Q-learning vs Expected SARSA on shared seeds", run:|c| compare::run(c.seed, c.args.runs), params:runs },
+        Exercise { id:"ex6.6", chapter:Some(6), title:"cliff walking, SARSA vs Q-learning vs Expected SARSA", run:|c| compare::run(c.seed, c.args.runs), params:runs },
+        Exercise { id:"7_2", chapter:Some(7), title:"19-state walk, n-step TD over n and α", run:|c| walk19::run_nstep(c.seed, c.args.runs), params:runs },
+        Exercise { id:"fig7.2", chapter:Some(7), title:"19-state random walk, n-step TD over n and α", run:|c| walk19::run_nstep(c.seed, c.args.runs), params:runs },
+        Exercise { id:"7_4", chapter:Some(7), title:"19-state walk, off-policy n-step TD with and without control variates", run:|c| walk19::run(c.seed, c.args.runs), params:runs },
+        Exercise { id:"ex7.10", chapter:Some(7), title:"19-state random walk, off-policy n-step TD with control variates", run:|c| walk19::run(c.seed, c.args.runs), params:runs },
+        Exercise { id:"compare_nstep", chapter:Some(7), title:"cliff walking, n-step SARSA vs tree backup", run:|c| compare::run_nstep(c.seed, c.args.runs), params:runs },
+        Exercise { id:"compare_qsigma", chapter:Some(7), title:"cliff walking, Q(σ) from n-step SARSA (σ=1) to tree backup (σ=0)", run:|c| compare::run_qsigma(c.seed, c.args.runs), params:runs },
+        Exercise { id:"8_4", chapter:Some(8), title:"blocking maze, Dyna-Q vs Dyna-Q+ through the change", run:|c| dyna_maze::run_blocking(c.seed, c.args.runs), params:runs },
+        Exercise { id:"fig8.4", chapter:Some(8), title:"blocking maze, Dyna-Q vs Dyna-Q+", run:|c| dyna_maze::run_blocking(c.seed, c.args.runs), params:runs },
+        Exercise { id:"8_5", chapter:Some(8), title:"shortcut maze, Dyna-Q vs Dyna-Q+ through the change", run:|c| dyna_maze::run_shortcut(c.seed, c.args.runs), params:runs },
+        Exercise { id:"fig8.5", chapter:Some(8), title:"shortcut maze, Dyna-Q vs Dyna-Q+", run:|c| dyna_maze::run_shortcut(c.seed, c.args.runs), params:runs },
+        Exercise { id:"8_4_prioritized", chapter:Some(8), title:"dyna maze by resolution, updates until optimal of prioritized sweeping vs Dyna-Q",
+            run:|c| dyna_maze::run_prioritized(c.seed, c.args.runs), params:runs },
+        Exercise { id:"ex8.4", chapter:Some(8), title:"mazes by resolution, prioritized sweeping vs Dyna-Q", run:|c| dyna_maze::run_prioritized(c.seed, c.args.runs), params:runs },
+        Exercise { id:"8_7", chapter:Some(8), title:"one (s, a) with b successors, expected vs sample updates per computation", run:|c| sample_update::run(c.seed, c.args.runs), params:runs },
+        Exercise { id:"fig8.7", chapter:Some(8), title:"expected vs sample updates", run:|c| sample_update::run(c.seed, c.args.runs), params:runs },
+        Exercise { id:"9_1", chapter:Some(9), title:"1000-state random walk, state aggregation", run:|c| random_walk::run(c.seed), params:fixed },
+        Exercise { id:"fig9.1", chapter:Some(9), title:"1000-state random walk, state aggregation", run:|c| random_walk::run(c.seed), params:fixed },
+        Exercise { id:"9_5", chapter:Some(9), title:"1000-state random walk, polynomial vs fourier bases", run:|c| random_walk::run_basis(c.seed, c.args.runs), params:runs },
+        Exercise { id:"fig9.5", chapter:Some(9), title:"1000-state random walk, polynomial vs fourier bases", run:|c| random_walk::run_basis(c.seed, c.args.runs), params:runs },
+        Exercise { id:"9_lstd", chapter:Some(9), title:"1000-state random walk, LSTD vs semi-gradient TD", run:|c| random_walk::run_lstd(c.seed, c.args.runs), params:runs },
+        Exercise { id:"9_8", chapter:Some(9), title:"square wave, coarse coding and rbf generalization width", run:|c| square_wave::run(c.seed), params:fixed },
+        Exercise { id:"fig9.8", chapter:Some(9), title:"square wave, coarse coding feature width", run:|c| square_wave::run(c.seed), params:fixed },
+        Exercise { id:"10_1", chapter:Some(10), title:"mountain car, semi-gradient SARSA with tile coding", run:|c| mountain_car::run(c.seed), params:fixed },
+        Exercise { id:"fig10.1", chapter:Some(10), title:"mountain car, semi-gradient SARSA with tile coding", run:|c| mountain_car::run(c.seed), params:fixed },
+        Exercise { id:"10_4", chapter:Some(10), title:"mountain car, n-step SARSA over n and α", run:|c| mountain_car::run_nstep(c.seed, c.args.runs), params:runs },
+        Exercise { id:"fig10.4", chapter:Some(10), title:"mountain car, n-step SARSA over n and α", run:|c| mountain_car::run_nstep(c.seed, c.args.runs), params:runs },
+        Exercise { id:"10_2", chapter:Some(10), title:"access-control queuing, differential semi-gradient SARSA", run:|c| access_control::run(c.seed), params:fixed },
+        Exercise { id:"fig10.5", chapter:Some(10), title:"access-control queuing, differential semi-gradient SARSA", run:|c| access_control::run(c.seed), params:fixed },
+        Exercise { id:"11_2", chapter:Some(11), title:"baird counterexample, off-policy divergence vs gradient and emphatic TD", run:|c| baird::run(c.seed), params:fixed },
+        Exercise { id:"fig11.2", chapter:Some(11), title:"baird's counterexample", run:|c| baird::run(c.seed), params:fixed },
+        Exercise { id:"13_1", chapter:Some(13), title:"short corridor, REINFORCE with/without baseline, actor-critic", run:|c| short_corridor::run(c.seed, c.args.runs), params:runs },
+        Exercise { id:"fig13.1", chapter:Some(13), title:"short corridor, REINFORCE", run:|c| short_corridor::run(c.seed, c.args.runs), params:runs },
+        Exercise { id:"13_1_sweep", chapter:Some(13), title:"short corridor, v(start) over the probability of right", run:|_| short_corridor::run_sweep(), params:fixed },
+        Exercise { id:"grid", chapter:None, title:"every combination of a parameter grid, e.g. --config config/cliff_walking.json",
+            run:|c| match c.args.config.as_deref() {
+                Some(config) => grid_search::run(config, c.seed),
+                None => Err("grid needs --config file.json".into()),
+            },
+            params:|| vec!(Param::new("--config", Kind::Path, Value::Null, "json file of the parameter grid").required()) },
+        Exercise { id:"maze", chapter:None, title:"Q-learning on a gridworld drawn in a text file, e.g. --map maps/windy.txt",
+            run:|c| match c.args.map.as_deref() {
+                Some(map) => maze::run(map, c.seed, 500),
+                None => Err("maze needs --map file.txt".into()),
+            },
+            params:|| vec!(Param::new("--map", Kind::Path, Value::Null, "ascii map of the maze, see maze.rs").required()) },
+        Exercise { id:"external", chapter:None, title:"tabular Q-learning against an environment process speaking JSON lines",
+            run:|c| match c.args.env.as_deref() {
+                Some(cmd) => external::run(cmd, c.seed),
+                None => Err("external needs --env \"command\"".into()),
+            },
+            params:|| vec!(Param::new("--env", Kind::Text, Value::Null, "command line of an environment speaking json lines").required()) },
+    );
+    #[cfg(feature = "dqn")]
+    let exercise = exercise.into_iter()
+        .chain(std::iter::once(Exercise { id:"dqn", chapter:None, title:"cart pole and mountain car, deep Q-network", run:|c| crate::dqn::run(c.seed), params:fixed }))
+        .collect();
+    exercise
+}
+
+//every exercise under a heading per chapter, what --list prints
+pub fn list() -> String {
+    let exercise = exercises();
+    let width = exercise.iter().map(|e| e.id.len()).max().unwrap_or(0);
+    let mut s = String::new();
+    let mut chapter = None;
+    for (k, e) in exercise.iter().enumerate() {
+        if k == 0 || chapter != e.chapter {
+            chapter = e.chapter;
+            s += &match chapter {
+                Some(n) => format!("chapter {}\n", n),
+                None => "other\n".to_string(),
+            };
+        }
+        s += &format!("  {:width$}  {}\n", e.id, e.title);
+    }
+    s
+}
+
+pub fn find(id:&str) -> Option<Exercise> {
+    exercises().into_iter().find(|e| e.id == id)
+}
@@ -0,0 +1,156 @@
+use rand::prelude::*;
+
+use crate::rng::SeededRng;
+
+//one step of experience, what a Dyna-style or approximate agent learns from again later
+#[derive(Clone, Debug, PartialEq)]
+pub struct Experience<S, A> {
+    pub state:S,
+    pub action:A,
+    pub reward:f64,
+    pub next:S,
+    pub done:bool,
+}
+
+//the last capacity items, the oldest overwritten first. items are addressed by slot
+pub struct Replay<T> {
+    pub capacity:usize,
+    item:Vec<T>,
+    next:usize,//slot of the next push once full
+}
+
+//sum of the leaf values over a complete binary tree in one array, node k has children 2k + 1
+//and 2k + 2 and leaf i sits at node leaves - 1 + i
+struct SumTree {
+    leaves:usize,
+    node:Vec<f64>,
+}
+
+//replay drawn in proportion to priority^alpha (Schaul et al. 2016). new items get the largest
+//priority so far, so each one is replayed soon after it is stored
+pub struct Prioritized<T> {
+    pub replay:Replay<T>,
+    pub alpha:f64,//priority exponent, 0 draws uniformly
+    tree:SumTree,
+    max_priority:f64,
+}
+
+impl<T> Replay<T> {
+    pub fn new(capacity:usize) -> Self {
+        assert!(capacity > 0, "replay capacity must be positive");
+        Self { capacity, item:Vec::with_capacity(capacity), next:0 }
+    }
+
+    //slot the item went to
+    pub fn push(&mut self, item:T) -> usize {
+        let slot = self.next;
+        if self.item.len() < self.capacity { self.item.push(item) } else { self.item[slot] = item }
+        self.next = (slot + 1) % self.capacity;
+        slot
+    }
+
+    pub fn len(&self) -> usize {
+        self.item.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.item.is_empty()
+    }
+
+    pub fn get(&self, slot:usize) -> &T {
+        &self.item[slot]
+    }
+
+    //batch slots drawn uniformly with replacement, none while empty
+    pub fn sample(&self, batch:usize, rng:&mut SeededRng) -> Vec<usize> {
+        if self.is_empty() { return Vec::new() }
+        (0..batch).map(|_| rng.gen_range(0..self.len())).collect()
+    }
+}
+
+impl SumTree {
+    fn new(leaves:usize) -> Self {
+        Self { leaves, node:vec![0.0; 2 * leaves - 1] }
+    }
+
+    fn total(&self) -> f64 {
+        self.node[0]
+    }
+
+    fn get(&self, i:usize) -> f64 {
+        self.node[self.leaves - 1 + i]
+    }
+
+    fn set(&mut self, i:usize, v:f64) {
+        let mut k = self.leaves - 1 + i;
+        let change = v - self.node[k];
+        self.node[k] = v;
+        while k > 0 {
+            k = (k - 1) / 2;
+            self.node[k] += change;
+        }
+    }
+
+    //the leaf whose share of the running sum covers mass, 0 <= mass < total
+    fn find(&self, mut mass:f64) -> usize {
+        let mut k = 0;
+        while k < self.leaves - 1 {
+            let left = 2 * k + 1;
+            //a zero-sum child is never entered, rounding can leave mass just past the left sum
+            if mass < self.node[left] || self.node[left + 1] <= 0.0 { k = left } else {
+                mass -= self.node[left];
+                k = left + 1;
+            }
+        }
+        k + 1 - self.leaves
+    }
+}
+
+impl<T> Prioritized<T> {
+    pub fn new(capacity:usize, alpha:f64) -> Self {
+        Self { replay:Replay::new(capacity), alpha, tree:SumTree::new(capacity), max_priority:1.0 }
+    }
+
+    pub fn push(&mut self, item:T) -> usize {
+        let slot = self.replay.push(item);
+        self.tree.set(slot, self.max_priority.powf(self.alpha));
+        slot
+    }
+
+    pub fn len(&self) -> usize {
+        self.replay.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.replay.is_empty()
+    }
+
+    pub fn get(&self, slot:usize) -> &T {
+        self.replay.get(slot)
+    }
+
+    //usually |δ| plus a small constant, 0 keeps the slot from being drawn until updated
+    pub fn update(&mut self, slot:usize, priority:f64) {
+        self.max_priority = self.max_priority.max(priority);
+        self.tree.set(slot, priority.powf(self.alpha));
+    }
+
+    //P(slot) = p^alpha / Σ p^alpha
+    pub fn probability(&self, slot:usize) -> f64 {
+        self.tree.get(slot) / self.tree.total()
+    }
+
+    //batch (slot, weight), one draw from each of batch equal parts of the total priority.
+    //weight is the importance sampling correction (N P(slot))^-beta, scaled so the largest
+    //in the batch is 1. empty while nothing can be drawn
+    pub fn sample(&self, batch:usize, beta:f64, rng:&mut SeededRng) -> Vec<(usize, f64)> {
+        let total = self.tree.total();
+        if self.is_empty() || total <= 0.0 { return Vec::new() }
+        let part = total / batch as f64;
+        let slot:Vec<usize> = (0..batch).map(|k| self.tree.find((k as f64 + rng.gen::<f64>()) * part)).collect();
+        let n = self.len() as f64;
+        let weight:Vec<f64> = slot.iter().map(|s| (n * self.probability(*s)).powf(-beta)).collect();
+        let max = weight.iter().cloned().fold(0.0, f64::max);
+        slot.into_iter().zip(weight).map(|(s, w)| (s, w / max)).collect()
+    }
+}
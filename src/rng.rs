@@ -0,0 +1,24 @@
+use rand::prelude::*;
+
+//every stochastic run draws from a generator seeded here, so a reported seed reproduces it
+pub type SeededRng = StdRng;
+
+pub fn from_seed(seed:u64) -> SeededRng {
+    StdRng::seed_from_u64(seed)
+}
+
+pub fn random_seed() -> u64 {
+    rand::thread_rng().gen()
+}
+
+//independent stream for a sub-component, derived from the parent
+pub fn fork(rng:&mut SeededRng) -> SeededRng {
+    from_seed(rng.gen())
+}
+
+//Box-Muller
+pub fn normal(rng:&mut SeededRng, mean:f64, std:f64) -> f64 {
+    let u0:f64 = 1.0 - rng.gen::<f64>();
+    let u1:f64 = rng.gen();
+    mean + std * (-2.0 * u0.ln()).sqrt() * (2.0 * std::f64::consts::PI * u1).cos()
+}
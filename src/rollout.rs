@@ -0,0 +1,62 @@
+use crate::env::Environment;
+use crate::rng::SeededRng;
+
+//S0, A0, R1, S1, ..., state has one more entry than action and reward
+pub struct Trajectory<S, A> {
+    pub state:Vec<S>,
+    pub action:Vec<A>,
+    pub reward:Vec<f64>,
+    pub terminal:bool,//false when cut off at max_step
+}
+
+impl<S, A> Trajectory<S, A> {
+    pub fn steps(&self) -> usize {
+        self.reward.len()
+    }
+
+    //G_t for every step
+    pub fn returns(&self, gamma:f64) -> Vec<f64> {
+        let mut g = vec![0.0; self.steps()];
+        let mut acc = 0.0;
+        for (t, r) in self.reward.iter().enumerate().rev() {
+            acc = r + gamma * acc;
+            g[t] = acc;
+        }
+        g
+    }
+
+    pub fn total(&self, gamma:f64) -> f64 {
+        self.reward.iter().rev().fold(0.0, |acc, r| r + gamma * acc)
+    }
+}
+
+pub fn simulate_episode<E, P>(env:&mut E, mut policy:P, rng:&mut SeededRng, max_step:usize) -> Trajectory<E::State, E::Action>
+    where E:Environment, P:FnMut(&E::State, &mut SeededRng) -> E::Action {
+    let mut s = env.reset(rng);
+    let mut trajectory = Trajectory { state:vec!(s.clone()), action:Vec::new(), reward:Vec::new(), terminal:false };
+    while trajectory.steps() < max_step {
+        let a = policy(&s, rng);
+        let (ss, r, done) = env.step(&a, rng);
+        trajectory.action.push(a);
+        trajectory.reward.push(r);
+        trajectory.state.push(ss.clone());
+        s = ss;
+        if done {
+            trajectory.terminal = true;
+            break
+        }
+    }
+    trajectory
+}
+
+//sample mean and standard error of the return from the start state
+pub fn mc_return<E, P>(env:&mut E, mut policy:P, rng:&mut SeededRng, episode:usize, max_step:usize, gamma:f64) -> (f64, f64)
+    where E:Environment, P:FnMut(&E::State, &mut SeededRng) -> E::Action {
+    let g:Vec<f64> = (0..episode)
+        .map(|_| simulate_episode(env, &mut policy, rng, max_step).total(gamma))
+        .collect();
+    let n = g.len() as f64;
+    let mean = g.iter().sum::<f64>() / n;
+    let var = g.iter().map(|g| (g - mean).powi(2)).sum::<f64>() / (n - 1.0).max(1.0);
+    (mean, (var / n).sqrt())
+}
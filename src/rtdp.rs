@@ -0,0 +1,84 @@
+use crate::policy;
+use crate::rng::SeededRng;
+use crate::tabular::{ Model, Row };
+
+//real-time dynamic programming, section 8.7. value iteration backups only at the states a
+//greedy trajectory from a start state passes through. with v optimistic and a goal reachable
+//under every policy, the states relevant to the starts converge while the rest may never be visited.
+//the model is only asked about the states a trial and its backups touch, see tabular::Lazy
+pub struct Rtdp<M:Model> {
+    pub model:M,
+    pub gamma:f64,
+    pub v:Vec<f64>,
+    pub backups:usize,//state backups so far
+    pub visited:Vec<bool>,//backed up at least once
+    seen:Vec<bool>,//v set, to v0 or 0 for a terminal
+}
+
+//one trajectory from a start state
+pub struct Trial {
+    pub steps:usize,
+    pub residual:f64,//largest |v' - v| of its backups
+}
+
+impl<M:Model> Rtdp<M> {
+    //v0 for every nonterminal state, an upper bound of v* keeps the greedy choice exploring
+    pub fn new(model:M, gamma:f64, v0:f64) -> Self {
+        let n = model.states();
+        Self { model, gamma, v:vec![v0; n], backups:0, visited:vec![false; n], seen:vec![false; n] }
+    }
+
+    //v(s), terminals are found and set to 0 the first time they are read
+    fn value(&mut self, s:usize) -> f64 {
+        if !self.seen[s] {
+            self.seen[s] = true;
+            if self.model.rows(s).is_empty() { self.v[s] = 0.0 }
+        }
+        self.v[s]
+    }
+
+    //index of the best action among the rows of s and its backup, the first one on ties.
+    //None for terminals
+    pub fn greedy(&mut self, s:usize) -> Option<(usize, f64)> {
+        let rows = self.model.rows(s).to_vec();
+        self.best(&rows)
+    }
+
+    fn best(&mut self, rows:&[Row]) -> Option<(usize, f64)> {
+        let mut best:Option<(usize, f64)> = None;
+        for (k, row) in rows.iter().enumerate() {
+            let mut next = 0.0;
+            for (ss, p) in row.next.iter() {
+                next += p * self.value(*ss);
+            }
+            let q = row.reward + self.gamma * next;
+            if best.is_none_or(|(_, b)| q > b) { best = Some((k, q)) }
+        }
+        best
+    }
+
+    //backs up s, follows its greedy action and samples the next state, until a terminal
+    //or max_step
+    pub fn trial(&mut self, start:usize, rng:&mut SeededRng, max_step:usize) -> Trial {
+        let mut s = start;
+        let mut trial = Trial { steps:0, residual:0.0 };
+        while trial.steps < max_step {
+            let rows = self.model.rows(s).to_vec();
+            let (k, q) = match self.best(&rows) {
+                Some(v) => v,
+                None => break,
+            };
+            self.seen[s] = true;
+            trial.residual = trial.residual.max((q - self.v[s]).abs());
+            self.v[s] = q;
+            self.visited[s] = true;
+            self.backups += 1;
+            trial.steps += 1;
+            let next = &rows[k].next;
+            let p:Vec<f64> = next.iter().map(|(_, p)| *p).collect();
+            s = next[policy::sample_index(&p, rng)].0;
+        }
+        trial
+    }
+
+}
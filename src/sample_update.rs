@@ -0,0 +1,91 @@
+use std::error::Error;
+use plotters::{prelude::*, coord::Shift};
+
+use rand::prelude::*;
+
+use crate::experiment::{ Curve, Experiment };
+use crate::rng::{ self, SeededRng };
+
+struct ControlInfo {
+    pub branching:Vec<usize>,
+    pub points:usize,//evaluations along 0..=2b computations
+}
+
+//x = computations / b at evaluation k
+fn x(k:usize, points:usize) -> f64 {
+    2.0 * k as f64 / points as f64
+}
+
+//squared error of the sample update of one (s, a) over computations, relative to the initial
+//error. the b successor values are standard normal and equally likely, α = 1/t makes the
+//estimate the mean of the t samples drawn so far, so the first one replaces the initial guess
+fn sample_error(b:usize, points:usize, rng:&mut SeededRng) -> Vec<f64> {
+    let next:Vec<f64> = (0..b).map(|_| rng::normal(rng, 0.0, 1.0)).collect();
+    let truth = next.iter().sum::<f64>() / b as f64;
+    let mut sum = 0.0;
+    let mut t = 0;
+    (0..=points).map(|k| {
+        let computations = (x(k, points) * b as f64).round() as usize;
+        while t < computations {
+            sum += next[rng.gen_range(0..b)];
+            t += 1;
+        }
+        if t == 0 { 1.0 } else { (sum / t as f64 - truth).powi(2) }
+    }).collect()
+}
+
+//the expected update costs b computations and is exact once done
+fn expected_error(k:usize, points:usize) -> f64 {
+    if x(k, points) < 1.0 { 1.0 } else { 0.0 }
+}
+
+//(b - 1) / bt, the sample update's error with t = x b
+fn analytic(b:usize, x:f64) -> f64 {
+    if x == 0.0 { 1.0 } else { ((b - 1) as f64 / (b as f64 * x * b as f64)).sqrt().min(1.0) }
+}
+
+fn draw_error(branching:&[usize], points:usize, rms:&[Vec<f64>], runs:usize, canvas:&DrawingArea<BitMapBackend, Shift>) -> Result<(), Box<dyn Error>> {
+    let mut chart = ChartBuilder::on(canvas)
+        .caption(format!("expected vs sample updates, {} runs", runs), ("sans-serif", 30))
+        .margin(5)
+        .x_label_area_size(50)
+        .y_label_area_size(50)
+        .build_cartesian_2d(0.0..2.0, 0.0..1.0)?;
+    chart.configure_mesh().x_desc("number of max Q(s', a') computations / b").y_desc("rms error in value estimate").draw()?;
+    chart.draw_series(LineSeries::new((0..=points).map(|k| (x(k, points), expected_error(k, points))), BLACK.stroke_width(2)))?
+        .label("expected update")
+        .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], BLACK));
+    for (k, (b, e)) in branching.iter().zip(rms.iter()).enumerate() {
+        let color = Palette99::pick(k).to_rgba();
+        chart.draw_series(LineSeries::new((0..=points).map(|i| (x(i, points), e[i])), color))?
+            .label(format!("sample update b={}", b))
+            .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], color));
+    }
+    chart
+        .configure_series_labels()
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
+        .draw()?;
+    Ok(())
+}
+
+//figure 8.7, one (s, a) with b equally likely successors
+pub fn run(seed:u64, runs:Option<usize>) -> Result<(), Box<dyn Error>> {
+    let c_info = ControlInfo { branching:vec!(2, 10, 100, 1000, 10000), points:200 };
+    let experiment = Experiment::new(runs.unwrap_or(100), seed);
+    //the root is taken of the mean over the runs
+    let rms:Vec<Vec<f64>> = c_info.branching.iter().map(|b| {
+        let mse:Curve = experiment.repeat(|rng| sample_error(*b, c_info.points, rng));
+        mse.mean.iter().map(|v| v.sqrt()).collect()
+    }).collect();
+    let half = c_info.points / 4;
+    for (b, e) in c_info.branching.iter().zip(rms.iter()) {
+        println!("b={}: sample update error at 0.5b {:.3} (analytic {:.3}), at b {:.3} (analytic {:.3}), expected update 0 after b",
+            b, e[half], analytic(*b, 0.5), e[half * 2], analytic(*b, 1.0));
+    }
+    let canvas = BitMapBackend::new("8_7.png", (1440, 960)).into_drawing_area();
+    canvas.fill(&WHITE)?;
+    draw_error(&c_info.branching, c_info.points, &rms, experiment.runs, &canvas)?;
+    canvas.present()?;
+    Ok(())
+}
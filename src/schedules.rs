@@ -0,0 +1,68 @@
+use crate::policy::Behavior;
+
+//a value that changes with a count n = 1, 2, ..., the updates of one pair so far for a step
+//size or the episode for exploration
+pub trait Schedule {
+    fn at(&self, n:usize) -> f64;
+}
+
+//a plain number is a constant schedule, so agents still take an α of 0.5
+impl Schedule for f64 {
+    fn at(&self, _:usize) -> f64 {
+        *self
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Constant(pub f64);
+
+//1/n, the sample average of section 2.4
+#[derive(Clone, Copy, Debug)]
+pub struct Harmonic;
+
+//start · rate^(n-1), never below floor
+#[derive(Clone, Copy, Debug)]
+pub struct Exponential {
+    pub start:f64,
+    pub rate:f64,
+    pub floor:f64,
+}
+
+//c / (c + n - 1), 1 at first and going to 0 like 1/n. as ε every action is still tried
+//infinitely often while the behavior turns greedy in the limit (GLIE, section 5.4)
+#[derive(Clone, Copy, Debug)]
+pub struct Glie {
+    pub c:f64,
+}
+
+impl Schedule for Constant {
+    fn at(&self, _:usize) -> f64 {
+        self.0
+    }
+}
+
+impl Schedule for Harmonic {
+    fn at(&self, n:usize) -> f64 {
+        1.0 / n.max(1) as f64
+    }
+}
+
+impl Schedule for Exponential {
+    fn at(&self, n:usize) -> f64 {
+        (self.start * self.rate.powi(n.max(1) as i32 - 1)).max(self.floor)
+    }
+}
+
+impl Schedule for Glie {
+    fn at(&self, n:usize) -> f64 {
+        self.c / (self.c + n.max(1) as f64 - 1.0)
+    }
+}
+
+//ε-greedy with ε of episode n under a schedule, the behavior as it is without one
+pub fn behavior<E:Schedule>(behavior:Behavior, epsilon:Option<&E>, n:usize) -> Behavior {
+    match epsilon {
+        Some(e) => Behavior::EpsilonGreedy(e.at(n)),
+        None => behavior,
+    }
+}
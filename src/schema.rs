@@ -0,0 +1,97 @@
+use serde::Serialize;
+use serde_json::{ json, Value };
+
+use crate::exercise4_7::Params;
+use crate::registry;
+
+//the command line parameters an exercise reads, what --describe prints as json so sweep tools
+//and front ends can drive the binary without parsing the usage line
+#[derive(Serialize)]
+pub struct Schema {
+    pub exercise:String,
+    pub title:&'static str,
+    pub params:Vec<Param>,
+}
+
+#[derive(Serialize)]
+pub struct Param {
+    pub flag:&'static str,
+    pub kind:Kind,
+    pub default:Value,//null when unset means the exercise picks, see description
+    pub min:Option<f64>,//inclusive bounds of a number or of every list element
+    pub max:Option<f64>,
+    pub choices:Option<Vec<&'static str>>,
+    pub required:bool,
+    pub description:&'static str,
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Kind {
+    Flag,//present or not, takes no value
+    Integer,
+    Number,
+    Choice,
+    Path,
+    Text,
+    IntegerList,//comma separated
+    NumberList,
+}
+
+impl Param {
+    pub fn new(flag:&'static str, kind:Kind, default:Value, description:&'static str) -> Self {
+        Self { flag, kind, default, min:None, max:None, choices:None, required:false, description }
+    }
+
+    pub fn flag(flag:&'static str, description:&'static str) -> Self {
+        Self::new(flag, Kind::Flag, json!(false), description)
+    }
+
+    pub fn range(self, min:Option<f64>, max:Option<f64>) -> Self {
+        Self { min, max, ..self }
+    }
+
+    pub fn choices(self, choices:&[&'static str]) -> Self {
+        Self { choices:Some(choices.to_vec()), ..self }
+    }
+
+    pub fn required(self) -> Self {
+        Self { required:true, ..self }
+    }
+}
+
+//--runs, None when the exercise keeps its own
+pub fn runs(default:Option<usize>) -> Param {
+    Param::new("--runs", Kind::Integer, json!(default), "independent runs averaged, the exercise's own when unset").range(Some(1.0), None)
+}
+
+//the flags every car rental solver builds its model from, defaults from Params
+pub fn car_rental() -> Vec<Param> {
+    let p = Params::default();
+    vec!(
+        Param::new("--sweeps", Kind::Integer, json!(p.max_iter), "evaluation sweeps per improvement").range(Some(1.0), None),
+        Param::new("--span", Kind::Number, Value::Null, "span seminorm stopping with this epsilon instead of the max norm")
+            .range(Some(f64::EPSILON), None),
+        Param::flag("--exact", "evaluates until the stopping rule, ignoring the sweep limit"),
+        Param::flag("--direct", "evaluates by solving the linear system, ignoring both"),
+        Param::flag("--parallel", "sweeps and improvement on every core"),
+        Param::flag("--renormalize", "drops poisson mass above the cap instead of lumping it"),
+        Param::flag("--q", "action values, exercise 4.5"),
+        Param::new("--tie", Kind::Choice, json!("last"), "choice among equally good actions")
+            .choices(&["last", "current", "smallest", "lexicographic"]),
+        Param::new("--init", Kind::Choice, json!("zero"), "v before the first evaluation").choices(&["zero", "reward", "optimistic"]),
+        Param::flag("--cold", "every evaluation starts over from init"),
+        Param::new("--mmap", Kind::Path, Value::Null, "directory for the value and policy files"),
+    )
+}
+
+//what an exercise of the registry reads from the command line, None for an unknown id
+pub fn describe(index:&str) -> Option<Schema> {
+    let exercise = registry::find(index)?;
+    let mut params = vec!(
+        Param::new("--seed", Kind::Integer, Value::Null, "random when unset, printed first").range(Some(0.0), None),
+        Param::flag("--deterministic", "byte-identical reruns, a fixed seed when unset"),
+    );
+    params.extend((exercise.params)());
+    Some(Schema { exercise:exercise.id.to_string(), title:exercise.title, params })
+}
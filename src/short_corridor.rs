@@ -0,0 +1,266 @@
+use std::error::Error;
+use plotters::{prelude::*, coord::Shift};
+
+use crate::policy_gradient::{ ActorCritic, Baseline, Reinforce, SoftmaxPolicy };
+use crate::env::Environment;
+use crate::experiment::{ Curve, Experiment };
+use crate::rollout;
+use crate::rng::SeededRng;
+use crate::vfa::{ FeatureVec, Features, LinearVfa };
+
+type State = i32;//0..=2, 3 is terminal
+type Action = i32;//-1 left, 1 right
+
+const ACTION:[Action; 2] = [1, -1];
+
+//example 13.1, actions are reversed in state 1
+pub struct World {
+    pub state:State,
+}
+
+struct ControlInfo {
+    pub max_episode:usize,
+    pub max_step:usize,
+    pub floor:f64,//keeps a fast learning rate from collapsing into a never ending policy
+}
+
+//the same features in every state, the policy can only be stochastic
+struct ActionFeatures {}
+
+//a single constant feature, the baseline learns the average return
+struct Constant {}
+
+//tabular critic, a constant one would make every TD error -1
+struct OneHot {}
+
+impl Default for World {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl World {
+    pub fn new() -> Self {
+        Self { state:0 }
+    }
+
+    fn transition(&self, s:State, a:Action) -> (State, f64) {
+        let a = if s == 1 { -a } else { a };
+        ((s + a).max(0), -1.0)
+    }
+
+    fn is_terminal(&self, s:State) -> bool {
+        s == 3
+    }
+
+    //v(start) when every state takes right with probability p
+    fn value(&self, p:f64, theta:f64) -> f64 {
+        let mut v = [0.0; 4];
+        loop {
+            let mut delta:f64 = 0.0;
+            for s in 0..3 {
+                let v_new = ACTION.iter().zip([p, 1.0 - p]).map(|(a, prob)| {
+                    let (ss, r) = self.transition(s, *a);
+                    prob * (r + v[ss as usize])
+                }).sum::<f64>();
+                delta = delta.max((v_new - v[s as usize]).abs());
+                v[s as usize] = v_new;
+            }
+            if delta < theta { break v[0] }
+        }
+    }
+}
+
+//actions are indices into ACTION, matching the policy's action list
+impl Environment for World {
+    type State = State;
+    type Action = usize;
+
+    fn reset(&mut self, _:&mut SeededRng) -> State {
+        self.state = 0;
+        self.state
+    }
+
+    fn step(&mut self, a:&usize, _:&mut SeededRng) -> (State, f64, bool) {
+        let (ss, r) = self.transition(self.state, ACTION[*a]);
+        self.state = ss;
+        (ss, r, self.is_terminal(ss))
+    }
+
+    fn actions(&self, _:&State) -> Vec<usize> {
+        (0..ACTION.len()).collect()
+    }
+}
+
+impl Features<(State, Action)> for ActionFeatures {
+    fn dim(&self) -> usize {
+        2
+    }
+
+    fn features(&self, (_, a):&(State, Action)) -> FeatureVec {
+        vec!((if *a > 0 { 0 } else { 1 }, 1.0))
+    }
+}
+
+impl Features<State> for Constant {
+    fn dim(&self) -> usize {
+        1
+    }
+
+    fn features(&self, _:&State) -> FeatureVec {
+        vec!((0, 1.0))
+    }
+}
+
+impl Features<State> for OneHot {
+    fn dim(&self) -> usize {
+        3
+    }
+
+    fn features(&self, s:&State) -> FeatureVec {
+        vec!((*s as usize, 1.0))
+    }
+}
+
+//total reward per episode over the runs of an experiment
+fn corridor_curve(c_info:&ControlInfo, alpha:f64, baseline:Option<f64>, experiment:&Experiment) -> Curve {
+    experiment.repeat(|rng| {
+        let mut w = World::new();
+        //π(right) ≈ 0.05 initially
+        let mut policy = SoftmaxPolicy::new(ACTION.to_vec(), ActionFeatures {}, vec!(-1.47, 1.47));
+        policy.floor = c_info.floor;
+        let baseline = baseline.map(|alpha| Baseline { v:LinearVfa::new(Constant {}), alpha });
+        let mut agent = Reinforce::new(policy, alpha, 1.0, baseline);
+        (0..c_info.max_episode).map(|_| {
+            let episode = rollout::simulate_episode(&mut w, |s, rng| agent.policy.sample(s, rng), rng, c_info.max_step);
+            agent.learn(&episode);
+            episode.total(1.0)
+        }).collect()
+    })
+}
+
+fn actor_critic_curve(c_info:&ControlInfo, alpha:f64, alpha_w:f64, experiment:&Experiment) -> Curve {
+    let w = World::new();
+    experiment.repeat(|rng| {
+        let mut policy = SoftmaxPolicy::new(ACTION.to_vec(), ActionFeatures {}, vec!(-1.47, 1.47));
+        policy.floor = c_info.floor;
+        let mut agent = ActorCritic::new(policy, LinearVfa::new(OneHot {}), alpha, alpha_w, 1.0);
+        (0..c_info.max_episode).map(|_| {
+            agent.reset();
+            let mut s = 0;
+            let mut step = 0;
+            let mut g = 0.0;
+            while !w.is_terminal(s) && step < c_info.max_step {
+                let a = agent.policy.sample(&s, rng);
+                let (ss, r) = w.transition(s, agent.policy.action[a]);
+                let next = if w.is_terminal(ss) { None } else { Some(&ss) };
+                agent.step(&s, a, r, next);
+                g += r;
+                s = ss;
+                step += 1;
+            }
+            g
+        }).collect()
+    })
+}
+
+fn draw_curve(curve:&[(String, Curve)], v_opt:f64, canvas:&DrawingArea<BitMapBackend, Shift>) -> Result<(), Box<dyn Error>> {
+    let n = curve[0].1.len();
+    let mut chart = ChartBuilder::on(canvas)
+        .caption(format!("total reward per episode, {} runs", curve[0].1.runs), ("sans-serif", 30))
+        .margin(5)
+        .x_label_area_size(50)
+        .y_label_area_size(50)
+        .build_cartesian_2d(0..n, -90.0..-10.0)?;
+    chart.configure_mesh().draw()?;
+    chart.draw_series(LineSeries::new((0..n).map(|k| (k, v_opt)), &BLACK))?
+        .label(format!("v*(s0) {:.1}", v_opt))
+        .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], BLACK));
+    for (k, (label, v)) in curve.iter().enumerate() {
+        let color = Palette99::pick(k).to_rgba();
+        chart.draw_series(LineSeries::new(v.mean.iter().cloned().enumerate(), color))?
+            .label(label.as_str())
+            .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], color));
+    }
+    chart
+        .configure_series_labels()
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
+        .draw()?;
+    Ok(())
+}
+
+fn draw_sweep(p:&[f64], j:&[f64], marker:&[(&str, f64, f64)], canvas:&DrawingArea<BitMapBackend, Shift>) -> Result<(), Box<dyn Error>> {
+    let mut chart = ChartBuilder::on(canvas)
+        .caption("v(start) against π(right)", ("sans-serif", 30))
+        .margin(5)
+        .x_label_area_size(50)
+        .y_label_area_size(50)
+        .build_cartesian_2d(0.0..1.0, -100.0..0.0)?;
+    chart.configure_mesh().draw()?;
+    chart.draw_series(LineSeries::new(p.iter().cloned().zip(j.iter().cloned()), &BLUE))?;
+    for (k, (label, p, j)) in marker.iter().enumerate() {
+        let color = Palette99::pick(k + 1).to_rgba();
+        chart.draw_series(std::iter::once(Circle::new((*p, *j), 6, color.filled())))?
+            .label(format!("{} p={:.2} v={:.2}", label, p, j))
+            .legend(move |(x, y)| Circle::new((x + 10, y), 6, color.filled()));
+    }
+    chart
+        .configure_series_labels()
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
+        .draw()?;
+    Ok(())
+}
+
+//example 13.1, the best stochastic policy beats both ε-greedy ones
+pub fn run_sweep() -> Result<(), Box<dyn Error>> {
+    let w = World::new();
+    let theta = 1e-9;
+    let epsilon = 0.1;
+    //the ends diverge, J → -∞ as p → 0 or 1
+    let p:Vec<f64> = (1..100).map(|k| k as f64 * 0.01).collect();
+    let j:Vec<f64> = p.iter().map(|p| w.value(*p, theta)).collect();
+    let (p_opt, j_opt) = p.iter().cloned().zip(j.iter().cloned())
+        .fold((0.0, f64::NEG_INFINITY), |a, b| if b.1 > a.1 { b } else { a });
+    let marker = [
+        ("optimal stochastic", p_opt, j_opt),
+        ("ε-greedy right", 1.0 - epsilon / 2.0, w.value(1.0 - epsilon / 2.0, theta)),
+        ("ε-greedy left", epsilon / 2.0, w.value(epsilon / 2.0, theta)),
+    ];
+    for (label, p, j) in marker.iter() {
+        println!("{}: π(right) {:.2} v(start) {:.2}", label, p, j);
+    }
+    let canvas = BitMapBackend::new("13_1_sweep.png", (1440, 960)).into_drawing_area();
+    canvas.fill(&WHITE)?;
+    draw_sweep(&p, &j, &marker, &canvas)?;
+    canvas.present()?;
+    Ok(())
+}
+
+pub fn run(seed:u64, runs:Option<usize>) -> Result<(), Box<dyn Error>> {
+    let c_info = ControlInfo { max_episode:1000, max_step:100000, floor:0.05 };
+    let experiment = Experiment::new(runs.unwrap_or(100), seed);
+    let v_opt = (1..100).map(|k| World::new().value(k as f64 * 0.01, 1e-9)).fold(f64::NEG_INFINITY, f64::max);
+    let mut fig_1 = Vec::new();
+    for e in [12, 13, 14] {
+        let alpha = 2f64.powi(-e);
+        fig_1.push((format!("α=2^-{}", e), corridor_curve(&c_info, alpha, None, &experiment)));
+    }
+    let fig_2 = vec!(
+        ("REINFORCE α=2^-13".to_string(), corridor_curve(&c_info, 2f64.powi(-13), None, &experiment)),
+        ("with baseline α=2^-9 αw=2^-6".to_string(), corridor_curve(&c_info, 2f64.powi(-9), Some(2f64.powi(-6)), &experiment)),
+        ("actor-critic α=2^-9 αw=2^-4".to_string(), actor_critic_curve(&c_info, 2f64.powi(-9), 2f64.powi(-4), &experiment)),
+    );
+    for (label, v) in fig_1.iter().chain(fig_2.iter()) {
+        let last = v.len() - 1;
+        println!("{}: last 100 episodes {:.2}, final episode ±{:.2}", label, v.tail(100), 1.96 * v.stderr[last]);
+    }
+    let canvas = BitMapBackend::new("13_1.png", (1440, 1440)).into_drawing_area();
+    canvas.fill(&WHITE)?;
+    let canvas_split = canvas.split_evenly((2, 1));
+    draw_curve(&fig_1, v_opt, &canvas_split[0])?;
+    draw_curve(&fig_2, v_opt, &canvas_split[1])?;
+    canvas.present()?;
+    Ok(())
+}
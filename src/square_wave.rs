@@ -0,0 +1,89 @@
+use std::error::Error;
+use plotters::{prelude::*, coord::Shift};
+
+use rand::prelude::*;
+
+use crate::coarse::{ self, CoarseCoding, Rbf };
+use crate::rng::{ self, SeededRng };
+use crate::vfa::{ Features, LinearVfa };
+
+//example 9.3, one-dimensional square wave learned from uniform samples
+const LO:f64 = 0.0;
+const HI:f64 = 2.0;
+
+struct ControlInfo {
+    pub features:usize,
+    pub alpha:f64,//divided by the total activation, the number of active binary features
+    pub checkpoint:Vec<usize>,//examples seen at every plotted estimate
+    pub width:Vec<(&'static str, f64)>,//receptive field, the interval width for coarse coding
+}
+
+fn target(x:f64) -> f64 {
+    if (0.5..1.5).contains(&x) { 1.0 } else { 0.0 }
+}
+
+//the estimate over the domain after every checkpoint
+fn learn<F:Features<Vec<f64>>>(feature:F, c_info:&ControlInfo, rng:&mut SeededRng) -> Vec<Vec<(f64, f64)>> {
+    let mut v = LinearVfa::new(feature);
+    let mut seen = 0;
+    c_info.checkpoint.iter().map(|n| {
+        while seen < *n {
+            let x = vec!(rng.gen_range(LO..HI));
+            let active = v.feature.features(&x).iter().filter(|(_, f)| *f > 0.0).map(|(_, f)| f).sum::<f64>();
+            let error = target(x[0]) - v.value(&x);
+            v.update(&x, c_info.alpha / active.max(1.0), error);
+            seen += 1;
+        }
+        (0..=400).map(|k| {
+            let x = LO + (HI - LO) * k as f64 / 400.0;
+            (x, v.value(&vec!(x)))
+        }).collect()
+    }).collect()
+}
+
+fn draw_estimate(caption:&str, estimate:&[(f64, f64)], canvas:&DrawingArea<BitMapBackend, Shift>) -> Result<(), Box<dyn Error>> {
+    let mut chart = ChartBuilder::on(canvas)
+        .caption(caption, ("sans-serif", 20))
+        .margin(5)
+        .x_label_area_size(30)
+        .y_label_area_size(30)
+        .build_cartesian_2d(LO..HI, -0.5..1.5)?;
+    chart.configure_mesh().disable_mesh().draw()?;
+    chart.draw_series(LineSeries::new(estimate.iter().map(|(x, _)| (*x, target(*x))), BLACK.mix(0.3)))?;
+    chart.draw_series(LineSeries::new(estimate.iter().cloned(), &BLUE))?;
+    Ok(())
+}
+
+//figure 9.8, rows are the examples seen, columns the feature width. the last column is
+//a normalized rbf with σ at half the medium width
+pub fn run(seed:u64) -> Result<(), Box<dyn Error>> {
+    let c_info = ControlInfo {
+        features:50, alpha:0.2,
+        checkpoint:vec!(10, 40, 160, 640, 2560, 10240),
+        width:vec!(("narrow", 0.2), ("medium", 0.4), ("broad", 1.0)),
+    };
+    let center = coarse::grid(&[LO], &[HI], &[c_info.features]);
+    let mut column = Vec::new();
+    for (label, width) in c_info.width.iter() {
+        let feature = CoarseCoding::new(center.clone(), width / 2.0);
+        column.push((format!("{} features", label), learn(feature, &c_info, &mut rng::from_seed(seed))));
+    }
+    let sigma = c_info.width[1].1 / 2.0;
+    let feature = Rbf::new(center.clone(), sigma, true);
+    column.push((format!("rbf σ={}", sigma), learn(feature, &c_info, &mut rng::from_seed(seed))));
+    for (label, estimate) in column.iter() {
+        let last = &estimate[estimate.len() - 1];
+        let rms = (last.iter().map(|(x, v)| (v - target(*x)).powi(2)).sum::<f64>() / last.len() as f64).sqrt();
+        println!("{}: rms error after {} examples {:.4}", label, c_info.checkpoint[c_info.checkpoint.len() - 1], rms);
+    }
+    let canvas = BitMapBackend::new("9_8.png", (1800, 1800)).into_drawing_area();
+    canvas.fill(&WHITE)?;
+    let canvas_split = canvas.split_evenly((c_info.checkpoint.len(), column.len()));
+    for (i, n) in c_info.checkpoint.iter().enumerate() {
+        for (j, (label, estimate)) in column.iter().enumerate() {
+            draw_estimate(&format!("{}, {} examples", label, n), &estimate[i], &canvas_split[i * column.len() + j])?;
+        }
+    }
+    canvas.present()?;
+    Ok(())
+}
@@ -0,0 +1,80 @@
+use serde::Serialize;
+
+//when the values have settled enough to stop evaluating or iterating, tested on v' - v after every sweep.
+//MaxNorm stops once max |v' - v| <= theta. Span stops once
+//sp(v' - v) = max (v' - v) - min (v' - v) < ε (1 - γ) / γ, which leaves the greedy policy of
+//value iteration ε-optimal; a γ = 1 task has no such bound and ε is used as is
+#[derive(Serialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum StoppingRule {
+    MaxNorm(f64),
+    Span(f64),
+}
+
+//range of v' - v over one sweep
+#[derive(Clone, Copy, Debug)]
+pub struct Change {
+    pub lo:f64,
+    pub hi:f64,
+}
+
+impl Default for Change {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Change {
+    pub fn new() -> Self {
+        Self { lo:f64::INFINITY, hi:f64::NEG_INFINITY }
+    }
+
+    pub fn add(&mut self, d:f64) {
+        self.lo = self.lo.min(d);
+        self.hi = self.hi.max(d);
+    }
+
+    fn is_empty(&self) -> bool {
+        self.lo > self.hi
+    }
+
+    pub fn max_norm(&self) -> f64 {
+        if self.is_empty() { 0.0 } else { self.lo.abs().max(self.hi.abs()) }
+    }
+
+    pub fn span(&self) -> f64 {
+        if self.is_empty() { 0.0 } else { self.hi - self.lo }
+    }
+}
+
+impl StoppingRule {
+    pub fn tolerance(&self) -> f64 {
+        match self {
+            Self::MaxNorm(v) | Self::Span(v) => *v,
+        }
+    }
+
+    //what gets compared against the threshold
+    pub fn measure(&self, c:&Change) -> f64 {
+        match self {
+            Self::MaxNorm(_) => c.max_norm(),
+            Self::Span(_) => c.span(),
+        }
+    }
+
+    pub fn threshold(&self, gamma:f64) -> f64 {
+        match self {
+            Self::MaxNorm(theta) => *theta,
+            Self::Span(epsilon) if gamma < 1.0 => epsilon * (1.0 - gamma) / gamma,
+            Self::Span(epsilon) => *epsilon,
+        }
+    }
+
+    //the max norm keeps the <= of the original theta test, the span bound wants it strict
+    pub fn met(&self, c:&Change, gamma:f64) -> bool {
+        match self {
+            Self::MaxNorm(_) => self.measure(c) <= self.threshold(gamma),
+            Self::Span(_) => self.measure(c) < self.threshold(gamma),
+        }
+    }
+}
@@ -0,0 +1,131 @@
+use std::io;
+use std::ops::{ Deref, DerefMut };
+#[cfg(unix)]
+use std::path::Path;
+
+//elements of an array too large for RAM: a Vec, or a file mapped into memory that the os
+//pages in and out. only plain numbers can be mapped, see Plain
+pub enum Store<T> {
+    Ram(Vec<T>),
+    #[cfg(unix)]
+    Mapped(Mapped<T>),
+}
+
+//numbers for which every byte pattern, zero bytes included, is a valid value, so a file can
+//be read as them. sealed, a reference or an enum must never be mapped
+pub trait Plain:Copy + private::Sealed {}
+
+mod private {
+    pub trait Sealed {}
+}
+
+macro_rules! plain {
+    ($($t:ty),*) => {
+        $(
+            impl private::Sealed for $t {}
+            impl Plain for $t {}
+        )*
+    };
+}
+
+plain!(u8, u16, u32, u64, usize, i8, i16, i32, i64, isize, f32, f64);
+
+impl<T:Plain> Store<T> {
+    //a new file at path holding a copy of data, the file keeps the last values afterwards
+    #[cfg(unix)]
+    pub fn mapped(path:&Path, data:&[T]) -> io::Result<Self> {
+        let mut m = Mapped::create(path, data.len())?;
+        m.copy_from_slice(data);
+        Ok(Self::Mapped(m))
+    }
+
+    #[cfg(not(unix))]
+    pub fn mapped(_:&std::path::Path, _:&[T]) -> io::Result<Self> {
+        Err(io::Error::new(io::ErrorKind::Unsupported, "memory-mapped storage needs unix"))
+    }
+}
+
+impl<T> From<Vec<T>> for Store<T> {
+    fn from(v:Vec<T>) -> Self {
+        Self::Ram(v)
+    }
+}
+
+impl<T> Deref for Store<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        match self {
+            Self::Ram(v) => v,
+            #[cfg(unix)]
+            Self::Mapped(m) => m,
+        }
+    }
+}
+
+impl<T> DerefMut for Store<T> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        match self {
+            Self::Ram(v) => v,
+            #[cfg(unix)]
+            Self::Mapped(m) => m,
+        }
+    }
+}
+
+//len elements of a shared mapping, unmapped on drop
+#[cfg(unix)]
+pub struct Mapped<T> {
+    ptr:*mut T,
+    len:usize,
+}
+
+//the mapping is owned like a Box<[T]>
+#[cfg(unix)]
+unsafe impl<T:Send> Send for Mapped<T> {}
+#[cfg(unix)]
+unsafe impl<T:Sync> Sync for Mapped<T> {}
+
+#[cfg(unix)]
+impl<T:Plain> Mapped<T> {
+    //truncates or creates path, the elements start as zero bytes
+    pub fn create(path:&Path, len:usize) -> io::Result<Self> {
+        use std::os::unix::io::AsRawFd;
+        let bytes = len * std::mem::size_of::<T>();
+        let file = std::fs::OpenOptions::new().read(true).write(true).create(true).truncate(true).open(path)?;
+        file.set_len(bytes as u64)?;
+        if bytes == 0 { return Ok(Self { ptr:std::ptr::NonNull::dangling().as_ptr(), len }) }
+        //the mapping outlives the file handle
+        let ptr = unsafe {
+            libc::mmap(std::ptr::null_mut(), bytes, libc::PROT_READ | libc::PROT_WRITE, libc::MAP_SHARED, file.as_raw_fd(), 0)
+        };
+        if ptr == libc::MAP_FAILED { return Err(io::Error::last_os_error()) }
+        Ok(Self { ptr:ptr as *mut T, len })
+    }
+}
+
+#[cfg(unix)]
+impl<T> Drop for Mapped<T> {
+    fn drop(&mut self) {
+        let bytes = self.len * std::mem::size_of::<T>();
+        if bytes > 0 {
+            unsafe { libc::munmap(self.ptr as *mut libc::c_void, bytes) };
+        }
+    }
+}
+
+#[cfg(unix)]
+impl<T> Deref for Mapped<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        unsafe { std::slice::from_raw_parts(self.ptr, self.len) }
+    }
+}
+
+#[cfg(unix)]
+impl<T> DerefMut for Mapped<T> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        unsafe { std::slice::from_raw_parts_mut(self.ptr, self.len) }
+    }
+}
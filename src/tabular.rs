@@ -0,0 +1,306 @@
+use std::io;
+
+use crate::npy;
+use crate::stopping::{ Change, StoppingRule };
+
+//a finite mdp written out as one row per (state, action).
+//states without a row are terminal and worth 0
+pub struct Tabular {
+    pub states:usize,
+    pub row:Vec<Row>,
+}
+
+//gain-optimal solution of a continuing mdp, section 10.3
+pub struct AverageReward {
+    pub rate:f64,//r̄, the same from every state
+    pub value:Vec<f64>,//differential values, 0 at the reference state, up to the transformation τ
+    pub action:Vec<usize>,//index among the rows of each state
+    pub iterations:usize,
+}
+
+#[derive(Clone)]
+pub struct Row {
+    pub state:usize,
+    pub reward:f64,//expected immediate reward
+    pub next:Vec<(usize, f64)>,//(s', p(s'|s,a))
+}
+
+//what Tabular::prune took out
+pub struct Pruned {
+    pub transitions:usize,
+    pub mass:f64,//largest probability dropped from a single row
+}
+
+//an mdp read one state at a time, so a model can build a state's rows when a solver first
+//asks for them instead of enumerating all of them up front
+pub trait Model {
+    fn states(&self) -> usize;
+    //rows of the actions of s, empty for terminals
+    fn rows(&mut self, s:usize) -> &[Row];
+}
+
+//every state's rows up front, see Tabular::by_state
+impl Model for Vec<Vec<Row>> {
+    fn states(&self) -> usize {
+        self.len()
+    }
+
+    fn rows(&mut self, s:usize) -> &[Row] {
+        &self[s]
+    }
+}
+
+//rows from build(s) on first access. with cache they are kept, otherwise every access
+//builds them again and only the last ones are held
+pub struct Lazy<G> {
+    pub states:usize,
+    pub built:usize,//calls of build so far
+    build:G,
+    cache:bool,
+    row:Vec<Option<Vec<Row>>>,
+    last:Vec<Row>,
+}
+
+impl<G:FnMut(usize) -> Vec<Row>> Lazy<G> {
+    pub fn new(states:usize, cache:bool, build:G) -> Self {
+        let row = if cache { (0..states).map(|_| None).collect() } else { Vec::new() };
+        Self { states, built:0, build, cache, row, last:Vec::new() }
+    }
+
+    //states whose rows are held
+    pub fn cached(&self) -> usize {
+        self.row.iter().filter(|r| r.is_some()).count()
+    }
+}
+
+impl<G:FnMut(usize) -> Vec<Row>> Model for Lazy<G> {
+    fn states(&self) -> usize {
+        self.states
+    }
+
+    fn rows(&mut self, s:usize) -> &[Row] {
+        if !self.cache {
+            self.built += 1;
+            self.last = (self.build)(s);
+            return &self.last
+        }
+        if self.row[s].is_none() {
+            self.built += 1;
+            self.row[s] = Some((self.build)(s));
+        }
+        self.row[s].as_deref().unwrap()
+    }
+}
+
+impl Tabular {
+    pub fn new(states:usize) -> Self {
+        Self { states, row:Vec::new() }
+    }
+
+    pub fn push(&mut self, state:usize, reward:f64, next:Vec<(usize, f64)>) {
+        self.row.push(Row { state, reward, next });
+    }
+
+    //r(s,a) + γ Σ p(s'|s,a) v(s')
+    pub fn backup(&self, row:&Row, v:&[f64], gamma:f64) -> f64 {
+        row.reward + gamma * row.next.iter().map(|(s, p)| p * v[*s]).sum::<f64>()
+    }
+
+    //synchronous value iteration, every sweep backs up from the previous values only
+    pub fn value_iteration(&self, gamma:f64, stopping:StoppingRule) -> Vec<f64> {
+        self.value_iteration_sweeps(gamma, stopping).0
+    }
+
+    //same, with the number of sweeps it took
+    pub fn value_iteration_sweeps(&self, gamma:f64, stopping:StoppingRule) -> (Vec<f64>, usize) {
+        let mut v = vec![0.0; self.states];
+        let mut sweeps = 0;
+        loop {
+            sweeps += 1;
+            let mut best = vec![f64::NEG_INFINITY; self.states];
+            for r in self.row.iter() {
+                best[r.state] = best[r.state].max(self.backup(r, &v, gamma));
+            }
+            let mut change = Change::new();
+            for (v, b) in v.iter_mut().zip(best) {
+                //terminal
+                if b == f64::NEG_INFINITY { change.add(0.0); continue }
+                change.add(b - *v);
+                *v = b;
+            }
+            if stopping.met(&change, gamma) { break (v, sweeps) }
+        }
+    }
+
+    //on-policy distribution of section 9.2, η = h + γ P_π' η normalized to sum to 1. action[s]
+    //picks among the rows of s in order, start is h. the expected number of visits per episode
+    //is finite for γ < 1 or when π always terminates, stopping tests the change of η.
+    //terminal states are left out of the distribution
+    pub fn occupancy(&self, action:&[usize], start:&[f64], gamma:f64, stopping:StoppingRule) -> Vec<f64> {
+        let rows = self.rows();
+        let mut eta = start.to_vec();
+        loop {
+            let mut next = start.to_vec();
+            for (s, r) in rows.iter().enumerate() {
+                if r.is_empty() || eta[s] == 0.0 { continue }
+                for (ss, p) in self.row[r[action[s]]].next.iter() {
+                    next[*ss] += gamma * p * eta[s];
+                }
+            }
+            let mut change = Change::new();
+            for (e, n) in eta.iter().zip(next.iter()) {
+                change.add(n - e);
+            }
+            eta = next;
+            if stopping.met(&change, gamma) { break }
+        }
+        for (e, r) in eta.iter_mut().zip(rows.iter()) {
+            if r.is_empty() { *e = 0.0 }
+        }
+        let total = eta.iter().sum::<f64>();
+        eta.iter().map(|e| e / total).collect()
+    }
+
+    //one sweep of v ← v + τ (r_π + P_π v - v) for the action index of every state, returns
+    //v' - v. τ < 1 is the aperiodicity transformation, it keeps a periodic P_π from cycling
+    //without changing what v' - v tends to, τ times the reward rate of each state
+    fn relative_sweep(&self, rows:&[Vec<usize>], action:&[usize], v:&[f64], tau:f64) -> Vec<f64> {
+        rows.iter().enumerate()
+            .map(|(s, r)| {
+                assert!(!r.is_empty(), "state {} is terminal in a continuing task", s);
+                tau * (self.backup(&self.row[r[action[s]]], v, 1.0) - v[s])
+            })
+            .collect()
+    }
+
+    //reward rate of a deterministic policy from every state, action[s] as in occupancy. a
+    //policy with several recurrent classes earns a different rate in each, so this is per
+    //state. stopping tests how much the rates still move between sweeps
+    pub fn gain(&self, action:&[usize], tau:f64, stopping:StoppingRule) -> Vec<f64> {
+        let rows = self.rows();
+        let mut v = vec![0.0; self.states];
+        let mut d_old = vec![f64::INFINITY; self.states];
+        loop {
+            let d = self.relative_sweep(&rows, action, &v, tau);
+            let mut change = Change::new();
+            for ((v, d), d_old) in v.iter_mut().zip(d.iter()).zip(d_old.iter()) {
+                *v += d;
+                change.add((d - d_old) / tau);
+            }
+            if stopping.met(&change, 1.0) { break d.iter().map(|d| d / tau).collect() }
+            d_old = d;
+        }
+    }
+
+    //modified policy iteration on the average reward, section 10.3 and Puterman 8.7. evaluation
+    //is `sweeps` relative sweeps with v(reference) pinned to 0, improvement is greedy on
+    //r(s,a) + Σ p v(s') and keeps the current action on ties. it stops once the span of
+    //Tv - v is below epsilon, which needs one reward rate for the optimal policies, e.g. an
+    //mdp where every state can reach every other one under some policy
+    pub fn average_policy_iteration(&self, reference:usize, sweeps:usize, tau:f64, epsilon:f64) -> AverageReward {
+        const TIE:f64 = 1e-9;
+        let rows = self.rows();
+        let mut action = vec![0; self.states];
+        let mut v = vec![0.0; self.states];
+        let mut iterations = 0;
+        loop {
+            iterations += 1;
+            let mut change = Change::new();
+            for (s, r) in rows.iter().enumerate() {
+                assert!(!r.is_empty(), "state {} is terminal in a continuing task", s);
+                let q:Vec<f64> = r.iter().map(|k| self.backup(&self.row[*k], &v, 1.0)).collect();
+                let best = q.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+                if q[action[s]] < best - TIE * best.abs().max(1.0) {
+                    action[s] = q.iter().position(|q| *q == best).unwrap();
+                }
+                change.add(best - v[s]);
+            }
+            if change.span() < epsilon {
+                let rate = (change.lo + change.hi) / 2.0;
+                break AverageReward { rate, value:v, action, iterations }
+            }
+            for _ in 0..sweeps {
+                let d = self.relative_sweep(&rows, &action, &v, tau);
+                let pin = v[reference] + d[reference];
+                for (v, d) in v.iter_mut().zip(d.iter()) {
+                    *v += d - pin;
+                }
+            }
+        }
+    }
+
+    //drops every s' with p(s'|s,a) < epsilon, a row keeps at least its most likely s'.
+    //with renormalize the rest is scaled back up to 1, otherwise the dropped mass ends the
+    //episode with 0. either way a row moves by at most 2 * mass in L1, so for rewards within
+    //±r_max the values move by at most γ 2 mass r_max / (1 - γ)²
+    pub fn prune(&mut self, epsilon:f64, renormalize:bool) -> Pruned {
+        let mut pruned = Pruned { transitions:0, mass:0.0 };
+        for r in self.row.iter_mut() {
+            let keep = r.next.iter().map(|(_, p)| *p).fold(f64::NEG_INFINITY, f64::max).min(epsilon);
+            let before = r.next.len();
+            let total:f64 = r.next.iter().map(|(_, p)| p).sum();
+            r.next.retain(|(_, p)| *p >= keep);
+            let left:f64 = r.next.iter().map(|(_, p)| p).sum();
+            if renormalize && left > 0.0 {
+                r.next.iter_mut().for_each(|(_, p)| *p *= total / left);
+            }
+            pruned.transitions += before - r.next.len();
+            pruned.mass = pruned.mass.max(total - left);
+        }
+        pruned
+    }
+
+    //copies of the rows grouped by state, the eager Model
+    pub fn by_state(&self) -> Vec<Vec<Row>> {
+        let mut rows = vec![Vec::new(); self.states];
+        for r in self.row.iter() {
+            rows[r.state].push(r.clone());
+        }
+        rows
+    }
+
+    //indices into row of every state's actions, empty for terminals
+    pub fn rows(&self) -> Vec<Vec<usize>> {
+        let mut rows = vec![Vec::new(); self.states];
+        for (k, r) in self.row.iter().enumerate() {
+            rows[r.state].push(k);
+        }
+        rows
+    }
+
+    //dense P as [action][state][next] and R as [state][action], the layout of python's
+    //mdptoolbox. a state's rows are its actions in order, missing ones and terminal
+    //states stay put with reward 0 so every P[a] is stochastic
+    pub fn dense(&self) -> (usize, Vec<f64>, Vec<f64>) {
+        let n = self.states;
+        let mut count = vec![0; n];
+        for r in self.row.iter() {
+            count[r.state] += 1;
+        }
+        let actions = count.iter().copied().max().unwrap_or(0).max(1);
+        let mut p = vec![0.0; actions * n * n];
+        let mut reward = vec![0.0; n * actions];
+        let mut filled = vec![0; n];
+        for r in self.row.iter() {
+            let a = filled[r.state];
+            filled[r.state] += 1;
+            for (s, prob) in r.next.iter() {
+                p[(a * n + r.state) * n + s] += prob;
+            }
+            reward[r.state * actions + a] = r.reward;
+        }
+        for (s, c) in filled.iter().enumerate() {
+            for a in *c..actions {
+                p[(a * n + s) * n + s] = 1.0;
+            }
+        }
+        (actions, p, reward)
+    }
+
+    //{prefix}_p.npy and {prefix}_r.npy, see dense
+    pub fn write_npy(&self, prefix:&str) -> io::Result<()> {
+        let (actions, p, reward) = self.dense();
+        npy::write(&format!("{}_p.npy", prefix), &[actions, self.states, self.states], &p)?;
+        npy::write(&format!("{}_r.npy", prefix), &[self.states, actions], &reward)
+    }
+}
@@ -0,0 +1,117 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::env::Environment;
+use crate::policy::Behavior;
+use crate::rng::SeededRng;
+use crate::schedules::{ self, Schedule };
+
+//one-step TD control targets, chapter 6.4 - 6.6
+#[derive(Clone, Copy)]
+pub enum Method {
+    Sarsa,
+    QLearning,
+    ExpectedSarsa,
+}
+
+//tabular action values over env.actions(s), in the order the environment lists them
+//α and ε take any Schedule, a number for a constant one
+pub struct TdControl<S, A = f64, X = f64> {
+    pub method:Method,
+    pub behavior:Behavior,
+    pub alpha:A,//by the updates of (s, a) so far
+    pub epsilon:Option<X>,//by episode, replaces behavior with ε-greedy when set
+    pub gamma:f64,
+    pub q:HashMap<S, Vec<f64>>,
+    pub initial:f64,//of every unseen (s, a), optimistic above every return
+    pub visits:HashMap<S, usize>,//steps taken from each state over all episodes
+    pub updates:HashMap<S, Vec<usize>>,//of every (s, a), what alpha counts
+    pub episodes:usize,//learned so far, what epsilon counts
+}
+
+impl Method {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Method::Sarsa => "SARSA",
+            Method::QLearning => "Q-learning",
+            Method::ExpectedSarsa => "Expected SARSA",
+        }
+    }
+
+    //bootstrap from the values of the next state's actions, aa is the one drawn from behavior
+    pub fn target(&self, behavior:Behavior, q:&[f64], aa:usize) -> f64 {
+        match self {
+            Method::Sarsa => q[aa],
+            Method::QLearning => q.iter().cloned().fold(f64::NEG_INFINITY, f64::max),
+            Method::ExpectedSarsa => {
+                let p = behavior.prob_q(q, &vec![true; q.len()]);
+                p.iter().zip(q.iter()).map(|(p, q)| p * q).sum::<f64>()
+            },
+        }
+    }
+}
+
+impl<S:Clone + Eq + Hash> TdControl<S> {
+    pub fn new(method:Method, behavior:Behavior, alpha:f64, gamma:f64) -> Self {
+        Self { method, behavior, alpha, epsilon:None, gamma, q:HashMap::new(), initial:0.0, visits:HashMap::new(), updates:HashMap::new(), episodes:0 }
+    }
+}
+
+impl<S:Clone + Eq + Hash, A:Schedule, X:Schedule> TdControl<S, A, X> {
+    pub fn with_alpha<B:Schedule>(self, alpha:B) -> TdControl<S, B, X> {
+        let Self { method, behavior, epsilon, gamma, q, initial, visits, updates, episodes, .. } = self;
+        TdControl { method, behavior, alpha, epsilon, gamma, q, initial, visits, updates, episodes }
+    }
+
+    pub fn with_epsilon<B:Schedule>(self, epsilon:B) -> TdControl<S, A, B> {
+        let Self { method, behavior, alpha, gamma, q, initial, visits, updates, episodes, .. } = self;
+        TdControl { method, behavior, alpha, epsilon:Some(epsilon), gamma, q, initial, visits, updates, episodes }
+    }
+
+    //α for the next update of (s, a)
+    fn step_size(&mut self, s:&S, a:usize) -> f64 {
+        let n = self.q[s].len();
+        let count = &mut self.updates.entry(s.clone()).or_insert_with(|| vec![0; n])[a];
+        *count += 1;
+        self.alpha.at(*count)
+    }
+
+    //unseen states start at initial for every action
+    fn entry<E:Environment<State = S>>(&mut self, s:&S, env:&E) -> &mut Vec<f64> {
+        let initial = self.initial;
+        self.q.entry(s.clone()).or_insert_with(|| vec![initial; env.actions(s).len()])
+    }
+
+    pub fn select<E:Environment<State = S>>(&mut self, s:&S, env:&E, rng:&mut SeededRng) -> usize {
+        let behavior = self.behavior;
+        let q = self.entry(s, env);
+        behavior.sample(q, &vec![true; q.len()], rng)
+    }
+
+    //learns along one episode, returns the sum of rewards and the steps taken
+    pub fn episode<E:Environment<State = S>>(&mut self, env:&mut E, rng:&mut SeededRng, max_step:usize) -> (f64, usize) {
+        self.episodes += 1;
+        self.behavior = schedules::behavior(self.behavior, self.epsilon.as_ref(), self.episodes);
+        let mut s = env.reset(rng);
+        let mut a = self.select(&s, env, rng);
+        let mut g = 0.0;
+        let mut step = 0;
+        while step < max_step {
+            step += 1;
+            *self.visits.entry(s.clone()).or_insert(0) += 1;
+            let action = env.actions(&s)[a].clone();
+            let (ss, r, done) = env.step(&action, rng);
+            g += r;
+            //the next action is drawn before the update for every method, so they share the behavior
+            let aa = if done { 0 } else { self.select(&ss, env, rng) };
+            let q_next = if done { 0.0 } else { self.method.target(self.behavior, &self.q[&ss], aa) };
+            let (alpha, gamma) = (self.step_size(&s, a), self.gamma);
+            let q = &mut self.entry(&s, env)[a];
+            *q += alpha * (r + gamma * q_next - *q);
+            if done { break }
+            s = ss;
+            a = aa;
+        }
+        (g, step)
+    }
+}
@@ -0,0 +1,259 @@
+use std::error::Error;
+use plotters::{prelude::*, coord::Shift};
+
+use rand::prelude::*;
+
+use crate::afterstate::{ Afterstate, AfterstateControl };
+use crate::env::Environment;
+use crate::experiment::{ Curve, Experiment };
+use crate::policy::Behavior;
+use crate::rollout;
+use crate::rng::SeededRng;
+use crate::schedules::{ self, Schedule };
+use crate::td::Method;
+
+//cells row by row, 0 empty, 1 the player to move, 2 the other one. boards are always seen
+//by the player to move, so one table of afterstate values serves both sides
+type Board = [u8; 9];
+
+const LINE:[[usize; 3]; 8] = [
+    [0, 1, 2], [3, 4, 5], [6, 7, 8],
+    [0, 3, 6], [1, 4, 7], [2, 5, 8],
+    [0, 4, 8], [2, 4, 6],
+];
+
+//the fixed side the agent is measured against
+#[derive(Clone, Copy)]
+pub enum Opponent {
+    Random,
+    Blocker,//wins when it can, blocks an immediate loss, random otherwise
+}
+
+//the agent plays 1 against a fixed opponent, +1 for a win, -1 for a loss, 0 for a draw
+pub struct Game {
+    pub opponent:Opponent,
+    pub first:bool,//agent moves first
+    pub board:Board,
+}
+
+struct ControlInfo {
+    pub max_episode:usize,
+    pub interval:usize,//self-play episodes between evaluations
+    pub games:usize,//per evaluation, half of them moving first
+    pub epsilon:f64,
+    pub alpha:f64,
+}
+
+fn wins(board:&Board, player:u8) -> bool {
+    LINE.iter().any(|l| l.iter().all(|k| board[*k] == player))
+}
+
+fn empty(board:&Board) -> Vec<usize> {
+    (0..9).filter(|k| board[*k] == 0).collect()
+}
+
+//the same position seen by the other player
+fn swap(board:&Board) -> Board {
+    board.map(|v| match v { 1 => 2, 2 => 1, _ => 0 })
+}
+
+impl Opponent {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Random => "random",
+            Self::Blocker => "blocker",
+        }
+    }
+
+    //a cell for player 2
+    fn play(&self, board:&Board, rng:&mut SeededRng) -> usize {
+        let cell = empty(board);
+        if let Opponent::Blocker = self {
+            for player in [2, 1] {
+                let k = cell.iter().find(|k| {
+                    let mut b = *board;
+                    b[**k] = player;
+                    wins(&b, player)
+                });
+                if let Some(k) = k { return *k }
+            }
+        }
+        *cell.choose(rng).unwrap()
+    }
+}
+
+impl Game {
+    pub fn new(opponent:Opponent) -> Self {
+        Self { opponent, first:true, board:[0; 9] }
+    }
+
+    //the opponent's move and its outcome
+    fn reply(&mut self, rng:&mut SeededRng) -> (Board, f64, bool) {
+        let k = self.opponent.play(&self.board, rng);
+        self.board[k] = 2;
+        if wins(&self.board, 2) { return (self.board, -1.0, true) }
+        (self.board, 0.0, empty(&self.board).is_empty())
+    }
+}
+
+impl Environment for Game {
+    type State = Board;
+    type Action = usize;
+
+    fn reset(&mut self, rng:&mut SeededRng) -> Board {
+        self.board = [0; 9];
+        if !self.first { self.reply(rng); }
+        self.board
+    }
+
+    fn step(&mut self, a:&usize, rng:&mut SeededRng) -> (Board, f64, bool) {
+        self.board[*a] = 1;
+        if wins(&self.board, 1) { return (self.board, 1.0, true) }
+        if empty(&self.board).is_empty() { return (self.board, 0.0, true) }
+        self.reply(rng)
+    }
+
+    fn actions(&self, s:&Board) -> Vec<usize> {
+        empty(s)
+    }
+}
+
+//the board right after the mover's mark, before the reply
+impl Afterstate for Game {
+    type After = Board;
+
+    fn afterstate(&self, s:&Board, a:&usize) -> Board {
+        let mut b = *s;
+        b[*a] = 1;
+        b
+    }
+}
+
+//one game against itself. each side backs its previous afterstate up toward its next one,
+//and the final afterstates of both toward the outcome for their side
+pub fn self_play<A:Schedule, X:Schedule>(agent:&mut AfterstateControl<Board, A, X>, game:&Game, rng:&mut SeededRng) {
+    agent.episodes += 1;
+    agent.behavior = schedules::behavior(agent.behavior, agent.epsilon.as_ref(), agent.episodes);
+    let mut board = [0; 9];
+    let mut last:[Option<Board>; 2] = [None, None];
+    let mut player = 0;
+    loop {
+        let (_, after) = agent.select(&board, game, rng);
+        let value = agent.v.get(&after).copied().unwrap_or(0.0);
+        let outcome = if wins(&after, 1) { Some((1.0, -1.0)) }
+            else if empty(&after).is_empty() { Some((0.0, 0.0)) }
+            else { None };
+        let target = match outcome { Some((mine, _)) => mine, None => agent.gamma * value };
+        if let Some(prev) = last[player] {
+            let alpha = agent.step_size(&prev);
+            let v = agent.v.entry(prev).or_insert(0.0);
+            *v += alpha * (target - *v);
+        }
+        if let Some((mine, theirs)) = outcome {
+            let alpha = agent.step_size(&after);
+            let v = agent.v.entry(after).or_insert(0.0);
+            *v += alpha * (mine - *v);
+            if let Some(prev) = last[1 - player] {
+                let alpha = agent.step_size(&prev);
+                let v = agent.v.entry(prev).or_insert(0.0);
+                *v += alpha * (theirs - *v);
+            }
+            break
+        }
+        last[player] = Some(after);
+        board = swap(&after);
+        player = 1 - player;
+    }
+}
+
+//win and loss rate of the greedy agent, moving first in half of the games
+pub fn evaluate<A:Schedule, X:Schedule>(agent:&AfterstateControl<Board, A, X>, opponent:Opponent, games:usize, rng:&mut SeededRng) -> (f64, f64) {
+    let mut game = Game::new(opponent);
+    let rules = Game::new(opponent);
+    let (mut win, mut loss) = (0, 0);
+    for k in 0..games {
+        game.first = k % 2 == 0;
+        let trajectory = rollout::simulate_episode(&mut game, |s, _| empty(s)[agent.greedy(s, &rules)], rng, 9);
+        match trajectory.total(1.0) {
+            g if g > 0.0 => win += 1,
+            g if g < 0.0 => loss += 1,
+            _ => {},
+        }
+    }
+    (win as f64 / games as f64, loss as f64 / games as f64)
+}
+
+//v of the afterstate of every opening move, as the board
+fn print_opening(agent:&AfterstateControl<Board>) {
+    println!("value of the opening moves:");
+    for row in 0..3 {
+        let v:Vec<String> = (0..3).map(|col| {
+            let mut b = [0; 9];
+            b[row * 3 + col] = 1;
+            format!("{:+.3}", agent.v.get(&b).copied().unwrap_or(0.0))
+        }).collect();
+        println!("\t{}", v.join(" "));
+    }
+}
+
+fn draw_rate(episode:&[usize], curve:&[(String, Curve)], canvas:&DrawingArea<BitMapBackend, Shift>) -> Result<(), Box<dyn Error>> {
+    let mut chart = ChartBuilder::on(canvas)
+        .caption(format!("greedy agent, {} runs", curve[0].1.runs), ("sans-serif", 30))
+        .margin(5)
+        .x_label_area_size(50)
+        .y_label_area_size(50)
+        .build_cartesian_2d(0..episode[episode.len() - 1], 0.0..1.0)?;
+    chart.configure_mesh().x_desc("self-play episodes").y_desc("rate").draw()?;
+    for (k, (label, c)) in curve.iter().enumerate() {
+        let color = Palette99::pick(k).to_rgba();
+        chart.draw_series(LineSeries::new(episode.iter().cloned().zip(c.mean.iter().cloned()), color))?
+            .label(label.as_str())
+            .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], color));
+    }
+    chart
+        .configure_series_labels()
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
+        .draw()?;
+    Ok(())
+}
+
+//chapter 1, TD(0) self-play on afterstates with Ɛ-greedy moves, measured against fixed opponents
+pub fn run(seed:u64, runs:Option<usize>) -> Result<(), Box<dyn Error>> {
+    let c_info = ControlInfo { max_episode:20000, interval:500, games:200, epsilon:0.1, alpha:0.2 };
+    let experiment = Experiment::new(runs.unwrap_or(10), seed);
+    let opponent = [Opponent::Random, Opponent::Blocker];
+    let episode:Vec<usize> = (0..=c_info.max_episode).step_by(c_info.interval).collect();
+    let mut last = None;
+    let series = experiment.repeat_series(opponent.len() * 2, |rng| {
+        let game = Game::new(Opponent::Random);
+        let mut agent = AfterstateControl::new(Method::Sarsa, Behavior::EpsilonGreedy(c_info.epsilon), c_info.alpha, 1.0);
+        let mut rate = vec![Vec::new(); opponent.len() * 2];
+        for k in 0..=c_info.max_episode {
+            if k % c_info.interval == 0 {
+                for (i, o) in opponent.iter().enumerate() {
+                    let (win, loss) = evaluate(&agent, *o, c_info.games, rng);
+                    rate[i * 2].push(win);
+                    rate[i * 2 + 1].push(loss);
+                }
+            }
+            if k < c_info.max_episode { self_play(&mut agent, &game, rng); }
+        }
+        last = Some(agent);
+        rate
+    });
+    if let Some(agent) = last {
+        print_opening(&agent);
+        println!("afterstates valued: {}", agent.v.len());
+    }
+    for (o, c) in opponent.iter().zip(series.chunks(2)) {
+        println!("against {}: final win rate {:.3}, loss rate {:.3}", o.label(), c[0].mean[c[0].len() - 1], c[1].mean[c[1].len() - 1]);
+    }
+    let label = opponent.iter().flat_map(|o| [format!("win vs {}", o.label()), format!("loss vs {}", o.label())]);
+    let curve:Vec<(String, Curve)> = label.zip(series).collect();
+    let canvas = BitMapBackend::new("tic_tac_toe.png", (1440, 960)).into_drawing_area();
+    canvas.fill(&WHITE)?;
+    draw_rate(&episode, &curve, &canvas)?;
+    canvas.present()?;
+    Ok(())
+}
@@ -0,0 +1,93 @@
+use std::cell::{ Cell, RefCell };
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{ Hash, Hasher };
+
+//tile coding after Sutton's tiles3:
+//http://incompleteideas.net/tiles/tiles3.html
+
+//maps tile coordinates to feature indices, first come first served
+//indices are allocated behind a shared reference so lookups stay usable as a feature extractor
+pub struct IndexHashTable {
+    pub size:usize,
+    overflow:Cell<usize>,//coordinates hashed into a full table
+    map:RefCell<HashMap<Vec<i64>, usize>>,
+}
+
+pub struct TileCoder {
+    pub tilings:usize,
+    pub width:Vec<f64>,//tile width per dimension
+    pub wrap:Vec<Option<i64>>,//wrap-around after this many tiles per dimension
+    pub iht:IndexHashTable,
+}
+
+impl IndexHashTable {
+    pub fn new(size:usize) -> Self {
+        Self { size, overflow:Cell::new(0), map:RefCell::new(HashMap::new()) }
+    }
+
+    pub fn count(&self) -> usize {
+        self.map.borrow().len()
+    }
+
+    pub fn overflow(&self) -> usize {
+        self.overflow.get()
+    }
+
+    fn hash(&self, coord:&[i64]) -> usize {
+        let mut h = DefaultHasher::new();
+        coord.hash(&mut h);
+        (h.finish() % self.size as u64) as usize
+    }
+
+    fn index(&self, coord:Vec<i64>) -> usize {
+        let mut map = self.map.borrow_mut();
+        if let Some(v) = map.get(&coord) { return *v }
+        let c = map.len();
+        if c >= self.size {
+            self.overflow.set(self.overflow.get() + 1);
+            return self.hash(&coord)
+        }
+        map.insert(coord, c);
+        c
+    }
+}
+
+impl TileCoder {
+    pub fn new(size:usize, tilings:usize, width:Vec<f64>, wrap:Vec<Option<i64>>) -> Self {
+        assert_eq!(wrap.len(), width.len());
+        Self { tilings, width, wrap, iht:IndexHashTable::new(size) }
+    }
+
+    pub fn size(&self) -> usize {
+        self.iht.size
+    }
+
+    fn coord(&self, x:&[f64], ints:&[i64]) -> Vec<Vec<i64>> {
+        assert_eq!(x.len(), self.width.len());
+        let n = self.tilings as i64;
+        //tilings are offset by 1/n of a tile, displaced (1, 3, 5, ...) per dimension
+        let q:Vec<i64> = x.iter().zip(self.width.iter())
+            .map(|(v, w)| (v / w * n as f64).floor() as i64)
+            .collect();
+        (0..n).map(|t| {
+            let mut coord = vec![t];
+            let mut b = t;
+            for (q, wrap) in q.iter().zip(self.wrap.iter()) {
+                let c = (q + b % n).div_euclid(n);
+                coord.push(match wrap {
+                    Some(w) => c.rem_euclid(*w),
+                    None => c,
+                });
+                b += t * 2;
+            }
+            coord.extend_from_slice(ints);
+            coord
+        }).collect()
+    }
+
+    //one active feature per tiling, allocating indices for unseen tiles
+    pub fn active(&self, x:&[f64], ints:&[i64]) -> Vec<usize> {
+        self.coord(x, ints).into_iter().map(|c| self.iht.index(c)).collect()
+    }
+}
@@ -0,0 +1,130 @@
+use std::error::Error;
+use std::time::Duration;
+use ratatui::{
+    DefaultTerminal, Frame,
+    crossterm::event::{ self, Event, KeyCode, KeyEventKind },
+    layout::{ Constraint, Layout },
+    style::{ Color, Style },
+    symbols::Marker,
+    text::{ Line, Span },
+    widgets::{ Axis, Block, Chart, Dataset, GraphType, Paragraph },
+};
+
+use crate::ansi;
+use crate::exercise4_7::{ Params, Solver };
+
+//live view of exercise 4.7, one evaluation sweep per tick
+struct Dashboard {
+    pub solver:Solver,
+    pub move_limit:i32,
+    pub delta:Vec<(f64, f64)>,//(sweep, log10 delta) over the whole run
+    pub changed:Option<usize>,//states changed by the last improvement
+    pub stable:bool,
+    pub paused:bool,
+}
+
+const TICK:Duration = Duration::from_millis(20);
+
+impl Dashboard {
+    fn new(params:&Params) -> Self {
+        Self {
+            solver:Solver::new(params),
+            move_limit:params.move_limit,
+            delta:Vec::new(), changed:None,
+            stable:false, paused:false,
+        }
+    }
+
+    fn tick(&mut self) {
+        if self.paused || self.stable { return }
+        let (delta, done) = self.solver.sweep();
+        self.delta.push((self.delta.len() as f64, delta.max(1e-12).log10()));
+        if done {
+            let changed = self.solver.improve();
+            self.changed = Some(changed);
+            self.stable = changed == 0;
+        }
+    }
+
+    //same gradients as the printed grids, rows bottom up like figure 4.2
+    fn value_lines(&self) -> Vec<Line<'static>> {
+        let value = self.solver.value();
+        let v_min = value.iter().flatten().cloned().fold(f64::INFINITY, f64::min);
+        let v_max = value.iter().flatten().cloned().fold(f64::NEG_INFINITY, f64::max);
+        value.iter().rev().map(|row| {
+            Line::from(row.iter().map(|v| {
+                let (r, g, b) = ansi::sequential(ansi::scale(*v, v_min, v_max));
+                Span::styled("  ", Style::default().bg(Color::Rgb(r, g, b)))
+            }).collect::<Vec<Span>>())
+        }).collect()
+    }
+
+    //cars moved from the first location to the second are positive
+    fn policy_lines(&self) -> Vec<Line<'static>> {
+        let limit = self.move_limit.max(1) as f64;
+        self.solver.policy().iter().rev().map(|row| {
+            Line::from(row.iter().map(|a| {
+                let (r, g, b) = ansi::diverging(*a as f64 / limit);
+                Span::styled(format!("{:+2} ", a), Style::default().fg(Color::Black).bg(Color::Rgb(r, g, b)))
+            }).collect::<Vec<Span>>())
+        }).collect()
+    }
+
+    fn draw(&self, frame:&mut Frame) {
+        let [grid, chart, status] = Layout::vertical([
+            Constraint::Length(self.solver.value().len() as u16 + 2),
+            Constraint::Min(8),
+            Constraint::Length(1),
+        ]).areas(frame.area());
+        let [value, policy] = Layout::horizontal([Constraint::Length(44), Constraint::Min(66)]).areas(grid);
+        frame.render_widget(Paragraph::new(self.value_lines()).block(Block::bordered().title("value")), value);
+        frame.render_widget(Paragraph::new(self.policy_lines()).block(Block::bordered().title("policy")), policy);
+        let x_max = (self.delta.len() as f64).max(1.0);
+        let y_min = self.delta.iter().map(|(_, y)| *y).fold(0.0, f64::min).floor();
+        let y_max = self.delta.iter().map(|(_, y)| *y).fold(0.0, f64::max).ceil();
+        let dataset = Dataset::default()
+            .marker(Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(Style::default().fg(Color::Cyan))
+            .data(&self.delta);
+        let delta_chart = Chart::new(vec!(dataset))
+            .block(Block::bordered().title("log10 delta per sweep"))
+            .x_axis(Axis::default().bounds([0.0, x_max]).labels(["0".to_string(), format!("{}", x_max)]))
+            .y_axis(Axis::default().bounds([y_min, y_max]).labels([format!("{}", y_min), format!("{}", y_max)]));
+        frame.render_widget(delta_chart, chart);
+        let state = if self.stable { "stable" } else if self.paused { "paused" } else { "running" };
+        let changed = self.changed.map_or("-".to_string(), |c| c.to_string());
+        let delta = self.delta.last().map_or("-".to_string(), |(_, d)| format!("{:.4}", 10f64.powf(*d)));
+        let stop = self.solver.stop.map_or("-", |s| s.label());
+        frame.render_widget(Line::from(format!(
+            " iteration {} sweep {} delta {} changed {} last stop {} | {} | q quit, space pause",
+            self.solver.iterations, self.solver.sweeps, delta, changed, stop, state)), status);
+    }
+}
+
+fn run_loop(terminal:&mut DefaultTerminal, params:&Params) -> Result<(), Box<dyn Error>> {
+    let mut dashboard = Dashboard::new(params);
+    loop {
+        dashboard.tick();
+        terminal.draw(|frame| dashboard.draw(frame))?;
+        if event::poll(TICK)? {
+            if let Event::Key(key) = event::read()? {
+                if key.kind != KeyEventKind::Press { continue }
+                match key.code {
+                    KeyCode::Char('q') | KeyCode::Esc => break,
+                    KeyCode::Char(' ') => dashboard.paused = !dashboard.paused,
+                    _ => {},
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+//restores the terminal before returning, also on error
+pub fn policy_iteration(params:&Params) -> Result<(), Box<dyn Error>> {
+    let mut terminal = ratatui::init();
+    let result = run_loop(&mut terminal, params);
+    ratatui::restore();
+    result
+}
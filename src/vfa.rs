@@ -0,0 +1,207 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::env::Environment;
+use crate::policy::Behavior;
+use crate::rng::SeededRng;
+use crate::schedules::Schedule;
+
+//value function approximation, chapter 9. agents written against ValueFunction or QFunction
+//work with a table, a linear approximator or a network alike
+
+//sparse feature vector: (index, value), indices need not be sorted
+pub type FeatureVec = Vec<(usize, f64)>;
+
+pub trait Features<S> {
+    fn dim(&self) -> usize;
+    fn features(&self, s:&S) -> FeatureVec;
+    //multiplies the step size of feature k, for bases whose features vary at different rates
+    fn step_scale(&self, _k:usize) -> f64 {
+        1.0
+    }
+}
+
+//v(s, w) and the semi-gradient step w += step * error * ∇v(s, w)
+pub trait ValueFunction<S> {
+    fn predict(&self, s:&S) -> f64;
+    fn gradient_step(&mut self, s:&S, step:f64, error:f64);
+}
+
+//q(s, a, w) and its semi-gradient step
+pub trait QFunction<S, A> {
+    fn predict(&self, s:&S, a:&A) -> f64;
+    fn gradient_step(&mut self, s:&S, a:&A, step:f64, error:f64);
+}
+
+pub struct LinearVfa<F> {
+    pub w:Vec<f64>,
+    pub feature:F,
+}
+
+//one value per key, 0 until stepped. the gradient is 1 at the key, so a step is the tabular update
+pub struct Table<K> {
+    pub v:HashMap<K, f64>,
+}
+
+//episodic semi-gradient SARSA of section 10.1, ε-greedy over env.actions with ties to the first.
+//α is counted by updates and ε by episodes, a number for a constant one
+pub struct Sarsa<Q, A = f64, X = f64> {
+    pub q:Q,
+    pub epsilon:X,
+    pub alpha:A,
+    pub gamma:f64,
+    pub updates:usize,
+    pub episodes:usize,
+}
+
+impl<F> LinearVfa<F> {
+    pub fn new<S>(feature:F) -> Self
+        where F:Features<S> {
+        let w = vec![0.0; feature.dim()];
+        Self { w, feature }
+    }
+
+    pub fn value<S>(&self, s:&S) -> f64
+        where F:Features<S> {
+        self.feature.features(s).iter().map(|(k, x)| self.w[*k] * x).sum::<f64>()
+    }
+
+    //w += step * error * ∇v(s), the gradient of a linear v is its feature vector.
+    //each component scaled by the feature's step_scale
+    pub fn update<S>(&mut self, s:&S, step:f64, error:f64)
+        where F:Features<S> {
+        for (k, x) in self.feature.features(s) {
+            self.w[k] += self.feature.step_scale(k) * step * error * x;
+        }
+    }
+}
+
+impl<S, F:Features<S>> ValueFunction<S> for LinearVfa<F> {
+    fn predict(&self, s:&S) -> f64 {
+        self.value(s)
+    }
+
+    fn gradient_step(&mut self, s:&S, step:f64, error:f64) {
+        self.update(s, step, error)
+    }
+}
+
+impl<S:Clone, A:Clone, F:Features<(S, A)>> QFunction<S, A> for LinearVfa<F> {
+    fn predict(&self, s:&S, a:&A) -> f64 {
+        self.value(&(s.clone(), a.clone()))
+    }
+
+    fn gradient_step(&mut self, s:&S, a:&A, step:f64, error:f64) {
+        self.update(&(s.clone(), a.clone()), step, error)
+    }
+}
+
+impl<K> Default for Table<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K> Table<K> {
+    pub fn new() -> Self {
+        Self { v:HashMap::new() }
+    }
+}
+
+impl<K:Clone + Eq + Hash> ValueFunction<K> for Table<K> {
+    fn predict(&self, s:&K) -> f64 {
+        self.v.get(s).copied().unwrap_or(0.0)
+    }
+
+    fn gradient_step(&mut self, s:&K, step:f64, error:f64) {
+        *self.v.entry(s.clone()).or_insert(0.0) += step * error;
+    }
+}
+
+impl<S:Clone + Eq + Hash, A:Clone + Eq + Hash> QFunction<S, A> for Table<(S, A)> {
+    fn predict(&self, s:&S, a:&A) -> f64 {
+        ValueFunction::predict(self, &(s.clone(), a.clone()))
+    }
+
+    fn gradient_step(&mut self, s:&S, a:&A, step:f64, error:f64) {
+        ValueFunction::gradient_step(self, &(s.clone(), a.clone()), step, error)
+    }
+}
+
+impl<Q> Sarsa<Q> {
+    pub fn new(q:Q, epsilon:f64, alpha:f64, gamma:f64) -> Self {
+        Self { q, epsilon, alpha, gamma, updates:0, episodes:0 }
+    }
+}
+
+impl<Q, A:Schedule, X:Schedule> Sarsa<Q, A, X> {
+    pub fn with_alpha<B:Schedule>(self, alpha:B) -> Sarsa<Q, B, X> {
+        let Self { q, epsilon, gamma, updates, episodes, .. } = self;
+        Sarsa { q, epsilon, alpha, gamma, updates, episodes }
+    }
+
+    pub fn with_epsilon<B:Schedule>(self, epsilon:B) -> Sarsa<Q, A, B> {
+        let Self { q, alpha, gamma, updates, episodes, .. } = self;
+        Sarsa { q, epsilon, alpha, gamma, updates, episodes }
+    }
+
+    //α for the next update
+    fn step_size(&mut self) -> f64 {
+        self.updates += 1;
+        self.alpha.at(self.updates)
+    }
+
+    pub fn select<E:Environment>(&self, s:&E::State, env:&E, rng:&mut SeededRng) -> E::Action
+        where Q:QFunction<E::State, E::Action> {
+        let action = env.actions(s);
+        let q:Vec<f64> = action.iter().map(|a| self.q.predict(s, a)).collect();
+        let k = Behavior::EpsilonGreedy(self.epsilon.at(self.episodes)).sample(&q, &vec![true; q.len()], rng);
+        action[k].clone()
+    }
+
+    //return and steps. only a terminal bootstraps from nothing, the step cap keeps the (S', A')
+    //it cut at and bootstraps from q(S', A') like mountain car does
+    pub fn episode<E:Environment>(&mut self, env:&mut E, rng:&mut SeededRng, max_step:usize) -> (f64, usize)
+        where Q:QFunction<E::State, E::Action> {
+        self.episodes += 1;
+        let mut s = env.reset(rng);
+        let mut a = self.select(&s, env, rng);
+        let (mut g, mut step) = (0.0, 0);
+        loop {
+            let (ss, r, done) = env.step(&a, rng);
+            g += r;
+            step += 1;
+            if done {
+                let (error, alpha) = (r - self.q.predict(&s, &a), self.step_size());
+                self.q.gradient_step(&s, &a, alpha, error);
+                break (g, step)
+            }
+            let aa = self.select(&ss, env, rng);
+            let (error, alpha) = (r + self.gamma * self.q.predict(&ss, &aa) - self.q.predict(&s, &a), self.step_size());
+            self.q.gradient_step(&s, &a, alpha, error);
+            if step >= max_step { break (g, step) }
+            s = ss;
+            a = aa;
+        }
+    }
+}
+
+//semi-gradient TD(0), ss is None for a terminal next state
+pub fn td0<S, V:ValueFunction<S>>(v:&mut V, s:&S, r:f64, ss:Option<&S>, alpha:f64, gamma:f64) {
+    let v_next = match ss {
+        Some(ss) => v.predict(ss),
+        None => 0.0,
+    };
+    let error = r + gamma * v_next - v.predict(s);
+    v.gradient_step(s, alpha, error);
+}
+
+//gradient Monte Carlo over one episode of (state, following reward) pairs
+pub fn gradient_mc<S, V:ValueFunction<S>>(v:&mut V, trajectory:&[(S, f64)], alpha:f64, gamma:f64) {
+    let mut g = 0.0;
+    for (s, r) in trajectory.iter().rev() {
+        g = r + gamma * g;
+        let error = g - v.predict(s);
+        v.gradient_step(s, alpha, error);
+    }
+}
@@ -0,0 +1,82 @@
+use rand::prelude::*;
+
+use crate::env::Environment;
+use crate::rollout::{ self, Trajectory };
+use crate::rng::SeededRng;
+
+//the random walks of examples 6.2, 7.1 and 9.1. states 1..=n between terminals 0 and n + 1,
+//every step jumps 1..=jump to the left or to the right with equal probability, a jump past
+//either end terminates there. only leaving pays, left or right
+pub struct RandomWalk {
+    pub n:usize,
+    pub left:f64,
+    pub right:f64,
+    pub jump:usize,
+    pub start:usize,
+    pub state:usize,
+}
+
+impl RandomWalk {
+    //single steps from the middle state
+    pub fn new(n:usize, left:f64, right:f64) -> Self {
+        let start = n.div_ceil(2);
+        Self { n, left, right, jump:1, start, state:start }
+    }
+
+    pub fn is_terminal(&self, s:usize) -> bool {
+        s == 0 || s > self.n
+    }
+
+    //reward for arriving at s
+    fn reward(&self, s:usize) -> f64 {
+        if s == 0 { self.left } else if s > self.n { self.right } else { 0.0 }
+    }
+
+    //one episode from start, no actions since the walk is its own policy
+    pub fn episode(&mut self, rng:&mut SeededRng) -> Trajectory<usize, ()> {
+        rollout::simulate_episode(self, |_, _| (), rng, usize::MAX)
+    }
+
+    //undiscounted v of every state, terminals included at 0, by expected update sweeps
+    //until no value moves by theta
+    pub fn true_value(&self, theta:f64) -> Vec<f64> {
+        let mut v = vec![0.0; self.n + 2];
+        let p = 0.5 / self.jump as f64;
+        loop {
+            let mut delta:f64 = 0.0;
+            for s in 1..=self.n {
+                let mut v_new = 0.0;
+                for d in 1..=self.jump {
+                    for ss in [s.saturating_sub(d), (s + d).min(self.n + 1)] {
+                        v_new += p * (self.reward(ss) + v[ss]);
+                    }
+                }
+                delta = delta.max((v_new - v[s]).abs());
+                v[s] = v_new;
+            }
+            if delta < theta { break v }
+        }
+    }
+}
+
+impl Environment for RandomWalk {
+    type State = usize;
+    type Action = ();
+
+    fn reset(&mut self, _:&mut SeededRng) -> usize {
+        self.state = self.start;
+        self.state
+    }
+
+    //single steps draw only the direction
+    fn step(&mut self, _:&(), rng:&mut SeededRng) -> (usize, f64, bool) {
+        let d = if self.jump > 1 { rng.gen_range(1..=self.jump) } else { 1 };
+        let s = self.state;
+        self.state = if rng.gen::<bool>() { (s + d).min(self.n + 1) } else { s.saturating_sub(d) };
+        (self.state, self.reward(self.state), self.is_terminal(self.state))
+    }
+
+    fn actions(&self, _:&usize) -> Vec<()> {
+        vec!(())
+    }
+}
@@ -0,0 +1,132 @@
+use std::error::Error;
+use plotters::{prelude::*, coord::Shift};
+
+use crate::experiment::{ Curve, Experiment };
+use crate::nstep::{ Correction, NStepPrediction };
+use crate::rng::SeededRng;
+use crate::stopping::StoppingRule;
+use crate::tabular::Tabular;
+use crate::walk::RandomWalk;
+
+struct ControlInfo {
+    pub episode:usize,//rms error is averaged over these
+    pub p_right:f64,//of π, b is equiprobable
+    pub step:Vec<usize>,
+    pub alpha:Vec<f64>,
+}
+
+//v_π of example 7.1 with the direction chosen by π, right with p_right. a one-row-per-state
+//tabular mdp, terminals get no row
+fn true_value(w:&RandomWalk, p_right:f64) -> Vec<f64> {
+    let mut mdp = Tabular::new(w.n + 2);
+    for s in 1..=w.n {
+        let reward = if s == w.n { p_right * w.right } else if s == 1 { (1.0 - p_right) * w.left } else { 0.0 };
+        mdp.push(s, reward, vec!((s - 1, 1.0 - p_right), (s + 1, p_right)));
+    }
+    mdp.value_iteration(1.0, StoppingRule::MaxNorm(1e-12))
+}
+
+//rms error over the first episodes of one run, averaged over those episodes
+fn rms_error(w:&mut RandomWalk, v_true:&[f64], n:usize, alpha:f64, correction:Correction, c_info:&ControlInfo, rng:&mut SeededRng) -> f64 {
+    let mut v = NStepPrediction::new(n, alpha, 1.0, correction);
+    let mut total = 0.0;
+    for _ in 0..c_info.episode {
+        //b is the equiprobable walk itself, the direction is read off the states
+        let trajectory = w.episode(rng);
+        let rho:Vec<f64> = trajectory.state.windows(2)
+            .map(|s| if s[1] > s[0] { c_info.p_right / 0.5 } else { (1.0 - c_info.p_right) / 0.5 })
+            .collect();
+        v.episode(&trajectory, &rho);
+        let se = (1..=w.n).map(|s| (v.value(&s) - v_true[s]).powi(2)).sum::<f64>();
+        total += (se / w.n as f64).sqrt();
+    }
+    total / c_info.episode as f64
+}
+
+//one line per n over α, figure 7.2 with diverging points cut at the top
+fn draw_alpha(alpha:&[f64], step:&[usize], curve:&[Curve], caption:&str, canvas:&DrawingArea<BitMapBackend, Shift>) -> Result<(), Box<dyn Error>> {
+    let y_max = 1.0;
+    let mut chart = ChartBuilder::on(canvas)
+        .caption(caption, ("sans-serif", 30))
+        .margin(5)
+        .x_label_area_size(50)
+        .y_label_area_size(50)
+        .build_cartesian_2d(0.0..1.0, 0.0..y_max)?;
+    chart.configure_mesh().x_desc("α").y_desc("rms error").draw()?;
+    for (k, (n, c)) in step.iter().zip(curve.iter()).enumerate() {
+        let color = Palette99::pick(k).to_rgba();
+        let point:Vec<(f64, f64)> = alpha.iter().cloned().zip(c.mean.iter().cloned())
+            .filter(|(_, e)| e.is_finite())
+            .map(|(a, e)| (a, e.min(y_max)))
+            .collect();
+        chart.draw_series(LineSeries::new(point, color))?
+            .label(format!("n={}", n))
+            .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], color));
+    }
+    chart
+        .configure_series_labels()
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
+        .draw()?;
+    Ok(())
+}
+
+//the α with the lowest mean rms error of every n, α = 0 excluded
+fn print_best(label:&str, c_info:&ControlInfo, curve:&[Curve]) {
+    for (n, e) in c_info.step.iter().zip(curve.iter()) {
+        let (k, best) = e.mean.iter().enumerate().skip(1)
+            .fold((0, f64::INFINITY), |a, (k, e)| if *e < a.1 { (k, *e) } else { a });
+        println!("{} n={}: best α {:.2} rms {:.4}", label, n, c_info.alpha[k], best);
+    }
+}
+
+//on-policy n-step TD on the 19-state walk over n and α, figure 7.2. with π = b every ρ is 1
+//and the ordinary correction is plain n-step TD
+pub fn run_nstep(seed:u64, runs:Option<usize>) -> Result<(), Box<dyn Error>> {
+    let c_info = ControlInfo {
+        episode:10, p_right:0.5,
+        step:(0..10).map(|k| 1 << k).collect(),
+        alpha:(0..=20).map(|k| k as f64 * 0.05).collect(),
+    };
+    let experiment = Experiment::new(runs.unwrap_or(100), seed);
+    let mut w = RandomWalk::new(19, -1.0, 1.0);
+    let v_true = true_value(&w, c_info.p_right);
+    let curve:Vec<Curve> = c_info.step.iter().map(|n| {
+        experiment.repeat(|rng| {
+            c_info.alpha.iter().map(|alpha| rms_error(&mut w, &v_true, *n, *alpha, Correction::Ordinary, &c_info, rng)).collect()
+        })
+    }).collect();
+    print_best("n-step TD", &c_info, &curve);
+    let canvas = BitMapBackend::new("7_2.png", (1440, 960)).into_drawing_area();
+    canvas.fill(&WHITE)?;
+    draw_alpha(&c_info.alpha, &c_info.step, &curve, &format!("n-step TD, first {} episodes, {} runs", c_info.episode, curve[0].runs), &canvas)?;
+    canvas.present()?;
+    Ok(())
+}
+
+//off-policy n-step TD on the 19-state walk, b equiprobable and π leaning right
+pub fn run(seed:u64, runs:Option<usize>) -> Result<(), Box<dyn Error>> {
+    let c_info = ControlInfo {
+        episode:10, p_right:0.75,
+        step:vec!(1, 2, 4, 8, 16),
+        alpha:(0..=10).map(|k| k as f64 * 0.1).collect(),
+    };
+    let experiment = Experiment::new(runs.unwrap_or(100), seed);
+    let mut w = RandomWalk::new(19, -1.0, 1.0);
+    let v_true = true_value(&w, c_info.p_right);
+    let correction = [Correction::Ordinary, Correction::PerDecision, Correction::ControlVariate];
+    let canvas = BitMapBackend::new("7_4.png", (1440, 2160)).into_drawing_area();
+    canvas.fill(&WHITE)?;
+    let canvas_split = canvas.split_evenly((correction.len(), 1));
+    for (c, area) in correction.iter().zip(canvas_split.iter()) {
+        let curve:Vec<Curve> = c_info.step.iter().map(|n| {
+            experiment.repeat(|rng| {
+                c_info.alpha.iter().map(|alpha| rms_error(&mut w, &v_true, *n, *alpha, *c, &c_info, rng)).collect()
+            })
+        }).collect();
+        print_best(c.label(), &c_info, &curve);
+        draw_alpha(&c_info.alpha, &c_info.step, &curve, c.label(), area)?;
+    }
+    canvas.present()?;
+    Ok(())
+}
@@ -0,0 +1,156 @@
+use std::error::Error;
+use plotters::{prelude::*, coord::Shift};
+
+use crate::experiment::{ Curve, Experiment };
+use crate::rollout::Trajectory;
+use crate::rng::SeededRng;
+use crate::walk::RandomWalk;
+
+type State = usize;
+
+struct ControlInfo {
+    pub max_episode:usize,
+    pub v0:f64,//initial estimate of every nonterminal state
+    pub batch_alpha:f64,
+    pub batch_theta:f64,//batch sweeps stop once the largest summed increment is below
+}
+
+#[derive(Clone, Copy)]
+enum Method {
+    Td,
+    Mc,
+}
+
+fn rms_error(w:&RandomWalk, v:&[f64], v_true:&[f64]) -> f64 {
+    ((1..=w.n).map(|s| (v[s] - v_true[s]).powi(2)).sum::<f64>() / w.n as f64).sqrt()
+}
+
+impl Method {
+    fn label(&self) -> &'static str {
+        match self {
+            Method::Td => "TD(0)",
+            Method::Mc => "constant-α MC",
+        }
+    }
+
+    //α-free increments of one episode, target - V(S_t) for every step. terminals stay at 0
+    fn increment(&self, t:&Trajectory<State, ()>, v:&[f64], inc:&mut [f64]) {
+        let g = t.returns(1.0);
+        for k in 0..t.steps() {
+            let s = t.state[k];
+            let target = match self {
+                Method::Td => t.reward[k] + v[t.state[k + 1]],
+                Method::Mc => g[k],
+            };
+            inc[s] += target - v[s];
+        }
+    }
+
+    //rms error after every episode, online updates after each one
+    fn online(&self, w:&mut RandomWalk, v_true:&[f64], alpha:f64, c_info:&ControlInfo, rng:&mut SeededRng) -> Vec<f64> {
+        let mut v = initial(w, c_info);
+        let mut error = vec!(rms_error(w, &v, v_true));
+        for _ in 0..c_info.max_episode {
+            let t = w.episode(rng);
+            match self {
+                //in step order, later targets see the earlier updates
+                Method::Td => for k in 0..t.steps() {
+                    let (s, ss) = (t.state[k], t.state[k + 1]);
+                    v[s] += alpha * (t.reward[k] + v[ss] - v[s]);
+                },
+                Method::Mc => {
+                    let mut inc = vec![0.0; v.len()];
+                    self.increment(&t, &v, &mut inc);
+                    for (v, inc) in v.iter_mut().zip(inc.iter()) {
+                        *v += alpha * inc;
+                    }
+                },
+            }
+            error.push(rms_error(w, &v, v_true));
+        }
+        error
+    }
+
+    //rms error after every episode, each time presenting every episode so far until the
+    //summed increments vanish, figure 6.2
+    fn batch(&self, w:&mut RandomWalk, v_true:&[f64], c_info:&ControlInfo, rng:&mut SeededRng) -> Vec<f64> {
+        let mut v = initial(w, c_info);
+        let mut seen = Vec::new();
+        let mut error = Vec::new();
+        for _ in 0..c_info.max_episode {
+            seen.push(w.episode(rng));
+            loop {
+                let mut inc = vec![0.0; v.len()];
+                for t in seen.iter() {
+                    self.increment(t, &v, &mut inc);
+                }
+                let delta = inc.iter().map(|i| (c_info.batch_alpha * i).abs()).fold(0.0, f64::max);
+                for (v, inc) in v.iter_mut().zip(inc.iter()) {
+                    *v += c_info.batch_alpha * inc;
+                }
+                if delta < c_info.batch_theta { break }
+            }
+            error.push(rms_error(w, &v, v_true));
+        }
+        error
+    }
+}
+
+fn initial(w:&RandomWalk, c_info:&ControlInfo) -> Vec<f64> {
+    (0..=w.n + 1).map(|s| if s == 0 || s == w.n + 1 { 0.0 } else { c_info.v0 }).collect()
+}
+
+fn draw_error(title:&str, x0:usize, curve:&[(String, Curve)], canvas:&DrawingArea<BitMapBackend, Shift>) -> Result<(), Box<dyn Error>> {
+    let y_max = curve.iter().flat_map(|(_, c)| c.mean.iter().cloned()).fold(0.0, f64::max);
+    let mut chart = ChartBuilder::on(canvas)
+        .caption(format!("{}, {} runs", title, curve[0].1.runs), ("sans-serif", 30))
+        .margin(5)
+        .x_label_area_size(50)
+        .y_label_area_size(50)
+        .build_cartesian_2d(0..curve[0].1.len() + x0, 0.0..y_max)?;
+    chart.configure_mesh().x_desc("walks / episodes").y_desc("rms error, averaged over states").draw()?;
+    for (k, (label, c)) in curve.iter().enumerate() {
+        let color = Palette99::pick(k).to_rgba();
+        chart.draw_series(LineSeries::new(c.mean.iter().enumerate().map(|(e, v)| (e + x0, *v)), color))?
+            .label(label.as_str())
+            .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], color));
+    }
+    chart
+        .configure_series_labels()
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
+        .draw()?;
+    Ok(())
+}
+
+//example 6.2 and figure 6.2, TD(0) against constant-α MC on the 5-state walk, with updates
+//after every episode and with batch updating
+pub fn run(seed:u64, runs:Option<usize>) -> Result<(), Box<dyn Error>> {
+    let c_info = ControlInfo { max_episode:100, v0:0.5, batch_alpha:0.001, batch_theta:1e-4 };
+    let experiment = Experiment::new(runs.unwrap_or(100), seed);
+    //example 6.2, states A..E and only the right end pays
+    let mut w = RandomWalk::new(5, 0.0, 1.0);
+    let v_true = w.true_value(1e-12);
+    let setting = [
+        (Method::Td, 0.05), (Method::Td, 0.1), (Method::Td, 0.15),
+        (Method::Mc, 0.01), (Method::Mc, 0.02), (Method::Mc, 0.03), (Method::Mc, 0.04),
+    ];
+    let online:Vec<(String, Curve)> = setting.iter().map(|(m, alpha)| {
+        let c = experiment.repeat(|rng| m.online(&mut w, &v_true, *alpha, &c_info, rng));
+        (format!("{} α={}", m.label(), alpha), c)
+    }).collect();
+    let batch:Vec<(String, Curve)> = [Method::Td, Method::Mc].iter().map(|m| {
+        let c = experiment.repeat(|rng| m.batch(&mut w, &v_true, &c_info, rng));
+        (format!("batch {}", m.label()), c)
+    }).collect();
+    for (label, c) in online.iter().chain(batch.iter()) {
+        println!("{}: rms error after {} episodes {:.4}", label, c_info.max_episode, c.mean[c.len() - 1]);
+    }
+    let canvas = BitMapBackend::new("6_2.png", (1440, 1920)).into_drawing_area();
+    canvas.fill(&WHITE)?;
+    let canvas_split = canvas.split_evenly((2, 1));
+    draw_error("updates after every episode", 0, &online, &canvas_split[0])?;
+    draw_error("batch training", 1, &batch, &canvas_split[1])?;
+    canvas.present()?;
+    Ok(())
+}
@@ -0,0 +1,48 @@
+use wasm_bindgen::prelude::*;
+
+use crate::exercise4_7::{ Params, Solver };
+
+//browser bindings, `wasm-pack build --target web` then serve web/
+
+//exercise 4.7 stepped from javascript, grids are flattened row by row over [cars at 0][cars at 1]
+#[wasm_bindgen]
+pub struct CarRental {
+    solver:Solver,
+    size:usize,
+}
+
+#[wasm_bindgen]
+impl CarRental {
+    #[wasm_bindgen(constructor)]
+    pub fn new(discount:f64, move_limit:i32, change:bool, free_shuttle:i32, parking_limit:i32, parking_cost:i32) -> CarRental {
+        let params = Params { discount, move_limit, change, free_shuttle, parking_limit, parking_cost, ..Params::default() };
+        let size = params.state_range as usize + 1;
+        CarRental { solver:Solver::new(&params), size }
+    }
+
+    //one evaluation sweep, true once the evaluation is done
+    pub fn sweep(&mut self) -> bool {
+        self.solver.sweep().1
+    }
+
+    //returns the number of changed states, 0 when the policy is stable
+    pub fn improve(&mut self) -> usize {
+        self.solver.improve()
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn iterations(&self) -> usize {
+        self.solver.iterations
+    }
+
+    pub fn value(&self) -> Vec<f64> {
+        self.solver.value().concat()
+    }
+
+    pub fn policy(&self) -> Vec<i32> {
+        self.solver.policy().concat()
+    }
+}
@@ -0,0 +1,258 @@
+//value function backends behind the approximator traits, feature bases, linear prediction on and off
+//policy, differential sarsa, the softmax policy gradient and the deep Q-network
+use rl_exercise::{ cliff_walking, coarse, differential, emphatic, fourier, gtd, lstd, polynomial, rng };
+use rl_exercise::vfa::Features;
+
+//cliff (x, y) and move one-hot, the linear twin of a table
+struct CliffHot;
+
+impl Features<((i32, i32), (i32, i32))> for CliffHot {
+    fn dim(&self) -> usize {
+        12 * 4 * 9
+    }
+
+    fn features(&self, ((x, y), (dx, dy)):&((i32, i32), (i32, i32))) -> rl_exercise::vfa::FeatureVec {
+        vec!((((y * 12 + x) * 9 + (dy + 1) * 3 + dx + 1) as usize, 1.0))
+    }
+}
+
+//one semi-gradient SARSA over a table and over one-hot linear features takes the same steps
+#[test]
+fn approximator_backends() {
+    use rl_exercise::vfa::{ self, LinearVfa, QFunction, Sarsa, Table, ValueFunction };
+    let mut env = cliff_walking::World::new();
+    let mut table = Sarsa::new(Table::new(), 0.1, 0.5, 1.0);
+    let mut linear = Sarsa::new(LinearVfa::new(CliffHot), 0.1, 0.5, 1.0);
+    let (mut rng_t, mut rng_l) = (rng::from_seed(5), rng::from_seed(5));
+    for _ in 0..200 {
+        assert_eq!(table.episode(&mut env, &mut rng_t, 1000), linear.episode(&mut env, &mut rng_l, 1000));
+    }
+    let (s, a) = ((0, 0), (0, 1));
+    assert_eq!(QFunction::predict(&table.q, &s, &a), QFunction::predict(&linear.q, &s, &a));
+    table.epsilon = 0.0;
+    let (g, _) = table.episode(&mut env, &mut rng_t, 1000);
+    assert!((-17.0..-12.0).contains(&g), "{}", g);
+    let mut v = Table::new();
+    vfa::td0(&mut v, &0, 1.0, None, 0.5, 1.0);
+    assert_eq!(v.predict(&0), 0.5);
+}
+
+//a one-step task where the second action pays 1, and the pole falling under a constant push
+#[cfg(feature = "dqn")]
+#[test]
+fn dqn_one_step() {
+    use rl_exercise::{ cart_pole, dqn, Environment };
+    let mut rng = rng::from_seed(3);
+    let mut pole = cart_pole::World::new();
+    pole.reset(&mut rng);
+    let fall = (1..200).find(|_| pole.step(&1, &mut rng).2).unwrap();
+    assert!(fall < 100);
+    struct OneStep;
+    impl Environment for OneStep {
+        type State = Vec<f64>;
+        type Action = usize;
+        fn reset(&mut self, _:&mut rl_exercise::SeededRng) -> Vec<f64> { vec!(1.0) }
+        fn step(&mut self, a:&usize, _:&mut rl_exercise::SeededRng) -> (Vec<f64>, f64, bool) { (vec!(1.0), *a as f64, true) }
+        fn actions(&self, _:&Vec<f64>) -> Vec<usize> { vec!(0, 1) }
+    }
+    let info = dqn::Info { hidden:vec!(8), learn_start:10, sync:20, ..dqn::Info::default() };
+    let mut agent = dqn::Dqn::new(1, 2, 0.5, info, &mut rng);
+    for _ in 0..1000 {
+        agent.episode(&mut OneStep, &mut rng, 10);
+    }
+    let q = agent.q(&[1.0]);
+    assert!(q[0].abs() < 0.05 && (q[1] - 1.0).abs() < 0.05, "{:?}", q);
+}
+
+//one-hot per action of a stateless softmax
+struct ActionHot;
+
+impl Features<((), usize)> for ActionHot {
+    fn dim(&self) -> usize {
+        3
+    }
+
+    fn features(&self, (_, a):&((), usize)) -> rl_exercise::vfa::FeatureVec {
+        vec!((*a, 1.0))
+    }
+}
+
+//∇ln π against central differences of ln π, with the third action held at the floor and without one
+#[test]
+fn softmax_grad_ln() {
+    use rl_exercise::policy_gradient::SoftmaxPolicy;
+    for floor in [0.0, 0.05] {
+        let mut policy = SoftmaxPolicy::new(vec!(0, 1, 2), ActionHot, vec!(2.0, 0.0, -3.0));
+        policy.floor = floor;
+        for a in 0..3 {
+            let mut grad = [0.0; 3];
+            for (k, g) in policy.grad_ln(&(), a) {
+                grad[k] += g;
+            }
+            for (k, g) in grad.iter().enumerate() {
+                let h = 1e-6;
+                let ln = |d:f64| {
+                    let mut p = SoftmaxPolicy::new(vec!(0, 1, 2), ActionHot, policy.theta.clone());
+                    p.floor = floor;
+                    p.theta[k] += d;
+                    p.prob(&())[a].ln()
+                };
+                let numeric = (ln(h) - ln(-h)) / (2.0 * h);
+                assert!((g - numeric).abs() < 1e-6, "floor {} a {} k {}: {} {}", floor, a, k, g, numeric);
+            }
+        }
+    }
+}
+
+//a 3 x 2 grid of centers, circles reaching the direct neighbors only, and rbfs summing to 1
+#[test]
+fn coarse_features() {
+    let center = coarse::grid(&[0.0, 0.0], &[1.0, 1.0], &[3, 2]);
+    assert_eq!(center, vec!(vec!(0.0, 0.0), vec!(0.5, 0.0), vec!(1.0, 0.0), vec!(0.0, 1.0), vec!(0.5, 1.0), vec!(1.0, 1.0)));
+    let cc = coarse::CoarseCoding::new(center.clone(), 0.6);
+    assert_eq!(cc.dim(), 6);
+    let mut active:Vec<usize> = cc.features(&vec!(0.5, 0.0)).iter().map(|(k, _)| *k).collect();
+    active.sort();
+    assert_eq!(active, vec!(0, 1, 2));
+    let rbf = coarse::Rbf::new(center, 0.3, true);
+    let x = rbf.features(&vec!(0.2, 0.7));
+    assert!((x.iter().map(|(_, v)| v).sum::<f64>() - 1.0).abs() < 1e-12);
+    assert_eq!(x.iter().cloned().fold((0, 0.0), |a, b| if b.1 > a.1 { b } else { a }).0, 3);
+}
+
+#[test]
+fn fourier_features() {
+    let basis = fourier::Fourier::new(2, 2);
+    assert_eq!(basis.dim(), 9);
+    let x = basis.features(&vec!(0.5, 1.0));
+    let k = basis.c.iter().position(|c| *c == vec!(1.0, 1.0)).unwrap();
+    assert!((x[0].1 - 1.0).abs() < 1e-12);
+    assert!((x[k].1 - (1.5 * std::f64::consts::PI).cos()).abs() < 1e-12);
+    assert!((basis.step_scale(0) - 1.0).abs() < 1e-12);
+    assert!((basis.step_scale(k) - 0.5f64.sqrt()).abs() < 1e-12);
+}
+
+#[test]
+fn polynomial_features() {
+    let basis = polynomial::Polynomial::new(2, &[0.0, -1.0], &[2.0, 1.0]);
+    assert_eq!(basis.dim(), 9);
+    let x = basis.features(&vec!(1.0, 1.0));
+    let expect = |c:&Vec<f64>| 0.5f64.powi(c[0] as i32);
+    for (k, c) in basis.c.iter().enumerate() {
+        assert!((x[k].1 - expect(c)).abs() < 1e-12);
+    }
+}
+
+//one-hot features over 0..n
+struct OneHot(usize);
+
+impl Features<usize> for OneHot {
+    fn dim(&self) -> usize {
+        self.0
+    }
+
+    fn features(&self, s:&usize) -> rl_exercise::vfa::FeatureVec {
+        vec!((*s, 1.0))
+    }
+}
+
+#[test]
+fn lstd_chain() {
+    //0 -> 1 -> 2 -> end, +1 per step: one episode pins both LSTD(0) and LSTD(1) to the returns
+    let trajectory = vec!((0usize, 1.0), (1, 1.0), (2, 1.0));
+    for lambda in [0.0, 1.0] {
+        let mut v = lstd::Lstd::new(OneHot(3), 1.0, lambda, 1e-9);
+        v.episode(&trajectory);
+        let w = v.weights();
+        for (s, g) in [3.0, 2.0, 1.0].iter().enumerate() {
+            assert!((v.value(&w, &s) - g).abs() < 1e-6, "λ={} s={} {}", lambda, s, w[s]);
+        }
+    }
+}
+
+#[test]
+fn emphatic_followon() {
+    //on-policy with λ = 0 the update is TD(0) scaled by F_t = 1 + γ + ... + γ^t
+    let mut v = rl_exercise::vfa::LinearVfa::new(OneHot(4));
+    let mut td = rl_exercise::vfa::LinearVfa::new(OneHot(4));
+    let mut etd = emphatic::EmphaticTd::new(0.0, 0.5, 0.1, 4);
+    let mut f = 0.0;
+    for s in 0..3 {
+        f = 0.5 * f + 1.0;
+        etd.step(&mut v, &s, 1.0, Some(&(s + 1)), 1.0, 1.0);
+        rl_exercise::vfa::td0(&mut td, &s, 1.0, Some(&(s + 1)), 0.1 * f, 0.5);
+        assert!((etd.f - f).abs() < 1e-12);
+    }
+    assert!(v.w.iter().zip(td.w.iter()).all(|(a, b)| (a - b).abs() < 1e-12));
+    etd.step(&mut v, &3, 1.0, None, 1.0, 1.0);
+    assert_eq!(etd.f, 0.0);
+}
+
+#[test]
+fn gradient_td_chain() {
+    //0 -> 1 -> end, +1 per step, both methods reach the TD fixed point v = (2, 1)
+    for method in [gtd::Method::Gtd2, gtd::Method::Tdc] {
+        let mut v = rl_exercise::vfa::LinearVfa::new(OneHot(2));
+        let mut g = gtd::GradientTd::new(method, 0.05, 0.5, 1.0, 2);
+        for _ in 0..5000 {
+            g.step(&mut v, &0, 1.0, Some(&1), 1.0);
+            g.step(&mut v, &1, 1.0, None, 1.0);
+        }
+        assert!((v.w[0] - 2.0).abs() < 1e-3 && (v.w[1] - 1.0).abs() < 1e-3, "{} {:?}", method.label(), v.w);
+    }
+}
+
+//one state forever, action 1 pays 1 and action 0 nothing
+struct Lever;
+
+impl rl_exercise::env::Environment for Lever {
+    type State = usize;
+    type Action = usize;
+
+    fn reset(&mut self, _:&mut rng::SeededRng) -> usize {
+        0
+    }
+
+    fn step(&mut self, a:&usize, _:&mut rng::SeededRng) -> (usize, f64, bool) {
+        (0, *a as f64, false)
+    }
+
+    fn actions(&self, _:&usize) -> Vec<usize> {
+        vec!(0, 1)
+    }
+}
+
+//an episode cut by the step cap still bootstraps: at α = 1 the last target is R + q(S', A')
+//and keeps the lever at the 5 it starts from instead of the 0 of a terminal
+#[test]
+fn sarsa_step_cap_bootstraps() {
+    use rl_exercise::vfa::{ Sarsa, Table };
+    let mut agent = Sarsa::new(Table::new(), 0.0, 1.0, 1.0);
+    agent.q.v.extend([((0, 0), 5.0), ((0, 1), 5.0)]);
+    let mut rng = rng::from_seed(7);
+    assert_eq!(agent.episode(&mut Lever, &mut rng, 1), (0.0, 1));
+    assert_eq!(agent.q.v[&(0, 0)], 5.0);
+}
+
+//one-hot over the lever pulled
+struct LeverHot(usize);
+
+impl Features<(usize, usize)> for LeverHot {
+    fn dim(&self) -> usize {
+        self.0
+    }
+
+    fn features(&self, (_, a):&(usize, usize)) -> rl_exercise::vfa::FeatureVec {
+        vec!((*a, 1.0))
+    }
+}
+
+#[test]
+fn differential_sarsa_reward_rate() {
+    let mut agent = differential::DifferentialSarsa::new(LeverHot(2), 0.1, 0.1, 0.01);
+    let mut rng = rng::from_seed(7);
+    agent.run(&mut Lever, 20000, &mut rng, |_| {});
+    //ε-greedy on the paying lever earns 1 - ε/2
+    assert_eq!(agent.greedy(&0, &Lever), 1);
+    assert!((agent.r_avg - 0.95).abs() < 0.05, "{}", agent.r_avg);
+}
@@ -0,0 +1,163 @@
+//jack's car rental of exercise 4.7 solved every other way against plain policy iteration
+use std::fs;
+
+use rl_exercise::{ exercise4_7, rng, rtdp, StoppingRule };
+
+//equal within 1e-6 relative to the larger of 1 and the expected value
+fn assert_close(at:&str, expected:&[f64], actual:&[f64]) {
+    assert_eq!(expected.len(), actual.len(), "{}: length", at);
+    for (k, (e, a)) in expected.iter().zip(actual.iter()).enumerate() {
+        assert!((e - a).abs() <= 1e-6 * e.abs().max(1.0), "{}[{}]: expected {} got {}", at, k, e, a);
+    }
+}
+
+//exact evaluation to a tight theta against the lp optimum
+#[test]
+fn car_rental_small_lp() {
+    let params = exercise4_7::Params {
+        state_range:5, stopping:StoppingRule::MaxNorm(1e-10), evaluation:exercise4_7::Evaluation::Exact,
+        ..Default::default()
+    };
+    let s = exercise4_7::solve(&params);
+    let value = exercise4_7::solve_lp(&params).unwrap();
+    assert_close("car_rental_small_lp", &s.value.concat(), &value.concat());
+}
+
+//the iterative evaluator driven to a tight theta should agree with the linear solve
+#[test]
+fn car_rental_small_direct() {
+    let params = exercise4_7::Params {
+        state_range:5, stopping:StoppingRule::MaxNorm(1e-10), evaluation:exercise4_7::Evaluation::Exact,
+        ..Default::default()
+    };
+    let exact = exercise4_7::solve(&params);
+    let direct = exercise4_7::solve(&exercise4_7::Params { evaluation:exercise4_7::Evaluation::Direct, ..params });
+    assert_close("car_rental_small_direct", &exact.value.concat(), &direct.value.concat());
+    assert_eq!(exact.policy, direct.policy);
+}
+
+//with no rentals or returns the model is exact, sampled returns only miss the cut-off tail
+#[test]
+fn car_rental_cross_validate() {
+    let params = exercise4_7::Params {
+        state_range:5, rent:(0, 0), ret:(0, 0), parking_limit:2,
+        stopping:StoppingRule::MaxNorm(1e-10), evaluation:exercise4_7::Evaluation::Exact,
+        ..Default::default()
+    };
+    let cv = exercise4_7::cross_validate(&params, 2, 7);
+    assert_eq!(cv.model.len(), 36);
+    assert!(cv.model.iter().any(|v| *v < -1.0));
+    for (m, v) in cv.mean.iter().zip(cv.model.iter()) {
+        assert!((m - v).abs() < 0.1, "{} {}", m, v);
+    }
+}
+
+#[test]
+fn car_rental_validate() {
+    let v = exercise4_7::validate(&exercise4_7::Params { state_range:5, ..Default::default() });
+    assert!(v.error.is_empty(), "{:?}", v.error);
+}
+
+#[test]
+fn car_rental_discount_sweep() {
+    let base = exercise4_7::Params { state_range:8, move_limit:3, ..Default::default() };
+    let discount = [0.5, 0.9];
+    let sweep = exercise4_7::solve_discounts(&base, &discount);
+    for (d, s) in discount.iter().zip(sweep.iter()) {
+        let one = exercise4_7::solve(&exercise4_7::Params { discount:*d, state_range:8, move_limit:3, ..Default::default() });
+        assert_eq!(s.policy, one.policy);
+        assert_eq!(s.value, one.value);
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn car_rental_mapped_storage() {
+    let params = exercise4_7::Params { state_range:8, move_limit:3, ..Default::default() };
+    let dir = std::env::temp_dir().join(format!("rl_exercise_mmap_{}", std::process::id()));
+    let mut solver = exercise4_7::Solver::<f64>::new(&params);
+    solver.map_to(dir.to_str().unwrap()).unwrap();
+    while solver.iterate() > 0 {}
+    let ram = exercise4_7::solve(&params);
+    assert_eq!(solver.value(), ram.value);
+    assert_eq!(solver.policy(), ram.policy);
+    drop(solver);
+    let bytes = fs::read(dir.join("value.bin")).unwrap();
+    assert_eq!(bytes.len(), 81 * 8);
+    assert_eq!(f64::from_ne_bytes(bytes[..8].try_into().unwrap()), ram.value[0][0]);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+//the lazy car rental rows match the enumerated model, and rtdp from one start builds only
+//the states its trials and backups reach
+#[test]
+fn car_rental_lazy_model() {
+    use rl_exercise::tabular::Model;
+    let params = exercise4_7::Params { state_range:8, move_limit:3, ..Default::default() };
+    let eager = exercise4_7::tabular(&params).by_state();
+    let mut lazy = exercise4_7::lazy_model(&params, true);
+    for (s, rows) in eager.iter().enumerate() {
+        let built = lazy.rows(s);
+        assert_eq!(built.len(), rows.len());
+        for (a, b) in built.iter().zip(rows.iter()) {
+            assert_eq!((a.state, a.reward, &a.next), (b.state, b.reward, &b.next));
+        }
+    }
+    let mut agent = rtdp::Rtdp::new(exercise4_7::lazy_model(&params, true), params.discount, 1000.0);
+    let mut rng = rng::from_seed(3);
+    for _ in 0..20 {
+        agent.trial(0, &mut rng, 30);
+    }
+    assert_eq!(agent.model.built, agent.model.cached());
+    assert!(agent.model.built < agent.model.states, "{}", agent.model.built);
+}
+
+//every start of v ends at the same policy, warm starts take fewer sweeps than starting over
+#[test]
+fn car_rental_value_init() {
+    use exercise4_7::{ Evaluation, Init };
+    let base = exercise4_7::Params { state_range:5, evaluation:Evaluation::Exact, stopping:StoppingRule::MaxNorm(1e-6), ..Default::default() };
+    let golden = exercise4_7::solve(&base);
+    for init in [Init::Zero, Init::Reward, Init::Optimistic] {
+        let mut total = Vec::new();
+        for warm_start in [true, false] {
+            let params = exercise4_7::Params { init, warm_start, ..base.clone() };
+            let mut solver = exercise4_7::Solver::<f64>::new(&params);
+            let mut sweeps = 0;
+            loop {
+                while !solver.sweep().1 {}
+                sweeps += solver.sweeps;
+                if solver.improve() == 0 { break }
+            }
+            assert_eq!(solver.policy(), golden.policy, "{:?} warm {}", init, warm_start);
+            assert_close(&format!("car_rental_value_init {:?}", init), &golden.value.concat(), &solver.value().concat());
+            total.push(sweeps);
+        }
+        assert!(total[0] < total[1], "{:?}: {} warm against {} cold sweeps", init, total[0], total[1]);
+    }
+}
+
+//the model built on the rayon pool is the serial one row for row
+#[test]
+fn car_rental_parallel_setup() {
+    let params = exercise4_7::Params { state_range:8, ..Default::default() };
+    let serial = exercise4_7::tabular(&params);
+    let parallel = exercise4_7::tabular(&exercise4_7::Params { parallel:true, ..params.clone() });
+    assert_eq!(serial.row.len(), parallel.row.len());
+    for (a, b) in serial.row.iter().zip(parallel.row.iter()) {
+        assert_eq!((a.state, a.reward, &a.next), (b.state, b.reward, &b.next));
+    }
+}
+
+//two moves on half the states each is one bit
+#[test]
+fn car_rental_policy_stats() {
+    let stats = exercise4_7::PolicyStats::new(&[2, 2, -1, -1], 2);
+    assert_eq!(stats.histogram, vec!((-2, 0), (-1, 2), (0, 0), (1, 0), (2, 2)));
+    assert!((stats.entropy - 1.0).abs() < 1e-12);
+    assert_eq!(stats.out, (4, 2));
+    assert!((stats.net - 0.5).abs() < 1e-12);
+    let s = exercise4_7::solve(&exercise4_7::Params { state_range:5, ..Default::default() });
+    let stats = exercise4_7::PolicyStats::new(&s.policy.concat(), 5);
+    assert_eq!(stats.histogram.iter().map(|(_, c)| c).sum::<usize>(), 36);
+}
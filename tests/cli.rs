@@ -0,0 +1,55 @@
+//the exercise registry behind the command line, --list and --describe
+use serde_json::{ json, Value };
+
+use rl_exercise::{ cli, exercise4_7, registry, schema };
+
+#[test]
+fn figure_registry() {
+    let exercise = registry::exercises();
+    let id:std::collections::HashSet<&str> = exercise.iter().map(|e| e.id).collect();
+    assert_eq!(id.len(), exercise.len());
+    //every index of the cli is there, next to the book figures
+    for index in ["1_1", "4_7", "4_7_gamma", "bench", "5_12_rtdp", "8_4_prioritized", "compare_qsigma", "grid", "maze", "external", "fig7.2", "ex6.10"] {
+        assert_eq!(registry::find(index).map(|e| e.id), Some(index));
+    }
+    assert!(registry::find("nope").is_none());
+    //chapters in order, the tools after them
+    let chapter:Vec<u32> = exercise.iter().map(|e| e.chapter.unwrap_or(u32::MAX)).collect();
+    assert!(chapter.windows(2).all(|w| w[0] <= w[1]));
+    assert_eq!(registry::find("ex5.12").and_then(|e| e.chapter), Some(5));
+    let list = registry::list();
+    assert_eq!(list.lines().filter(|l| l.starts_with("chapter")).count(), 11);
+    assert!(list.lines().filter(|l| l.starts_with("  ")).count() == exercise.len());
+    assert!(list.contains("chapter 8\n  8_4              blocking maze"), "{}", list);
+    assert!(list.contains("other\n  grid "), "{}", list);
+}
+
+#[test]
+fn parameter_schema() {
+    assert!(schema::describe("nope").is_none());
+    let maze = schema::describe("maze").unwrap();
+    assert!(maze.params.iter().any(|p| p.flag == "--map" && p.required));
+    let fig = schema::describe("fig8.4").unwrap();
+    assert_eq!(fig.title, "blocking maze, Dyna-Q vs Dyna-Q+");
+    assert!(fig.params.iter().any(|p| p.flag == "--runs"));
+    assert!(!schema::describe("fig4.2").unwrap().params.iter().any(|p| p.flag == "--sweeps"));
+    let sweeps = schema::describe("4_7").unwrap().params.into_iter().find(|p| p.flag == "--sweeps").unwrap();
+    assert_eq!(sweeps.default, json!(exercise4_7::Params::default().max_iter));
+    //every default given back on the command line parses
+    let index = registry::exercises().iter().map(|e| e.id).collect::<Vec<_>>();
+    for index in index {
+        let schema = schema::describe(index).unwrap_or_else(|| panic!("{}", index));
+        let json = serde_json::to_value(&schema).unwrap();
+        assert_eq!(json["exercise"], json!(index));
+        for p in schema.params.iter().filter(|p| p.kind != schema::Kind::Flag && !p.default.is_null()) {
+            let value = match &p.default {
+                Value::String(s) => s.clone(),
+                Value::Array(a) => a.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(","),
+                v => v.to_string(),
+            };
+            if let Some(choices) = &p.choices { assert!(choices.contains(&value.as_str()), "{} {}", index, p.flag) }
+            let args = [index, p.flag, &value].map(String::from);
+            assert!(cli::Args::parse(args.into_iter()).is_ok(), "{} {} {}", index, p.flag, value);
+        }
+    }
+}
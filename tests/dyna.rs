@@ -0,0 +1,58 @@
+//planning from a learned model, chapter 8
+use std::fs;
+
+use rl_exercise::rng;
+
+//the blocking maze closes its gap at step 1000, and Dyna-Q with 50 planning steps walks the
+//dyna maze of example 8.1 near its 14-step optimum within a few episodes
+#[test]
+fn dyna_maze() {
+    use rl_exercise::dyna::DynaQ;
+    use rl_exercise::maze::{ Cell, Changing, Maze };
+    let root = env!("CARGO_MANIFEST_DIR");
+    let mut blocking = Changing::parse(&fs::read_to_string(format!("{}/maps/blocking_maze.txt", root)).unwrap()).unwrap();
+    assert_eq!(blocking.at, 1000);
+    assert_eq!((blocking.maze.cell[(8, 2)], blocking.maze.cell[(0, 2)]), (Cell::Open, Cell::Wall));
+    let mut rng = rng::from_seed(1);
+    DynaQ::new(4, 1.0, 0.95, 0.1, 0, 0.0).run(&mut blocking, 1001, &mut rng, |_| ());
+    assert_eq!((blocking.maze.cell[(8, 2)], blocking.maze.cell[(0, 2)]), (Cell::Wall, Cell::Open));
+    let mut maze = Maze::load(&format!("{}/maps/dyna_maze.txt", root)).unwrap();
+    let mut agent = DynaQ::new(4, 0.1, 0.95, 0.1, 50, 0.0);
+    let mut length = vec!(0);
+    agent.run(&mut maze, 3000, &mut rng, |r| {
+        *length.last_mut().unwrap() += 1;
+        if r > 0.0 { length.push(0) }
+    });
+    length.pop();
+    let late = &length[5..];
+    assert!(late.iter().sum::<usize>() as f64 / (late.len() as f64) < 20.0, "{:?}", length);
+    assert!(*late.iter().min().unwrap() >= 14);
+}
+
+//example 8.4 on the plain dyna maze: prioritized sweeping gets a greedy path within 1.2 times
+//the shortest with far fewer updates than Dyna-Q at the same 5 planning updates per step
+#[test]
+fn prioritized_sweeping() {
+    use rl_exercise::dyna::{ self, DynaQ, Planner, PrioritizedSweeping };
+    use rl_exercise::maze::{ self, Maze };
+    let mut base = Maze::load(&format!("{}/maps/dyna_maze.txt", env!("CARGO_MANIFEST_DIR"))).unwrap();
+    assert_eq!(base.shortest(), Some(14));
+    let big = base.scaled(2);
+    assert_eq!((big.size(), big.start), ((18, 12), (0, 6)));
+    assert_eq!(big.shortest(), Some(26));
+    fn updates<P:Planner<(i32, i32)>>(mut agent:P, maze:&mut Maze, seed:u64) -> usize {
+        let mut rng = rng::from_seed(seed);
+        loop {
+            let path = maze::greedy_path(maze, agent.q());
+            if maze.cell[path[path.len() - 1]] == maze::Cell::Goal && path.len() <= 17 { break }
+            dyna::episode(&mut agent, maze, &mut rng, usize::MAX);
+        }
+        agent.updates()
+    }
+    let (mut sweeping, mut dyna_q) = (0, 0);
+    for seed in 0..5 {
+        sweeping += updates(PrioritizedSweeping::new(4, 0.5, 0.95, 0.1, 5, 1e-4), &mut base, seed);
+        dyna_q += updates(DynaQ::new(4, 0.5, 0.95, 0.1, 5, 0.0), &mut base, seed);
+    }
+    assert!(sweeping * 3 < dyna_q, "{} {}", sweeping, dyna_q);
+}
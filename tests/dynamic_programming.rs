@@ -0,0 +1,107 @@
+//tabular models, value iteration and the solvers checked against it
+use rl_exercise::{ gen, gridworld, lp, rng, rtdp, StoppingRule };
+use rl_exercise::tabular::Tabular;
+
+//equal within 1e-6 relative to the larger of 1 and the expected value
+fn assert_close(at:&str, expected:&[f64], actual:&[f64]) {
+    assert_eq!(expected.len(), actual.len(), "{}: length", at);
+    for (k, (e, a)) in expected.iter().zip(actual.iter()).enumerate() {
+        assert!((e - a).abs() <= 1e-6 * e.abs().max(1.0), "{}[{}]: expected {} got {}", at, k, e, a);
+    }
+}
+
+//discounted value iteration stopped on the span should give the max-norm greedy policy
+#[test]
+fn gridworld_4x4_span() {
+    let w = gridworld::World::new(4);
+    let (_, exact) = w.solve(StoppingRule::MaxNorm(1e-9), 0.9);
+    let (_, span) = w.solve(StoppingRule::Span(1e-6), 0.9);
+    assert_eq!(exact, span);
+}
+
+//value iteration against the lp on a few random models
+#[test]
+fn random_mdp_lp() {
+    for seed in 0..4 {
+        let mdp = gen::random_mdp(30, 4, 3, seed);
+        let value = mdp.value_iteration(0.9, StoppingRule::MaxNorm(1e-10));
+        let exact = lp::solve(&mdp, 0.9).unwrap();
+        assert_close(&format!("random_mdp_lp {}", seed), &exact, &value);
+    }
+}
+
+//dense branching pruned both ways stays within the bound of Tabular::prune
+#[test]
+fn random_mdp_prune() {
+    let gamma = 0.9;
+    let full = gen::random_mdp(40, 3, 40, 7);
+    let value = full.value_iteration(gamma, StoppingRule::MaxNorm(1e-10));
+    for renormalize in [false, true] {
+        let mut mdp = gen::random_mdp(40, 3, 40, 7);
+        let pruned = mdp.prune(0.02, renormalize);
+        assert!(pruned.transitions > 0 && pruned.mass > 0.0);
+        assert!(mdp.row.iter().all(|r| !r.next.is_empty() && r.next.iter().all(|(_, p)| *p >= 0.02)));
+        if renormalize {
+            assert!(mdp.row.iter().all(|r| (r.next.iter().map(|(_, p)| p).sum::<f64>() - 1.0).abs() < 1e-12));
+        }
+        let bound = gamma * 2.0 * pruned.mass / ((1.0 - gamma) * (1.0 - gamma));
+        let pruned_value = mdp.value_iteration(gamma, StoppingRule::MaxNorm(1e-10));
+        let error = value.iter().zip(pruned_value).map(|(a, b)| (a - b).abs()).fold(0.0, f64::max);
+        assert!(error <= bound, "renormalize {}: error {} above {}", renormalize, error, bound);
+    }
+}
+
+//terminal corners stay put, so every dense row is a distribution
+#[test]
+fn gridworld_4x4_dense() {
+    let mdp = gridworld::World::new(4).tabular();
+    let (actions, p, reward) = mdp.dense();
+    assert_eq!((actions, p.len(), reward.len()), (4, 4 * 16 * 16, 16 * 4));
+    for row in p.chunks(16) {
+        assert!((row.iter().sum::<f64>() - 1.0).abs() < 1e-12);
+    }
+}
+
+//trials from every state of the 4x4 gridworld end at the optimal values of value iteration
+#[test]
+fn gridworld_4x4_rtdp() {
+    let mdp = gridworld::World::new(4).tabular();
+    let exact = mdp.value_iteration(1.0, StoppingRule::MaxNorm(1e-10));
+    let mut agent = rtdp::Rtdp::new(mdp.by_state(), 1.0, 0.0);
+    let mut rng = rng::from_seed(7);
+    for _ in 0..20 {
+        for s in 0..mdp.states {
+            agent.trial(s, &mut rng, 100);
+        }
+    }
+    assert_close("gridworld_4x4_rtdp", &exact, &agent.v);
+}
+
+//0 -> 1 -> terminal 2 with the second action of 0 looping back: one visit each per episode
+//under the first action, and 0 taking all of the discounted mass under the loop
+#[test]
+fn occupancy() {
+    let mut mdp = Tabular::new(3);
+    mdp.push(0, 0.0, vec!((1, 1.0)));
+    mdp.push(0, 0.0, vec!((0, 1.0)));
+    mdp.push(1, 0.0, vec!((2, 1.0)));
+    let start = [1.0, 0.0, 0.0];
+    assert_close("occupancy", &[0.5, 0.5, 0.0], &mdp.occupancy(&[0, 0, 0], &start, 1.0, StoppingRule::MaxNorm(1e-12)));
+    assert_close("occupancy loop", &[1.0, 0.0, 0.0], &mdp.occupancy(&[1, 0, 0], &start, 0.9, StoppingRule::MaxNorm(1e-12)));
+}
+
+#[test]
+fn average_reward_policy_iteration() {
+    //each state can stay for its own reward or move to the other one for nothing
+    let mut mdp = Tabular::new(2);
+    mdp.push(0, 1.0, vec!((0, 1.0)));
+    mdp.push(0, 0.0, vec!((1, 1.0)));
+    mdp.push(1, 2.0, vec!((1, 1.0)));
+    mdp.push(1, 0.0, vec!((0, 1.0)));
+    let gain = mdp.gain(&[0, 0], 0.5, StoppingRule::MaxNorm(1e-12));
+    assert!((gain[0] - 1.0).abs() < 1e-9 && (gain[1] - 2.0).abs() < 1e-9);
+    let avg = mdp.average_policy_iteration(0, 4, 0.5, 1e-9);
+    assert!((avg.rate - 2.0).abs() < 1e-6);
+    assert_eq!(avg.action, vec!(1, 0));
+    assert!((avg.value[1] - avg.value[0] - 2.0).abs() < 1e-6);
+}
@@ -0,0 +1,95 @@
+//environments, maps and the wrappers around them
+use rl_exercise::{ cliff_walking, rng, td, walk };
+use rl_exercise::policy::Behavior;
+
+//shaping toward the goal of the cliff telescopes to -Φ(start) over an episode, and Q-learning
+//on the shaped rewards still walks the optimal path once shaping is off
+#[test]
+fn potential_shaping() {
+    use rl_exercise::{ rollout, Environment };
+    use rand::Rng;
+    let potential = |s:&(i32, i32)| -((11 - s.0).abs() + s.1) as f64;
+    let random = |_:&(i32, i32), rng:&mut rl_exercise::SeededRng| [(0, 1), (1, 0), (0, -1), (-1, 0)][rng.gen_range(0..4)];
+    let plain = rollout::simulate_episode(&mut cliff_walking::World::new(), random, &mut rng::from_seed(3), 100000);
+    let mut env = cliff_walking::World::new().shaped(potential, 1.0);
+    let shaped = rollout::simulate_episode(&mut env, random, &mut rng::from_seed(3), 100000);
+    assert!(plain.terminal && shaped.terminal);
+    assert!((shaped.total(1.0) - (plain.total(1.0) - potential(&(0, 0)))).abs() < 1e-9);
+
+    let mut agent = td::TdControl::new(td::Method::QLearning, Behavior::EpsilonGreedy(0.1), 0.5, 1.0);
+    let mut rng = rng::from_seed(7);
+    for _ in 0..200 {
+        agent.episode(&mut env, &mut rng, 1000);
+    }
+    env.shaping = false;
+    let actions = env.actions(&(0, 0));
+    let greedy = |s:&(i32, i32), _:&mut rl_exercise::SeededRng| {
+        let q = &agent.q[s];
+        actions[(0..q.len()).fold(0, |best, k| if q[k] > q[best] { k } else { best })]
+    };
+    assert_eq!(rollout::simulate_episode(&mut env, greedy, &mut rng, 100).total(1.0), -13.0);
+}
+
+//the cliff seen as a cell index, with rewards a tenth and the cliff clipped to -1, cut after
+//5 steps. a learner runs on it unchanged
+#[test]
+fn env_wrappers() {
+    use rl_exercise::{ rollout, Environment };
+    let mut env = cliff_walking::World::new().map_state(|s| s.0 + 12 * s.1).scale_reward(0.1, Some(1.0)).time_limit(5);
+    let right = |_:&i32, _:&mut rl_exercise::SeededRng| (1, 0);
+    let t = rollout::simulate_episode(&mut env, right, &mut rng::from_seed(0), 100);
+    assert_eq!((t.state, t.reward, t.terminal, env.truncated), (vec!(0, 0, 0, 0, 0, 0), vec!(-1.0; 5), true, true));
+    let up = |_:&i32, _:&mut rl_exercise::SeededRng| (0, 1);
+    let t = rollout::simulate_episode(&mut env, up, &mut rng::from_seed(0), 100);
+    assert_eq!((t.state.last(), t.reward[0], env.truncated), (Some(&36), -0.1, true));
+
+    let mut agent = td::TdControl::new(td::Method::QLearning, Behavior::EpsilonGreedy(0.1), 0.5, 1.0);
+    let mut rng = rng::from_seed(7);
+    let steps:usize = (0..20).map(|_| agent.episode(&mut env, &mut rng, 1000).1).sum();
+    assert_eq!(steps, 100);
+    assert!(agent.q.keys().all(|k| (0..48).contains(k)));
+}
+
+#[test]
+fn random_walk_values() {
+    let five = walk::RandomWalk::new(5, 0.0, 1.0);
+    for (s, v) in five.true_value(1e-12).iter().enumerate().take(6).skip(1) {
+        assert!((v - s as f64 / 6.0).abs() < 1e-9, "state {}: {}", s, v);
+    }
+    let nineteen = walk::RandomWalk::new(19, -1.0, 1.0);
+    assert_eq!(nineteen.start, 10);
+    assert!(nineteen.true_value(1e-12)[10].abs() < 1e-9);
+    let mut jump = walk::RandomWalk { jump:100, ..walk::RandomWalk::new(1000, -1.0, 1.0) };
+    let mut rng = rng::from_seed(7);
+    let t = jump.episode(&mut rng);
+    assert!(t.terminal && t.state[..t.steps()].iter().all(|s| !jump.is_terminal(*s)));
+    assert!(t.reward.last().unwrap().abs() == 1.0);
+}
+
+//a map read from text: walls block, the wind of the column left pushes up until a wall,
+//the cliff resets and the goal ends. maps/cliff.txt walks like the built-in cliff
+#[test]
+fn maze_from_text() {
+    use rl_exercise::maze::Maze;
+    use rl_exercise::Environment;
+    let mut rng = rng::from_seed(0);
+    let mut m = Maze::parse("; test\nstep -2\nwind 0 1 0\n.#G\n...\nSC.\n").unwrap();
+    assert_eq!(m.size(), (3, 3));
+    assert_eq!(m.reset(&mut rng), (0, 0));
+    assert_eq!(m.step(&(0, 1), &mut rng), ((0, 1), -2.0, false));
+    assert_eq!(m.step(&(1, 0), &mut rng), ((1, 1), -2.0, false));
+    assert_eq!(m.step(&(1, 0), &mut rng), ((2, 2), -2.0, true));
+    m.reset(&mut rng);
+    assert_eq!(m.step(&(1, 0), &mut rng), ((0, 0), -100.0, false));
+    assert_eq!(m.render(&[(0, 1), (1, 1)]), ".#G\n**.\nSC.\n");
+    assert_eq!(Maze::parse("S.\n.").err().unwrap(), "line 2: 1 cells, the first map line has 2");
+    assert_eq!(Maze::parse("S.G\nwind 1").err().unwrap(), "wind has 1 columns, the map 3");
+    assert!(Maze::parse("S.x").is_err());
+    let mut maze = Maze::load(&format!("{}/maps/cliff.txt", env!("CARGO_MANIFEST_DIR"))).unwrap();
+    let mut cliff = cliff_walking::World::new();
+    let (mut a, mut b) = (td::TdControl::new(td::Method::Sarsa, Behavior::EpsilonGreedy(0.1), 0.5, 1.0), td::TdControl::new(td::Method::Sarsa, Behavior::EpsilonGreedy(0.1), 0.5, 1.0));
+    let (mut rng_a, mut rng_b) = (rng::from_seed(9), rng::from_seed(9));
+    for _ in 0..50 {
+        assert_eq!(a.episode(&mut maze, &mut rng_a, 1000), b.episode(&mut cliff, &mut rng_b, 1000));
+    }
+}
@@ -0,0 +1,97 @@
+//replay memories, experience logs and what is learned from them offline
+use std::fs;
+
+use rl_exercise::{ cliff_walking, mc, rng, td, StoppingRule };
+use rl_exercise::policy::Behavior;
+
+//the ring keeps the last items, and prioritized draws follow p^alpha with zero never drawn
+#[test]
+fn replay_buffer() {
+    use rl_exercise::replay::{ Prioritized, Replay };
+    let mut r = Replay::new(3);
+    let slot:Vec<usize> = (0..5).map(|k| r.push(k)).collect();
+    assert_eq!(slot, vec!(0, 1, 2, 0, 1));
+    assert_eq!((r.len(), *r.get(0), *r.get(1), *r.get(2)), (3, 3, 4, 2));
+    let mut rng = rng::from_seed(1);
+    assert!(r.sample(100, &mut rng).iter().all(|s| *s < 3));
+
+    let mut p = Prioritized::new(5, 1.0);
+    for k in 0..4 {
+        p.push(k);
+    }
+    for (slot, priority) in [(0, 1.0), (1, 3.0), (2, 0.0), (3, 4.0)] {
+        p.update(slot, priority);
+    }
+    assert!((p.probability(1) - 0.375).abs() < 1e-12);
+    let mut count = [0; 5];
+    for _ in 0..1000 {
+        for (s, w) in p.sample(8, 0.5, &mut rng) {
+            count[s] += 1;
+            assert!(w > 0.0 && w <= 1.0);
+        }
+    }
+    assert_eq!((count[2], count[4]), (0, 0));
+    assert!((count[1] as f64 / count[0] as f64 - 3.0).abs() < 0.2, "{:?}", count);
+    assert!((count[3] as f64 / 8000.0 - 0.5).abs() < 0.02, "{:?}", count);
+    let uniform = Prioritized::<i32>::new(4, 0.0);
+    assert!(uniform.sample(4, 1.0, &mut rng).is_empty());
+}
+
+//sarsa on a logged cliff, read back step for step, the steps chain within an episode and a
+//file cut inside a step is an error
+#[test]
+fn experience_log() {
+    use rl_exercise::experience_log::{ Logged, Reader, Writer };
+    type Step = rl_exercise::replay::Experience<(i32, i32), (i32, i32)>;
+    let path = std::env::temp_dir().join(format!("rl_exercise_log_{}.bin", std::process::id()));
+    let path = path.to_str().unwrap();
+    let mut env = Logged::new(cliff_walking::World::new(), Writer::create(path).unwrap());
+    let mut agent = td::TdControl::new(td::Method::Sarsa, Behavior::EpsilonGreedy(0.1), 0.5, 1.0);
+    let mut rng = rng::from_seed(7);
+    let steps:usize = (0..3).map(|_| agent.episode(&mut env, &mut rng, 1000).1).sum();
+    env.log.flush().unwrap();
+    assert!(env.error.is_none());
+    let step:Vec<Step> = Reader::open(path).unwrap().collect::<std::io::Result<_>>().unwrap();
+    assert_eq!(step.len(), steps);
+    assert_eq!(step.iter().filter(|e| e.done).count(), 3);
+    assert_eq!(step[0].state, (0, 0));
+    assert!(step.windows(2).all(|w| w[0].done || w[0].next == w[1].state));
+    let bytes = fs::read(path).unwrap();
+    assert_eq!(bytes.len(), 5 + steps * (8 + 8 + 8 + 8 + 1));
+    let cut:Vec<std::io::Result<Step>> = Reader::new(&bytes[..bytes.len() - 3]).unwrap().collect();
+    assert_eq!(cut.len(), steps);
+    assert!(cut[steps - 1].is_err());
+    fs::remove_file(path).unwrap();
+}
+
+//a two-step chain logged under a uniform behavior, action 0 pays 1 each step. the target
+//always takes 0, worth 2
+#[test]
+fn offline_evaluation() {
+    use rand::Rng;
+    use rl_exercise::experience_log::Writer;
+    use rl_exercise::offline;
+    use rl_exercise::replay::Experience;
+    let path = std::env::temp_dir().join(format!("rl_exercise_offline_{}.bin", std::process::id()));
+    let path = path.to_str().unwrap();
+    let mut log = Writer::<i32, i32, _>::create(path).unwrap();
+    let mut rng = rng::from_seed(11);
+    for _ in 0..400 {
+        for s in 0..2 {
+            let a = rng.gen_range(0..2);
+            log.log(&Experience { state:s, action:a, reward:(a == 0) as i32 as f64, next:s + 1, done:s == 1 }).unwrap();
+        }
+    }
+    log.flush().unwrap();
+    let episode = offline::read::<i32, i32>(path).unwrap();
+    fs::remove_file(path).unwrap();
+    assert_eq!(episode.len(), 400);
+    assert!(episode.iter().all(|t| t.terminal && t.state == vec!(0, 1, 2)));
+    let rho = |_:&i32, a:&i32| if *a == 0 { 2.0 } else { 0.0 };
+    assert!((offline::importance_sampling(&episode, mc::Sampling::Weighted, 1.0, rho) - 2.0).abs() < 1e-12);
+    assert!((offline::importance_sampling(&episode, mc::Sampling::Ordinary, 1.0, rho) - 2.0).abs() < 0.5);
+    let (v, q) = offline::fitted_q(&episode, 1.0, StoppingRule::MaxNorm(1e-9), |_| vec!((0, 1.0)));
+    assert!((v - 2.0).abs() < 1e-12);
+    assert_eq!(q[&(1, 1)], 0.0);
+    assert_eq!(q[&(0, 1)], 1.0);
+}
@@ -0,0 +1,106 @@
+//solver results against files in tests/golden, rewrite them with GOLDEN_UPDATE=1
+//after a change that is meant to move the numbers
+use std::fs;
+use std::path::PathBuf;
+use serde_json::{ json, Value };
+
+use rl_exercise::{ exercise4_7, exercise4_9, gridworld, lp, StoppingRule };
+
+const TOLERANCE:f64 = 1e-6;
+
+fn path(name:&str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/golden").join(format!("{}.json", name))
+}
+
+fn compare(at:&str, expected:&Value, actual:&Value) {
+    match (expected, actual) {
+        (Value::Array(e), Value::Array(a)) => {
+            assert_eq!(e.len(), a.len(), "{}: length", at);
+            for (k, (e, a)) in e.iter().zip(a.iter()).enumerate() {
+                compare(&format!("{}[{}]", at, k), e, a);
+            }
+        },
+        (Value::Object(e), Value::Object(a)) => {
+            for (k, e) in e.iter() {
+                let a = a.get(k).unwrap_or_else(|| panic!("{}: missing {}", at, k));
+                compare(&format!("{}.{}", at, k), e, a);
+            }
+        },
+        (Value::Number(e), Value::Number(a)) => {
+            let (e, a) = (e.as_f64().unwrap(), a.as_f64().unwrap());
+            assert!((e - a).abs() <= TOLERANCE * e.abs().max(1.0), "{}: expected {} got {}", at, e, a);
+        },
+        (e, a) => assert_eq!(e, a, "{}", at),
+    }
+}
+
+fn check(name:&str, actual:Value) {
+    let file = path(name);
+    if std::env::var_os("GOLDEN_UPDATE").is_some() {
+        fs::write(&file, serde_json::to_string_pretty(&actual).unwrap()).unwrap();
+        return
+    }
+    let text = fs::read_to_string(&file)
+        .unwrap_or_else(|e| panic!("{}: {}, run with GOLDEN_UPDATE=1 to create it", file.display(), e));
+    compare(name, &serde_json::from_str(&text).unwrap(), &actual);
+}
+
+#[test]
+fn gridworld_4x4() {
+    let w = gridworld::World::new(4);
+    let random = w.evaluate_random(StoppingRule::MaxNorm(1e-9), 1.0);
+    let (value, policy) = w.solve(StoppingRule::MaxNorm(1e-9), 1.0);
+    check("gridworld_4x4", json!({ "random":random, "value":value, "policy":policy }));
+}
+
+#[test]
+fn gambler() {
+    let (value, policy) = exercise4_9::solve(0.4, StoppingRule::MaxNorm(1e-9), 100);
+    check("gambler", json!({ "value":value, "policy":policy }));
+}
+
+#[test]
+fn car_rental_small() {
+    let params = exercise4_7::Params { state_range:5, ..Default::default() };
+    let s = exercise4_7::solve(&params);
+    check("car_rental_small", json!({ "value":s.value, "policy":s.policy, "iterations":s.iterations }));
+}
+
+//single precision should land on the same policy as the stored f64 run
+#[test]
+fn car_rental_small_f32() {
+    let params = exercise4_7::Params { state_range:5, ..Default::default() };
+    let s = exercise4_7::solve_as::<f32>(&params);
+    let text = fs::read_to_string(path("car_rental_small")).unwrap();
+    let golden:Value = serde_json::from_str(&text).unwrap();
+    compare("car_rental_small_f32", &golden["policy"], &json!(s.policy));
+}
+
+//exercise 4.5, policy iteration on q should agree with the v-based run
+#[test]
+fn car_rental_small_q() {
+    let params = exercise4_7::Params { state_range:5, action_value:true, ..Default::default() };
+    let s = exercise4_7::solve(&params);
+    let text = fs::read_to_string(path("car_rental_small")).unwrap();
+    let golden:Value = serde_json::from_str(&text).unwrap();
+    compare("car_rental_small_q", &golden["policy"], &json!(s.policy));
+}
+
+//the lp gives v* directly, value iteration should have converged onto it
+#[test]
+fn gridworld_4x4_lp() {
+    let w = gridworld::World::new(4);
+    let value = lp::solve(&w.tabular(), 1.0).unwrap();
+    let text = fs::read_to_string(path("gridworld_4x4")).unwrap();
+    let golden:Value = serde_json::from_str(&text).unwrap();
+    compare("gridworld_4x4_lp", &golden["value"], &json!(value));
+}
+
+//the lp holds the goal at 0 and pays its 1 as a reward, so only the active states line up
+#[test]
+fn gambler_lp() {
+    let value = lp::solve(&exercise4_9::tabular(0.4, 100), 1.0).unwrap();
+    let text = fs::read_to_string(path("gambler")).unwrap();
+    let golden:Value = serde_json::from_str(&text).unwrap();
+    compare("gambler_lp", &json!(golden["value"].as_array().unwrap()[1..100]), &json!(value[1..100]));
+}
@@ -0,0 +1,26 @@
+//settings of the grid search and what a sweep refuses to run
+use rl_exercise::grid_search::{ self, GridConfig };
+
+fn config(json:&str) -> GridConfig {
+    serde_json::from_str(json).unwrap()
+}
+
+//n-step TD over n and α and LSTD over λ on the 19-state walk, every setting one row
+#[test]
+fn walk_axes() {
+    let c = config(r#"{"task":"random_walk","runs":5,"episode":10,"grid":{"method":["nstep_td"],"n":[1,4],"alpha":[0.2,0.4]}}"#);
+    let outcome = grid_search::search(&c, 3).unwrap();
+    assert_eq!(outcome.len(), 4);
+    assert!(outcome.iter().all(|o| o.average.is_finite() && o.last < 0.6), "{:?}", outcome.iter().map(|o| o.last).collect::<Vec<_>>());
+    let c = config(r#"{"task":"random_walk","runs":5,"episode":10,"grid":{"method":["lstd"],"lambda":[0.0,0.8]}}"#);
+    assert_eq!(grid_search::search(&c, 3).unwrap().len(), 2);
+    let c = config(r#"{"task":"random_walk","runs":5,"episode":10,"grid":{"method":["lstd"],"n":[2]}}"#);
+    assert_eq!(grid_search::search(&c, 3).err().map(|e| e.to_string()), Some("lstd does not read n".to_string()));
+}
+
+//no episode has no last episode to report
+#[test]
+fn empty_sweep() {
+    let c = config(r#"{"task":"cliff_walking","runs":2,"episode":0,"grid":{"alpha":[0.5]}}"#);
+    assert!(grid_search::search(&c, 1).is_err());
+}
@@ -0,0 +1,31 @@
+//what policy iteration records per sweep and iteration
+use std::fs;
+use serde_json::json;
+
+use rl_exercise::history::RunHistory;
+
+#[test]
+fn phase_timing() {
+    let mut history = RunHistory::new();
+    history.setup = 0.5;
+    history.record_sweep(1.0, 0.25);
+    history.record_sweep(0.1, 0.25);
+    history.record_iteration(3, 0.125);
+    history.record_sweep(0.0, 0.25);
+    history.record_iteration(0, 0.125);
+    let t = history.timing();
+    assert_eq!((t.setup, t.sweeps, t.evaluation, t.improvements, t.improvement), (0.5, 3, 0.75, 2, 0.25));
+    assert_eq!(history.iteration.iter().map(|r| r.sweeps).collect::<Vec<_>>(), vec!(2, 1));
+    let json = serde_json::to_value(&t).unwrap();
+    assert_eq!(json["sweeps"], json!(3));
+    let prefix = std::env::temp_dir().join(format!("rl_exercise_history_{}", std::process::id()));
+    let prefix = prefix.to_str().unwrap();
+    history.write_csv(prefix).unwrap();
+    let sweep = fs::read_to_string(format!("{}_sweep.csv", prefix)).unwrap();
+    let iteration = fs::read_to_string(format!("{}_iteration.csv", prefix)).unwrap();
+    fs::remove_file(format!("{}_sweep.csv", prefix)).unwrap();
+    fs::remove_file(format!("{}_iteration.csv", prefix)).unwrap();
+    assert_eq!(sweep.lines().collect::<Vec<_>>(), vec!("iteration,sweep,delta,seconds", "0,0,1,0.25", "0,1,0.1,0.25", "1,0,0,0.25"));
+    assert!(iteration.starts_with("iteration,sweeps,policy_changes,elapsed,improvement\n0,2,3,"));
+    assert!(iteration.lines().all(|l| l.ends_with(",improvement") || l.ends_with(",0.125")), "{}", iteration);
+}
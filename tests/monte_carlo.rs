@@ -0,0 +1,35 @@
+//first- and every-visit prediction and off-policy importance sampling
+use rl_exercise::mc;
+use rl_exercise::rollout::Trajectory;
+
+//A A B then terminal with reward 1 on every step: first-visit sees G_0 for A only,
+//every-visit averages G_0 and G_1
+#[test]
+fn mc_visit() {
+    let trajectory = Trajectory { state:vec!('A', 'A', 'B', 'T'), action:vec!((), (), ()), reward:vec!(1.0, 1.0, 1.0), terminal:true };
+    let mut first = mc::McPrediction::new(mc::Visit::First, 1.0);
+    let mut every = mc::McPrediction::new(mc::Visit::Every, 1.0);
+    first.update(&trajectory);
+    every.update(&trajectory);
+    assert_eq!((first.v[&'A'], first.v[&'B']), (3.0, 1.0));
+    assert_eq!((every.v[&'A'], every.v[&'B']), (2.5, 1.0));
+}
+
+//episodes from one state with ρ = 2, 4 and 0, all left ones returning 1: ordinary
+//averages ρ G over the three, weighted gives the target's 1
+#[test]
+fn mc_importance_sampling() {
+    let episode = [
+        Trajectory { state:vec!((), ()), action:vec!(0), reward:vec!(1.0), terminal:true },
+        Trajectory { state:vec!((), (), ()), action:vec!(0, 0), reward:vec!(0.0, 1.0), terminal:true },
+        Trajectory { state:vec!((), ()), action:vec!(1), reward:vec!(0.0), terminal:true },
+    ];
+    let rho = |_:&(), a:&usize| if *a == 0 { 2.0 } else { 0.0 };
+    for (sampling, v) in [(mc::Sampling::Ordinary, 2.0), (mc::Sampling::Weighted, 1.0)] {
+        let mut est = mc::OffPolicyMc::new(sampling, mc::Visit::First, 1.0);
+        for e in episode.iter() {
+            est.update(e, rho);
+        }
+        assert_eq!((est.v[&()], est.ratio.clone()), (v, vec!(2.0, 4.0, 0.0)), "{}", sampling.label());
+    }
+}
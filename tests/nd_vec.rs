@@ -0,0 +1,59 @@
+//NdVec1 and NdVec2 indexing, bulk construction, printing and ndarray conversion
+use rl_exercise::{ NdVec1, NdVec2 };
+
+#[test]
+fn nd_vec_bounds() {
+    let mut v = NdVec2::new((-1, 1), (0, 2));
+    v.fill(0);
+    v[(1, 2)] = 5;
+    assert_eq!(v.get((1, 2)), Some(&5));
+    assert_eq!(v.get((2, 0)), None);
+    assert_eq!(v.get((-2, 1)), None);
+    *v.get_mut((-1, 0)).unwrap() = 3;
+    assert_eq!(v[(-1, 0)], 3);
+    let panic = std::panic::catch_unwind(|| v[(2, 0)]).unwrap_err();
+    let message = panic.downcast_ref::<String>().unwrap();
+    assert!(message.contains("(2, 0)") && message.contains("x -1..2"), "{}", message);
+    let mut w = NdVec1::new((-2, 2));
+    w.push(1.0);
+    assert_eq!(w.get(-2), Some(&1.0));
+    assert_eq!(w.get(-1), None);
+}
+
+#[test]
+fn nd_vec_bulk() {
+    let mut v = NdVec2::from_fn(((-1, 1), (0, 1)), |(x, y)| x * 10 + y);
+    assert_eq!(v.data, vec!(-10, 0, 10, -9, 1, 11));
+    assert_eq!(v[(1, 1)], 11);
+    let half = v.map(|e| *e as f64 / 2.0);
+    assert_eq!(half[(-1, 0)], -5.0);
+    v.fill(7);
+    assert!(v.iter().all(|e| *e == 7) && v.data.len() == 6);
+}
+
+#[test]
+fn nd_vec_display() {
+    let v = NdVec2::from_fn(((0, 2), (-1, 0)), |(x, y)| x as f64 + y as f64 / 2.0);
+    assert_eq!(format!("{:5.1}", v), "       0    1    2\n-1  -0.5  0.5  1.5\n 0   0.0  1.0  2.0\n");
+    assert_eq!(format!("{}", NdVec2::from_fn(((0, 1), (0, 0)), |(x, _)| x * 10)), "    0  1\n0   0 10\n");
+}
+
+#[cfg(feature = "ndarray")]
+#[test]
+fn nd_vec_ndarray() {
+    use ndarray::{ Array1, Array2 };
+    let v = NdVec2::from_fn(((-1, 1), (0, 1)), |(x, y)| x * 10 + y);
+    assert_eq!(v.view().unwrap()[[2, 1]], v[(1, 1)]);
+    let a = Array2::try_from(v).unwrap();
+    assert_eq!(a.dim(), (3, 2));
+    assert_eq!(a[[0, 1]], -9);
+    let back = NdVec2::from(a.clone());
+    assert_eq!(back[(0, 1)], a[[0, 1]]);
+    assert_eq!(back[(2, 0)], 10);
+    let mut partial = NdVec2::new((0, 1), (0, 1));
+    partial.push(1.0);
+    assert!(Array2::try_from(partial).is_err());
+    let w = NdVec1::from(Array1::from(vec!(1, 2, 3)));
+    assert_eq!(w[2], 3);
+    assert_eq!(Array1::from(w).to_vec(), vec!(1, 2, 3));
+}
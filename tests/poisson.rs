@@ -0,0 +1,14 @@
+//the truncated poisson the car rental model reads its expected counts from
+use rl_exercise::poisson::{ Tail, TruncatedPoisson };
+
+//one distribution cut at 20 gives the mean of every lower cut, for both tails
+#[test]
+fn mean_below() {
+    for tail in [Tail::Lump, Tail::Renormalize] {
+        let full:TruncatedPoisson = TruncatedPoisson::new(4, 20, tail);
+        for v in 0..=25 {
+            let cut:TruncatedPoisson = TruncatedPoisson::new(4, v.min(20), tail);
+            assert!((full.mean_below(v) - cut.mean()).abs() < 1e-12, "{:?} {}", tail, v);
+        }
+    }
+}
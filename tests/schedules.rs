@@ -0,0 +1,55 @@
+//step sizes and exploration rates that change with a count
+use rl_exercise::{ cliff_walking, mc, nstep, rng, td };
+use rl_exercise::policy::Behavior;
+use rl_exercise::rollout::Trajectory;
+
+//the schedules at a few counts, and one step n-step SARSA matching SARSA under 1/n steps and
+//GLIE exploration, each update counted once
+#[test]
+fn schedules() {
+    use rl_exercise::schedules::{ Constant, Exponential, Glie, Harmonic, Schedule };
+    assert_eq!((0.3.at(7), Constant(0.3).at(7)), (0.3, 0.3));
+    assert_eq!((Harmonic.at(1), Harmonic.at(4)), (1.0, 0.25));
+    let e = Exponential { start:1.0, rate:0.5, floor:0.1 };
+    assert_eq!((e.at(1), e.at(3), e.at(10)), (1.0, 0.25, 0.1));
+    let g = Glie { c:10.0 };
+    assert_eq!((g.at(1), g.at(11)), (1.0, 0.5));
+
+    let behavior = Behavior::EpsilonGreedy(0.1);
+    let mut a = nstep::NStepControl::new(nstep::Method::Sarsa, 1, behavior, 0.5, 1.0).with_alpha(Harmonic).with_epsilon(Glie { c:10.0 });
+    let mut b = td::TdControl::new(td::Method::Sarsa, behavior, 0.5, 1.0).with_alpha(Harmonic).with_epsilon(Glie { c:10.0 });
+    let (mut env_a, mut env_b) = (cliff_walking::World::new(), cliff_walking::World::new());
+    let (mut rng_a, mut rng_b) = (rng::from_seed(7), rng::from_seed(7));
+    let mut steps = 0;
+    for _ in 0..20 {
+        let result = a.episode(&mut env_a, &mut rng_a, 1000);
+        assert_eq!(result, b.episode(&mut env_b, &mut rng_b, 1000));
+        steps += result.1;
+    }
+    assert_eq!(a.q, b.q);
+    assert_eq!(b.updates.values().flatten().sum::<usize>(), steps);
+    assert!(matches!(b.behavior, Behavior::EpsilonGreedy(e) if e == 10.0 / 29.0));
+}
+
+//the remaining agents take schedules too: constant α = 0.5 on the every-visit returns 3 then
+//2 of A, Dyna-Q under 1/n averaging the two rewards seen from one pair, and n-step TD past the
+//end of the episode under 1/n averaging the returns 3 and 2 of A as Monte Carlo would
+#[test]
+fn schedules_in_other_agents() {
+    use rl_exercise::dyna::DynaQ;
+    use rl_exercise::schedules::{ Constant, Harmonic };
+    let trajectory = Trajectory { state:vec!('A', 'A', 'B', 'T'), action:vec!((), (), ()), reward:vec!(1.0, 1.0, 1.0), terminal:true };
+    let mut every = mc::McPrediction::new(mc::Visit::Every, 1.0).with_alpha(Constant(0.5));
+    every.update(&trajectory);
+    assert_eq!((every.v[&'A'], every.v[&'B']), (1.75, 0.5));
+
+    let mut rng = rng::from_seed(7);
+    let mut agent = DynaQ::new(4, 0.5, 0.95, 0.1, 0, 0.0).with_alpha(Harmonic).with_epsilon(Constant(0.0));
+    agent.learn(&0, 1, -1.0, &1, true, &mut rng);
+    agent.learn(&0, 1, -3.0, &1, true, &mut rng);
+    assert_eq!(agent.q[&0][1], -2.0);
+
+    let mut td = nstep::NStepPrediction::new(4, 0.5, 1.0, nstep::Correction::Ordinary).with_alpha(Harmonic);
+    td.episode(&trajectory, &[1.0; 3]);
+    assert_eq!((td.value(&'A'), td.value(&'B')), (2.5, 1.0));
+}
@@ -0,0 +1,15 @@
+//the stopping rules tested on v' - v after every sweep
+use rl_exercise::stopping::{ Change, StoppingRule };
+
+//the max norm stops at theta itself, as the original delta <= theta did, so an unchanged
+//sweep converges even against theta 0. the span bound stays strict
+#[test]
+fn threshold_inclusive() {
+    let mut c = Change::new();
+    c.add(0.0);
+    assert!(StoppingRule::MaxNorm(0.0).met(&c, 0.9));
+    c.add(0.1);
+    assert!(StoppingRule::MaxNorm(0.1).met(&c, 0.9));
+    assert!(!StoppingRule::Span(0.1).met(&c, 1.0));
+    assert!(StoppingRule::Span(0.2).met(&c, 1.0));
+}
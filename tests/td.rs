@@ -0,0 +1,175 @@
+//one-step and n-step TD control, afterstates and the agents they are checked against
+use std::collections::HashMap;
+
+use rl_exercise::{ afterstate, cliff_walking, nstep, rng, td, tic_tac_toe };
+use rl_exercise::policy::Behavior;
+use rl_exercise::rollout::Trajectory;
+
+//with n = 1 the control variate form ρ (R + γ V') + (1 - ρ) V steps exactly like ρ (R + γ V' - V)
+#[test]
+fn nstep_control_variate_one_step() {
+    let trajectory = Trajectory { state:vec!(0, 1, 2, 1, 3), action:vec!((), (), (), ()), reward:vec!(0.5, -1.0, 0.0, 2.0), terminal:true };
+    let rho = [2.0, 0.0, 0.5, 1.5];
+    let v = HashMap::from([(1, 0.3), (2, -0.2)]);
+    let mut ordinary = nstep::NStepPrediction { v:v.clone(), ..nstep::NStepPrediction::new(1, 0.1, 0.9, nstep::Correction::Ordinary) };
+    let mut cv = nstep::NStepPrediction { v, ..nstep::NStepPrediction::new(1, 0.1, 0.9, nstep::Correction::ControlVariate) };
+    ordinary.episode(&trajectory, &rho);
+    cv.episode(&trajectory, &rho);
+    for s in 0..3 {
+        assert!((ordinary.value(&s) - cv.value(&s)).abs() < 1e-12, "state {}", s);
+    }
+}
+
+//one step tree backup toward the greedy policy is Q-learning and one step n-step SARSA is
+//SARSA, both draw from the generator in the same order so the tables match exactly
+#[test]
+fn nstep_one_step() {
+    for (m, td_m) in [(nstep::Method::Sarsa, td::Method::Sarsa), (nstep::Method::TreeBackup, td::Method::QLearning)] {
+        let behavior = Behavior::EpsilonGreedy(0.1);
+        let mut a = nstep::NStepControl::new(m, 1, behavior, 0.5, 1.0);
+        let mut b = td::TdControl::new(td_m, behavior, 0.5, 1.0);
+        let (mut env_a, mut env_b) = (cliff_walking::World::new(), cliff_walking::World::new());
+        let (mut rng_a, mut rng_b) = (rng::from_seed(7), rng::from_seed(7));
+        for _ in 0..20 {
+            assert_eq!(a.episode(&mut env_a, &mut rng_a, 1000), b.episode(&mut env_b, &mut rng_b, 1000), "{}", m.label());
+        }
+        assert_eq!(a.q, b.q, "{}", m.label());
+    }
+}
+
+//every reward of the cliff is negative, so a greedy learner starting from 0 tries every
+//action of the start. from far below every return the first action tried only improves
+#[test]
+fn optimistic_q_table() {
+    use rl_exercise::Environment;
+    let tried = |initial:f64| {
+        let mut agent = td::TdControl { initial, ..td::TdControl::new(td::Method::QLearning, Behavior::EpsilonGreedy(0.0), 0.5, 0.9) };
+        let mut env = cliff_walking::World::new();
+        let mut rng = rng::from_seed(7);
+        let start = env.reset(&mut rng);
+        for _ in 0..10 {
+            agent.episode(&mut env, &mut rng, 1000);
+        }
+        agent.updates[&start].iter().filter(|n| **n > 0).count()
+    };
+    assert_eq!(tried(0.0), 4);
+    assert_eq!(tried(-1000.0), 1);
+}
+
+//σ=0 is tree backup at any n, and σ=1 on-policy at n=1 collapses to expected sarsa
+#[test]
+fn nstep_qsigma_limits() {
+    let behavior = Behavior::EpsilonGreedy(0.1);
+    let mut a = nstep::NStepControl::new(nstep::Method::QSigma(nstep::Sigma::Constant(0.0)), 3, behavior, 0.5, 1.0);
+    let mut b = nstep::NStepControl::new(nstep::Method::TreeBackup, 3, behavior, 0.5, 1.0);
+    let (mut env_a, mut env_b) = (cliff_walking::World::new(), cliff_walking::World::new());
+    let (mut rng_a, mut rng_b) = (rng::from_seed(7), rng::from_seed(7));
+    for _ in 0..20 {
+        assert_eq!(a.episode(&mut env_a, &mut rng_a, 1000), b.episode(&mut env_b, &mut rng_b, 1000));
+    }
+    assert_eq!(a.q, b.q);
+
+    let mut a = nstep::NStepControl::new(nstep::Method::QSigma(nstep::Sigma::Constant(1.0)), 1, behavior, 0.5, 1.0);
+    a.target = behavior;
+    let mut b = td::TdControl::new(td::Method::ExpectedSarsa, behavior, 0.5, 1.0);
+    let (mut env_a, mut env_b) = (cliff_walking::World::new(), cliff_walking::World::new());
+    let (mut rng_a, mut rng_b) = (rng::from_seed(7), rng::from_seed(7));
+    for _ in 0..20 {
+        assert_eq!(a.episode(&mut env_a, &mut rng_a, 1000), b.episode(&mut env_b, &mut rng_b, 1000));
+    }
+    assert_eq!(a.q, b.q);
+}
+
+//(state, action) as its own afterstate is plain TD control
+struct Pair(cliff_walking::World);
+
+impl rl_exercise::Environment for Pair {
+    type State = (i32, i32);
+    type Action = (i32, i32);
+
+    fn reset(&mut self, rng:&mut rl_exercise::SeededRng) -> Self::State {
+        self.0.reset(rng)
+    }
+
+    fn step(&mut self, a:&Self::Action, rng:&mut rl_exercise::SeededRng) -> (Self::State, f64, bool) {
+        self.0.step(a, rng)
+    }
+
+    fn actions(&self, s:&Self::State) -> Vec<Self::Action> {
+        self.0.actions(s)
+    }
+}
+
+impl afterstate::Afterstate for Pair {
+    type After = ((i32, i32), (i32, i32));
+
+    fn afterstate(&self, s:&Self::State, a:&Self::Action) -> Self::After {
+        (*s, *a)
+    }
+}
+
+#[test]
+fn afterstate_control() {
+    use rl_exercise::Environment;
+    let behavior = Behavior::EpsilonGreedy(0.1);
+    for m in [td::Method::Sarsa, td::Method::QLearning, td::Method::ExpectedSarsa] {
+        let mut a = afterstate::AfterstateControl::new(m, behavior, 0.5, 1.0);
+        let mut b = td::TdControl::new(m, behavior, 0.5, 1.0);
+        let (mut env_a, mut env_b) = (Pair(cliff_walking::World::new()), cliff_walking::World::new());
+        let (mut rng_a, mut rng_b) = (rng::from_seed(7), rng::from_seed(7));
+        for _ in 0..20 {
+            assert_eq!(a.episode(&mut env_a, &mut rng_a, 1000), b.episode(&mut env_b, &mut rng_b, 1000), "{}", m.label());
+        }
+        for (s, q) in b.q.iter() {
+            for (k, action) in env_b.actions(s).iter().enumerate() {
+                assert_eq!(a.v[&(*s, *action)], q[k], "{}", m.label());
+            }
+        }
+    }
+
+    //cells as afterstates, q-learning still finds the 13-step path along the cliff
+    let mut env = cliff_walking::World::new();
+    let mut agent = afterstate::AfterstateControl::new(td::Method::QLearning, behavior, 0.5, 1.0);
+    let mut rng = rng::from_seed(7);
+    for _ in 0..200 {
+        agent.episode(&mut env, &mut rng, 10000);
+    }
+    assert!(agent.v.len() <= 12 * 4);
+    let mut s = env.reset(&mut rng);
+    let mut g = 0.0;
+    for _ in 0..100 {
+        let a = env.actions(&s)[agent.greedy(&s, &env)];
+        let (ss, r, done) = env.step(&a, &mut rng);
+        g += r;
+        s = ss;
+        if done { break }
+    }
+    assert_eq!(g, -13.0);
+}
+
+//a short self-play run already beats the random player and rarely loses to the blocker
+#[test]
+fn tic_tac_toe_self_play() {
+    use tic_tac_toe::{ Game, Opponent };
+    let mut agent = afterstate::AfterstateControl::new(td::Method::Sarsa, Behavior::EpsilonGreedy(0.1), 0.2, 1.0);
+    let game = Game::new(Opponent::Random);
+    let mut rng = rng::from_seed(7);
+    for _ in 0..20000 {
+        tic_tac_toe::self_play(&mut agent, &game, &mut rng);
+    }
+    let (win, loss) = tic_tac_toe::evaluate(&agent, Opponent::Random, 200, &mut rng);
+    assert!(win > 0.8 && loss < 0.05, "{} {}", win, loss);
+    let (_, loss) = tic_tac_toe::evaluate(&agent, Opponent::Blocker, 200, &mut rng);
+    assert!(loss < 0.1, "{}", loss);
+}
+
+//every step of TD control is counted at the state it was taken from
+#[test]
+fn visit_counts() {
+    let mut agent = td::TdControl::new(td::Method::Sarsa, Behavior::EpsilonGreedy(0.1), 0.5, 1.0);
+    let mut env = cliff_walking::World::new();
+    let mut rng = rng::from_seed(7);
+    let steps:usize = (0..20).map(|_| agent.episode(&mut env, &mut rng, 1000).1).sum();
+    assert_eq!(agent.visits.values().sum::<usize>(), steps);
+    assert!(agent.visits[&(0, 0)] >= 20);
+}
@@ -0,0 +1,15 @@
+//sarsa on the windy gridworld of example 6.5 and exercises 6.9 and 6.10
+use rl_exercise::exercise6_9::{ self, Moves };
+
+//moves outside the set keep their initial 0, above every learned value, and must never be greedy
+#[test]
+fn greedy_allowed() {
+    for moves in [Moves::Rook, Moves::King] {
+        for a in exercise6_9::greedy_actions(1, moves, 50) {
+            match moves {
+                Moves::Rook => assert_eq!(a.0.abs() + a.1.abs(), 1, "{:?}", a),
+                _ => assert_ne!(a, (0, 0)),
+            }
+        }
+    }
+}
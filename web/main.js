@@ -0,0 +1,82 @@
+import init, { CarRental } from "../pkg/rl_exercise.js";
+
+const ids = ["discount", "move_limit", "change", "free_shuttle", "parking_limit", "parking_cost"];
+const sweepsPerFrame = 4;
+
+let solver = null;
+let frame = 0;
+let sweeps = 0;
+
+function read(id) {
+    const e = document.getElementById(id);
+    return e.type === "checkbox" ? e.checked : Number(e.value);
+}
+
+function restart() {
+    for (const id of ids) {
+        const e = document.getElementById(id);
+        e.nextElementSibling.textContent = e.type === "checkbox" ? "" : e.value;
+    }
+    if (solver) solver.free();
+    solver = new CarRental(...ids.map(read));
+    sweeps = 0;
+    cancelAnimationFrame(frame);
+    frame = requestAnimationFrame(step);
+}
+
+//row m is cars at the first location, drawn bottom up like figure 4.2
+function drawGrid(canvas, size, cell) {
+    const ctx = canvas.getContext("2d");
+    const w = canvas.width / size;
+    for (let m = 0; m < size; m++) {
+        for (let n = 0; n < size; n++) {
+            ctx.fillStyle = cell(m * size + n);
+            ctx.fillRect(n * w, (size - 1 - m) * w, w, w);
+        }
+    }
+}
+
+//blue moves cars to the second location, red to the first
+function actionColor(a, limit) {
+    const t = Math.min(Math.abs(a) / limit, 1);
+    const c = Math.round(255 * (1 - t));
+    return a > 0 ? `rgb(${c},${c},255)` : a < 0 ? `rgb(255,${c},${c})` : "rgb(255,255,255)";
+}
+
+function draw(sweeps) {
+    const size = solver.size();
+    const policy = solver.policy();
+    const value = solver.value();
+    const limit = read("move_limit");
+    drawGrid(document.getElementById("policy"), size, k => actionColor(policy[k], limit));
+    const vMin = Math.min(...value), vMax = Math.max(...value);
+    drawGrid(document.getElementById("value"), size, k => {
+        const t = vMax > vMin ? (value[k] - vMin) / (vMax - vMin) : 0;
+        return `hsl(${240 - 240 * t},80%,50%)`;
+    });
+    document.getElementById("status").textContent =
+        `iteration ${solver.iterations()} sweep ${sweeps} value ${vMin.toFixed(1)}..${vMax.toFixed(1)}`;
+}
+
+function step() {
+    let done = false;
+    for (let k = 0; k < sweepsPerFrame && !done; k++) {
+        sweeps++;
+        done = solver.sweep();
+    }
+    if (done) {
+        const changed = solver.improve();
+        sweeps = 0;
+        if (changed === 0) {
+            draw(sweeps);
+            document.getElementById("status").textContent += " stable";
+            return;
+        }
+    }
+    draw(sweeps);
+    frame = requestAnimationFrame(step);
+}
+
+await init();
+for (const id of ids) document.getElementById(id).addEventListener("input", restart);
+restart();